@@ -0,0 +1,74 @@
+/*
+ * C interface to sunk, a Subsonic client.
+ *
+ * Build the library with:
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Strings are UTF-8 and nul-terminated. Functions that fail return NULL, or
+ * -1 where they return an int, and leave a message for sunk_last_error().
+ * Everything returned is owned by the caller, and freed with the matching
+ * sunk_*_free function. A client may be used from one thread at a time.
+ */
+
+#ifndef SUNK_H
+#define SUNK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A connection to a Subsonic server. */
+typedef struct SunkClient SunkClient;
+
+/* A song found by a search. Strings other than id and title are NULL when
+ * the server doesn't send them. */
+typedef struct SunkSong {
+    char *id;
+    char *title;
+    char *artist;
+    char *album;
+    char *cover_id;
+    /* The length of the song in seconds, or 0 if unknown. */
+    uint64_t duration;
+} SunkSong;
+
+/* The songs found by a search. */
+typedef struct SunkSongList {
+    SunkSong *songs;
+    size_t len;
+} SunkSongList;
+
+/* Returns the message of the last error on this thread, or NULL. The message
+ * is owned by the library, and lasts until the next call that fails. */
+const char *sunk_last_error(void);
+
+/* Connects to the server at url as user. No request is made. */
+SunkClient *sunk_client_new(const char *url, const char *user, const char *password);
+void sunk_client_free(SunkClient *client);
+
+/* Searches for up to count songs matching query. */
+SunkSongList *sunk_search(const SunkClient *client, const char *query, size_t count);
+void sunk_song_list_free(SunkSongList *list);
+
+/* Returns a URL streaming the song. The URL holds the client's credentials. */
+char *sunk_stream_url(const SunkClient *client, const char *song_id);
+
+/* Downloads the song to the file at path. Returns 0 on success, or -1. */
+int sunk_download(const SunkClient *client, const char *song_id, const char *path);
+
+/* Fetches cover art, scaled to size pixels if not 0. Its length is written to
+ * len. */
+uint8_t *sunk_cover_art(const SunkClient *client, const char *cover_id, size_t size, size_t *len);
+void sunk_bytes_free(uint8_t *bytes, size_t len);
+
+void sunk_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SUNK_H */
@@ -0,0 +1,190 @@
+//! Finding problems in the library.
+//!
+//! Libraries that grew over years tend to pick up the same track more than
+//! once: ripped from the single and from the album, or imported twice in
+//! different formats. [`duplicates`] groups such tracks for review.
+//!
+//! [`duplicates`]: fn.duplicates.html
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::library;
+#[cfg(feature = "mirror")]
+use crate::mirror::Mirror;
+use crate::{Client, Result, Song};
+
+/// Finds songs that are likely duplicates of each other, paging through the
+/// whole library with [`library::all_songs`].
+///
+/// Songs are duplicates if they have the same MusicBrainz recording ID, or
+/// the same artist and title, ignoring case, and durations no more than
+/// `tolerance` apart. Each group holds two or more songs; groups are ordered
+/// by artist and title.
+///
+/// Use [`Mirror::duplicates`] instead if the library is mirrored.
+///
+/// [`library::all_songs`]: ../library/fn.all_songs.html
+/// [`Mirror::duplicates`]: ../mirror/struct.Mirror.html#method.duplicates
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use std::time::Duration;
+/// use sunk::{analysis, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// for group in analysis::duplicates(&client, Duration::from_secs(2))? {
+///     println!("{} copies of {}", group.len(), group[0]);
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn duplicates(client: &Client, tolerance: Duration) -> Result<Vec<Vec<Song>>> {
+    let songs = library::all_songs(client).collect::<Result<Vec<_>>>()?;
+    Ok(group_duplicates(songs, tolerance))
+}
+
+#[cfg(feature = "mirror")]
+impl Mirror {
+    /// Finds songs in the mirror that are likely duplicates of each other.
+    ///
+    /// See [`analysis::duplicates`] for what makes songs duplicates.
+    ///
+    /// [`analysis::duplicates`]: ../analysis/fn.duplicates.html
+    pub fn duplicates(&self, tolerance: Duration) -> Vec<Vec<&Song>> {
+        group_duplicates(self.songs().iter().collect(), tolerance)
+    }
+}
+
+/// Groups songs that are likely duplicates, dropping the others.
+fn group_duplicates<S: Borrow<Song>>(songs: Vec<S>, tolerance: Duration) -> Vec<Vec<S>> {
+    let mut groups = Groups::new(songs.len());
+
+    let mut by_recording = HashMap::new();
+    let mut by_title = HashMap::<_, Vec<usize>>::new();
+    for (i, song) in songs.iter().enumerate() {
+        let song = song.borrow();
+        if let Some(id) = song.musicbrainz_id.as_deref().filter(|id| !id.is_empty()) {
+            if let Some(&first) = by_recording.get(id) {
+                groups.join(first, i);
+            } else {
+                by_recording.insert(id, i);
+            }
+        }
+        by_title.entry(title_key(song)).or_default().push(i);
+    }
+
+    // Songs of the same title are duplicates when their durations are close,
+    // so songs sorted by duration are joined to their neighbours.
+    let tolerance = tolerance.as_secs();
+    for mut same in by_title.into_values() {
+        same.retain(|&i| songs[i].borrow().duration.is_some());
+        same.sort_by_key(|&i| songs[i].borrow().duration);
+        for pair in same.windows(2) {
+            let (a, b) = (songs[pair[0]].borrow(), songs[pair[1]].borrow());
+            if b.duration.unwrap_or(0) - a.duration.unwrap_or(0) <= tolerance {
+                groups.join(pair[0], pair[1]);
+            }
+        }
+    }
+
+    let mut grouped = HashMap::<usize, Vec<S>>::new();
+    let roots = (0..songs.len()).map(|i| groups.root(i)).collect::<Vec<_>>();
+    for (song, root) in songs.into_iter().zip(roots) {
+        grouped.entry(root).or_default().push(song);
+    }
+
+    let mut grouped = grouped
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect::<Vec<_>>();
+    grouped.sort_by_cached_key(|group| title_key(group[0].borrow()));
+    grouped
+}
+
+/// Returns the artist and title of a song, ignoring case.
+fn title_key(song: &Song) -> (String, String) {
+    let artist = song.artist.as_deref().unwrap_or("").to_lowercase();
+    (artist, song.title.to_lowercase())
+}
+
+/// Sets of indices that can be merged, as a union-find forest.
+struct Groups {
+    parent: Vec<usize>,
+}
+
+impl Groups {
+    fn new(len: usize) -> Groups {
+        Groups {
+            parent: (0..len).collect(),
+        }
+    }
+
+    /// Returns the index representing the set `i` is in.
+    fn root(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merges the sets `a` and `b` are in.
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        self.parent[b] = a;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn song(id: u64, artist: &str, title: &str, duration: u64, mbid: &str) -> Song {
+        test_util::song_with(
+            &id.to_string(),
+            serde_json::json!({
+                "title": title, "artist": artist, "duration": duration, "musicBrainzId": mbid,
+            }),
+        )
+    }
+
+    #[test]
+    fn find_duplicates() {
+        let songs = [
+            song(1, "Misteur Valaire", "Interlude", 100, ""),
+            song(2, "Misteur Valaire", "Interlude", 250, ""),
+            song(3, "misteur valaire", "INTERLUDE", 101, ""),
+            song(4, "Misteur Valaire", "Bellevue Avenue", 200, "a1b2"),
+            song(
+                5,
+                "Misteur Valaire",
+                "Bellevue Avenue (Single)",
+                180,
+                "a1b2",
+            ),
+            song(6, "Misteur Valaire", "Interlude", 103, ""),
+        ];
+
+        let groups = group_duplicates(songs.iter().collect(), Duration::from_secs(2));
+        let ids = groups
+            .iter()
+            .map(|g| {
+                let mut ids = g.iter().map(|s| s.id.as_str()).collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [vec!["4", "5"], vec!["1", "3", "6"]]);
+    }
+}
@@ -1,16 +1,43 @@
-use crate::query::Query;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::endpoint::{Scrobble, SetRating, Star, Unstar};
+use crate::history::PlayKind;
+use crate::timestamp;
 use crate::{Album, Artist, Client, Error, Result, Song};
 
 /// Allows starring, rating, and scrobbling media.
+///
+/// Whether an artist, album or song is starred, and how the user has rated
+/// it, is available as its `starred` and `user_rating` fields. Starring and
+/// rating content updates these fields once the server has accepted the
+/// change, so they stay in step without fetching the content again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::{Album, Annotatable, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// let client = Client::new("http://subsonic.example.com", "admin", "hunter2")?;
+/// let mut album = Album::get(&client, 1)?;
+/// if album.starred.is_none() {
+///     album.star(&client)?;
+/// }
+/// album.set_rating(&client, 5)?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
 pub trait Annotatable {
     /// Attaches a star to the content.
-    fn star(&self, client: &Client) -> Result<()>;
+    fn star(&mut self, client: &Client) -> Result<()>;
 
     /// Removes a star from the content.
-    fn unstar(&self, client: &Client) -> Result<()>;
+    fn unstar(&mut self, client: &Client) -> Result<()>;
 
-    /// Sets the rating for the content.
-    fn set_rating(&self, client: &Client, rating: u8) -> Result<()>;
+    /// Sets the rating for the content, from 1 to 5. A rating of 0 removes
+    /// the rating.
+    fn set_rating(&mut self, client: &Client, rating: u8) -> Result<()>;
 
     /// Registers the local playback of the content. Typically used when playing
     /// media that is cached on the client. This operation includes the
@@ -25,8 +52,13 @@ pub trait Annotatable {
     ///
     /// [`Client::now_playing()`]: ./struct.Client.html#method.now_playing
     ///
-    /// `time` should be a valid ISO8601 timestamp. In the future, this will be
-    /// validated.
+    /// `time` is when the content was played, as a number of milliseconds
+    /// since the Unix epoch, as the API takes it, or an ISO8601 timestamp. It
+    /// is sent to the server as given.
+    ///
+    /// Scrobbling a song also records the play in the client's history and
+    /// sends it to its scrobblers, at `time` if it can be read, and at the
+    /// current time otherwise.
     fn scrobble<'a, B, T>(&self, client: &Client, time: T, now_playing: B) -> Result<()>
     where
         B: Into<Option<bool>>,
@@ -34,23 +66,28 @@ pub trait Annotatable {
 }
 
 impl Annotatable for Artist {
-    fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("artistId", self.id))?;
+    fn star(&mut self, client: &Client) -> Result<()> {
+        client.call(&Star::one(self.star_key(), self.id.as_str()))?;
+        self.starred = Some(now());
         Ok(())
     }
 
-    fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("artistId", self.id))?;
+    fn unstar(&mut self, client: &Client) -> Result<()> {
+        client.call(&Unstar::from(Star::one(self.star_key(), self.id.as_str())))?;
+        self.starred = None;
         Ok(())
     }
 
-    fn set_rating(&self, client: &Client, rating: u8) -> Result<()> {
+    fn set_rating(&mut self, client: &Client, rating: u8) -> Result<()> {
         if rating > 5 {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        client.call(&SetRating {
+            id: self.id.to_string(),
+            rating,
+        })?;
+        self.user_rating = Some(rating).filter(|&r| r > 0);
         Ok(())
     }
 
@@ -59,33 +96,38 @@ impl Annotatable for Artist {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("time", time.into())
-            .arg("submission", now_playing.into().map(|b| !b))
-            .build();
-        client.get("scrobble", args)?;
+        client.call(&Scrobble {
+            id: self.id.to_string(),
+            time: time.into().map(String::from),
+            submission: now_playing.into().map(|b| !b),
+        })?;
         Ok(())
     }
 }
 
 impl Annotatable for Album {
-    fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("albumId", self.id))?;
+    fn star(&mut self, client: &Client) -> Result<()> {
+        client.call(&Star::one(self.star_key(), self.id.as_str()))?;
+        self.starred = Some(now());
         Ok(())
     }
 
-    fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("albumId", self.id))?;
+    fn unstar(&mut self, client: &Client) -> Result<()> {
+        client.call(&Unstar::from(Star::one(self.star_key(), self.id.as_str())))?;
+        self.starred = None;
         Ok(())
     }
 
-    fn set_rating(&self, client: &Client, rating: u8) -> Result<()> {
+    fn set_rating(&mut self, client: &Client, rating: u8) -> Result<()> {
         if rating > 5 {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        client.call(&SetRating {
+            id: self.id.to_string(),
+            rating,
+        })?;
+        self.user_rating = Some(rating).filter(|&r| r > 0);
         Ok(())
     }
 
@@ -94,33 +136,38 @@ impl Annotatable for Album {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("time", time.into())
-            .arg("submission", now_playing.into().map(|b| !b))
-            .build();
-        client.get("scrobble", args)?;
+        client.call(&Scrobble {
+            id: self.id.to_string(),
+            time: time.into().map(String::from),
+            submission: now_playing.into().map(|b| !b),
+        })?;
         Ok(())
     }
 }
 
 impl Annotatable for Song {
-    fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("id", self.id))?;
+    fn star(&mut self, client: &Client) -> Result<()> {
+        client.call(&Star::one("id", self.id.as_str()))?;
+        self.starred = Some(now());
         Ok(())
     }
 
-    fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("id", self.id))?;
+    fn unstar(&mut self, client: &Client) -> Result<()> {
+        client.call(&Unstar::from(Star::one("id", self.id.as_str())))?;
+        self.starred = None;
         Ok(())
     }
 
-    fn set_rating(&self, client: &Client, rating: u8) -> Result<()> {
+    fn set_rating(&mut self, client: &Client, rating: u8) -> Result<()> {
         if rating > 5 {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        client.call(&SetRating {
+            id: self.id.to_string(),
+            rating,
+        })?;
+        self.user_rating = Some(rating).filter(|&r| r > 0);
         Ok(())
     }
 
@@ -129,11 +176,81 @@ impl Annotatable for Song {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("time", time.into())
-            .arg("submission", now_playing.into().map(|b| !b))
-            .build();
-        client.get("scrobble", args)?;
+        let now_playing = now_playing.into();
+        let time = time.into();
+        client.call(&Scrobble {
+            id: self.id.to_string(),
+            time: time.map(String::from),
+            submission: now_playing.map(|b| !b),
+        })?;
+        let kind = match now_playing {
+            Some(true) => PlayKind::NowPlaying,
+            _ => PlayKind::Scrobble,
+        };
+        let played = time.and_then(|t| {
+            let played = parse_time(t);
+            if played.is_none() {
+                warn!(
+                    "Recording play of {} now, not at unreadable time {:?}",
+                    self.id, t
+                );
+            }
+            played
+        });
+        client.record_play(self, kind, played.unwrap_or_else(SystemTime::now));
         Ok(())
     }
 }
+
+/// Returns the current time as an ISO8601 timestamp, as the server sends
+/// when content is starred.
+fn now() -> String {
+    timestamp::format(SystemTime::now())
+}
+
+/// Parses the time a scrobble was played at, given either as milliseconds
+/// since the Unix epoch, as the API takes it, or as an ISO8601 timestamp.
+fn parse_time(time: &str) -> Option<SystemTime> {
+    match time.parse::<u64>() {
+        Ok(millis) => UNIX_EPOCH.checked_add(Duration::from_millis(millis)),
+        Err(_) => timestamp::parse_system(time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::test_util;
+
+    #[test]
+    fn parse_scrobble_time() {
+        let at = |secs| Some(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(parse_time("1496346505000"), at(1_496_346_505));
+        assert_eq!(parse_time("2017-06-01T19:48:25Z"), at(1_496_346_505));
+        assert_eq!(parse_time("2000-02-29T12:00:00+00:00"), at(951_825_600));
+        assert_eq!(parse_time("1970-01-01T00:00:00Z"), at(0));
+        assert_eq!(parse_time("2017-06-01T21:48:25+02:00"), at(1_496_346_505));
+        assert_eq!(parse_time("2017-13-01T19:48:25Z"), None);
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn scrobble_records_time() {
+        use crate::history::History;
+
+        let args = Query::with("id", "27").arg("time", "1496346505000").build();
+        let history = History::new();
+        let client = test_util::replay_client(&[("scrobble", args, serde_json::json!({}))])
+            .with_history(history.clone());
+        let song = test_util::song("27");
+        song.scrobble(&client, "1496346505000", None).unwrap();
+
+        let plays = history.plays();
+        assert_eq!(plays.len(), 1);
+        assert_eq!(
+            plays[0].time,
+            UNIX_EPOCH + Duration::from_secs(1_496_346_505)
+        );
+    }
+}
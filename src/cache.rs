@@ -0,0 +1,700 @@
+//! Caching of library metadata.
+//!
+//! Browsing the same artists and albums again and again asks the server for
+//! the same, rarely changing, metadata. A cache given to the client with
+//! [`Client::with_cache`] keeps the responses to such requests, such as
+//! `getArtist` and `getAlbum`, and answers them without contacting the
+//! server.
+//!
+//! Where responses are kept is up to the [`Cache`] backend. [`MemoryCache`]
+//! keeps them for as long as the program runs, and [`DiskCache`] keeps them in
+//! a directory across runs. Other backends, such as a database an application
+//! already uses, can be plugged in by implementing [`Cache`].
+//!
+//! The client drops the responses it cached for its user and server when it
+//! stars or rates something or starts a scan; those cached for other users
+//! are kept. Changes made by other clients are not seen until the cache is
+//! cleared, or the responses expire.
+//!
+//! Cover art can be kept in a cache of its own with
+//! [`Client::with_cover_cache`].
+//!
+//! Both backends grow without bound by default. [`Limits`] cap the number of
+//! responses kept, their total size and their age; once a cache is full, the
+//! least recently used responses are evicted.
+//!
+//! [`Client::with_cache`]: ../struct.Client.html#method.with_cache
+//! [`Client::with_cover_cache`]: ../struct.Client.html#method.with_cover_cache
+//! [`Limits`]: struct.Limits.html
+//! [`Cache`]: trait.Cache.html
+//! [`MemoryCache`]: struct.MemoryCache.html
+//! [`DiskCache`]: struct.DiskCache.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::time::Duration;
+//! use sunk::cache::{DiskCache, Limits};
+//! use sunk::{Artist, Client};
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let week = Duration::from_secs(7 * 24 * 60 * 60);
+//! let limits = Limits {
+//!     max_bytes: Some(64 << 20),
+//!     max_age: Some(week),
+//!     ..Default::default()
+//! };
+//! let cache = DiskCache::new("/var/cache/my-player")?.with_limits(limits);
+//! let client = Client::new(site, user, password)?.with_cache(cache);
+//!
+//! // Only the first request reaches the server.
+//! let artist = Artist::get(&client, 20)?;
+//! let artist = Artist::get(&client, 20)?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use crate::query::Query;
+use crate::Result;
+
+/// Numbers the temporary files responses are written to.
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The endpoints whose responses are cached.
+const CACHED: &[&str] = &[
+    "getMusicFolders",
+    "getIndexes",
+    "getMusicDirectory",
+    "getGenres",
+    "getArtists",
+    "getArtist",
+    "getAlbum",
+    "getSong",
+    "getArtistInfo",
+    "getArtistInfo2",
+    "getAlbumInfo",
+    "getAlbumInfo2",
+    "getLyrics",
+    "getLyricsBySongId",
+];
+
+/// The endpoints that change cached metadata, and drop the responses cached
+/// for the user.
+const INVALIDATING: &[&str] = &["star", "unstar", "setRating", "startScan"];
+
+/// A store of server responses.
+///
+/// Responses are stored by a key made from the request, as bytes. A backend
+/// that fails to read or write an entry should treat it as missing rather than
+/// fail; the response is then fetched from the server.
+pub trait Cache: Debug + Send + Sync {
+    /// Returns the response stored under `key`, if any.
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    /// Stores a response under `key`, replacing any stored before.
+    fn insert(&self, key: &str, value: Vec<u8>);
+
+    /// Removes every stored response.
+    fn clear(&self);
+
+    /// Removes every stored response whose key starts with `prefix`.
+    ///
+    /// The default removes every stored response, which is never wrong but
+    /// may throw away more than it needs to.
+    fn remove_prefix(&self, prefix: &str) {
+        let _ = prefix;
+        self.clear();
+    }
+}
+
+/// How much a cache may hold.
+///
+/// Limits left as `None` are not enforced; the default is an unbounded cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The most responses kept.
+    pub max_entries: Option<usize>,
+    /// The most bytes kept, adding up the size of every response. A response
+    /// larger than this is not kept at all.
+    pub max_bytes: Option<u64>,
+    /// How long a response is kept after it is stored.
+    pub max_age: Option<Duration>,
+}
+
+/// A cache kept in memory.
+///
+/// The cache is a handle: clones share the same entries, so one can be given
+/// to the client while another is kept to clear it.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCache {
+    inner: Arc<Mutex<Memory>>,
+}
+
+#[derive(Debug, Default)]
+struct Memory {
+    index: Lru,
+    values: HashMap<String, Vec<u8>>,
+}
+
+impl Memory {
+    fn remove_all(&mut self, keys: Vec<String>) {
+        for key in keys {
+            self.values.remove(&key);
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Creates an empty cache.
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+
+    /// Limits how much the cache holds, evicting responses if it already
+    /// holds more.
+    pub fn with_limits(self, limits: Limits) -> MemoryCache {
+        {
+            let mut memory = self.lock();
+            memory.index.limits = limits;
+            let evicted = memory.index.trim(SystemTime::now());
+            memory.remove_all(evicted);
+        }
+        self
+    }
+
+    /// Returns the number of responses in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().values.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().values.is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Memory> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut memory = self.lock();
+        if memory.index.touch(key, SystemTime::now()) {
+            memory.values.get(key).cloned()
+        } else {
+            memory.values.remove(key);
+            None
+        }
+    }
+
+    fn insert(&self, key: &str, value: Vec<u8>) {
+        let mut memory = self.lock();
+        let evicted = memory
+            .index
+            .insert(key, value.len() as u64, SystemTime::now());
+        memory.values.insert(key.to_string(), value);
+        memory.remove_all(evicted);
+    }
+
+    fn clear(&self) {
+        let mut memory = self.lock();
+        memory.index.clear();
+        memory.values.clear();
+    }
+
+    fn remove_prefix(&self, prefix: &str) {
+        let mut memory = self.lock();
+        let keys = memory
+            .values
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &keys {
+            memory.index.remove(key);
+        }
+        memory.remove_all(keys);
+    }
+}
+
+/// A cache kept in a directory, one file per response.
+///
+/// Files are named after a hash of their key, and start with the key on a
+/// line of its own, followed by the response. The directory may be shared by
+/// several caches, in this process or others, but not with anything else, as
+/// clearing the cache empties it.
+///
+/// When limits are set, responses are evicted by when they were last used in
+/// this process; responses from previous runs, or stored by other caches,
+/// count as used when they were stored.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    index: Arc<Mutex<Lru>>,
+}
+
+impl DiskCache {
+    /// Opens a cache in `dir`, creating the directory if it doesn't exist.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<DiskCache> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let meta = entry.metadata()?;
+            if meta.is_file() && !name.ends_with(".tmp") {
+                files.push((meta.modified()?, name, meta.len()));
+            }
+        }
+        files.sort();
+
+        let mut index = Lru::default();
+        for (stored, name, size) in files {
+            index.insert(&name, size, stored);
+        }
+
+        Ok(DiskCache {
+            dir,
+            index: Arc::new(Mutex::new(index)),
+        })
+    }
+
+    /// Limits how much the cache holds, evicting responses if it already
+    /// holds more.
+    pub fn with_limits(self, limits: Limits) -> DiskCache {
+        let evicted = {
+            let mut index = self.lock();
+            index.limits = limits;
+            index.trim(SystemTime::now())
+        };
+        self.remove_all(evicted);
+        self
+    }
+
+    /// Returns the directory the cache is kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn name(key: &str) -> String {
+        format!("{:x}", md5::compute(key.as_bytes()))
+    }
+
+    fn remove_all(&self, names: Vec<String>) {
+        for name in names {
+            let path = self.dir.join(name);
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != ErrorKind::NotFound {
+                    warn!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let name = DiskCache::name(key);
+        let now = SystemTime::now();
+        if !self.lock().contains(&name) {
+            // Stored by another cache on the same directory since this one was
+            // opened.
+            let meta = fs::metadata(self.dir.join(&name)).ok()?;
+            let stored = meta.modified().unwrap_or(now);
+            let evicted = self.lock().insert(&name, meta.len(), stored);
+            self.remove_all(evicted);
+        }
+        if !self.lock().touch(&name, now) {
+            self.remove_all(vec![name]);
+            return None;
+        }
+
+        match fs::read(self.dir.join(&name)) {
+            Ok(mut bytes) => {
+                let header = bytes.iter().position(|&b| b == b'\n')?;
+                if &bytes[..header] != key.as_bytes() {
+                    // Another key with the same hash, or a file written
+                    // before keys were stored.
+                    return None;
+                }
+                Some(bytes.split_off(header + 1))
+            }
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    warn!("Failed to read cached response: {}", e);
+                }
+                self.lock().remove(&name);
+                None
+            }
+        }
+    }
+
+    fn insert(&self, key: &str, value: Vec<u8>) {
+        // Write to a temporary file first so that a reader never sees half a
+        // response.
+        let name = DiskCache::name(key);
+        let path = self.dir.join(&name);
+        // Named uniquely, so that caches writing the same key at once don't
+        // write to the same file.
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp = self
+            .dir
+            .join(format!("{}.{}-{}.tmp", name, process::id(), n));
+        let mut contents = Vec::with_capacity(key.len() + 1 + value.len());
+        contents.extend_from_slice(key.as_bytes());
+        contents.push(b'\n');
+        contents.extend(value);
+        let size = contents.len() as u64;
+        if let Err(e) = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, &path)) {
+            let _ = fs::remove_file(&tmp);
+            warn!("Failed to cache response in {}: {}", self.dir.display(), e);
+            return;
+        }
+
+        let evicted = self.lock().insert(&name, size, SystemTime::now());
+        self.remove_all(evicted);
+    }
+
+    fn clear(&self) {
+        self.lock().clear();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to clear {}: {}", self.dir.display(), e);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                warn!("Failed to remove {}: {}", entry.path().display(), e);
+            }
+        }
+    }
+
+    fn remove_prefix(&self, prefix: &str) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to clear {}: {}", self.dir.display(), e);
+                return;
+            }
+        };
+
+        let mut names = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".tmp") {
+                continue;
+            }
+            let mut key = Vec::new();
+            let read = fs::File::open(entry.path())
+                .and_then(|file| BufReader::new(file).read_until(b'\n', &mut key));
+            if read.is_ok() && key.starts_with(prefix.as_bytes()) {
+                names.push(name);
+            }
+        }
+
+        {
+            let mut index = self.lock();
+            for name in &names {
+                index.remove(name);
+            }
+        }
+        self.remove_all(names);
+    }
+}
+
+/// Tracks the size, age and last use of the entries of a cache, and picks
+/// which to evict to stay within its limits.
+#[derive(Debug, Default)]
+struct Lru {
+    limits: Limits,
+    entries: HashMap<String, Entry>,
+    /// The keys of the entries by when they were last used, least recently
+    /// used first.
+    by_use: BTreeMap<u64, String>,
+    bytes: u64,
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    size: u64,
+    stored: SystemTime,
+    used: u64,
+}
+
+impl Lru {
+    /// Marks an entry as used. Returns `false` if there is no such entry, or
+    /// if it has expired, in which case it is forgotten.
+    fn touch(&mut self, key: &str, now: SystemTime) -> bool {
+        let expired = match self.entries.get(key) {
+            Some(entry) => self.is_expired(entry, now),
+            None => return false,
+        };
+        if expired {
+            self.remove(key);
+            return false;
+        }
+
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            let key = self
+                .by_use
+                .remove(&entry.used)
+                .unwrap_or_else(|| key.to_string());
+            entry.used = self.clock;
+            self.by_use.insert(self.clock, key);
+        }
+        true
+    }
+
+    /// Adds an entry, replacing any under the same key, and returns the keys
+    /// evicted to make room for it. An entry larger than the cache may hold
+    /// is evicted straight away.
+    fn insert(&mut self, key: &str, size: u64, stored: SystemTime) -> Vec<String> {
+        self.remove(key);
+        if self.limits.max_bytes.is_some_and(|max| size > max) {
+            return vec![key.to_string()];
+        }
+        self.clock += 1;
+        self.bytes += size;
+        let entry = Entry {
+            size,
+            stored,
+            used: self.clock,
+        };
+        self.entries.insert(key.to_string(), entry);
+        self.by_use.insert(self.clock, key.to_string());
+        self.trim(SystemTime::now())
+    }
+
+    /// Returns whether there is an entry under `key`.
+    fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Forgets an entry.
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.by_use.remove(&entry.used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    /// Forgets every entry.
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.by_use.clear();
+        self.bytes = 0;
+    }
+
+    /// Evicts expired entries, then the least recently used ones until the
+    /// cache is within its limits. Returns the keys evicted.
+    fn trim(&mut self, now: SystemTime) -> Vec<String> {
+        let mut evicted = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry, now))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in &evicted {
+            self.remove(key);
+        }
+
+        while self.is_full() {
+            match self.by_use.pop_first() {
+                Some((_, key)) => {
+                    self.remove(&key);
+                    evicted.push(key);
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    fn is_expired(&self, entry: &Entry, now: SystemTime) -> bool {
+        match (self.limits.max_age, now.duration_since(entry.stored)) {
+            (Some(max_age), Ok(age)) => age > max_age,
+            _ => false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.limits
+            .max_entries
+            .is_some_and(|max| self.entries.len() > max)
+            || self.limits.max_bytes.is_some_and(|max| self.bytes > max)
+    }
+}
+
+/// Returns the key a request is cached under, or `None` if its response
+/// isn't cached.
+pub(crate) fn key(query: &str, args: &Query) -> Option<String> {
+    if CACHED.contains(&query) {
+        Some(format!("{}?{}", query, args))
+    } else {
+        None
+    }
+}
+
+/// Returns whether a request changes cached metadata.
+pub(crate) fn invalidates(query: &str) -> bool {
+    INVALIDATING.contains(&query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn round_trip<C: Cache>(cache: &C) {
+        assert_eq!(cache.get("getArtist?id=1"), None);
+        cache.insert("getArtist?id=1", b"Misteur Valaire".to_vec());
+        cache.insert("getArtist?id=2", b"Pomplamoose".to_vec());
+        assert_eq!(cache.get("getArtist?id=1").unwrap(), b"Misteur Valaire");
+
+        cache.insert(
+            "alice@http://host/getArtist?id=1",
+            b"Misteur Valaire".to_vec(),
+        );
+        cache.insert(
+            "bob@http://host/getArtist?id=1",
+            b"Misteur Valaire".to_vec(),
+        );
+        cache.remove_prefix("alice@http://host/");
+        assert_eq!(cache.get("alice@http://host/getArtist?id=1"), None);
+        assert!(cache.get("bob@http://host/getArtist?id=1").is_some());
+        assert!(cache.get("getArtist?id=1").is_some());
+
+        cache.clear();
+        assert_eq!(cache.get("getArtist?id=2"), None);
+    }
+
+    #[test]
+    fn memory_cache() {
+        let cache = MemoryCache::new();
+        round_trip(&cache.clone());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn disk_cache() {
+        let dir = std::env::temp_dir().join(format!("sunk-cache-{}", std::process::id()));
+        round_trip(&DiskCache::new(&dir).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shared_disk_cache() {
+        let dir = std::env::temp_dir().join(format!("sunk-cache-shared-{}", std::process::id()));
+        let first = DiskCache::new(&dir).unwrap();
+        let second = DiskCache::new(&dir).unwrap();
+        first.insert("getArtist?id=1", b"Misteur Valaire".to_vec());
+        assert_eq!(second.get("getArtist?id=1").unwrap(), b"Misteur Valaire");
+        assert_eq!(first.get("getArtist?id=1").unwrap(), b"Misteur Valaire");
+
+        thread::scope(|s| {
+            for cache in [&first, &second] {
+                s.spawn(move || {
+                    for _ in 0..20 {
+                        cache.insert("getArtist?id=2", b"Pomplamoose".to_vec());
+                    }
+                });
+            }
+        });
+        assert_eq!(first.get("getArtist?id=2").unwrap(), b"Pomplamoose");
+        let files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, 2);
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let limits = Limits {
+            max_entries: Some(2),
+            max_bytes: Some(10),
+            ..Default::default()
+        };
+        let cache = MemoryCache::new().with_limits(limits);
+        cache.insert("a", vec![0; 4]);
+        cache.insert("b", vec![0; 4]);
+        cache.get("a");
+        cache.insert("c", vec![0; 4]);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+
+        cache.insert("d", vec![0; 6]);
+        assert!(cache.get("c").is_none());
+        assert!(cache.get("d").is_some());
+
+        cache.insert("e", vec![0; 11]);
+        assert!(cache.get("e").is_none());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn expire_old_entries() {
+        let mut lru = Lru::default();
+        let now = SystemTime::now();
+        lru.insert("old", 1, now - Duration::from_secs(120));
+        lru.insert("new", 1, now);
+        lru.limits.max_age = Some(Duration::from_secs(60));
+
+        assert!(!lru.touch("old", now));
+        assert!(lru.touch("new", now));
+        assert_eq!(lru.entries.len(), 1);
+        assert_eq!(lru.by_use.len(), 1);
+    }
+
+    #[test]
+    fn disk_cache_limits() {
+        let dir = std::env::temp_dir().join(format!("sunk-cache-limits-{}", std::process::id()));
+        let cache = DiskCache::new(&dir).unwrap();
+        cache.insert("a", vec![0; 4]);
+        cache.insert("b", vec![0; 4]);
+
+        let limits = Limits {
+            max_entries: Some(1),
+            ..Default::default()
+        };
+        let reopened = DiskCache::new(&dir).unwrap().with_limits(limits);
+        let files = fs::read_dir(&dir).unwrap().count();
+        let kept = ["a", "b"]
+            .iter()
+            .filter(|k| reopened.get(k).is_some())
+            .count();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((files, kept), (1, 1));
+    }
+
+    #[test]
+    fn cached_endpoints() {
+        let args = Query::with("id", 1);
+        assert_eq!(key("getArtist", &args).unwrap(), "getArtist?id=1");
+        assert_eq!(key("getRandomSongs", &args), None);
+    }
+}
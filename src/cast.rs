@@ -0,0 +1,160 @@
+//! URLs to hand to cast devices.
+//!
+//! Chromecast and DLNA renderers fetch media themselves, from a URL they are
+//! given, and can't log in to a Subsonic server. A cast URL lets them in
+//! without the user's password, in one of two ways:
+//!
+//! - A *share* URL comes from a share created for the media, which expires
+//!   when asked. What it serves is up to the server; most serve a page with
+//!   a player, which suits renderers that open web pages rather than play
+//!   media. [`song_url`] and [`album_url`] return these.
+//! - A *signed* URL is a stream URL carrying a salted token in place of the
+//!   password, which servers accept from anyone holding it. It serves the
+//!   media itself, so any renderer can play it, but **it never expires, and
+//!   works for the whole API**: whoever holds it can act as the user until
+//!   their password changes. Only [`signed_url`] returns these, and only
+//!   devices trusted with the account should be given one.
+//!
+//! [`song_url`]: fn.song_url.html
+//! [`album_url`]: fn.album_url.html
+//! [`signed_url`]: fn.signed_url.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::time::Duration;
+//! use sunk::cast;
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let song = Song::get(&client, 27)?;
+//!
+//! let cast = cast::song_url(&client, &song, Duration::from_secs(60 * 60))?;
+//! println!("Casting {}", cast.url);
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+use url::Url;
+
+use crate::{Album, Client, Error, Result, Share, Song, Streamable};
+
+/// How a cast URL lets a device in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastAccess {
+    /// The URL carries a token standing in for the user's password, as
+    /// returned by [`signed_url`].
+    ///
+    /// [`signed_url`]: fn.signed_url.html
+    Signed,
+    /// The URL is that of a share.
+    Share,
+}
+
+/// A URL a cast device can fetch without logging in.
+#[derive(Debug, Clone)]
+pub struct CastUrl {
+    /// The URL to hand to the device.
+    pub url: Url,
+    /// How the URL lets the device in.
+    pub access: CastAccess,
+    /// The share the URL belongs to, if it is a share URL. Delete it once
+    /// casting is done to revoke access before it expires.
+    pub share: Option<Share>,
+}
+
+/// Returns a URL to `song` that a cast device can fetch, by sharing it for
+/// `expires_in`.
+///
+/// For a URL streaming the song itself, see [`signed_url`] and the warning
+/// there.
+///
+/// [`signed_url`]: fn.signed_url.html
+///
+/// # Errors
+///
+/// Errors if the song can't be shared, such as when the user isn't allowed
+/// to share.
+pub fn song_url(client: &Client, song: &Song, expires_in: Duration) -> Result<CastUrl> {
+    share_url(client, &[song.id.as_str()], expires_in)
+}
+
+/// Returns a URL to `album` that a cast device can fetch, by sharing it for
+/// `expires_in`.
+///
+/// # Errors
+///
+/// Errors if the album can't be shared, such as when the user isn't allowed
+/// to share.
+pub fn album_url(client: &Client, album: &Album, expires_in: Duration) -> Result<CastUrl> {
+    share_url(client, &[album.id.as_str()], expires_in)
+}
+
+/// Returns a signed URL streaming `song`, with the maximum bit rate set on
+/// it applied.
+///
+/// # Security
+///
+/// **The URL hands over the user's account.** The token it carries never
+/// expires and is accepted by every endpoint of the API, not only for this
+/// song, until the user's password changes. Only give it to devices trusted
+/// with the account; prefer [`song_url`] for anything else.
+///
+/// [`song_url`]: fn.song_url.html
+///
+/// # Errors
+///
+/// Errors if the client authenticates by sending its password, as the URL
+/// would then hold the password itself.
+pub fn signed_url(client: &Client, song: &Song) -> Result<CastUrl> {
+    if client.target_ver < "1.13.0".into() {
+        return Err(Error::Other(
+            "client sends its password rather than a token",
+        ));
+    }
+    warn!("Handing out a signed URL, which grants access to the whole account");
+    Ok(CastUrl {
+        url: song.stream_url(client)?,
+        access: CastAccess::Signed,
+        share: None,
+    })
+}
+
+/// Shares `ids` for `expires_in`, and returns the share's URL.
+fn share_url(client: &Client, ids: &[&str], expires_in: Duration) -> Result<CastUrl> {
+    let share = Share::create(client, ids, None, SystemTime::now() + expires_in)?;
+    Ok(CastUrl {
+        url: share.url.parse()?,
+        access: CastAccess::Share,
+        share: Some(share),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::Version;
+
+    #[test]
+    fn signs_with_tokens() {
+        let client = Client::new("http://localhost", "user", "hunter2").unwrap();
+        let cast = signed_url(&client, &test_util::song("27")).unwrap();
+        assert_eq!(cast.access, CastAccess::Signed);
+        let url = cast.url.as_str();
+        assert!(url.contains("stream") && url.contains("t=") && url.contains("s="));
+        assert!(!url.contains("hunter2"));
+
+        let old = client.with_target(Version::from("1.12.0"));
+        assert!(signed_url(&old, &test_util::song("27")).is_err());
+    }
+}
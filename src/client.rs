@@ -1,15 +1,76 @@
 use reqwest::blocking::Client as ReqwestClient;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_TYPE, RANGE};
 use reqwest::Url;
+use serde::de::DeserializeOwned;
 use serde_json;
+use std::borrow::Cow;
+#[cfg(feature = "cache")]
+use std::collections::HashSet;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+#[cfg(any(feature = "cache", feature = "scrobble"))]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-use crate::media::NowPlaying;
-use crate::query::Query;
-use crate::response::Response;
+#[cfg(feature = "cache")]
+use crate::cache::{self, Cache};
+use crate::coalesce::{self, InFlight};
+use crate::de;
+use crate::endpoint::{
+    GetArtists, GetGenres, GetLicense, GetLyrics, GetMusicFolders, GetNowPlaying,
+    GetOpenSubsonicExtensions, GetScanStatus, GetStarred, GetStarred2, Ping, Search3, StartScan,
+};
+use crate::history::{History, Play, PlayKind};
+#[cfg(feature = "cache")]
+use crate::media::HasCoverArt;
+use crate::media::{NowPlaying, StreamReader};
+#[cfg(feature = "shared-strings")]
+use crate::name;
+use crate::progress::{self, Progress, ProgressObserver};
+use crate::query::{encode, Query};
+use crate::response::{RawResponse, Response};
+#[cfg(feature = "scrobble")]
+use crate::scrobbler::{Scrobbler, Scrobblers};
+#[cfg(feature = "unicode")]
+use crate::search::Normalization;
 use crate::search::{SearchPage, SearchResult};
-use crate::{Album, Artist, Error, Genre, Hls, Lyrics, MusicFolder, Result, Song, Version};
+use crate::storage::Storage;
+#[cfg(feature = "chrono")]
+use crate::timestamp;
+use crate::vcr::Cassette;
+#[cfg(feature = "shared-strings")]
+use crate::Interner;
+use crate::Version;
+use crate::{ApiError, Bitrate, Error, Genre, GenreMap, Hls, Lyrics, MusicFolder, Result, Song};
+use crate::{Quirks, ServerKind};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
 
 const SALT_SIZE: usize = 36; // Minimum 6 characters.
 
+/// The longest a request is held back for a server that asked to be retried
+/// later.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// The content type of arguments sent in a `POST` body.
+pub(crate) const FORM: &str = "application/x-www-form-urlencoded";
+
+/// Endpoints that take a `musicFolderId` to browse a single music folder.
+const FOLDER_SCOPED: &[&str] = &[
+    "getIndexes",
+    "getArtists",
+    "getAlbumList",
+    "getAlbumList2",
+    "getRandomSongs",
+    "getSongsByGenre",
+    "getStarred",
+    "getStarred2",
+    "search2",
+    "search3",
+];
+
 /// A client to make requests to a Subsonic instance.
 ///
 /// The `Client` holds an internal connection pool and stores authentication
@@ -55,6 +116,74 @@ pub struct Client {
     /// Version that the `Client` is targeting; currently only has an effect on
     /// the authentication method.
     pub target_ver: Version,
+    genre_map: Option<GenreMap>,
+    lenient_lists: Option<bool>,
+    json_capture: Option<usize>,
+    post_threshold: Option<usize>,
+    retries: u32,
+    in_flight: Option<InFlight<serde_json::Value>>,
+    covers_in_flight: Option<InFlight<RawResponse>>,
+    cassette: Option<Cassette>,
+    #[cfg(feature = "unicode")]
+    query_normalization: Option<Normalization>,
+    max_bitrate: Option<Bitrate>,
+    music_folder: Option<usize>,
+    quirks: Quirks,
+    #[cfg(feature = "shared-strings")]
+    interner: Option<Arc<Mutex<Interner>>>,
+    extensions: Mutex<Option<Vec<Extension>>>,
+    history: Option<History>,
+    #[cfg(feature = "scrobble")]
+    scrobblers: Scrobblers,
+    #[cfg(feature = "cache")]
+    cache: Option<Arc<dyn Cache>>,
+    #[cfg(feature = "cache")]
+    cover_cache: Option<Arc<dyn Cache>>,
+}
+
+/// Returns how long to wait before sending a request that failed with `err`
+/// again, for the `attempt`th time, or `None` if it shouldn't be.
+fn retry_wait(err: &Error, attempt: u32) -> Option<Duration> {
+    match *err.inner() {
+        Error::Unavailable { retry_after, .. } => match retry_after {
+            Some(wait) if wait > MAX_RETRY_WAIT => None,
+            Some(wait) => Some(wait),
+            None => Some(Duration::from_secs(1 << attempt.min(5)).min(MAX_RETRY_WAIT)),
+        },
+        _ => None,
+    }
+}
+
+/// How long [`Client::scan_library_and_wait`] waits for a scan to be seen
+/// running.
+const SCAN_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns whether an error means the server doesn't organise the library by
+/// ID3 tags: it doesn't know the endpoint, answering with an error asking it
+/// to be upgraded or a missing page, or doesn't know the ID.
+///
+/// Generic errors are not taken as such, as servers also send them when a
+/// request fails for other reasons.
+fn lacks_id3(e: &Error) -> bool {
+    match *e.inner() {
+        Error::Api(ref api) => matches!(*api, ApiError::ServerMustUpgrade | ApiError::NotFound),
+        Error::Connection(status) => {
+            status == reqwest::StatusCode::NOT_FOUND
+                || status == reqwest::StatusCode::NOT_IMPLEMENTED
+        }
+        _ => false,
+    }
+}
+
+/// Appends encoded arguments `args`, if any, to the URL `base` of an
+/// endpoint, which already carries authentication.
+fn join_url(base: String, args: &str) -> Result<Url> {
+    let url = if args.is_empty() {
+        base
+    } else {
+        [base.as_str(), args].join("&")
+    };
+    url.parse().map_err(Error::UrlParseError)
 }
 
 #[derive(Debug)]
@@ -87,9 +216,18 @@ impl SubsonicAuth {
             let pre_t = self.password.to_string() + &salt;
             let token = format!("{:x}", md5::compute(pre_t.as_bytes()));
 
-            format!("u={u}&t={t}&s={s}", u = self.user, t = token, s = salt)
+            format!(
+                "u={u}&t={t}&s={s}",
+                u = encode(&self.user),
+                t = token,
+                s = salt
+            )
         } else {
-            format!("u={u}&p={p}", u = self.user, p = self.password)
+            format!(
+                "u={u}&p={p}",
+                u = encode(&self.user),
+                p = encode(&self.password)
+            )
         };
 
         let format = "json";
@@ -121,6 +259,29 @@ impl Client {
             reqclient,
             ver,
             target_ver,
+            genre_map: None,
+            lenient_lists: None,
+            json_capture: None,
+            post_threshold: None,
+            retries: 0,
+            in_flight: None,
+            covers_in_flight: None,
+            cassette: None,
+            #[cfg(feature = "unicode")]
+            query_normalization: None,
+            max_bitrate: None,
+            music_folder: None,
+            quirks: Quirks::default(),
+            #[cfg(feature = "shared-strings")]
+            interner: None,
+            extensions: Mutex::new(None),
+            history: None,
+            #[cfg(feature = "scrobble")]
+            scrobblers: Scrobblers::default(),
+            #[cfg(feature = "cache")]
+            cache: None,
+            #[cfg(feature = "cache")]
+            cover_cache: None,
         })
     }
 
@@ -142,23 +303,575 @@ impl Client {
         cli
     }
 
+    /// Merges spelling variants of genres using the provided map.
+    ///
+    /// Without a map, genres are returned exactly as the server reports them.
+    /// With one, [`genres`] merges variants such as "Hip Hop" and "Hip-Hop"
+    /// into a single genre, and [`Song::list_in_genre`] returns the songs of
+    /// every variant.
+    ///
+    /// [`genres`]: #method.genres
+    /// [`Song::list_in_genre`]: ./song/struct.Song.html#method.list_in_genre
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sunk::{Client, GenreMap};
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    ///
+    /// let client = Client::new(site, user, password)?.with_genre_map(GenreMap::default());
+    /// let genres = client.genres()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_genre_map(self, map: GenreMap) -> Client {
+        let mut cli = self;
+        cli.genre_map = Some(map);
+        cli
+    }
+
+    /// Returns the genre map set with [`with_genre_map`], if any.
+    ///
+    /// [`with_genre_map`]: #method.with_genre_map
+    pub fn genre_map(&self) -> Option<&GenreMap> {
+        self.genre_map.as_ref()
+    }
+
+    /// Records every song reported as playing or played through the client
+    /// in `history`.
+    ///
+    /// See the [`history`] module for more information.
+    ///
+    /// [`history`]: ./history/index.html
+    pub fn with_history(self, history: History) -> Client {
+        let mut cli = self;
+        cli.history = Some(history);
+        cli
+    }
+
+    /// Returns the history set with [`with_history`], if any.
+    ///
+    /// [`with_history`]: #method.with_history
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Submits every song reported as playing or played through the client
+    /// to `scrobbler` as well as the server. Can be called more than once to
+    /// submit to several services.
+    ///
+    /// See the [`scrobbler`] module for more information.
+    ///
+    /// [`scrobbler`]: ./scrobbler/index.html
+    #[cfg(feature = "scrobble")]
+    pub fn with_scrobbler<S: Scrobbler + 'static>(self, scrobbler: S) -> Client {
+        let mut cli = self;
+        cli.scrobblers.push(Arc::new(scrobbler));
+        cli
+    }
+
+    /// Returns the scrobblers added with [`with_scrobbler`].
+    ///
+    /// [`with_scrobbler`]: #method.with_scrobbler
+    #[cfg(feature = "scrobble")]
+    pub fn scrobblers(&self) -> &[Arc<dyn Scrobbler>] {
+        self.scrobblers.as_slice()
+    }
+
+    /// Answers requests for library metadata from `cache` where possible,
+    /// and stores the responses to such requests in it.
+    ///
+    /// See the [`cache`] module for more information.
+    ///
+    /// [`cache`]: ./cache/index.html
+    #[cfg(feature = "cache")]
+    pub fn with_cache<C: Cache + 'static>(self, cache: C) -> Client {
+        let mut cli = self;
+        cli.cache = Some(Arc::new(cache));
+        cli
+    }
+
+    /// Returns the cache set with [`with_cache`], if any.
+    ///
+    /// [`with_cache`]: #method.with_cache
+    #[cfg(feature = "cache")]
+    pub fn cache(&self) -> Option<&dyn Cache> {
+        self.cache.as_deref()
+    }
+
+    /// Keeps the cover art fetched with [`HasCoverArt::cover_art`] in `cache`, and
+    /// answers later requests for the same image from it.
+    ///
+    /// Images are far larger than other responses, so they are kept apart
+    /// from the cache set with [`with_cache`], typically with a limit on
+    /// their total size.
+    ///
+    /// [`HasCoverArt::cover_art`]: ./trait.HasCoverArt.html#method.cover_art
+    /// [`with_cache`]: #method.with_cache
+    #[cfg(feature = "cache")]
+    pub fn with_cover_cache<C: Cache + 'static>(self, cache: C) -> Client {
+        let mut cli = self;
+        cli.cover_cache = Some(Arc::new(cache));
+        cli
+    }
+
+    /// Returns the cache set with [`with_cover_cache`], if any.
+    ///
+    /// [`with_cover_cache`]: #method.with_cover_cache
+    #[cfg(feature = "cache")]
+    pub fn cover_cache(&self) -> Option<&dyn Cache> {
+        self.cover_cache.as_deref()
+    }
+
+    /// Fetches the cover art of each of `items` into the cover art cache, so
+    /// that it can be shown later without waiting on the server. Returns the
+    /// number of covers fetched.
+    ///
+    /// Covers already cached aren't fetched again, and items without a cover
+    /// are skipped. `observer` is told the number of items done and the bytes
+    /// fetched.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the client has no cover art cache, or a cover can't be
+    /// fetched.
+    #[cfg(feature = "cache")]
+    pub fn prefetch_covers<T, U, O>(&self, items: &[T], size: U, mut observer: O) -> Result<usize>
+    where
+        T: HasCoverArt,
+        U: Into<Option<usize>>,
+        O: ProgressObserver,
+    {
+        let size = size.into();
+        let mut progress = Progress {
+            total_items: Some(items.len() as u64),
+            ..Progress::default()
+        };
+        observer.started(&progress);
+        let res = match self.cover_cache {
+            Some(ref cache) => {
+                self.prefetch_each(cache.as_ref(), items, size, &mut progress, &mut observer)
+            }
+            None => Err(Error::Other("client has no cover art cache")),
+        };
+        progress::finish(&mut observer, &progress, res)
+    }
+
+    #[cfg(feature = "cache")]
+    fn prefetch_each<T, O>(
+        &self,
+        cache: &dyn Cache,
+        items: &[T],
+        size: Option<usize>,
+        progress: &mut Progress,
+        observer: &mut O,
+    ) -> Result<usize>
+    where
+        T: HasCoverArt,
+        O: ProgressObserver,
+    {
+        let mut seen = HashSet::new();
+        let mut fetched = 0;
+        for item in items {
+            if let Some(cover) = item.cover_id().filter(|&c| seen.insert(c)) {
+                let args = Query::with("id", cover).arg("size", size).build();
+                if cache.get(&self.cover_key(&args)).is_none() {
+                    let res = self.get_cover_art_cached(cache, args)?;
+                    progress.bytes += res.bytes.len() as u64;
+                    fetched += 1;
+                }
+            }
+            progress.items += 1;
+            observer.progress(progress);
+        }
+        Ok(fetched)
+    }
+
+    /// Records a play in the client's history, if it has one, and submits
+    /// it to the client's scrobblers.
+    ///
+    /// The scrobblers are called from a background thread, one play at a
+    /// time, so that a slow scrobbling service doesn't hold up the caller.
+    pub(crate) fn record_play(&self, song: &Song, kind: PlayKind, time: SystemTime) {
+        if let Some(ref history) = self.history {
+            history.record(Play::new(song, kind, time));
+        }
+        #[cfg(feature = "scrobble")]
+        self.scrobblers.submit(song, kind, time);
+    }
+
+    /// Skips malformed elements of lists instead of failing the whole request.
+    ///
+    /// By default, a single entry that can't be parsed (such as one song with
+    /// a missing field in a response of thousands) fails the request. In
+    /// lenient mode, such entries are dropped from the returned list, and
+    /// each one is logged as a warning along with its raw JSON.
+    ///
+    /// This overrides the default of the client's [`Quirks`].
+    ///
+    /// [`Quirks`]: ./struct.Quirks.html
+    pub fn with_lenient_lists(self, lenient: bool) -> Client {
+        let mut cli = self;
+        cli.lenient_lists = Some(lenient);
+        cli
+    }
+
+    /// Returns whether lenient list parsing is enabled.
+    pub fn lenient_lists(&self) -> bool {
+        self.lenient_lists
+            .unwrap_or_else(|| self.quirks.lenient_lists())
+    }
+
+    /// Sets the quirks of the server, overriding any that were detected.
+    ///
+    /// By default, the client assumes the server follows the Subsonic API
+    /// exactly.
+    pub fn with_quirks(self, quirks: Quirks) -> Client {
+        let mut cli = self;
+        cli.quirks = quirks;
+        cli
+    }
+
+    /// Detects the server implementation and adopts its quirks.
+    ///
+    /// The server is identified by the `type` it reports when pinged, which
+    /// OpenSubsonic servers include in every response. Servers that don't
+    /// report a type are assumed to be Subsonic.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sunk::Client;
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    ///
+    /// let client = Client::new(site, user, password)?.detect_quirks()?;
+    /// println!("Connected to {}", client.quirks().server());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn detect_quirks(self) -> Result<Client> {
+        let raw = self.get_raw("ping", Query::none())?;
+        let body = raw.text()?;
+        let response =
+            serde_json::from_str::<Response>(body).map_err(|e| de::error(&self, e, body))?;
+        let server = ServerKind::from_type(response.server_type().unwrap_or(""));
+
+        info!("Detected server: {}", server);
+        Ok(self.with_quirks(Quirks::for_server(server)))
+    }
+
+    /// Returns the quirks of the server.
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Shares the storage of equal names across everything the client
+    /// fetches.
+    ///
+    /// The names of all lists and search results are interned with the
+    /// provided [`Interner`], so that songs by the same artist or on the same
+    /// album share one copy of those names. The interner lives as long as the
+    /// client, and is only locked while a name is looked up, so requests made
+    /// from several threads parse at the same time. Its table is bounded as
+    /// described on [`Interner`].
+    ///
+    /// [`Interner`]: ./struct.Interner.html
+    #[cfg(feature = "shared-strings")]
+    pub fn with_interner(self, interner: Interner) -> Client {
+        let mut cli = self;
+        cli.interner = Some(Arc::new(Mutex::new(interner)));
+        cli
+    }
+
+    /// Runs `f` with the client's interner in scope, if it has one.
+    #[cfg(feature = "shared-strings")]
+    pub(crate) fn intern<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        match self.interner {
+            Some(ref interner) => name::scope_shared(interner, f),
+            None => f(),
+        }
+    }
+
+    /// Runs `f` with the client's interner in scope, if it has one.
+    #[cfg(not(feature = "shared-strings"))]
+    pub(crate) fn intern<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        f()
+    }
+
+    /// Includes the offending JSON in errors when a response can't be parsed.
+    ///
+    /// Servers differ in how they format their responses, so a parse error is
+    /// much easier to track down with the JSON that caused it. When enabled,
+    /// such failures are returned as [`Error::Deserialize`], with the JSON
+    /// truncated to at most `max_len` bytes.
+    ///
+    /// [`Error::Deserialize`]: ./enum.Error.html#variant.Deserialize
+    pub fn with_json_capture(self, max_len: usize) -> Client {
+        let mut cli = self;
+        cli.json_capture = Some(max_len);
+        cli
+    }
+
+    /// Returns the maximum length of JSON captured in errors, if capturing is
+    /// enabled.
+    pub fn json_capture(&self) -> Option<usize> {
+        self.json_capture
+    }
+
+    /// Sends the arguments of requests in the body of a `POST` request
+    /// instead of the URL, once they take more than `max_len` bytes.
+    ///
+    /// Some proxies reject long URLs, such as those of a playlist created from
+    /// thousands of songs. Servers accept the arguments of any request as an
+    /// `application/x-www-form-urlencoded` body. URLs built for streaming and
+    /// downloading are not affected.
+    pub fn with_post_threshold(self, max_len: usize) -> Client {
+        let mut cli = self;
+        cli.post_threshold = Some(max_len);
+        cli
+    }
+
+    /// Returns the length of arguments above which they are sent in a `POST`
+    /// body, if set.
+    pub fn post_threshold(&self) -> Option<usize> {
+        self.post_threshold
+    }
+
+    /// Sends a request again, up to `retries` times, when the server answers
+    /// that it is overloaded or restarting with `429 Too Many Requests` or
+    /// `503 Service Unavailable`. Off by default.
+    ///
+    /// The client waits as long as the server asks with its `Retry-After`
+    /// header, or one second and then twice as long each time if it doesn't
+    /// say. A server asking for more than a minute isn't retried; see
+    /// [`Error::retry_after`] to wait longer.
+    ///
+    /// [`Error::retry_after`]: ./enum.Error.html#method.retry_after
+    pub fn with_retries(self, retries: u32) -> Client {
+        let mut cli = self;
+        cli.retries = retries;
+        cli
+    }
+
+    /// Returns how many times a request is sent again when the server is
+    /// unavailable.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Shares the response of a request among identical requests sent while
+    /// it is in flight. Off by default.
+    ///
+    /// Lists that load lazily may ask for the same artist or cover art
+    /// several times at once; with coalescing on, only the first request is
+    /// sent, and the rest are answered with its response. Only requests that
+    /// read from the server are coalesced, and not those for random songs or
+    /// albums. A request that fails isn't shared; one of those waiting on it
+    /// sends it again, and the rest wait on that one.
+    pub fn with_coalescing(self, coalesce: bool) -> Client {
+        let mut cli = self;
+        cli.in_flight = if coalesce {
+            Some(InFlight::new())
+        } else {
+            None
+        };
+        cli.covers_in_flight = if coalesce {
+            Some(InFlight::new())
+        } else {
+            None
+        };
+        cli
+    }
+
+    /// Returns whether identical requests in flight are coalesced.
+    pub fn coalescing(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    /// Records the client's requests to the server to a fixture file, or
+    /// answers them from one, as `cassette` is set to. See the [`vcr`]
+    /// module.
+    ///
+    /// [`vcr`]: ./vcr/index.html
+    pub fn with_cassette(self, cassette: Cassette) -> Client {
+        let mut cli = self;
+        cli.cassette = Some(cassette);
+        cli
+    }
+
+    /// Returns the cassette requests are recorded to or replayed from, if
+    /// any.
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_ref()
+    }
+
+    /// Normalizes search terms before they are sent, such as to find
+    /// "Björk" whether or not it was typed or tagged with its accent.
+    ///
+    /// [`search`] and [`search_for_each`] normalize their queries; other
+    /// arguments are sent as they are.
+    ///
+    /// [`search`]: #method.search
+    /// [`search_for_each`]: #method.search_for_each
+    #[cfg(feature = "unicode")]
+    pub fn with_query_normalization(self, normalization: Normalization) -> Client {
+        let mut cli = self;
+        cli.query_normalization = Some(normalization);
+        cli
+    }
+
+    /// Returns how search terms are normalized, if they are.
+    #[cfg(feature = "unicode")]
+    pub fn query_normalization(&self) -> Option<Normalization> {
+        self.query_normalization
+    }
+
+    /// Streams media at `bitrate` at most, unless the media sets its own
+    /// limit with [`Streamable::set_max_bit_rate`].
+    ///
+    /// [`Streamable::set_max_bit_rate`]: ./trait.Streamable.html#tymethod.set_max_bit_rate
+    pub fn with_max_bitrate(self, bitrate: Bitrate) -> Client {
+        let mut cli = self;
+        cli.max_bitrate = Some(bitrate);
+        cli
+    }
+
+    /// Returns the bit rate set with [`with_max_bitrate`], if any.
+    ///
+    /// [`with_max_bitrate`]: #method.with_max_bitrate
+    pub fn max_bitrate(&self) -> Option<Bitrate> {
+        self.max_bitrate
+    }
+
+    /// Browses only the music folder `id` in methods that take a music
+    /// folder, such as [`Client::starred`], unless they are given another.
+    ///
+    /// [`Client::starred`]: #method.starred
+    pub fn with_music_folder(self, id: usize) -> Client {
+        let mut cli = self;
+        cli.music_folder = Some(id);
+        cli
+    }
+
+    /// Returns the music folder set with [`with_music_folder`], if any.
+    ///
+    /// [`with_music_folder`]: #method.with_music_folder
+    pub fn music_folder(&self) -> Option<usize> {
+        self.music_folder
+    }
+
+    /// Replaces the HTTP client requests are sent with, such as with one
+    /// configured with a timeout.
+    pub(crate) fn with_reqwest(self, reqclient: ReqwestClient) -> Client {
+        let mut cli = self;
+        cli.reqclient = reqclient;
+        cli
+    }
+
     /// Internal helper function to construct a URL when the actual fetching is
     /// not required.
     #[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
-    pub(crate) fn build_url(&self, query: &str, args: Query) -> Result<String> {
+    pub(crate) fn build_url(&self, query: &str, args: Query) -> Result<Url> {
+        let args = self.with_defaults(query, args).to_string();
+        join_url(self.base_url(query)?, &args)
+    }
+
+    /// Constructs the URL of an endpoint with authentication, but without the
+    /// arguments of the request.
+    fn base_url(&self, query: &str) -> Result<String> {
         let scheme = self.url.scheme();
         let addr = self.url.host_str().ok_or_else(|| Error::Address)?;
+        if !self.quirks.supports(query) {
+            return Err(Error::Other("endpoint not supported by server"));
+        }
 
-        let mut url = [scheme, "://", addr, "/rest/"].concat();
+        // Servers behind a reverse proxy may live under a path.
+        let port = self
+            .url
+            .port()
+            .map(|p| format!(":{}", p))
+            .unwrap_or_default();
+        let path = self.url.path().trim_end_matches('/');
+        let mut url = [scheme, "://", addr, &port, path, "/rest/"].concat();
         url.push_str(query);
         url.push_str("?");
         url.push_str(&self.auth.to_url(self.target_ver));
-        url.push_str("&");
-        url.push_str(&args.to_string());
 
         Ok(url)
     }
 
+    /// Adds the client's default bit rate and music folder to the arguments
+    /// of a request that takes them but doesn't set them.
+    fn with_defaults(&self, query: &str, mut args: Query) -> Query {
+        if let Some(bitrate) = self.max_bitrate {
+            if query == "stream" && !args.contains("maxBitRate") {
+                args.arg("maxBitRate", bitrate);
+            }
+        }
+        if let Some(folder) = self.music_folder {
+            if FOLDER_SCOPED.contains(&query) && !args.contains("musicFolderId") {
+                args.arg("musicFolderId", folder);
+            }
+        }
+        args
+    }
+
+    /// Sends a request, with its arguments in a `POST` body if they are
+    /// longer than the client's threshold.
+    fn send(&self, query: &str, args: Query) -> Result<reqwest::blocking::Response> {
+        if let Some(ref cassette) = self.cassette {
+            let args = self.with_defaults(query, args);
+            if !cassette.is_recording() {
+                return cassette.replay_request(query, &args);
+            }
+            let res = self.send_live(query, args.clone())?;
+            return cassette.record_request(query, &args, res);
+        }
+        self.send_live(query, args)
+    }
+
+    /// Sends a request to the server, without consulting the cassette.
+    fn send_live(&self, query: &str, args: Query) -> Result<reqwest::blocking::Response> {
+        let res = match self.prepare(query, args)? {
+            (url, Some(body)) => {
+                info!("Posting {} bytes of arguments to {}", body.len(), url);
+                self.reqclient
+                    .post(url)
+                    .header(CONTENT_TYPE, FORM)
+                    .body(body)
+                    .send()?
+            }
+            (url, None) => {
+                info!("Connecting to {}", url);
+                self.reqclient.get(url).send()?
+            }
+        };
+        Ok(res)
+    }
+
+    /// Returns the URL a request is sent to, and the body to `POST` there if
+    /// its arguments are longer than the client's threshold.
+    pub(crate) fn prepare(&self, query: &str, args: Query) -> Result<(Url, Option<String>)> {
+        let args = self.with_defaults(query, args).to_string();
+        let url = self.base_url(query)?;
+        match self.post_threshold {
+            Some(max_len) if args.len() > max_len => Ok((join_url(url, "")?, Some(args))),
+            _ => Ok((join_url(url, &args)?, None)),
+        }
+    }
+
     /// Issues a request to the Subsonic server.
     ///
     /// A query should be one documented in the [official API].
@@ -173,41 +886,393 @@ impl Client {
     /// - connecting to the server fails
     /// - the server returns an API error
     pub(crate) fn get(&self, query: &str, args: Query) -> Result<serde_json::Value> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-
-        info!("Connecting to {}", uri);
-        let mut res = self.reqclient.get(uri).send()?;
-
-        if res.status().is_success() {
-            let response = res.json::<Response>()?;
-            if response.is_ok() {
-                Ok(match response.into_value() {
-                    Some(v) => v,
-                    None => serde_json::Value::Null,
-                })
+        if let Some(ref in_flight) = self.in_flight {
+            if let Some(key) = coalesce::key(query, &args) {
+                return in_flight.run(key, || self.get_uncoalesced(query, args));
+            }
+        }
+        self.get_uncoalesced(query, args)
+    }
+
+    /// Issues a request to the Subsonic server, through the cache if the
+    /// client has one.
+    fn get_uncoalesced(&self, query: &str, args: Query) -> Result<serde_json::Value> {
+        #[cfg(feature = "cache")]
+        {
+            if let Some(ref cache) = self.cache {
+                return self.get_cached(cache.as_ref(), query, args);
+            }
+        }
+        self.fetch(query, args)
+    }
+
+    /// Issues a request to the Subsonic server, answering it from `cache`
+    /// where possible.
+    #[cfg(feature = "cache")]
+    fn get_cached(&self, cache: &dyn Cache, query: &str, args: Query) -> Result<serde_json::Value> {
+        // Defaults are part of the request, so they must be part of the key.
+        let args = self.with_defaults(query, args);
+
+        let key = cache::key(query, &args).map(|k| format!("{}{}", self.cache_scope(), k));
+        if let Some(ref key) = key {
+            if let Some(bytes) = cache.get(key) {
+                match serde_json::from_slice(&bytes) {
+                    Ok(value) => {
+                        info!("Using cached response to {}", query);
+                        return Ok(value);
+                    }
+                    Err(e) => warn!("Ignoring unreadable cached response: {}", e),
+                }
+            }
+        }
+
+        let value = self.fetch(query, args)?;
+        match key {
+            Some(key) => {
+                if let Ok(bytes) = serde_json::to_vec(&value) {
+                    cache.insert(&key, bytes);
+                }
+            }
+            None if cache::invalidates(query) => cache.remove_prefix(&self.cache_scope()),
+            None => (),
+        }
+        Ok(value)
+    }
+
+    /// Issues a request to the Subsonic server, without consulting the cache.
+    fn fetch(&self, query: &str, args: Query) -> Result<serde_json::Value> {
+        self.request(query, args, |args| {
+            let res = self.send(query, args)?;
+
+            if res.status().is_success() {
+                let body = res.text()?;
+                self.parse_body(&body)
             } else {
-                Err(response
-                    .into_error()
-                    .map(|e| e.into())
-                    .ok_or_else(|| Error::Other("unable to retrieve error"))?)
+                Err(Error::from_status(res.status(), res.headers()))
+            }
+        })
+    }
+
+    /// Runs `f` to issue a request, attaching the method and arguments of the
+    /// request to any error it returns.
+    ///
+    /// If the server is unavailable, `f` is run again as set with
+    /// [`with_retries`].
+    ///
+    /// [`with_retries`]: #method.with_retries
+    fn request<T, F>(&self, query: &str, args: Query, mut f: F) -> Result<T>
+    where
+        F: FnMut(Query) -> Result<T>,
+    {
+        let context = args.sanitized();
+        if self.retries == 0 {
+            return f(args).map_err(|e| e.request(query, context));
+        }
+
+        let mut attempt = 0;
+        loop {
+            let res = f(args.clone());
+            let wait = match res {
+                Err(ref e) if attempt < self.retries => retry_wait(e, attempt),
+                _ => None,
+            };
+            match wait {
+                Some(wait) => {
+                    warn!("{} unavailable; retrying in {:?}", query, wait);
+                    thread::sleep(wait);
+                    attempt += 1;
+                }
+                None => return res.map_err(|e| e.request(query, context)),
             }
+        }
+    }
+
+    /// Extracts the value of a response, or the error the server returned.
+    pub(crate) fn parse_body(&self, body: &str) -> Result<serde_json::Value> {
+        let response =
+            serde_json::from_str::<Response>(body).map_err(|e| de::error(self, e, body))?;
+        if response.is_ok() {
+            Ok(match response.into_value() {
+                Some(v) => v,
+                None => serde_json::Value::Null,
+            })
         } else {
-            Err(Error::Connection(res.status()))
+            Err(response
+                .into_error()
+                .map(|e| e.into())
+                .ok_or_else(|| Error::Other("unable to retrieve error"))?)
+        }
+    }
+
+    /// Issues a request to the Subsonic server, passing the elements of the
+    /// list at `path` in the response to `f` as they are read.
+    ///
+    /// Unlike [`get`], the response is never held in memory as a whole. See
+    /// `de::stream` for the format of `path`.
+    ///
+    /// [`get`]: #method.get
+    pub(crate) fn get_streamed<T, F>(
+        &self,
+        query: &str,
+        args: Query,
+        path: &'static [&'static str],
+        mut f: F,
+    ) -> Result<()>
+    where
+        T: DeserializeOwned,
+        F: FnMut(T),
+    {
+        self.request(query, args, |args| {
+            let res = self.send(query, args)?;
+
+            if res.status().is_success() {
+                de::stream(self, BufReader::new(res), path, &mut f)
+            } else {
+                Err(Error::from_status(res.status(), res.headers()))
+            }
+        })
+    }
+
+    /// Fetches an endpoint that lists the library by ID3 tags.
+    ///
+    /// Returns `None` instead if the quirks profile allows browsing by folder
+    /// and the server can't answer the request, either because the profile
+    /// marks the endpoint as unsupported or because the server says it
+    /// doesn't know the endpoint or the item. Other errors, such as wrong
+    /// credentials or a lack of permission, are returned as they are.
+    pub(crate) fn get_id3(&self, query: &str, args: Query) -> Result<Option<serde_json::Value>> {
+        if !self.quirks.directory_fallback() {
+            return self.get(query, args).map(Some);
+        }
+        if !self.quirks.supports(query) {
+            return Ok(None);
+        }
+
+        match self.get(query, args) {
+            Err(ref e) if lacks_id3(e) => {
+                info!("{}; browsing by folder instead", e);
+                Ok(None)
+            }
+            res => res.map(Some),
         }
     }
 
     /// Fetches an unprocessed response from the server rather than a JSON- or
     /// XML-parsed one.
-    pub(crate) fn get_raw(&self, query: &str, args: Query) -> Result<String> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-        let mut res = self.reqclient.get(uri).send()?;
-        Ok(res.text()?)
+    pub(crate) fn get_raw(&self, query: &str, args: Query) -> Result<RawResponse> {
+        self.request(query, args, |args| {
+            RawResponse::read(self.send(query, args)?)
+        })
     }
 
     /// Returns a response as a vector of bytes rather than serialising it.
     pub(crate) fn get_bytes(&self, query: &str, args: Query) -> Result<Vec<u8>> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-        let res = self.reqclient.get(uri).send()?;
+        self.request(query, args, |args| {
+            let res = self.send(query, args)?;
+            Ok(res.bytes()?.to_vec())
+        })
+    }
+
+    /// Fetches cover art, from the cover art cache if the client has one.
+    ///
+    /// Only images are cached; an error the server sends in place of one is
+    /// not.
+    pub(crate) fn get_cover_art(&self, args: Query) -> Result<RawResponse> {
+        if let Some(ref in_flight) = self.covers_in_flight {
+            let key = format!("getCoverArt?{}", args);
+            return in_flight.run(key, || self.get_cover_art_uncoalesced(args));
+        }
+        self.get_cover_art_uncoalesced(args)
+    }
+
+    /// Fetches cover art, without sharing the response with identical
+    /// requests.
+    fn get_cover_art_uncoalesced(&self, args: Query) -> Result<RawResponse> {
+        #[cfg(feature = "cache")]
+        {
+            if let Some(ref cache) = self.cover_cache {
+                return self.get_cover_art_cached(cache.as_ref(), args);
+            }
+        }
+        self.get_raw("getCoverArt", args)
+    }
+
+    /// Fetches cover art, answering the request from `cache` where possible.
+    #[cfg(feature = "cache")]
+    fn get_cover_art_cached(&self, cache: &dyn Cache, args: Query) -> Result<RawResponse> {
+        let key = self.cover_key(&args);
+        if let Some(bytes) = cache.get(&key) {
+            return Ok(RawResponse::cached_image(bytes));
+        }
+
+        let res = self.get_raw("getCoverArt", args)?;
+        if res.is_image() {
+            cache.insert(&key, res.bytes.clone());
+        }
+        Ok(res)
+    }
+
+    /// Returns the key cover art fetched with `args` is cached under.
+    #[cfg(feature = "cache")]
+    fn cover_key(&self, args: &Query) -> String {
+        format!("{}getCoverArt?{}", self.cache_scope(), args)
+    }
+
+    /// Returns the start of every key the client caches responses under.
+    ///
+    /// Keys include the server and user, so that a cache on disk can be shared
+    /// between clients, and a client can drop its own responses only.
+    #[cfg(feature = "cache")]
+    fn cache_scope(&self) -> String {
+        let sep = if self.url.as_str().ends_with('/') {
+            ""
+        } else {
+            "/"
+        };
+        format!("{}@{}{}", self.auth.user, self.url, sep)
+    }
+
+    /// Sends a request for a file, such as a stream or download, and returns
+    /// the response once the server has started sending it.
+    ///
+    /// Errors are sent as an ordinary response in place of the file; these
+    /// are returned as errors.
+    pub(crate) fn open(&self, query: &str, args: Query) -> Result<reqwest::blocking::Response> {
+        let res = self.send(query, args)?;
+        if !res.status().is_success() {
+            return Err(Error::from_status(res.status(), res.headers()));
+        }
+
+        let is_response = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|t| t.to_str().ok())
+            .is_some_and(|t| t.starts_with("application/json"));
+        if is_response {
+            let body = res.text()?;
+            let response =
+                serde_json::from_str::<Response>(&body).map_err(|e| de::error(self, e, &body))?;
+            return Err(response
+                .into_error()
+                .map(|e| e.into())
+                .unwrap_or(Error::Other("server did not send a file")));
+        }
+        Ok(res)
+    }
+
+    /// Returns a reader over a file sent by the server, such as a stream, as
+    /// soon as the server starts sending it.
+    ///
+    /// If the server serves byte ranges of the file, the reader requests it
+    /// again from where it is read when that is far from what has arrived.
+    pub(crate) fn get_reader(&self, query: &str, args: Query) -> Result<StreamReader> {
+        self.request(query, args, |args| {
+            let res = self.open(query, args)?;
+            let len = res.content_length();
+            let content_type = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|t| t.to_str().ok())
+                .map(String::from);
+            // Requests sent as a `POST` carry their arguments in the body, so
+            // only a `GET` can be sent again from its URL.
+            let ranged = self.cassette.is_none()
+                && len.is_some()
+                && res.url().query().is_some()
+                && res
+                    .headers()
+                    .get(ACCEPT_RANGES)
+                    .is_some_and(|r| r.as_bytes() == b"bytes");
+            let url = res.url().clone();
+            let reader = StreamReader::new(res, len, content_type);
+            if !ranged {
+                return Ok(reader);
+            }
+
+            let http = self.reqclient.clone();
+            Ok(reader.with_ranges(Box::new(move |from| {
+                let res = http
+                    .get(url.clone())
+                    .header(RANGE, format!("bytes={}-", from))
+                    .send()
+                    .map_err(io::Error::other)?;
+                if res.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                    let msg = format!("server answered a range request with {}", res.status());
+                    return Err(io::Error::other(msg));
+                }
+                Ok(Box::new(res) as Box<dyn Read + Send>)
+            })))
+        })
+    }
+
+    /// Writes a file sent by the server, such as a download, to `writer`, and
+    /// returns the number of bytes written.
+    ///
+    /// `progress` is called as the file arrives with the number of bytes
+    /// written so far, and the size of the file if the server sent it.
+    pub(crate) fn get_to<W, F>(
+        &self,
+        query: &str,
+        args: Query,
+        writer: &mut W,
+        mut progress: F,
+    ) -> Result<u64>
+    where
+        W: Write,
+        F: FnMut(u64, Option<u64>),
+    {
+        self.request(query, args, |args| {
+            let mut res = self.open(query, args)?;
+            let total = res.content_length();
+            let mut buf = vec![0; 64 * 1024];
+            let mut written = 0;
+            loop {
+                let n = res.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                writer.write_all(&buf[..n])?;
+                written += n as u64;
+                progress(written, total);
+            }
+            Ok(written)
+        })
+    }
+
+    /// Writes a file sent by the server to `path` in `storage`, and returns
+    /// its size in bytes.
+    ///
+    /// `progress` is called as with [`get_to`]. The file is only finished,
+    /// and so stored, if the whole of it arrives.
+    ///
+    /// [`get_to`]: #method.get_to
+    pub(crate) fn get_to_storage<S, F>(
+        &self,
+        query: &str,
+        args: Query,
+        storage: &S,
+        path: &Path,
+        progress: F,
+    ) -> Result<u64>
+    where
+        S: Storage + ?Sized,
+        F: FnMut(u64, Option<u64>),
+    {
+        let mut writer = storage.create(path)?;
+        let written = self.get_to(query, args, &mut writer, progress)?;
+        writer.finish()?;
+        Ok(written)
+    }
+
+    /// Fetches a URL that isn't on the Subsonic server, such as a last.fm
+    /// image.
+    pub(crate) fn get_external(&self, url: &str) -> Result<Vec<u8>> {
+        let url: Url = url.parse()?;
+        info!("Connecting to {}", url);
+        let res = self.reqclient.get(url).send()?;
+        if !res.status().is_success() {
+            return Err(Error::from_status(res.status(), res.headers()));
+        }
         Ok(res.bytes()?.to_vec())
     }
 
@@ -220,8 +1285,7 @@ impl Client {
 
     /// Tests a connection with the server.
     pub fn ping(&self) -> Result<()> {
-        self.get("ping", Query::none())?;
-        Ok(())
+        self.call(&Ping)
     }
 
     /// Get details about the software license. Note that access to the REST API
@@ -232,8 +1296,36 @@ impl Client {
     /// this method will always return a valid license and trial when attempting
     /// to connect to these services.
     pub fn check_license(&self) -> Result<License> {
-        let res = self.get("getLicense", Query::none())?;
-        Ok(serde_json::from_value::<License>(res)?)
+        self.call(&GetLicense)
+    }
+
+    /// Returns the [OpenSubsonic extensions] the server supports.
+    ///
+    /// Servers that don't implement OpenSubsonic support no extensions. The
+    /// extensions are only fetched once; later calls return the same list.
+    ///
+    /// [OpenSubsonic extensions]: https://opensubsonic.netlify.app/docs/extensions/
+    pub fn extensions(&self) -> Result<Vec<Extension>> {
+        if let Some(ref extensions) = *self.extensions.lock().unwrap_or_else(|e| e.into_inner()) {
+            return Ok(extensions.clone());
+        }
+
+        // The lock isn't held while asking the server, so that a slow answer
+        // doesn't hold up other threads; if several ask at once, they all
+        // store the same answer.
+        let extensions = match self.call(&GetOpenSubsonicExtensions) {
+            Ok(extensions) => extensions,
+            Err(ref e) if e.api_error().is_some() => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        *self.extensions.lock().unwrap_or_else(|e| e.into_inner()) = Some(extensions.clone());
+        Ok(extensions)
+    }
+
+    /// Returns whether the server supports the named OpenSubsonic extension,
+    /// such as `"songLyrics"`.
+    pub fn has_extension(&self, name: &str) -> Result<bool> {
+        Ok(self.extensions()?.iter().any(|e| e.name == name))
     }
 
     /// Initiates a rescan of the media libraries.
@@ -243,10 +1335,54 @@ impl Client {
     /// This method was introduced in version 1.15.0. It will not be supported
     /// on servers with earlier versions of the Subsonic API.
     pub fn scan_library(&self) -> Result<()> {
-        self.get("startScan", Query::none())?;
+        self.call(&StartScan)?;
         Ok(())
     }
 
+    /// Starts a rescan of the media libraries and waits for it to finish,
+    /// checking its status every `interval`. Returns the number of files
+    /// scanned.
+    ///
+    /// `observer` is told the number of files scanned so far as an item
+    /// count; the total isn't known until the scan is done.
+    ///
+    /// A server may take a moment to start scanning, so the scan is only
+    /// taken to be done once it has been seen running, or the number of files
+    /// scanned has changed. If neither happens within 30 seconds of starting
+    /// it, the scan is taken to have finished too quickly to be seen.
+    ///
+    /// # Note
+    ///
+    /// This method was introduced in version 1.15.0. It will not be supported
+    /// on servers with earlier versions of the Subsonic API.
+    pub fn scan_library_and_wait<O>(&self, interval: Duration, mut observer: O) -> Result<u64>
+    where
+        O: ProgressObserver,
+    {
+        let mut progress = Progress::default();
+        observer.started(&progress);
+        let res = self.call(&StartScan).and_then(|status| {
+            let requested = SystemTime::now();
+            let (mut started, initial) = match status {
+                Some((scanning, count)) => (scanning, Some(count)),
+                None => (false, None),
+            };
+            loop {
+                let (scanning, count) = self.scan_status()?;
+                started = started || scanning || initial.is_some_and(|i| i != count);
+                let waited = requested.elapsed().unwrap_or_default();
+                progress.items = count;
+                if !scanning && (started || waited >= SCAN_START_TIMEOUT) {
+                    progress.total_items = Some(count);
+                    return Ok(count);
+                }
+                observer.progress(&progress);
+                thread::sleep(interval);
+            }
+        });
+        progress::finish(&mut observer, &progress, res)
+    }
+
     /// Gets the status of a scan. Returns the current status for media library
     /// scanning.
     ///
@@ -255,37 +1391,45 @@ impl Client {
     /// This method was introduced in version 1.15.0. It will not be supported
     /// on servers with earlier versions of the Subsonic API.
     pub fn scan_status(&self) -> Result<(bool, u64)> {
-        let res = self.get("getScanStatus", Query::none())?;
-
-        #[derive(Deserialize)]
-        struct ScanStatus {
-            count: u64,
-            scanning: bool,
-        }
-        let sc = serde_json::from_value::<ScanStatus>(res)?;
-
-        Ok((sc.scanning, sc.count))
+        self.call(&GetScanStatus)
     }
 
     /// Returns all configured top-level music folders.
     pub fn music_folders(&self) -> Result<Vec<MusicFolder>> {
-        #[allow(non_snake_case)]
-        let musicFolder = self.get("getMusicFolders", Query::none())?;
+        self.call(&GetMusicFolders)
+    }
 
-        Ok(get_list_as!(musicFolder, MusicFolder))
+    /// Returns the articles the server ignores when sorting artists, such as
+    /// "The" or "Les".
+    ///
+    /// See the [`sort`] module for sorting with these.
+    ///
+    /// [`sort`]: ./sort/index.html
+    pub fn ignored_articles(&self) -> Result<Vec<String>> {
+        Ok(self.call(&GetArtists::default())?.articles())
     }
 
     /// Returns all genres.
+    ///
+    /// If the client has a [genre map], variants of the same genre are merged.
+    ///
+    /// [genre map]: #method.with_genre_map
     pub fn genres(&self) -> Result<Vec<Genre>> {
-        let genre = self.get("getGenres", Query::none())?;
+        let genres = self.server_genres()?;
+        Ok(match self.genre_map {
+            Some(ref map) => map.merge(genres),
+            None => genres,
+        })
+    }
 
-        Ok(get_list_as!(genre, Genre))
+    /// Returns all genres exactly as the server names them.
+    pub(crate) fn server_genres(&self) -> Result<Vec<Genre>> {
+        self.call(&GetGenres)
     }
 
     /// Returns all currently playing media on the server.
     pub fn now_playing(&self) -> Result<Vec<NowPlaying>> {
-        let entry = self.get("getNowPlaying", Query::none())?;
-        Ok(get_list_as!(entry, NowPlaying))
+        self.call(&GetNowPlaying)
     }
 
     /// Searches for lyrics matching the artist and title. Returns `None` if no
@@ -294,16 +1438,10 @@ impl Client {
     where
         S: Into<Option<&'a str>>,
     {
-        let args = Query::with("artist", artist.into())
-            .arg("title", title.into())
-            .build();
-        let res = self.get("getLyrics", args)?;
-
-        if res.get("value").is_some() {
-            Ok(Some(serde_json::from_value(res)?))
-        } else {
-            Ok(None)
-        }
+        self.call(&GetLyrics {
+            artist: artist.into().map(String::from),
+            title: title.into().map(String::from),
+        })
     }
 
     /// Returns albums, artists and songs matching the given search criteria.
@@ -345,18 +1483,48 @@ impl Client {
         album_page: SearchPage,
         song_page: SearchPage,
     ) -> Result<SearchResult> {
-        // FIXME There has to be a way to make this nicer.
-        let args = Query::with("query", query)
-            .arg("artistCount", artist_page.count)
-            .arg("artistOffset", artist_page.offset)
-            .arg("albumCount", album_page.count)
-            .arg("albumOffset", album_page.offset)
+        self.call(&Search3 {
+            query: self.search_term(query).into_owned(),
+            artists: artist_page,
+            albums: album_page,
+            songs: song_page,
+        })
+    }
+
+    /// Searches for songs, passing each match to `f` as the response
+    /// arrives.
+    ///
+    /// This is meant for searches with very large pages, such as
+    /// [`search::ALL`], which would otherwise have to be read into memory in
+    /// full before any song is returned. Artists and albums are not searched.
+    ///
+    /// [`search::ALL`]: ./search/constant.ALL.html
+    pub fn search_for_each<F>(&self, query: &str, song_page: SearchPage, f: F) -> Result<()>
+    where
+        F: FnMut(Song),
+    {
+        let args = Query::with("query", self.search_term(query).as_ref())
+            .arg("artistCount", 0)
+            .arg("albumCount", 0)
             .arg("songCount", song_page.count)
             .arg("songOffset", song_page.offset)
             .build();
 
-        let res = self.get("search3", args)?;
-        Ok(serde_json::from_value::<SearchResult>(res)?)
+        self.get_streamed("search3", args, &["searchResult3", "song"], f)
+    }
+
+    /// Returns `query` as it is sent, normalized as set with
+    /// [`with_query_normalization`].
+    ///
+    /// [`with_query_normalization`]: #method.with_query_normalization
+    fn search_term<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "unicode")]
+        {
+            if let Some(normalization) = self.query_normalization {
+                return normalization.apply(query);
+            }
+        }
+        Cow::Borrowed(query)
     }
 
     /// Returns a list of all starred artists, albums, and songs.
@@ -364,13 +1532,39 @@ impl Client {
     where
         U: Into<Option<usize>>,
     {
-        let res = self.get("getStarred", Query::with("musicFolderId", folder_id.into()))?;
-        Ok(serde_json::from_value::<SearchResult>(res)?)
+        self.call(&GetStarred {
+            folder_id: folder_id.into(),
+        })
+    }
+
+    /// Returns a list of all starred artists, albums, and songs, organized by
+    /// ID3 tags.
+    ///
+    /// Unlike [`starred`], the artists and albums have the IDs used by the
+    /// ID3 endpoints, such as `getArtist` and `getSimilarSongs2`.
+    ///
+    /// [`starred`]: #method.starred
+    pub fn starred_id3<U>(&self, folder_id: U) -> Result<SearchResult>
+    where
+        U: Into<Option<usize>>,
+    {
+        self.call(&GetStarred2 {
+            folder_id: folder_id.into(),
+        })
     }
 }
 
+/// An OpenSubsonic extension supported by a server.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Extension {
+    /// The name of the extension, such as `"songLyrics"`.
+    pub name: String,
+    /// The versions of the extension the server supports.
+    pub versions: Vec<u32>,
+}
+
 /// A representation of a license associated with a server.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct License {
     /// Whether the license is valid or not.
@@ -384,10 +1578,24 @@ pub struct License {
     pub license_expires: Option<String>,
 }
 
+#[cfg(feature = "chrono")]
+impl License {
+    /// Returns when the server's trial expires, if it is in one.
+    pub fn trial_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.trial_expires.as_ref().and_then(|s| timestamp::parse(s))
+    }
+
+    /// Returns when the server's license expires, if it has one.
+    pub fn license_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.license_expires.as_ref().and_then(|s| timestamp::parse(s))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
+    use std::sync::OnceLock;
 
     #[test]
     fn test_token_auth() {
@@ -398,11 +1606,135 @@ mod tests {
 
         assert!(token_addr != legacy_addr);
         assert_eq!(
-            legacy_addr,
-            "http://demo.subsonic.org/rest/ping?u=guest3&p=guest&v=1.8.0&c=sunk&f=json&"
+            legacy_addr.as_str(),
+            "http://demo.subsonic.org/rest/ping?u=guest3&p=guest&v=1.8.0&c=sunk&f=json"
         );
     }
 
+    proptest::proptest! {
+        #[test]
+        fn urls_carry_arguments(user in ".+", query in ".*", artist in ".*") {
+            // Building a client is slow, so one is shared between cases.
+            static CLIENT: OnceLock<Client> = OnceLock::new();
+            let cli = CLIENT.get_or_init(|| Client::new("http://localhost", "user", "pass").unwrap());
+            let args = Query::with("query", query.as_str()).arg("artist", artist.as_str()).build();
+            let url = cli.build_url("search3", args).unwrap();
+
+            // Nothing the parser would have to escape, or read as a fragment.
+            proptest::prop_assert!(url.as_str().is_ascii() && !url.as_str().contains(' '));
+            proptest::prop_assert_eq!(url.fragment(), None);
+            let pairs = url.query_pairs().into_owned().collect::<Vec<_>>();
+            let has = |key: &str, value: &str| pairs.iter().any(|(k, v)| k == key && v == value);
+            proptest::prop_assert!(has("query", &query));
+            proptest::prop_assert!(has("artist", &artist));
+
+            let auth = SubsonicAuth::new(&user, "pass").to_url(cli.target_ver);
+            let mut auth = url::form_urlencoded::parse(auth.as_bytes());
+            proptest::prop_assert_eq!(auth.next(), Some(("u".into(), user.as_str().into())));
+        }
+    }
+
+    #[test]
+    fn escaped_urls() {
+        let cli = Client::new("http://localhost/sub sonic", "user", "pass").unwrap();
+        let args = Query::with("artist", "Simon & Garfunkel #1")
+            .arg("title", "東京 Love")
+            .build();
+        let url = cli.build_url("getLyrics", args).unwrap();
+        let query = url.query().unwrap();
+        assert!(url.path().starts_with("/sub%20sonic/rest/"));
+        assert!(query
+            .ends_with("&artist=Simon%20%26%20Garfunkel%20%231&title=%E6%9D%B1%E4%BA%AC%20Love"));
+
+        let ping = cli.build_url("ping", Query::none()).unwrap();
+        assert!(ping.as_str().ends_with("f=json"));
+    }
+
+    #[test]
+    fn request_defaults() {
+        let cli = Client::new("http://localhost", "user", "pass")
+            .unwrap()
+            .with_max_bitrate(Bitrate::LOW)
+            .with_music_folder(3);
+        let has = |url: Url, key: &str, value: &str| {
+            url.query_pairs().any(|(k, v)| k == key && v == value)
+        };
+
+        let stream = cli.build_url("stream", Query::with("id", 1)).unwrap();
+        assert!(has(stream, "maxBitRate", "64"));
+        let stream = cli
+            .build_url("stream", Query::with("maxBitRate", 320))
+            .unwrap();
+        assert!(has(stream, "maxBitRate", "320"));
+
+        let (starred, _) = cli.prepare("getStarred2", Query::none()).unwrap();
+        assert!(has(starred, "musicFolderId", "3"));
+        let (song, _) = cli.prepare("getSong", Query::with("id", 1)).unwrap();
+        assert!(!song.query_pairs().any(|(k, _)| k == "musicFolderId"));
+    }
+
+    #[test]
+    fn retries_unavailable() {
+        let busy = || Error::Unavailable {
+            status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: Some(Duration::from_secs(0)),
+        };
+        let cli = Client::new("http://localhost", "user", "pass")
+            .unwrap()
+            .with_retries(2);
+
+        let mut attempts = 0;
+        let res = cli.request("ping", Query::none(), |_| {
+            attempts += 1;
+            if attempts < 3 {
+                Err(busy())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(res.is_ok());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let res = cli.request("ping", Query::none(), |_| -> Result<()> {
+            attempts += 1;
+            Err(busy())
+        });
+        assert_eq!(res.unwrap_err().endpoint(), Some("ping"));
+        assert_eq!(attempts, 3);
+
+        // Other errors, and waits longer than the limit, aren't retried.
+        assert_eq!(retry_wait(&Error::Other("no"), 0), None);
+        let later = Error::Unavailable {
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            retry_after: Some(Duration::from_secs(3600)),
+        };
+        assert_eq!(retry_wait(&later, 0), None);
+        let unsaid = Error::Unavailable {
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            retry_after: None,
+        };
+        assert_eq!(retry_wait(&unsaid, 2), Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn folder_fallback_errors() {
+        let api = |e: ApiError| Error::Api(e).request("getArtist", "id=1".into());
+        assert!(lacks_id3(&api(ApiError::ServerMustUpgrade)));
+        assert!(lacks_id3(&api(ApiError::NotFound)));
+        assert!(lacks_id3(&Error::Connection(
+            reqwest::StatusCode::NOT_FOUND
+        )));
+        assert!(!lacks_id3(&api(ApiError::Generic(
+            "Database locked".into()
+        ))));
+        assert!(!lacks_id3(&api(ApiError::WrongAuth)));
+        assert!(!lacks_id3(&api(ApiError::NotAuthorized("no".into()))));
+        assert!(!lacks_id3(&Error::Connection(
+            reqwest::StatusCode::BAD_GATEWAY
+        )));
+    }
+
     #[test]
     fn demo_ping() {
         let cli = test_util::demo_site().unwrap();
@@ -426,20 +1758,38 @@ mod tests {
         assert_eq!(n, 521);
     }
 
+    #[test]
+    fn scan_waits_for_start() {
+        let status = |endpoint, scanning: bool, count: u64| {
+            let body = serde_json::json!({
+                "scanStatus": { "scanning": scanning, "count": count },
+            });
+            (endpoint, Query::none(), body)
+        };
+        let cli = test_util::replay_client(&[
+            status("startScan", false, 521),
+            status("getScanStatus", false, 521),
+            status("getScanStatus", true, 600),
+            status("getScanStatus", false, 640),
+        ]);
+        let count = cli.scan_library_and_wait(Duration::from_millis(1), ());
+        assert_eq!(count.unwrap(), 640);
+    }
+
     #[test]
     fn demo_search() {
         let cli = test_util::demo_site().unwrap();
         let s = SearchPage::new().with_size(1);
         let r = cli.search("dada", s, s, s).unwrap();
 
-        assert_eq!(r.artists[0].id, 14);
-        assert_eq!(r.artists[0].name, String::from("The Dada Weatherman"));
+        assert_eq!(r.artists[0].id.as_str(), "14");
+        assert_eq!(&*r.artists[0].name, "The Dada Weatherman");
         assert_eq!(r.artists[0].album_count, 4);
 
-        assert_eq!(r.albums[0].id, 23);
-        assert_eq!(r.albums[0].name, String::from("The Green Waltz"));
+        assert_eq!(r.albums[0].id.as_str(), "23");
+        assert_eq!(&*r.albums[0].name, "The Green Waltz");
 
-        assert_eq!(r.songs[0].id, 222);
+        assert_eq!(r.songs[0].id.as_str(), "222");
 
         // etc.
     }
@@ -0,0 +1,261 @@
+//! Sharing the responses of identical requests in flight.
+//!
+//! Views that load lazily, such as virtualized lists, tend to ask for the same
+//! artist or cover several times at once. With coalescing on, the first
+//! request is sent and the rest wait for its response, rather than each
+//! sending their own.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::query::Query;
+use crate::Result;
+
+/// Endpoints whose responses differ from one request to the next, even when
+/// nothing changes on the server.
+const RANDOM: &[&str] = &["getRandomSongs"];
+
+/// Returns the key requests sharing a response are grouped under, or `None`
+/// if the request can't share one.
+///
+/// Only requests that read from the server are shared, leaving out those for
+/// random results.
+pub(crate) fn key(query: &str, args: &Query) -> Option<String> {
+    let reads = query.starts_with("get") || query.starts_with("search");
+    let random = RANDOM.contains(&query) || args.value("type") == Some("random");
+    if reads && !random {
+        Some(format!("{}?{}", query, args))
+    } else {
+        None
+    }
+}
+
+/// The requests in flight, by key.
+pub(crate) struct InFlight<T> {
+    requests: Mutex<HashMap<String, Arc<Slot<T>>>>,
+}
+
+/// Where the response to a request in flight is left for those waiting on it.
+struct Slot<T> {
+    /// `None` until the request is done, then the response, or `None` if the
+    /// request failed.
+    response: Mutex<Option<Option<T>>>,
+    done: Condvar,
+}
+
+impl<T: Clone> InFlight<T> {
+    pub(crate) fn new() -> InFlight<T> {
+        InFlight {
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends the request under `key` with `send`, or waits for the response
+    /// to the same request if one is already in flight.
+    ///
+    /// Errors aren't shared: if the request waited on fails, one of those
+    /// waiting sends it again and the rest wait on that request instead.
+    pub(crate) fn run<F>(&self, key: String, send: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let slot = {
+            let mut requests = self.requests.lock().unwrap();
+            match requests.get(&key) {
+                Some(slot) => Some(Arc::clone(slot)),
+                None => {
+                    let slot = Arc::new(Slot {
+                        response: Mutex::new(None),
+                        done: Condvar::new(),
+                    });
+                    requests.insert(key.clone(), slot);
+                    None
+                }
+            }
+        };
+
+        match slot {
+            Some(slot) => {
+                let mut response = slot.response.lock().unwrap();
+                while response.is_none() {
+                    response = slot.done.wait(response).unwrap();
+                }
+                match *response {
+                    Some(Some(ref value)) => Ok(value.clone()),
+                    _ => {
+                        drop(response);
+                        self.run(key, send)
+                    }
+                }
+            }
+            None => {
+                // Waiters are let go even if `send` panics.
+                let mut guard = Guard {
+                    in_flight: self,
+                    key,
+                    response: None,
+                };
+                let res = send();
+                guard.response = res.as_ref().ok().cloned();
+                res
+            }
+        }
+    }
+}
+
+impl<T> InFlight<T> {
+    /// Returns the number of callers waiting on the request in flight under
+    /// `key`.
+    #[cfg(test)]
+    fn waiting(&self, key: &str) -> usize {
+        let requests = self.requests.lock().unwrap();
+        // The map holds one reference, and each caller waiting another.
+        requests
+            .get(key)
+            .map_or(0, |slot| Arc::strong_count(slot) - 1)
+    }
+}
+
+impl<T> fmt::Debug for InFlight<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.requests.lock().map(|r| r.len()).unwrap_or(0);
+        f.debug_struct("InFlight").field("requests", &len).finish()
+    }
+}
+
+/// Hands the response of a request to those waiting on it once dropped.
+struct Guard<'a, T> {
+    in_flight: &'a InFlight<T>,
+    key: String,
+    response: Option<T>,
+}
+
+impl<'a, T> Drop for Guard<'a, T> {
+    fn drop(&mut self) {
+        let slot = self.in_flight.requests.lock().unwrap().remove(&self.key);
+        if let Some(slot) = slot {
+            *slot.response.lock().unwrap() = Some(self.response.take());
+            slot.done.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+
+    const KEY: &str = "getArtist?id=1";
+
+    /// Blocks until `n` callers wait on the request under `KEY`.
+    fn until_waiting<T>(in_flight: &InFlight<T>, n: usize) {
+        while in_flight.waiting(KEY) < n {
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn shares_responses() {
+        let in_flight = InFlight::new();
+        let sent = AtomicUsize::new(0);
+        let send = || {
+            sent.fetch_add(1, Ordering::SeqCst);
+            until_waiting(&in_flight, 7);
+            Ok("Misteur Valaire")
+        };
+
+        thread::scope(|s| {
+            let handles = (0..8)
+                .map(|_| s.spawn(|| in_flight.run(KEY.into(), send)))
+                .collect::<Vec<_>>();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap().unwrap(), "Misteur Valaire");
+            }
+        });
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+
+        // Once done, the request is sent again.
+        in_flight
+            .run(KEY.into(), || {
+                sent.fetch_add(1, Ordering::SeqCst);
+                Ok("Misteur Valaire")
+            })
+            .unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn retries_failures() {
+        let in_flight = InFlight::<&str>::new();
+        let sent = AtomicUsize::new(0);
+        let sending = Barrier::new(2);
+        thread::scope(|s| {
+            let first = s.spawn(|| {
+                in_flight.run(KEY.into(), || {
+                    sent.fetch_add(1, Ordering::SeqCst);
+                    sending.wait();
+                    until_waiting(&in_flight, 1);
+                    Err(Error::Other("failed"))
+                })
+            });
+            sending.wait();
+            let second = in_flight.run(KEY.into(), || {
+                sent.fetch_add(1, Ordering::SeqCst);
+                Ok("Misteur Valaire")
+            });
+            assert!(first.join().unwrap().is_err());
+            assert_eq!(second.unwrap(), "Misteur Valaire");
+        });
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn retries_failures_once() {
+        let in_flight = InFlight::<&str>::new();
+        let sent = AtomicUsize::new(0);
+        let sending = Barrier::new(2);
+        thread::scope(|s| {
+            let first = s.spawn(|| {
+                in_flight.run(KEY.into(), || {
+                    sent.fetch_add(1, Ordering::SeqCst);
+                    sending.wait();
+                    until_waiting(&in_flight, 8);
+                    Err(Error::Other("failed"))
+                })
+            });
+            sending.wait();
+            let handles = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        in_flight.run(KEY.into(), || {
+                            sent.fetch_add(1, Ordering::SeqCst);
+                            // The rest wait on this request, rather than
+                            // sending their own.
+                            until_waiting(&in_flight, 7);
+                            Ok("Misteur Valaire")
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            assert!(first.join().unwrap().is_err());
+            for handle in handles {
+                assert_eq!(handle.join().unwrap().unwrap(), "Misteur Valaire");
+            }
+        });
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn keys() {
+        let id = Query::with("id", 1);
+        assert_eq!(key("getArtist", &id).unwrap(), "getArtist?id=1");
+        assert!(key("star", &id).is_none());
+        assert!(key("getRandomSongs", &Query::none()).is_none());
+        assert!(key("getAlbumList2", &Query::with("type", "random")).is_none());
+        assert!(key("getAlbumList2", &Query::with("type", "newest")).is_some());
+    }
+}
@@ -1,10 +1,33 @@
+use rand::Rng;
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::ser::{Serialize, Serializer};
+use std::hash::{Hash, Hasher};
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 use std::{fmt, result};
 
+use super::artist;
+use super::directory::{Directory, Folder};
+use crate::endpoint::{
+    GetAlbum, GetAlbumInfo, GetAlbumInfo2, GetAlbumList2, GetAlbumsByGenre, GetAlbumsByYear,
+};
+use crate::id3;
+use crate::name;
+use crate::progress::{self, Progress, ProgressObserver};
 use crate::query::{Arg, IntoArg, Query};
 use crate::search::SearchPage;
-use crate::{Client, Error, Media, Result, Song};
+use crate::song;
+use crate::storage::{LocalStorage, Storage};
+use crate::timestamp;
+use crate::{
+    AlbumId, Artist, ArtistId, ArtistRef, Client, CoverId, Downloadable, HasCoverArt, ImageUrls,
+    Name, Result, Song,
+};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use url::Url;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ListType {
@@ -49,18 +72,171 @@ impl IntoArg for ListType {
 
 #[derive(Debug, Clone)]
 pub struct Album {
-    pub id: u64,
-    pub name: String,
-    pub artist: Option<String>,
-    artist_id: Option<u64>,
-    cover_id: Option<String>,
+    pub id: AlbumId,
+    pub name: Name,
+    /// The name the album should be sorted by. Only provided by OpenSubsonic
+    /// servers; see the [`sort`] module for sorting on other servers.
+    ///
+    /// [`sort`]: ./sort/index.html
+    pub sort_name: Option<String>,
+    pub artist: Option<Name>,
+    artist_id: Option<ArtistId>,
+    /// All artists credited on the album. Only provided by OpenSubsonic
+    /// servers; see `artist` for older servers.
+    pub artists: Vec<ArtistRef>,
+    /// The full credit for the album's artists, as it should be displayed.
+    pub display_artist: Option<Name>,
+    cover_id: Option<CoverId>,
     pub duration: u64,
     pub year: Option<u64>,
-    pub genre: Option<String>,
+    /// Genre of the album. ID3v1 references such as `"(17)"` are decoded
+    /// into their names; see [`raw_genre`] for the value the server sent.
+    ///
+    /// [`raw_genre`]: #method.raw_genre
+    pub genre: Option<Name>,
+    raw_genre: Option<String>,
     pub song_count: u64,
-    songs: Vec<Song>,
+    /// An ISO8601 timestamp of when the album was added to the server.
+    pub created: String,
+    /// An ISO8601 timestamp of when the album was starred, if it has been.
+    ///
+    /// Use [`Annotatable`] to star or rate the album.
+    ///
+    /// [`Annotatable`]: ./trait.Annotatable.html
+    pub starred: Option<String>,
+    /// The rating the user has given the album, from 1 to 5.
+    pub user_rating: Option<u8>,
+    /// The album's [MusicBrainz](https://musicbrainz.org/) release ID.
+    pub musicbrainz_id: Option<String>,
+    /// Whether the album is tagged as a compilation. Only provided by
+    /// OpenSubsonic servers; see [`is_compilation`] for a check that works
+    /// on any server.
+    ///
+    /// [`is_compilation`]: #method.is_compilation
+    pub compilation: Option<bool>,
+    /// The titles of the album's discs, for discs that have one. Only
+    /// provided by OpenSubsonic servers.
+    pub disc_titles: Vec<DiscTitle>,
+    /// When the album was released. Only provided by OpenSubsonic servers;
+    /// see `year` for older servers.
+    pub release_date: Option<ItemDate>,
+    /// When the album was originally released, for reissues. Only provided by
+    /// OpenSubsonic servers.
+    pub original_release_date: Option<ItemDate>,
+    /// An ISO8601 timestamp of when the album was last played. Only provided
+    /// by OpenSubsonic servers.
+    pub played: Option<String>,
+    /// The labels that released the album. Only provided by OpenSubsonic
+    /// servers.
+    pub record_labels: Vec<String>,
+    /// The moods the album is tagged with. Only provided by OpenSubsonic
+    /// servers.
+    pub moods: Vec<String>,
+    /// Whether the album has explicit lyrics. Only provided by OpenSubsonic
+    /// servers, and only for albums that are tagged either way.
+    pub explicit_status: Option<ExplicitStatus>,
+    /// The version of the album, such as "Deluxe Edition". Only provided by
+    /// OpenSubsonic servers.
+    pub version: Option<String>,
+    /// The album's songs, once they are known.
+    songs: OnceLock<Vec<Song>>,
+    /// Whether the album is a folder, as listed when browsing by folder.
+    /// Kept as `isDir` when serialized, as the server lists folders.
+    folder: bool,
 }
 
+/// Whether an album has explicit lyrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplicitStatus {
+    /// The album has explicit lyrics.
+    Explicit,
+    /// The album is a version with explicit lyrics removed.
+    Clean,
+}
+
+impl ExplicitStatus {
+    fn parse(s: &str) -> Option<ExplicitStatus> {
+        match s {
+            "explicit" => Some(ExplicitStatus::Explicit),
+            "clean" => Some(ExplicitStatus::Clean),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ExplicitStatus::Explicit => "explicit",
+            ExplicitStatus::Clean => "clean",
+        }
+    }
+}
+
+/// A record label, as OpenSubsonic servers send them.
+#[derive(Debug, Deserialize, Serialize)]
+struct RecordLabel<N> {
+    name: N,
+}
+
+/// The title of one disc of an album.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DiscTitle {
+    /// The number of the disc, starting at 1.
+    pub disc: u64,
+    /// The title of the disc.
+    pub title: String,
+}
+
+/// A date that may only be partially known, such as just a year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct ItemDate {
+    /// The year.
+    pub year: Option<u32>,
+    /// The month, from 1 to 12.
+    pub month: Option<u32>,
+    /// The day of the month.
+    pub day: Option<u32>,
+}
+
+impl ItemDate {
+    fn is_empty(&self) -> bool {
+        self.year.is_none() && self.month.is_none() && self.day.is_none()
+    }
+}
+
+impl fmt::Display for ItemDate {
+    /// Formats the date as much of `YYYY-MM-DD` as is known.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ItemDate {
+                year: Some(y),
+                month: Some(m),
+                day: Some(d),
+            } => write!(f, "{:04}-{:02}-{:02}", y, m, d),
+            ItemDate {
+                year: Some(y),
+                month: Some(m),
+                ..
+            } => write!(f, "{:04}-{:02}", y, m),
+            ItemDate { year: Some(y), .. } => write!(f, "{:04}", y),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The songs on one disc of an album.
+#[derive(Debug, Clone)]
+pub struct Disc {
+    /// The number of the disc, starting at 1.
+    pub number: u64,
+    /// The title of the disc, if it has one.
+    pub title: Option<String>,
+    /// The songs on the disc, in track order.
+    pub songs: Vec<Song>,
+}
+
+/// Album artist names used for compilations by tagging tools.
+const VARIOUS_ARTISTS: &[&str] = &["various artists", "various", "va"];
+
 impl Album {
     /// Returns a single album from the Subsonic server.
     ///
@@ -68,8 +244,11 @@ impl Album {
     ///
     /// Aside from errors the `Client` may cause, the method will error if
     /// there is no album matching the provided ID.
-    pub fn get(client: &Client, id: usize) -> Result<Album> {
-        self::get_album(client, id as u64)
+    pub fn get<I>(client: &Client, id: I) -> Result<Album>
+    where
+        I: Into<AlbumId>,
+    {
+        self::get_album(client, &id.into())
     }
 
     /// Lists all albums on the server. Supports paging.
@@ -82,19 +261,290 @@ impl Album {
         self::get_albums(client, list_type, page.count, page.offset, folder)
     }
 
+    /// Picks a random album.
+    ///
+    /// Use [`random_with`] to only pick from albums of a genre, from a range
+    /// of years, or that the user has starred.
+    ///
+    /// [`random_with`]: #method.random_with
+    pub fn random(client: &Client) -> Result<Option<Album>> {
+        RandomAlbum::new(client).request()
+    }
+
+    /// Creates a new builder to pick a random album.
+    ///
+    /// See the [struct level documentation] for more information on how to use
+    /// the builder.
+    ///
+    /// [struct level documentation]: ./struct.RandomAlbum.html
+    pub fn random_with(client: &Client) -> RandomAlbum<'_> {
+        RandomAlbum::new(client)
+    }
+
+    /// Lists the albums released in a range of years, such as `1990..=1999`.
+    /// Supports paging.
+    ///
+    /// Albums are listed oldest first, or newest first if `newest_first` is
+    /// set. The range is read the same way round either way.
+    pub fn by_year(
+        client: &Client,
+        years: RangeInclusive<u32>,
+        newest_first: bool,
+        page: SearchPage,
+    ) -> Result<Vec<Album>> {
+        let (from, to) = self::year_bounds(years, newest_first);
+        client.call(&GetAlbumsByYear {
+            from_year: from as usize,
+            to_year: to as usize,
+            size: Some(page.count),
+            offset: Some(page.offset),
+            folder_id: None,
+        })
+    }
+
+    /// Returns the ID of the album's artist, if the server knows it.
+    pub fn artist_id(&self) -> Option<&ArtistId> {
+        self.artist_id.as_ref()
+    }
+
+    /// Fetches the album's artist. Returns `None` if the server doesn't know
+    /// who that is.
+    pub fn artist(&self, client: &Client) -> Result<Option<Artist>> {
+        match self.artist_id {
+            Some(ref id) if self.folder => Ok(Some(artist::get_folder_artist(client, id)?)),
+            Some(ref id) => Ok(Some(Artist::get(client, id)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Returns all songs in the album.
+    ///
+    /// Albums returned by lists and searches don't include their songs. These
+    /// are fetched from the server the first time they are asked for, and
+    /// kept for later calls.
     pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
-        if self.songs.len() as u64 != self.song_count {
-            Ok(self::get_album(client, self.id)?.songs)
+        if let Some(songs) = self.songs.get() {
+            return Ok(songs.clone());
+        }
+
+        let songs = if self.folder {
+            Directory::get(client, self.id.as_str())?.songs(client)?
         } else {
-            Ok(self.songs.clone())
+            self::get_album(client, &self.id)?
+                .songs
+                .into_inner()
+                .unwrap_or_default()
+        };
+        Ok(self.songs.get_or_init(|| songs).clone())
+    }
+
+    /// Returns a URL to download the whole album as a ZIP archive.
+    ///
+    /// This is [`Downloadable::download_url`], kept so that callers that
+    /// don't import the trait still build.
+    ///
+    /// [`Downloadable::download_url`]: ../trait.Downloadable.html#tymethod.download_url
+    #[deprecated(since = "0.2.0", note = "use `Downloadable::download_url` instead")]
+    pub fn download_url(&self, client: &Client) -> Result<Url> {
+        Downloadable::download_url(self, client)
+    }
+
+    /// Downloads the whole album as a ZIP archive to `path`, and returns its
+    /// size in bytes. See [`Downloadable`] for which servers support this.
+    ///
+    /// `observer` is told the bytes written as the archive arrives, and the
+    /// size of the archive if the server sent it. If the download fails, the
+    /// partly written file is removed.
+    ///
+    /// [`Downloadable`]: #impl-Downloadable-for-Album
+    pub fn download_zip_to<P, O>(&self, client: &Client, path: P, observer: O) -> Result<u64>
+    where
+        P: AsRef<Path>,
+        O: ProgressObserver,
+    {
+        // An empty root leaves `path` as it is, relative or not.
+        self.download_zip_to_storage(client, &LocalStorage::new(""), path.as_ref(), observer)
+    }
+
+    /// Downloads the whole album as a ZIP archive to `path` in `storage`, and
+    /// returns its size in bytes. See the [`storage`] module.
+    ///
+    /// `observer` is told the bytes written as with [`download_zip_to`].
+    ///
+    /// [`storage`]: ./storage/index.html
+    /// [`download_zip_to`]: #method.download_zip_to
+    pub fn download_zip_to_storage<S, O>(
+        &self,
+        client: &Client,
+        storage: &S,
+        path: &Path,
+        mut observer: O,
+    ) -> Result<u64>
+    where
+        S: Storage + ?Sized,
+        O: ProgressObserver,
+    {
+        let mut progress = Progress {
+            total_items: Some(1),
+            ..Progress::default()
+        };
+        observer.started(&progress);
+        let res = client.get_to_storage(
+            "download",
+            Query::with("id", &self.id),
+            storage,
+            path,
+            |written, total| {
+                progress.bytes = written;
+                progress.total_bytes = total;
+                observer.progress(&progress);
+            },
+        );
+        if res.is_ok() {
+            progress.items = 1;
+        }
+        progress::finish(&mut observer, &progress, res)
+    }
+
+    /// Downloads each of the album's songs into `dir`, and returns the paths
+    /// of the files written.
+    ///
+    /// Files are laid out as they are on the server, as with
+    /// [`Song::download_to`], and replace any already there. `observer` is
+    /// told the songs and bytes downloaded so far; the totals are the number
+    /// of songs and their sizes as the server reports them.
+    ///
+    /// [`Song::download_to`]: ./song/struct.Song.html#method.download_to
+    pub fn download_to<P, O>(&self, client: &Client, dir: P, observer: O) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+        O: ProgressObserver,
+    {
+        let storage = LocalStorage::new(dir.as_ref());
+        let written = self.download_to_storage(client, &storage, observer)?;
+        Ok(written.iter().map(|p| storage.root().join(p)).collect())
+    }
+
+    /// Downloads each of the album's songs into `storage`, and returns the
+    /// paths they are stored at, relative to the storage.
+    ///
+    /// Songs are laid out and `observer` is told progress as with
+    /// [`download_to`]. See the [`storage`] module.
+    ///
+    /// [`download_to`]: #method.download_to
+    /// [`storage`]: ./storage/index.html
+    pub fn download_to_storage<S, O>(
+        &self,
+        client: &Client,
+        storage: &S,
+        observer: O,
+    ) -> Result<Vec<PathBuf>>
+    where
+        S: Storage + ?Sized,
+        O: ProgressObserver,
+    {
+        let songs = self.songs(client)?;
+        song::download_songs(client, &songs, storage, true, observer)
+    }
+
+    /// Returns the album's songs grouped by disc, in disc and track order.
+    ///
+    /// Songs without a disc number are put on the first disc.
+    pub fn discs(&self, client: &Client) -> Result<Vec<Disc>> {
+        let mut songs = self.songs(client)?;
+        songs.sort_by_key(|s| (s.disc_number.unwrap_or(1), s.track));
+
+        let mut discs: Vec<Disc> = Vec::new();
+        for song in songs {
+            let number = song.disc_number.unwrap_or(1);
+            match discs.last_mut() {
+                Some(disc) if disc.number == number => disc.songs.push(song),
+                _ => discs.push(Disc {
+                    number,
+                    title: self
+                        .disc_titles
+                        .iter()
+                        .find(|t| t.disc == number)
+                        .map(|t| t.title.clone()),
+                    songs: vec![song],
+                }),
+            }
+        }
+        Ok(discs)
+    }
+
+    /// Returns whether the album is a compilation of various artists.
+    ///
+    /// OpenSubsonic servers report this directly. On other servers, albums
+    /// credited to "Various Artists" (or a common abbreviation of it) are
+    /// taken to be compilations.
+    pub fn is_compilation(&self) -> bool {
+        if let Some(compilation) = self.compilation {
+            return compilation;
         }
+        self.artist
+            .as_ref()
+            .map(|a| VARIOUS_ARTISTS.contains(&a.trim().to_lowercase().as_str()))
+            .unwrap_or(false)
     }
 
     /// Returns detailed information about the album.
     pub fn info(&self, client: &Client) -> Result<AlbumInfo> {
-        let res = client.get("getArtistInfo", Query::with("id", self.id))?;
-        Ok(serde_json::from_value(res)?)
+        let id = self.id.clone();
+        if self.folder {
+            client.call(&GetAlbumInfo { id })
+        } else {
+            client.call(&GetAlbumInfo2 { id })
+        }
+    }
+
+    /// Returns the genre exactly as the server reported it.
+    ///
+    /// The `genre` field has ID3v1 references such as `"(17)"` decoded into
+    /// names; this returns the undecoded value.
+    pub fn raw_genre(&self) -> Option<&str> {
+        self.raw_genre.as_deref()
+    }
+
+    /// Returns the total length of the album.
+    ///
+    /// The raw number of seconds is available as the `duration` field.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration)
+    }
+
+    /// Returns when the album was added to the server.
+    ///
+    /// Returns `None` if the server sent a timestamp that couldn't be parsed.
+    #[cfg(feature = "chrono")]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        timestamp::parse(&self.created)
+    }
+
+    /// Returns when the album was last played, if it has been.
+    #[cfg(feature = "chrono")]
+    pub fn played_at(&self) -> Option<DateTime<Utc>> {
+        self.played.as_ref().and_then(|s| timestamp::parse(s))
+    }
+
+    /// Returns when the album was starred, if it has been.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<DateTime<Utc>> {
+        self.starred.as_ref().and_then(|s| timestamp::parse(s))
+    }
+}
+
+impl PartialEq for Album {
+    fn eq(&self, other: &Album) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Album {}
+
+impl Hash for Album {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
 }
 
@@ -116,6 +566,61 @@ impl fmt::Display for Album {
     }
 }
 
+impl Album {
+    /// Returns the parameter `star` and `unstar` take the album's ID as.
+    ///
+    /// Albums organised by ID3 tags are starred by `albumId`, but folders are
+    /// starred by `id` like songs.
+    pub(crate) fn star_key(&self) -> &'static str {
+        if self.folder {
+            "id"
+        } else {
+            "albumId"
+        }
+    }
+
+    /// Adapts a folder into an album, for servers browsed by folder.
+    ///
+    /// Only the songs directly inside the folder are the album's; those in
+    /// subfolders, such as one per disc, are not.
+    pub(crate) fn from_folder(folder: Folder, artist_id: &ArtistId) -> Album {
+        Album {
+            id: AlbumId::new(folder.id),
+            name: name::shared(folder.title.into()),
+            sort_name: None,
+            artist: folder.artist.map(|a| name::shared(a.into())),
+            artist_id: Some(artist_id.clone()),
+            artists: Vec::new(),
+            display_artist: None,
+            cover_id: folder.cover_art,
+            duration: 0,
+            year: folder.year,
+            genre: folder
+                .genre
+                .as_deref()
+                .and_then(id3::decode)
+                .map(name::shared),
+            raw_genre: folder.genre,
+            song_count: 0,
+            created: folder.created,
+            starred: folder.starred,
+            user_rating: folder.user_rating,
+            musicbrainz_id: None,
+            compilation: None,
+            disc_titles: Vec::new(),
+            release_date: None,
+            original_release_date: None,
+            played: None,
+            record_labels: Vec::new(),
+            moods: Vec::new(),
+            explicit_status: None,
+            version: None,
+            songs: OnceLock::new(),
+            folder: true,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Album {
     fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
     where
@@ -124,71 +629,201 @@ impl<'de> Deserialize<'de> for Album {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Album {
-            id: String,
-            name: String,
-            artist: Option<String>,
-            artist_id: Option<String>,
-            cover_art: Option<String>,
+            id: AlbumId,
+            name: Name,
+            sort_name: Option<String>,
+            artist: Option<Name>,
+            artist_id: Option<ArtistId>,
+            #[serde(default)]
+            artists: Vec<ArtistRef>,
+            display_artist: Option<Name>,
+            cover_art: Option<CoverId>,
             song_count: u64,
             duration: u64,
             created: String,
+            starred: Option<String>,
+            user_rating: Option<u8>,
             year: Option<u64>,
             genre: Option<String>,
+            music_brainz_id: Option<String>,
+            is_compilation: Option<bool>,
+            #[serde(default)]
+            disc_titles: Vec<DiscTitle>,
+            release_date: Option<ItemDate>,
+            original_release_date: Option<ItemDate>,
+            played: Option<String>,
+            #[serde(default)]
+            record_labels: Vec<RecordLabel<String>>,
+            #[serde(default)]
+            moods: Vec<String>,
+            explicit_status: Option<String>,
+            version: Option<String>,
             #[serde(default)]
             song: Vec<Song>,
+            #[serde(default)]
+            is_dir: bool,
         }
 
         let raw = _Album::deserialize(de)?;
 
         Ok(Album {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
+            sort_name: raw.sort_name.filter(|s| !s.is_empty()),
             artist: raw.artist,
-            artist_id: raw.artist_id.map(|i| i.parse().unwrap()),
+            artist_id: raw.artist_id,
+            artists: raw.artists,
+            display_artist: raw.display_artist,
             cover_id: raw.cover_art,
             duration: raw.duration,
             year: raw.year,
-            genre: raw.genre,
+            genre: raw.genre.as_deref().and_then(id3::decode).map(name::shared),
+            raw_genre: raw.genre,
             song_count: raw.song_count,
-            songs: raw.song,
+            created: raw.created,
+            starred: raw.starred,
+            user_rating: raw.user_rating,
+            musicbrainz_id: raw.music_brainz_id.filter(|s| !s.is_empty()),
+            compilation: raw.is_compilation,
+            disc_titles: raw.disc_titles,
+            release_date: raw.release_date.filter(|d| !d.is_empty()),
+            original_release_date: raw.original_release_date.filter(|d| !d.is_empty()),
+            played: raw.played,
+            record_labels: raw.record_labels.into_iter().map(|l| l.name).collect(),
+            moods: raw.moods,
+            explicit_status: raw
+                .explicit_status
+                .as_deref()
+                .and_then(ExplicitStatus::parse),
+            version: raw.version.filter(|s| !s.is_empty()),
+            // Albums are only sent with their songs when they are fetched on
+            // their own, in which case every song is included.
+            songs: if raw.song.is_empty() && raw.song_count > 0 {
+                OnceLock::new()
+            } else {
+                OnceLock::from(raw.song)
+            },
+            folder: raw.is_dir,
         })
     }
 }
 
-impl Media for Album {
-    fn has_cover_art(&self) -> bool {
-        self.cover_id.is_some()
-    }
+impl Serialize for Album {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Album<'a> {
+            id: &'a AlbumId,
+            name: &'a str,
+            sort_name: Option<&'a str>,
+            artist: Option<&'a str>,
+            artist_id: Option<&'a ArtistId>,
+            artists: &'a [ArtistRef],
+            display_artist: Option<&'a str>,
+            cover_art: Option<&'a CoverId>,
+            song_count: u64,
+            duration: u64,
+            created: &'a str,
+            starred: Option<&'a str>,
+            user_rating: Option<u8>,
+            year: Option<u64>,
+            genre: Option<&'a str>,
+            music_brainz_id: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            is_compilation: Option<bool>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            disc_titles: &'a [DiscTitle],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            release_date: Option<ItemDate>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            original_release_date: Option<ItemDate>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            played: Option<&'a str>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            record_labels: Vec<RecordLabel<&'a str>>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            moods: &'a [String],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            explicit_status: Option<&'static str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            version: Option<&'a str>,
+            song: &'a [Song],
+            #[serde(skip_serializing_if = "std::ops::Not::not")]
+            is_dir: bool,
+        }
 
-    fn cover_id(&self) -> Option<&str> {
-        self.cover_id.as_ref().map(|s| s.as_str())
+        _Album {
+            id: &self.id,
+            name: &self.name,
+            sort_name: self.sort_name.as_deref(),
+            artist: self.artist.as_deref(),
+            artist_id: self.artist_id.as_ref(),
+            artists: &self.artists,
+            display_artist: self.display_artist.as_deref(),
+            cover_art: self.cover_id.as_ref(),
+            song_count: self.song_count,
+            duration: self.duration,
+            created: &self.created,
+            starred: self.starred.as_deref(),
+            user_rating: self.user_rating,
+            year: self.year,
+            genre: self.raw_genre.as_deref(),
+            music_brainz_id: self.musicbrainz_id.as_deref(),
+            is_compilation: self.compilation,
+            disc_titles: &self.disc_titles,
+            release_date: self.release_date,
+            original_release_date: self.original_release_date,
+            played: self.played.as_deref(),
+            record_labels: self
+                .record_labels
+                .iter()
+                .map(|name| RecordLabel { name: &**name })
+                .collect(),
+            moods: &self.moods,
+            explicit_status: self.explicit_status.map(ExplicitStatus::as_str),
+            version: self.version.as_deref(),
+            song: self.songs.get().map_or(&[], Vec::as_slice),
+            is_dir: self.folder,
+        }
+        .serialize(se)
     }
+}
 
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.get_bytes("getCoverArt", query)
+/// Albums are downloaded as a ZIP archive.
+///
+/// Subsonic itself only zips folders, so this needs an album from a server
+/// browsed by folder there; servers that organise the library by ID3 tags,
+/// such as Navidrome, zip albums.
+impl Downloadable for Album {
+    fn download(&self, client: &Client) -> Result<Vec<u8>> {
+        client.get_bytes("download", Query::with("id", &self.id))
     }
 
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+    fn download_url(&self, client: &Client) -> Result<Url> {
+        client.build_url("download", Query::with("id", &self.id))
+    }
+}
 
-        client.build_url("getCoverArt", query)
+impl HasCoverArt for Album {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
     }
 }
 
+/// Detailed information about an album.
 #[derive(Debug)]
 pub struct AlbumInfo {
+    /// Notes on the album. Empty if last.fm has none.
     pub notes: String,
+    /// The album's [last.fm](https://last.fm) landing page.
     pub lastfm_url: String,
+    /// The album's [MusicBrainz](https://musicbrainz.org/) release ID.
     pub musicbrainz_id: String,
-    pub image_urls: (String, String, String),
+    /// URLs for the album's cover image.
+    pub image_urls: ImageUrls,
 }
 
 impl<'de> Deserialize<'de> for AlbumInfo {
@@ -213,21 +848,258 @@ impl<'de> Deserialize<'de> for AlbumInfo {
             notes: raw.notes,
             musicbrainz_id: raw.music_brainz_id,
             lastfm_url: raw.last_fm_url,
-            image_urls: (
-                raw.small_image_url,
-                raw.medium_image_url,
-                raw.large_image_url,
-            ),
+            image_urls: ImageUrls {
+                small: raw.small_image_url,
+                medium: raw.medium_image_url,
+                large: raw.large_image_url,
+            },
         })
     }
 }
 
-fn get_album(client: &Client, id: u64) -> Result<Album> {
-    let res = client.get("getAlbum", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Album>(res)?)
+impl Serialize for AlbumInfo {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _AlbumInfo<'a> {
+            notes: &'a str,
+            music_brainz_id: &'a str,
+            last_fm_url: &'a str,
+            small_image_url: &'a str,
+            medium_image_url: &'a str,
+            large_image_url: &'a str,
+        }
+
+        _AlbumInfo {
+            notes: &self.notes,
+            music_brainz_id: &self.musicbrainz_id,
+            last_fm_url: &self.lastfm_url,
+            small_image_url: &self.image_urls.small,
+            medium_image_url: &self.image_urls.medium,
+            large_image_url: &self.image_urls.large,
+        }
+        .serialize(se)
+    }
+}
+
+fn get_album(client: &Client, id: &AlbumId) -> Result<Album> {
+    client.call(&GetAlbum { id: id.clone() })
+}
+
+/// The most albums `getAlbumList2` returns at once.
+const ALBUM_LIST_MAX: usize = 500;
+
+/// A builder to pick a random album that matches a set of constraints.
+///
+/// The Subsonic API can only pick random albums without constraints, so with
+/// any set, every album matching one of them is listed and one is picked from
+/// those that match the rest. Starring is the cheapest constraint to list,
+/// then genre, then years.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::{Album, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// // Play me a random 90s jazz album.
+/// let album = Album::random_with(&client)
+///     .genre("Jazz")
+///     .years(1990..=1999)
+///     .request()?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[derive(Debug)]
+pub struct RandomAlbum<'a> {
+    client: &'a Client,
+    genre: Option<&'a str>,
+    from_year: Option<usize>,
+    to_year: Option<usize>,
+    starred: bool,
+    folder_id: Option<usize>,
+}
+
+impl<'a> RandomAlbum<'a> {
+    fn new(client: &'a Client) -> RandomAlbum<'a> {
+        RandomAlbum {
+            client,
+            genre: None,
+            from_year: None,
+            to_year: None,
+            starred: false,
+            folder_id: None,
+        }
+    }
+
+    /// Sets the genre that the album must be in.
+    pub fn genre(&mut self, genre: &'a str) -> &mut RandomAlbum<'a> {
+        self.genre = Some(genre);
+        self
+    }
+
+    /// Sets a lower bound on the year that the album was released in.
+    pub fn from_year(&mut self, year: usize) -> &mut RandomAlbum<'a> {
+        self.from_year = Some(year);
+        self
+    }
+
+    /// Sets an upper bound on the year that the album was released in.
+    pub fn to_year(&mut self, year: usize) -> &mut RandomAlbum<'a> {
+        self.to_year = Some(year);
+        self
+    }
+
+    /// Sets both the lower and upper year bounds using a range.
+    ///
+    /// Like a standard Rust range, and unlike [`RandomSongs::in_years`], the
+    /// end year is excluded: `1990..2000` picks albums released in the
+    /// nineties.
+    ///
+    /// [`RandomSongs::in_years`]: ./song/struct.RandomSongs.html#method.in_years
+    pub fn in_years(&mut self, years: Range<usize>) -> &mut RandomAlbum<'a> {
+        self.from_year = Some(years.start);
+        self.to_year = Some(years.end.saturating_sub(1));
+        self
+    }
+
+    /// Sets both the lower and upper year bounds using an inclusive range.
+    pub fn years(&mut self, years: RangeInclusive<usize>) -> &mut RandomAlbum<'a> {
+        self.from_year = Some(*years.start());
+        self.to_year = Some(*years.end());
+        self
+    }
+
+    /// Only picks from albums the user has starred.
+    pub fn starred(&mut self) -> &mut RandomAlbum<'a> {
+        self.starred = true;
+        self
+    }
+
+    /// Sets the folder index that the album must be in.
+    pub fn in_folder(&mut self, id: usize) -> &mut RandomAlbum<'a> {
+        self.folder_id = Some(id);
+        self
+    }
+
+    /// Issues the queries to the Subsonic server. Returns `None` if no album
+    /// matches the constraints.
+    pub fn request(&mut self) -> Result<Option<Album>> {
+        if !self.starred && self.genre.is_none() && self.year_range().is_none() {
+            let album = get_albums(self.client, ListType::Random, Some(1), None, self.folder_id)?;
+            return Ok(album.into_iter().next());
+        }
+
+        let mut matching = Vec::new();
+        for offset in (0..).step_by(ALBUM_LIST_MAX) {
+            let page = self.page(offset)?;
+
+            let len = page.len();
+            matching.extend(page.into_iter().filter(|a| self.matches(a)));
+            if len < ALBUM_LIST_MAX {
+                break;
+            }
+        }
+
+        if matching.is_empty() {
+            return Ok(None);
+        }
+        let i = rand::thread_rng().gen_range(0..matching.len());
+        Ok(Some(matching.swap_remove(i)))
+    }
+
+    /// Returns the range of years set, if either bound is. A missing upper
+    /// bound is the current year.
+    /// Fetches a page of the albums matching the first constraint the
+    /// server can filter by: being starred, then the genre, then the years.
+    fn page(&self, offset: usize) -> Result<Vec<Album>> {
+        let (size, offset, folder_id) = (Some(ALBUM_LIST_MAX), Some(offset), self.folder_id);
+        if self.starred {
+            self.client.call(&GetAlbumList2 {
+                list_type: ListType::Starred,
+                size,
+                offset,
+                folder_id,
+            })
+        } else if let Some(genre) = self.genre {
+            self.client.call(&GetAlbumsByGenre {
+                genre: genre.to_string(),
+                size,
+                offset,
+                folder_id,
+            })
+        } else {
+            let (from_year, to_year) = self.year_range().unwrap_or((0, current_year()));
+            self.client.call(&GetAlbumsByYear {
+                from_year,
+                to_year,
+                size,
+                offset,
+                folder_id,
+            })
+        }
+    }
+
+    fn year_range(&self) -> Option<(usize, usize)> {
+        match (self.from_year, self.to_year) {
+            (None, None) => None,
+            (from, to) => Some((from.unwrap_or(0), to.unwrap_or_else(current_year))),
+        }
+    }
+
+    /// Returns whether an album matches every constraint set.
+    fn matches(&self, album: &Album) -> bool {
+        if self.starred && album.starred.is_none() {
+            return false;
+        }
+        if let Some(genre) = self.genre {
+            let same = |g: &str| g.eq_ignore_ascii_case(genre);
+            if !album.genre.as_deref().is_some_and(same) && !album.raw_genre().is_some_and(same) {
+                return false;
+            }
+        }
+        if let Some((from, to)) = self.year_range() {
+            match album.year {
+                Some(year) if (from as u64..=to as u64).contains(&year) => (),
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Returns the current year in UTC.
+fn current_year() -> usize {
+    timestamp::format(SystemTime::now())[..4]
+        .parse()
+        .unwrap_or(9999)
+}
+
+/// Returns the `fromYear` and `toYear` to list albums released in `years`
+/// with.
+///
+/// The API has no separate option for the order of the list; it lists albums
+/// newest first if the years are given the wrong way round.
+fn year_bounds(years: RangeInclusive<u32>, newest_first: bool) -> (u32, u32) {
+    let (start, end) = years.into_inner();
+    let (earliest, latest) = (start.min(end), start.max(end));
+    if newest_first {
+        (latest, earliest)
+    } else {
+        (earliest, latest)
+    }
 }
 
-fn get_albums<U>(
+pub(crate) fn get_albums<U>(
     client: &Client,
     list_type: ListType,
     size: U,
@@ -237,21 +1109,18 @@ fn get_albums<U>(
 where
     U: Into<Option<usize>>,
 {
-    let args = Query::new()
-        .arg("type", list_type)
-        .arg("size", size.into())
-        .arg("offset", offset.into())
-        .arg("musicFolderId", folder_id.into())
-        .build();
-
-    let album = client.get("getAlbumList2", args)?;
-    Ok(get_list_as!(album, Album))
+    client.call(&GetAlbumList2 {
+        list_type,
+        size: size.into(),
+        offset: offset.into(),
+        folder_id: folder_id.into(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
 
     #[test]
     fn demo_get_albums() {
@@ -265,18 +1134,209 @@ mod tests {
     fn parse_album() {
         let parsed = serde_json::from_value::<Album>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 1);
-        assert_eq!(parsed.name, String::from("Bellevue"));
+        assert_eq!(parsed.id.as_str(), "1");
+        assert_eq!(&*parsed.name, "Bellevue");
         assert_eq!(parsed.song_count, 9);
+        assert_eq!(parsed.duration(), Duration::from_secs(1920));
+        assert_eq!(
+            parsed.songs.get().unwrap()[0].duration(),
+            Some(Duration::from_secs(198))
+        );
+    }
+
+    #[test]
+    fn album_round_trip() {
+        let parsed = serde_json::from_value::<Album>(raw()).unwrap();
+        test_util::assert_round_trip(&parsed);
+
+        let info = serde_json::from_str::<AlbumInfo>(
+            r#"{
+            "notes" : "Bellevue is the fourth album by Misteur Valaire.",
+            "musicBrainzId" : "6f3b4d5e-1c2a-4a8e-9b6a-0c1d2e3f4a5b",
+            "lastFmUrl" : "https://www.last.fm/music/Misteur+Valaire/Bellevue",
+            "smallImageUrl" : "https://lastfm.freetls.fastly.net/i/u/34s/2.png",
+            "mediumImageUrl" : "https://lastfm.freetls.fastly.net/i/u/64s/2.png",
+            "largeImageUrl" : "https://lastfm.freetls.fastly.net/i/u/174s/2.png"
+        }"#,
+        )
+        .unwrap();
+        test_util::assert_round_trip(&info);
+    }
+
+    #[test]
+    fn parse_album_musicbrainz_id() {
+        let mut raw = raw();
+        raw["musicBrainzId"] = "".into();
+        let parsed = serde_json::from_value::<Album>(raw.clone()).unwrap();
+        assert_eq!(parsed.musicbrainz_id, None);
+
+        raw["musicBrainzId"] = "6f3b4d5e-1c2a-4a8e-9b6a-0c1d2e3f4a5b".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+        assert_eq!(
+            parsed.musicbrainz_id,
+            Some(String::from("6f3b4d5e-1c2a-4a8e-9b6a-0c1d2e3f4a5b"))
+        );
+    }
+
+    #[test]
+    fn parse_album_artists() {
+        let mut raw = raw();
+        raw["artists"] = serde_json::json!([
+            { "id": "1", "name": "Misteur Valaire" }
+        ]);
+        raw["displayArtist"] = "Misteur Valaire".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        assert_eq!(parsed.artists[0].id, Some(ArtistId::from(1)));
+        assert_eq!(parsed.display_artist.as_deref(), Some("Misteur Valaire"));
+    }
+
+    #[test]
+    fn parse_album_release() {
+        let mut raw = raw();
+        raw["isCompilation"] = false.into();
+        raw["discTitles"] = serde_json::json!([{ "disc": 2, "title": "Remixes" }]);
+        raw["releaseDate"] = serde_json::json!({ "year": 2017, "month": 3 });
+        raw["originalReleaseDate"] = serde_json::json!({});
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        assert_eq!(parsed.compilation, Some(false));
+        assert_eq!(parsed.disc_titles[0].title, "Remixes");
+        assert_eq!(parsed.release_date.unwrap().to_string(), "2017-03");
+        assert_eq!(parsed.original_release_date, None);
+        assert!(!parsed.is_compilation());
+        test_util::assert_round_trip(&parsed);
+    }
+
+    #[test]
+    fn parse_album_extensions() {
+        let mut raw = raw();
+        raw["played"] = "2023-01-05T20:13:02Z".into();
+        raw["recordLabels"] = serde_json::json!([{ "name": "Hawaiian Time" }]);
+        raw["moods"] = serde_json::json!(["energetic", "happy"]);
+        raw["explicitStatus"] = "clean".into();
+        raw["version"] = "Deluxe Edition".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        assert_eq!(parsed.record_labels, ["Hawaiian Time"]);
+        assert_eq!(parsed.moods, ["energetic", "happy"]);
+        assert_eq!(parsed.explicit_status, Some(ExplicitStatus::Clean));
+        assert_eq!(parsed.version.as_deref(), Some("Deluxe Edition"));
+        #[cfg(feature = "chrono")]
+        assert!(parsed.played_at().is_some());
+        test_util::assert_round_trip(&parsed);
+
+        let mut raw = self::raw();
+        raw["explicitStatus"] = "".into();
+        raw["version"] = "".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+        assert_eq!(parsed.explicit_status, None);
+        assert_eq!(parsed.version, None);
+    }
+
+    #[test]
+    fn parse_album_starred() {
+        let parsed = serde_json::from_value::<Album>(raw()).unwrap();
+        assert_eq!(parsed.starred, None);
+        assert_eq!(parsed.user_rating, None);
+
+        let mut raw = raw();
+        raw["starred"] = "2017-06-01T19:48:25.635Z".into();
+        raw["userRating"] = 5.into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+        assert_eq!(parsed.user_rating, Some(5));
+        assert_eq!(parsed.star_key(), "albumId");
+        #[cfg(feature = "chrono")]
+        assert!(parsed.starred_at().is_some());
+    }
+
+    #[test]
+    fn detect_compilation() {
+        let mut raw = raw();
+        raw["artist"] = "Various Artists".into();
+        let parsed = serde_json::from_value::<Album>(raw.clone()).unwrap();
+        assert!(parsed.is_compilation());
+
+        raw["isCompilation"] = true.into();
+        raw["artist"] = "Misteur Valaire".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+        assert!(parsed.is_compilation());
+    }
+
+    #[test]
+    fn random_album_constraints() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let mut raw = raw();
+        raw["year"] = 1997.into();
+        raw["genre"] = "Jazz".into();
+        let album = serde_json::from_value::<Album>(raw).unwrap();
+
+        let mut random = Album::random_with(&client);
+        assert!(random.genre("jazz").years(1990..=1999).matches(&album));
+        assert!(!random.starred().matches(&album));
+        assert!(!Album::random_with(&client).from_year(2000).matches(&album));
+        assert!(!Album::random_with(&client).genre("(52)").matches(&album));
+        assert!(Album::random_with(&client)
+            .in_years(1997..1998)
+            .matches(&album));
+        assert!(!Album::random_with(&client)
+            .in_years(1990..1997)
+            .matches(&album));
+
+        let open = Album::random_with(&client).from_year(1990).year_range();
+        assert_eq!(open, Some((1990, current_year())));
+        assert!(current_year() >= 2024);
+    }
+
+    #[test]
+    fn year_range() {
+        assert_eq!(year_bounds(1990..=1999, false), (1990, 1999));
+        assert_eq!(year_bounds(1990..=1999, true), (1999, 1990));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 1999..=1990;
+        assert_eq!(year_bounds(reversed, false), (1990, 1999));
+    }
+
+    #[test]
+    fn album_download_url() {
+        let parsed = serde_json::from_value::<Album>(raw()).unwrap();
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let url = Downloadable::download_url(&parsed, &client).unwrap();
+        assert_eq!(url.path(), "/rest/download");
+        assert!(url.query_pairs().any(|(k, v)| k == "id" && v == "1"));
+
+        #[allow(deprecated)]
+        let inherent = parsed.download_url(&client).unwrap();
+        assert_eq!(inherent.path(), url.path());
+    }
+
+    #[test]
+    fn songs_fetched_lazily() {
+        let mut listed = raw();
+        listed.as_object_mut().unwrap().remove("song");
+        let parsed = serde_json::from_value::<Album>(listed).unwrap();
+        assert!(parsed.songs.get().is_none());
+
+        // Only the server knows the songs, so they can only come from the
+        // `getAlbum` request the cassette answers.
+        let mut album = raw();
+        album["song"][0]["id"] = "99".into();
+        let body = serde_json::json!({ "album": album });
+        let client = test_util::replay_client(&[("getAlbum", Query::with("id", "1"), body)]);
+
+        let songs = parsed.songs(&client).unwrap();
+        assert_eq!(songs[0].id.as_str(), "99");
+        assert_eq!(parsed.songs.get().unwrap()[0].id.as_str(), "99");
     }
 
     #[test]
     fn parse_album_deep() {
         let parsed = serde_json::from_value::<Album>(raw()).unwrap();
 
-        assert_eq!(parsed.songs[0].id, 27);
-        assert_eq!(parsed.songs[0].title, String::from("Bellevue Avenue"));
-        assert_eq!(parsed.songs[0].duration, Some(198));
+        let songs = parsed.songs.get().unwrap();
+        assert_eq!(songs[0].id.as_str(), "27");
+        assert_eq!(songs[0].title, String::from("Bellevue Avenue"));
+        assert_eq!(songs[0].duration, Some(198));
     }
 
     fn raw() -> serde_json::Value {
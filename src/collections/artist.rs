@@ -1,147 +1,602 @@
-use std::{fmt, result};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::{fmt, panic, result, thread};
 
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::ser::{Serialize, Serializer};
 
+use super::directory::{self, Directory};
+use crate::endpoint::{
+    GetArtist, GetArtistInfo, GetArtistInfo2, GetArtists, GetSimilarSongs2, GetTopSongs,
+};
+use crate::name;
+use crate::progress::ProgressObserver;
 use crate::query::Query;
-use crate::{Album, Client, Error, Media, Result, Song};
+use crate::song;
+use crate::storage::{LocalStorage, Storage};
+#[cfg(feature = "chrono")]
+use crate::timestamp;
+use crate::{
+    Album, ArtistId, Client, CoverId, Error, HasCoverArt, ImageSize, ImageUrls, Name, Result, Song,
+};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
 
 /// Basic information about an artist.
+///
+/// Two artists are equal if they have the same ID.
 #[derive(Debug, Clone)]
 pub struct Artist {
-    pub id: usize,
-    pub name: String,
-    cover_id: Option<String>,
+    pub id: ArtistId,
+    pub name: Name,
+    /// The name the artist should be sorted by. Only provided by OpenSubsonic
+    /// servers; see the [`sort`] module for sorting on other servers.
+    ///
+    /// [`sort`]: ./sort/index.html
+    pub sort_name: Option<String>,
+    cover_id: Option<CoverId>,
     albums: Vec<Album>,
+    /// Whether the artist is a folder, as listed when browsing by folder.
+    /// Kept as `isDir` when serialized, as the server lists folders.
+    folder: bool,
     pub album_count: usize,
+    /// An ISO8601 timestamp of when the artist was starred, if it has been.
+    ///
+    /// Use [`Annotatable`] to star or rate the artist.
+    ///
+    /// [`Annotatable`]: ./trait.Annotatable.html
+    pub starred: Option<String>,
+    /// The rating the user has given the artist, from 1 to 5.
+    pub user_rating: Option<u8>,
+    /// The artist's [MusicBrainz](https://musicbrainz.org/) ID.
+    pub musicbrainz_id: Option<String>,
+}
+
+/// A reference to an artist credited on a song or album.
+///
+/// OpenSubsonic servers list every credited artist rather than a single
+/// combined string; the full `Artist` can be fetched with [`ArtistRef::get`].
+///
+/// [`ArtistRef::get`]: #method.get
+#[derive(Debug, Clone)]
+pub struct ArtistRef {
+    /// The ID of the artist, or `None` if the artist isn't in the library.
+    pub id: Option<ArtistId>,
+    /// The name of the artist.
+    pub name: Name,
+}
+
+impl ArtistRef {
+    /// Fetches the full artist from the Subsonic server.
+    ///
+    /// Fails if the artist isn't in the library.
+    pub fn get(&self, client: &Client) -> Result<Artist> {
+        match self.id {
+            Some(ref id) => self::get_artist(client, id),
+            None => Err(Error::Other("artist isn't in the library")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ArtistRef {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct _ArtistRef {
+            #[serde(default)]
+            id: Option<ArtistId>,
+            name: Name,
+        }
+
+        let raw = _ArtistRef::deserialize(de)?;
+
+        Ok(ArtistRef {
+            id: raw.id.filter(|id| !id.as_str().is_empty()),
+            name: raw.name,
+        })
+    }
+}
+
+impl Serialize for ArtistRef {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _ArtistRef<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            id: Option<&'a ArtistId>,
+            name: &'a str,
+        }
+
+        _ArtistRef {
+            id: self.id.as_ref(),
+            name: &self.name,
+        }
+        .serialize(se)
+    }
+}
+
+impl fmt::Display for ArtistRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 /// Detailed information about an artist.
 #[derive(Debug, Clone)]
 pub struct ArtistInfo {
-    /// A blurb about the artist.
+    /// A blurb about the artist. Empty if last.fm has none.
     pub biography: String,
     /// The artist's [MusicBrainz](https://musicbrainz.org/) ID.
-    pub musicbrainz_id: String,
+    pub musicbrainz_id: Option<String>,
     /// The artist's [last.fm](https://last.fm) landing page.
-    pub lastfm_url: String,
-    /// URLs for the artist's image; available in small, medium, and large.
-    pub image_urls: (String, String, String),
+    pub lastfm_url: Option<String>,
+    /// URLs for the artist's image.
+    pub image_urls: ImageUrls,
     /// Artists similar to this one. Provided by last.fm.
-    similar_artists: Vec<Artist>,
+    ///
+    /// Artists that aren't in the library, which are only included when
+    /// asked for with [`Artist::similar`], have no ID.
+    ///
+    /// [`Artist::similar`]: ./struct.Artist.html#method.similar
+    pub similar_artists: Vec<ArtistRef>,
+}
+
+impl ArtistInfo {
+    /// Downloads the artist's image in the given size from last.fm.
+    ///
+    /// Returns `None` if last.fm has no image of the artist, or only its
+    /// placeholder image.
+    pub fn download_image(&self, client: &Client, size: ImageSize) -> Result<Option<Vec<u8>>> {
+        self.image_urls.download(client, size)
+    }
 }
 
 impl Artist {
-    pub fn get(client: &Client, id: usize) -> Result<Artist> {
-        self::get_artist(client, id)
+    pub fn get<I>(client: &Client, id: I) -> Result<Artist>
+    where
+        I: Into<ArtistId>,
+    {
+        self::get_artist(client, &id.into())
+    }
+
+    /// Lists all artists on the server, optionally only those in the given
+    /// music folder.
+    ///
+    /// Artists are returned in the order the server indexes them in.
+    pub fn list<U>(client: &Client, folder_id: U) -> Result<Vec<Artist>>
+    where
+        U: Into<Option<usize>>,
+    {
+        let folder_id = folder_id.into();
+        if let Some(index) = client.call_id3(&GetArtists { folder_id })? {
+            let artists = index.into_artists();
+            if !artists.is_empty() || !client.quirks().directory_fallback() {
+                return Ok(artists);
+            }
+        }
+
+        let folders = directory::index(client, folder_id)?;
+        Ok(folders
+            .into_iter()
+            .map(|f| Artist::from_folder(f.id, f.name, Vec::new()))
+            .collect())
+    }
+
+    /// Passes every artist on the server to `f`, optionally only those in the
+    /// given music folder.
+    ///
+    /// Unlike [`list`], artists are parsed as the response arrives rather
+    /// than after it has been read in full, which keeps memory use flat on
+    /// servers with very large libraries. Unlike [`list`], this never falls
+    /// back to browsing by folder.
+    ///
+    /// [`list`]: #method.list
+    pub fn for_each<U, F>(client: &Client, folder_id: U, f: F) -> Result<()>
+    where
+        U: Into<Option<usize>>,
+        F: FnMut(Artist),
+    {
+        let args = Query::with("musicFolderId", folder_id.into());
+        client.get_streamed("getArtists", args, &["artists", "index", "artist"], f)
     }
 
     /// Returns a list of albums released by the artist.
     pub fn albums(&self, client: &Client) -> Result<Vec<Album>> {
-        if self.albums.len() != self.album_count {
-            Ok(self::get_artist(client, self.id)?.albums)
+        if self.folder && self.albums.is_empty() {
+            Ok(self::get_folder_artist(client, &self.id)?.albums)
+        } else if self.albums.len() != self.album_count {
+            Ok(self::get_artist(client, &self.id)?.albums)
         } else {
             Ok(self.albums.clone())
         }
     }
 
+    /// Returns every song on the artist's albums.
+    ///
+    /// Albums are ordered by year, then name, and each album's songs by disc
+    /// and track. Songs that appear on more than one album are only returned
+    /// the first time. The albums are fetched a few at a time, concurrently.
+    pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
+        let mut albums = self.albums(client)?;
+        albums.sort_by(|a, b| {
+            let key = |al: &Album| (al.year.is_none(), al.year, al.name.to_lowercase());
+            key(a).cmp(&key(b))
+        });
+
+        let mut seen = HashSet::new();
+        let mut songs = Vec::new();
+        for mut album_songs in self::album_songs(client, &albums)? {
+            album_songs.sort_by_key(|s| (s.disc_number, s.track));
+            songs.extend(
+                album_songs
+                    .into_iter()
+                    .filter(|s| seen.insert(s.id.clone())),
+            );
+        }
+        Ok(songs)
+    }
+
+    /// Downloads every song on the artist's albums into `dir`, and returns
+    /// the paths of the files written.
+    ///
+    /// Files are laid out as they are on the server, under `dir`, as with
+    /// [`Song::download_to`]. Songs whose
+    /// file already exists are skipped unless `overwrite` is set (defaults to
+    /// false). Songs are downloaded in their original format. `observer` is
+    /// told the songs and bytes done so far.
+    ///
+    /// [`Song::download_to`]: ./song/struct.Song.html#method.download_to
+    pub fn download_discography<P, B, O>(
+        &self,
+        client: &Client,
+        dir: P,
+        overwrite: B,
+        observer: O,
+    ) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+        B: Into<Option<bool>>,
+        O: ProgressObserver,
+    {
+        let storage = LocalStorage::new(dir.as_ref());
+        let written =
+            self.download_discography_to_storage(client, &storage, overwrite, observer)?;
+        Ok(written.iter().map(|p| storage.root().join(p)).collect())
+    }
+
+    /// Downloads every song on the artist's albums into `storage`, and
+    /// returns the paths they are stored at, relative to the storage.
+    ///
+    /// Songs are laid out, skipped and reported as with
+    /// [`download_discography`]. See the [`storage`] module.
+    ///
+    /// [`download_discography`]: #method.download_discography
+    /// [`storage`]: ./storage/index.html
+    pub fn download_discography_to_storage<S, B, O>(
+        &self,
+        client: &Client,
+        storage: &S,
+        overwrite: B,
+        observer: O,
+    ) -> Result<Vec<PathBuf>>
+    where
+        S: Storage + ?Sized,
+        B: Into<Option<bool>>,
+        O: ProgressObserver,
+    {
+        let overwrite = overwrite.into().unwrap_or(false);
+        let songs = self.songs(client)?;
+        song::download_songs(client, &songs, storage, overwrite, observer)
+    }
+
     /// Queries last.fm for more information about the artist.
+    ///
+    /// Artists organised by ID3 tags are looked up with `getArtistInfo2`, and
+    /// the similar artists returned have ID3 artist IDs, so they can be
+    /// fetched with [`ArtistRef::get`] or [`Artist::get`]. Artists adapted
+    /// from folders are looked up with `getArtistInfo`, which expects a
+    /// folder ID.
+    ///
+    /// [`ArtistRef::get`]: ./struct.ArtistRef.html#method.get
+    /// [`Artist::get`]: #method.get
     pub fn info(&self, client: &Client) -> Result<ArtistInfo> {
-        let res = client.get("getArtistInfo", Query::with("id", self.id))?;
-        Ok(serde_json::from_value(res)?)
+        let id = self.id.clone();
+        if self.folder {
+            client.call(&GetArtistInfo {
+                id,
+                count: None,
+                include_not_present: None,
+            })
+        } else {
+            client.call(&GetArtistInfo2 {
+                id,
+                count: None,
+                include_not_present: None,
+            })
+        }
     }
 
     /// Returns a number of random artists similar to this one.
     ///
     /// last.fm suggests a number of similar artists to the one the method is
     /// called on. Optionally takes a `count` to specify the maximum number of
-    /// results to return, and whether to only include artists in the Subsonic
-    /// library (defaults to true).
+    /// results to return, and whether to also include artists that are not in
+    /// the Subsonic library (defaults to false). Use [`ArtistRef::get`] to
+    /// fetch the full artist.
+    ///
+    /// [`ArtistRef::get`]: ./struct.ArtistRef.html#method.get
     pub fn similar<B, U>(
         &self,
         client: &Client,
         count: U,
         include_not_present: B,
-    ) -> Result<Vec<Artist>>
+    ) -> Result<Vec<ArtistRef>>
     where
         B: Into<Option<bool>>,
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("count", count.into())
-            .arg("includeNotPresent", include_not_present.into())
-            .build();
-        let res = serde_json::from_value::<ArtistInfo>(client.get("getArtistInfo", args)?)?;
-        Ok(res.similar_artists)
+        self::similar_artists(client, &self.id, count.into(), include_not_present.into())
     }
 
-    /// Returns the top `count` most played songs released by the artist.
+    /// Explores the artists similar to this one, and those similar to them,
+    /// up to `depth` links away.
+    ///
+    /// At most `fan_out` similar artists are followed from each artist.
+    /// Artists that aren't in the library are only included if
+    /// `include_not_present` is set, and are never explored further, as
+    /// last.fm can only be asked about artists in the library. Each artist
+    /// appears in the graph once, however many artists link to it.
+    ///
+    /// Every artist explored costs a request to the server, so keep `depth`
+    /// and `fan_out` small.
+    pub fn similar_graph<B>(
+        &self,
+        client: &Client,
+        depth: usize,
+        fan_out: usize,
+        include_not_present: B,
+    ) -> Result<SimilarityGraph>
+    where
+        B: Into<Option<bool>>,
+    {
+        let include_not_present = include_not_present.into();
+        let start = ArtistRef {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+        };
+        SimilarityGraph::explore(start, depth, |id| {
+            self::similar_artists(client, id, Some(fan_out), include_not_present)
+        })
+    }
+
+    /// Returns a number of random songs by this artist and similar ones, such
+    /// as to start a radio station from them.
+    ///
+    /// Optionally takes a `count` to specify the maximum number of results to
+    /// return (defaults to 50).
+    pub fn similar_songs<U>(&self, client: &Client, count: U) -> Result<Vec<Song>>
+    where
+        U: Into<Option<usize>>,
+    {
+        client.call(&GetSimilarSongs2 {
+            id: self.id.clone(),
+            count: count.into(),
+        })
+    }
+
+    /// Returns the artist's most popular songs, most popular first.
+    ///
+    /// Popularity is provided by last.fm, and only songs in the library are
+    /// returned. Optionally takes a `count` to specify the maximum number of
+    /// songs to return (defaults to 50).
     pub fn top_songs<U>(&self, client: &Client, count: U) -> Result<Vec<Song>>
     where
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("count", count.into())
-            .build();
+        // `getTopSongs` looks the artist up by name rather than by ID.
+        client.call(&GetTopSongs {
+            artist: self.name.to_string(),
+            count: count.into(),
+        })
+    }
+
+    /// Returns when the artist was starred, if it has been.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<DateTime<Utc>> {
+        self.starred.as_ref().and_then(|s| timestamp::parse(s))
+    }
+}
+
+/// Artists linked by similarity, as explored by [`Artist::similar_graph`].
+///
+/// [`Artist::similar_graph`]: ./struct.Artist.html#method.similar_graph
+#[derive(Debug, Clone, Default)]
+pub struct SimilarityGraph {
+    /// The artists in the graph, starting with the one explored from, in the
+    /// order they were reached.
+    pub artists: Vec<ArtistRef>,
+    /// Links from an artist to one similar to it, as indices into `artists`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl SimilarityGraph {
+    /// Explores the graph breadth first from `start`, asking `similar` for
+    /// the neighbours of each artist in the library.
+    fn explore<F>(start: ArtistRef, depth: usize, mut similar: F) -> Result<SimilarityGraph>
+    where
+        F: FnMut(&ArtistId) -> Result<Vec<ArtistRef>>,
+    {
+        // Artists outside the library have no ID, so go by their name.
+        fn key(artist: &ArtistRef) -> String {
+            match artist.id {
+                Some(ref id) => format!("id:{}", id),
+                None => format!("name:{}", artist.name.to_lowercase()),
+            }
+        }
 
-        let song = client.get("getTopSongs", args)?;
-        Ok(get_list_as!(song, Song))
+        let mut graph = SimilarityGraph::default();
+        let mut seen = HashMap::new();
+        seen.insert(key(&start), 0);
+        graph.artists.push(start);
+
+        let mut frontier = vec![0];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for from in frontier {
+                let id = match graph.artists[from].id {
+                    Some(ref id) => id.clone(),
+                    None => continue,
+                };
+                for artist in similar(&id)? {
+                    let to = match seen.get(&key(&artist)) {
+                        Some(&to) => to,
+                        None => {
+                            let to = graph.artists.len();
+                            seen.insert(key(&artist), to);
+                            graph.artists.push(artist);
+                            next.push(to);
+                            to
+                        }
+                    };
+                    if from != to && !graph.edges.contains(&(from, to)) {
+                        graph.edges.push((from, to));
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(graph)
+    }
+}
+
+impl Artist {
+    /// Returns the parameter `star` and `unstar` take the artist's ID as.
+    ///
+    /// Artists organised by ID3 tags are starred by `artistId`, but folders
+    /// are starred by `id` like songs.
+    pub(crate) fn star_key(&self) -> &'static str {
+        if self.folder {
+            "id"
+        } else {
+            "artistId"
+        }
+    }
+
+    /// Adapts a folder into an artist, for servers browsed by folder.
+    fn from_folder(id: String, name: String, albums: Vec<Album>) -> Artist {
+        Artist {
+            id: ArtistId::new(id),
+            name: name::shared(name.into()),
+            sort_name: None,
+            cover_id: None,
+            album_count: albums.len(),
+            albums,
+            folder: true,
+            starred: None,
+            user_rating: None,
+            musicbrainz_id: None,
+        }
     }
 }
 
 impl<'de> Deserialize<'de> for Artist {
-    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Artist {
-            id: String,
-            name: String,
-            cover_art: Option<String>,
+            id: ArtistId,
+            name: Name,
+            sort_name: Option<String>,
+            cover_art: Option<CoverId>,
             album_count: usize,
+            starred: Option<String>,
+            user_rating: Option<u8>,
+            music_brainz_id: Option<String>,
             #[serde(default)]
             album: Vec<Album>,
+            #[serde(default)]
+            is_dir: bool,
         }
 
         let raw = _Artist::deserialize(de)?;
 
         Ok(Artist {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
+            sort_name: raw.sort_name.filter(|s| !s.is_empty()),
             cover_id: raw.cover_art,
             album_count: raw.album_count,
+            starred: raw.starred,
+            user_rating: raw.user_rating,
+            musicbrainz_id: raw.music_brainz_id.filter(|s| !s.is_empty()),
             albums: raw.album,
+            folder: raw.is_dir,
         })
     }
 }
 
-impl Media for Artist {
-    fn has_cover_art(&self) -> bool {
-        self.cover_id.is_some()
-    }
+impl Serialize for Artist {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Artist<'a> {
+            id: &'a ArtistId,
+            name: &'a str,
+            sort_name: Option<&'a str>,
+            cover_art: Option<&'a CoverId>,
+            album_count: usize,
+            starred: Option<&'a str>,
+            user_rating: Option<u8>,
+            music_brainz_id: Option<&'a str>,
+            album: &'a [Album],
+            #[serde(skip_serializing_if = "std::ops::Not::not")]
+            is_dir: bool,
+        }
 
-    fn cover_id(&self) -> Option<&str> {
-        self.cover_id.as_ref().map(|s| s.as_str())
+        _Artist {
+            id: &self.id,
+            name: &self.name,
+            sort_name: self.sort_name.as_deref(),
+            cover_art: self.cover_id.as_ref(),
+            album_count: self.album_count,
+            starred: self.starred.as_deref(),
+            user_rating: self.user_rating,
+            music_brainz_id: self.musicbrainz_id.as_deref(),
+            album: &self.albums,
+            is_dir: self.folder,
+        }
+        .serialize(se)
     }
+}
 
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+impl HasCoverArt for Artist {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
+    }
+}
 
-        client.get_bytes("getCoverArt", query)
+impl PartialEq for Artist {
+    fn eq(&self, other: &Artist) -> bool {
+        self.id == other.id
     }
+}
 
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+impl Eq for Artist {}
 
-        client.build_url("getCoverArt", query)
+impl Hash for Artist {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
 }
 
@@ -159,49 +614,359 @@ impl<'de> Deserialize<'de> for ArtistInfo {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _ArtistInfo {
+            #[serde(default)]
             biography: String,
-            music_brainz_id: String,
-            last_fm_url: String,
+            music_brainz_id: Option<String>,
+            last_fm_url: Option<String>,
+            #[serde(default)]
             small_image_url: String,
+            #[serde(default)]
             medium_image_url: String,
+            #[serde(default)]
             large_image_url: String,
-            similar_artist: Vec<Artist>,
+            #[serde(default)]
+            similar_artist: Vec<ArtistRef>,
         }
 
         let raw = _ArtistInfo::deserialize(de)?;
 
         Ok(ArtistInfo {
             biography: raw.biography,
-            musicbrainz_id: raw.music_brainz_id,
-            lastfm_url: raw.last_fm_url,
-            image_urls: (
-                raw.small_image_url,
-                raw.medium_image_url,
-                raw.large_image_url,
-            ),
+            musicbrainz_id: raw.music_brainz_id.filter(|s| !s.is_empty()),
+            lastfm_url: raw.last_fm_url.filter(|s| !s.is_empty()),
+            image_urls: ImageUrls {
+                small: raw.small_image_url,
+                medium: raw.medium_image_url,
+                large: raw.large_image_url,
+            },
             similar_artists: raw.similar_artist,
         })
     }
 }
 
+impl Serialize for ArtistInfo {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _ArtistInfo<'a> {
+            biography: &'a str,
+            music_brainz_id: Option<&'a str>,
+            last_fm_url: Option<&'a str>,
+            small_image_url: &'a str,
+            medium_image_url: &'a str,
+            large_image_url: &'a str,
+            similar_artist: &'a [ArtistRef],
+        }
+
+        _ArtistInfo {
+            biography: &self.biography,
+            music_brainz_id: self.musicbrainz_id.as_deref(),
+            last_fm_url: self.lastfm_url.as_deref(),
+            small_image_url: &self.image_urls.small,
+            medium_image_url: &self.image_urls.medium,
+            large_image_url: &self.image_urls.large,
+            similar_artist: &self.similar_artists,
+        }
+        .serialize(se)
+    }
+}
+
+/// The artists of the library, as indexed by `getArtists`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistIndex {
+    /// Space separated articles to ignore when sorting.
+    #[serde(default)]
+    ignored_articles: String,
+    #[serde(default)]
+    index: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    #[serde(default)]
+    artist: Vec<Artist>,
+}
+
+impl ArtistIndex {
+    /// Returns the articles the server ignores when sorting artists, such as
+    /// "The" or "Les".
+    pub fn articles(&self) -> Vec<String> {
+        self.ignored_articles
+            .split_whitespace()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Returns the artists, in the order the server indexes them in.
+    pub fn into_artists(self) -> Vec<Artist> {
+        self.index.into_iter().flat_map(|i| i.artist).collect()
+    }
+}
+
+/// Fetches the artists last.fm considers similar to an artist.
+fn similar_artists(
+    client: &Client,
+    id: &ArtistId,
+    count: Option<usize>,
+    include_not_present: Option<bool>,
+) -> Result<Vec<ArtistRef>> {
+    let info = client.call(&GetArtistInfo2 {
+        id: id.clone(),
+        count,
+        include_not_present,
+    })?;
+    Ok(info.similar_artists)
+}
+
+/// The number of albums fetched at once by `Artist::songs`.
+const CONCURRENT_ALBUMS: usize = 4;
+
+/// Fetches the songs of each album, in the same order as `albums`.
+fn album_songs(client: &Client, albums: &[Album]) -> Result<Vec<Vec<Song>>> {
+    if albums.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let per_thread = albums.len().div_ceil(CONCURRENT_ALBUMS);
+    thread::scope(|scope| {
+        let handles = albums
+            .chunks(per_thread)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|album| album.songs(client))
+                        .collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut songs = Vec::with_capacity(albums.len());
+        for handle in handles {
+            let chunk = handle.join().unwrap_or_else(|e| panic::resume_unwind(e))?;
+            songs.extend(chunk);
+        }
+        Ok(songs)
+    })
+}
+
 /// Fetches an artist from the Subsonic server.
-fn get_artist(client: &Client, id: usize) -> Result<Artist> {
-    let res = client.get("getArtist", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Artist>(res)?)
+fn get_artist(client: &Client, id: &ArtistId) -> Result<Artist> {
+    match client.call_id3(&GetArtist { id: id.clone() })? {
+        Some(artist) => Ok(artist),
+        None => self::get_folder_artist(client, id),
+    }
+}
+
+/// Fetches an artist's folder from the Subsonic server.
+pub(crate) fn get_folder_artist(client: &Client, id: &ArtistId) -> Result<Artist> {
+    let dir = Directory::get(client, id.as_str())?;
+    let albums = dir
+        .folders()?
+        .into_iter()
+        .map(|f| Album::from_folder(f, id))
+        .collect();
+    Ok(Artist::from_folder(id.to_string(), dir.name, albums))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
+
+    #[test]
+    fn folder_round_trip() {
+        let artist = Artist::from_folder("11".into(), "Misteur Valaire".into(), Vec::new());
+        let json = serde_json::to_value(&artist).unwrap();
+        assert_eq!(json["isDir"], true);
+        let parsed = serde_json::from_value::<Artist>(json).unwrap();
+        assert_eq!(parsed.star_key(), "id");
+
+        let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
+        assert!(serde_json::to_value(&parsed)
+            .unwrap()
+            .get("isDir")
+            .is_none());
+    }
 
     #[test]
     fn parse_artist() {
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 1);
-        assert_eq!(parsed.name, String::from("Misteur Valaire"));
+        assert_eq!(parsed.id.as_str(), "1");
+        assert_eq!(&*parsed.name, "Misteur Valaire");
         assert_eq!(parsed.album_count, 1);
+        assert_eq!(parsed.musicbrainz_id, None);
+    }
+
+    #[test]
+    fn artist_round_trip() {
+        let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
+        test_util::assert_round_trip(&parsed);
+
+        let info = serde_json::from_str::<ArtistInfo>(
+            r#"{
+            "biography" : "Misteur Valaire is a band from Sherbrooke.",
+            "musicBrainzId" : "b4f3c1a6-6a0c-4d5c-a0a8-0f0f2f9d2c61",
+            "lastFmUrl" : "https://www.last.fm/music/Misteur+Valaire",
+            "smallImageUrl" : "https://lastfm.freetls.fastly.net/i/u/34s/1.png",
+            "mediumImageUrl" : "https://lastfm.freetls.fastly.net/i/u/64s/1.png",
+            "largeImageUrl" : "https://lastfm.freetls.fastly.net/i/u/174s/1.png",
+            "similarArtist" : [ {
+                "id" : "14",
+                "name" : "The Dada Weatherman",
+                "albumCount" : 4
+            } ]
+        }"#,
+        )
+        .unwrap();
+        test_util::assert_round_trip(&info);
+    }
+
+    #[test]
+    fn parse_artist_starred() {
+        let mut raw = raw();
+        raw["starred"] = "2017-06-01T19:48:25.635Z".into();
+        raw["userRating"] = 4.into();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+
+        assert_eq!(parsed.starred.as_deref(), Some("2017-06-01T19:48:25.635Z"));
+        assert_eq!(parsed.user_rating, Some(4));
+        test_util::assert_round_trip(&parsed);
+    }
+
+    #[test]
+    fn parse_artist_info() {
+        let info = serde_json::from_value::<ArtistInfo>(serde_json::json!({
+            "biography": "",
+            "largeImageUrl": "https://lastfm.freetls.fastly.net/i/u/174s/1.png",
+            "similarArtist": [
+                { "id": "14", "name": "The Dada Weatherman", "albumCount": 4 },
+                { "name": "Chinese Man" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(info.musicbrainz_id, None);
+        assert_eq!(
+            info.image_urls.largest(),
+            Some("https://lastfm.freetls.fastly.net/i/u/174s/1.png")
+        );
+        assert_eq!(info.image_urls.small, "");
+        assert_eq!(info.image_urls.get(ImageSize::Medium), None);
+        assert_eq!(info.similar_artists[0].id, Some(ArtistId::from(14)));
+        assert_eq!(&*info.similar_artists[1].name, "Chinese Man");
+        assert_eq!(info.similar_artists[1].id, None);
+    }
+
+    #[test]
+    fn info_endpoint_by_kind() {
+        let info = |biography: &str| serde_json::json!({ "biography": biography });
+        let client = test_util::replay_client(&[
+            (
+                "getArtistInfo2",
+                Query::with("id", "1"),
+                serde_json::json!({ "artistInfo2": info("tags") }),
+            ),
+            (
+                "getArtistInfo",
+                Query::with("id", "11"),
+                serde_json::json!({ "artistInfo": info("folder") }),
+            ),
+        ]);
+
+        let artist = serde_json::from_value::<Artist>(raw()).unwrap();
+        assert_eq!(artist.info(&client).unwrap().biography, "tags");
+        let folder = Artist::from_folder("11".into(), "Misteur Valaire".into(), Vec::new());
+        assert_eq!(folder.info(&client).unwrap().biography, "folder");
+    }
+
+    #[test]
+    fn placeholder_image() {
+        let placeholder =
+            "https://lastfm.freetls.fastly.net/i/u/174s/2a96cbd8b46e442fc41c2b86b821562f.png";
+        let urls = ImageUrls {
+            small: "https://lastfm.freetls.fastly.net/i/u/34s/1.png".into(),
+            medium: String::new(),
+            large: placeholder.into(),
+        };
+        assert_eq!(urls.get(ImageSize::Large), None);
+        assert_eq!(urls.largest(), Some(urls.small.as_str()));
+
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        assert_eq!(urls.download(&client, ImageSize::Large).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_artist_sort_name() {
+        let mut raw = raw();
+        raw["sortName"] = "valaire misteur".into();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+        assert_eq!(parsed.sort_name, Some(String::from("valaire misteur")));
+    }
+
+    #[test]
+    fn parse_artist_index() {
+        let parsed = serde_json::from_str::<ArtistIndex>(
+            r#"{
+            "ignoredArticles" : "The El La Los Las Le Les",
+            "index" : [ {
+                "name" : "M",
+                "artist" : [ {
+                    "id" : "1",
+                    "name" : "Misteur Valaire",
+                    "coverArt" : "ar-1",
+                    "albumCount" : 1
+                } ]
+            }, {
+                "name" : "D",
+                "artist" : [ {
+                    "id" : "14",
+                    "name" : "The Dada Weatherman",
+                    "coverArt" : "ar-14",
+                    "albumCount" : 4
+                } ]
+            } ]
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.articles().len(), 7);
+        assert_eq!(parsed.articles()[0], "The");
+
+        let artists = parsed.into_artists();
+        assert_eq!(artists.len(), 2);
+        assert_eq!(&*artists[1].name, "The Dada Weatherman");
+    }
+
+    #[test]
+    fn parse_artist_ref() {
+        let parsed =
+            serde_json::from_str::<ArtistRef>(r#"{ "id" : "1", "name" : "Misteur Valaire" }"#)
+                .unwrap();
+
+        assert_eq!(parsed.id, Some(ArtistId::from(1)));
+        assert_eq!(parsed.to_string(), "Misteur Valaire");
+
+        // Artists outside the library come without an ID, or an empty one.
+        for raw in &[
+            r#"{ "name" : "Caravan Palace" }"#,
+            r#"{ "id" : "", "name" : "Caravan Palace" }"#,
+        ] {
+            let parsed = serde_json::from_str::<ArtistRef>(raw).unwrap();
+            assert_eq!(parsed.id, None);
+            assert!(parsed
+                .get(&Client::new("http://localhost", "user", "pass").unwrap())
+                .is_err());
+            assert_eq!(
+                serde_json::to_string(&parsed).unwrap(),
+                r#"{"name":"Caravan Palace"}"#
+            );
+        }
     }
 
     #[test]
@@ -209,19 +974,81 @@ mod tests {
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
 
         assert_eq!(parsed.albums.len(), parsed.album_count);
-        assert_eq!(parsed.albums[0].id, 1);
-        assert_eq!(parsed.albums[0].name, String::from("Bellevue"));
+        assert_eq!(parsed.albums[0].id.as_str(), "1");
+        assert_eq!(&*parsed.albums[0].name, "Bellevue");
         assert_eq!(parsed.albums[0].song_count, 9);
     }
 
+    #[test]
+    fn artist_songs_ordered() {
+        let song = |id: &str, disc: u64, track: u64| {
+            serde_json::json!({
+                "id": id, "isDir": false, "title": id, "discNumber": disc,
+                "track": track, "size": 1, "contentType": "audio/mpeg",
+                "suffix": "mp3", "path": id, "created": "2017-03-12T11:07:25.000Z",
+                "type": "music"
+            })
+        };
+        let album = |id: &str, year: u64, songs: Vec<serde_json::Value>| {
+            serde_json::json!({
+                "id": id, "name": id, "songCount": songs.len(), "duration": 1,
+                "year": year, "created": "2017-03-12T11:07:25.000Z", "song": songs
+            })
+        };
+        let mut raw = raw();
+        raw["albumCount"] = 2.into();
+        raw["album"] = serde_json::json!([
+            album("Later", 2017, vec![song("3", 1, 1), song("1", 1, 2)]),
+            album("Earlier", 2010, vec![song("2", 2, 1), song("1", 1, 1)]),
+        ]);
+
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+        let ids = parsed
+            .songs(&client)
+            .unwrap()
+            .iter()
+            .map(|s| s.id.as_str().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn explore_similar() {
+        let artist = |id: &str, name: &str| ArtistRef {
+            id: Some(ArtistId::new(id)),
+            name: name.into(),
+        };
+        let remote = ArtistRef {
+            id: None,
+            name: "Caravan Palace".into(),
+        };
+        let graph = SimilarityGraph::explore(artist("1", "Misteur Valaire"), 2, |id| {
+            Ok(match id.as_str() {
+                "1" => vec![artist("2", "Chinese Man"), remote.clone()],
+                "2" => vec![artist("1", "Misteur Valaire"), artist("3", "Deluxe")],
+                "3" => panic!("explored past the maximum depth"),
+                _ => vec![],
+            })
+        })
+        .unwrap();
+
+        let names = graph.artists.iter().map(|a| &*a.name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Misteur Valaire", "Chinese Man", "Caravan Palace", "Deluxe"]
+        );
+        assert_eq!(graph.edges, [(0, 1), (0, 2), (1, 0), (1, 3)]);
+    }
+
     #[test]
     fn remote_artist_album_list() {
         let mut srv = test_util::demo_site().unwrap();
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
         let albums = parsed.albums(&mut srv).unwrap();
 
-        assert_eq!(albums[0].id, 1);
-        assert_eq!(albums[0].name, String::from("Bellevue"));
+        assert_eq!(albums[0].id.as_str(), "1");
+        assert_eq!(&*albums[0].name, "Bellevue");
         assert_eq!(albums[0].song_count, 9);
     }
 
@@ -229,7 +1056,7 @@ mod tests {
     fn remote_artist_cover_art() {
         let mut srv = test_util::demo_site().unwrap();
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
-        assert_eq!(parsed.cover_id, Some(String::from("ar-1")));
+        assert_eq!(parsed.cover_id, Some(CoverId::from("ar-1")));
 
         let cover = parsed.cover_art(&mut srv, None).unwrap();
         assert!(!cover.is_empty())
@@ -0,0 +1,184 @@
+//! Browsing the library by folder, for servers that don't organise it by ID3
+//! tags.
+//!
+//! Old Subsonic servers, and some forks, leave `getArtists`, `getArtist` and
+//! `getAlbum` out or answer them with nothing. Their library can still be
+//! browsed with `getIndexes` and `getMusicDirectory`, which list the folders
+//! on disk instead: artists are the top-level folders, albums the folders
+//! inside them. These are adapted into `Artist` and `Album` so that callers
+//! don't need to know which way the server was browsed.
+//!
+//! The folders themselves are public as `Directory`, for browsing them
+//! directly, such as from a song to the folder it is in.
+
+use serde::de::Deserialize;
+use serde_json::Value;
+
+use crate::endpoint::{GetIndexes, GetMusicDirectory};
+use crate::{de, Client, CoverId, Result, Song};
+
+/// A folder on the server, as listed by `getMusicDirectory`.
+///
+/// Songs link to the folder they are in with [`Song::directory`].
+///
+/// [`Song::directory`]: ./song/struct.Song.html#method.directory
+#[derive(Debug, Deserialize)]
+pub struct Directory {
+    /// The ID of the folder.
+    pub id: String,
+    /// The ID of the folder containing this one, or `None` for the top-level
+    /// folders of the library.
+    pub parent: Option<String>,
+    /// The name of the folder.
+    pub name: String,
+    #[serde(default)]
+    child: Vec<Value>,
+}
+
+/// A folder inside a [`Directory`].
+///
+/// [`Directory`]: struct.Directory.html
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Folder {
+    /// The ID of the folder.
+    pub id: String,
+    /// The name of the folder.
+    pub title: String,
+    /// The artist the server credits with the folder's songs, if any.
+    pub artist: Option<String>,
+    /// The year the folder's songs were released, if known.
+    pub year: Option<u64>,
+    /// The genre of the folder's songs, if known.
+    pub genre: Option<String>,
+    /// The ID of the folder's cover art.
+    pub cover_art: Option<CoverId>,
+    /// When the folder was added to the library.
+    #[serde(default)]
+    pub created: String,
+    /// When the user starred the folder, if they did.
+    pub starred: Option<String>,
+    /// The user's rating of the folder, from 1 to 5.
+    pub user_rating: Option<u8>,
+}
+
+impl Folder {
+    /// Fetches the folder, to list what is inside it.
+    pub fn directory(&self, client: &Client) -> Result<Directory> {
+        Directory::get(client, &self.id)
+    }
+}
+
+/// A top-level folder listed by `getIndexes`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct IndexedFolder {
+    pub id: String,
+    pub name: String,
+}
+
+/// The top-level folders of the library, as listed by `getIndexes`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Indexes {
+    #[serde(default)]
+    index: Vec<IndexEntry>,
+    /// Files at the top level of the library, outside any folder.
+    #[cfg(feature = "mirror")]
+    #[serde(default)]
+    child: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    #[serde(default)]
+    artist: Vec<IndexedFolder>,
+}
+
+impl Indexes {
+    /// Returns whether nothing is listed, as when nothing changed since the
+    /// time given to `getIndexes`.
+    #[cfg(feature = "mirror")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.index.is_empty() && self.child.is_empty()
+    }
+}
+
+impl Directory {
+    /// Fetches a folder from the server.
+    pub fn get(client: &Client, id: &str) -> Result<Directory> {
+        client.call(&GetMusicDirectory { id: id.to_string() })
+    }
+
+    /// Returns the folders inside the folder.
+    pub fn folders(&self) -> Result<Vec<Folder>> {
+        self.child
+            .iter()
+            .filter(|c| is_dir(c))
+            .map(|c| Ok(Folder::deserialize(c)?))
+            .collect()
+    }
+
+    /// Returns the songs directly inside the folder.
+    pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
+        let songs = self.child.iter().filter(|c| !is_dir(c)).cloned();
+        de::list(client, Value::Array(songs.collect()), "child")
+    }
+}
+
+/// Lists the top-level folders of the library, optionally only those in the
+/// given music folder.
+pub(crate) fn index(client: &Client, folder_id: Option<usize>) -> Result<Vec<IndexedFolder>> {
+    let indexes = client.call(&GetIndexes {
+        folder_id,
+        ..Default::default()
+    })?;
+    Ok(indexes.index.into_iter().flat_map(|i| i.artist).collect())
+}
+
+fn is_dir(child: &Value) -> bool {
+    child.get("isDir").and_then(Value::as_bool).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Album, ArtistId};
+
+    #[test]
+    fn parse_directory() {
+        let dir = serde_json::from_value::<Directory>(serde_json::json!({
+            "id": "11",
+            "parent": "1",
+            "name": "Misteur Valaire",
+            "child": [
+                { "id": "12", "parent": "11", "isDir": true, "title": "Bellevue",
+                  "artist": "Misteur Valaire", "year": 2017, "coverArt": "12" },
+                { "id": "27", "parent": "11", "isDir": false, "title": "Interlude",
+                  "size": 1, "contentType": "audio/mpeg", "suffix": "mp3",
+                  "path": "Misteur Valaire/Interlude.mp3",
+                  "created": "2017-03-12T11:07:25.000Z", "type": "music" }
+            ]
+        }))
+        .unwrap();
+
+        let folders = dir.folders().unwrap();
+        assert_eq!(dir.parent.as_deref(), Some("1"));
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].title, "Bellevue");
+        assert_eq!(folders[0].year, Some(2017));
+
+        let album = Album::from_folder(folders.into_iter().next().unwrap(), &ArtistId::from(11));
+        assert_eq!(album.id.as_str(), "12");
+        assert_eq!(album.artist.as_deref(), Some("Misteur Valaire"));
+        assert_eq!(album.star_key(), "id");
+        let json = serde_json::to_value(&album).unwrap();
+        assert_eq!(
+            serde_json::from_value::<Album>(json).unwrap().star_key(),
+            "id"
+        );
+
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let songs = dir.songs(&client).unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].title, "Interlude");
+    }
+}
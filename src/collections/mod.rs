@@ -1,13 +1,28 @@
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use std::result;
 
 mod album;
 mod artist;
+mod directory;
 mod playlist;
+#[cfg(feature = "sharing")]
+mod share;
 
-pub use self::album::{Album, AlbumInfo, ListType};
-pub use self::artist::{Artist, ArtistInfo};
+use crate::{Client, Result};
+
+#[cfg(feature = "mirror")]
+pub(crate) use self::album::get_albums;
+pub use self::album::{
+    Album, AlbumInfo, Disc, DiscTitle, ExplicitStatus, ItemDate, ListType, RandomAlbum,
+};
+pub use self::artist::{Artist, ArtistIndex, ArtistInfo, ArtistRef, SimilarityGraph};
+pub(crate) use self::directory::Indexes;
+pub use self::directory::{Directory, Folder};
 pub use self::playlist::Playlist;
+pub(crate) use self::playlist::{create_playlist, delete_playlist, get_playlist, get_playlists};
+#[cfg(feature = "sharing")]
+pub use self::share::Share;
 
 /// A representation of a music folder on a Subsonic server.
 #[derive(Debug)]
@@ -39,8 +54,86 @@ impl<'de> Deserialize<'de> for MusicFolder {
     }
 }
 
+impl Serialize for MusicFolder {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _MusicFolder<'a> {
+            id: String,
+            name: &'a str,
+        }
+
+        _MusicFolder {
+            id: self.id.to_string(),
+            name: &self.name,
+        }
+        .serialize(se)
+    }
+}
+
+/// Links to an image in the sizes provided by last.fm.
+///
+/// A size the server doesn't provide is left empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageUrls {
+    /// The small image, typically 34 pixels wide.
+    pub small: String,
+    /// The medium image, typically 64 pixels wide.
+    pub medium: String,
+    /// The large image, typically 174 pixels wide.
+    pub large: String,
+}
+
+/// The size of an image provided by last.fm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSize {
+    /// Typically 34 pixels wide.
+    Small,
+    /// Typically 64 pixels wide.
+    Medium,
+    /// Typically 174 pixels wide.
+    Large,
+}
+
+/// The name of the image last.fm links to when it has no image of an artist.
+const LASTFM_PLACEHOLDER: &str = "2a96cbd8b46e442fc41c2b86b821562f";
+
+impl ImageUrls {
+    /// Returns the image of the given size, if there is one.
+    ///
+    /// last.fm's placeholder image, a grey star, is treated as no image.
+    pub fn get(&self, size: ImageSize) -> Option<&str> {
+        let url = match size {
+            ImageSize::Small => &self.small,
+            ImageSize::Medium => &self.medium,
+            ImageSize::Large => &self.large,
+        };
+        Some(url.as_str()).filter(|url| !url.is_empty() && !url.contains(LASTFM_PLACEHOLDER))
+    }
+
+    /// Downloads the image of the given size.
+    ///
+    /// The image is fetched from last.fm rather than the Subsonic server.
+    /// Returns `None` if there is no image of that size.
+    pub fn download(&self, client: &Client, size: ImageSize) -> Result<Option<Vec<u8>>> {
+        match self.get(size) {
+            Some(url) => Ok(Some(client.get_external(url)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the largest image available, if there is one.
+    pub fn largest(&self) -> Option<&str> {
+        [ImageSize::Large, ImageSize::Medium, ImageSize::Small]
+            .iter()
+            .find_map(|&size| self.get(size))
+    }
+}
+
 /// A genre contained on a Subsonic server.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Genre {
     /// The name of the genre.
@@ -49,6 +142,6 @@ pub struct Genre {
     pub song_count: u64,
     /// The number of albums in the genre.
     pub album_count: u64,
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     _private: bool,
 }
@@ -1,29 +1,71 @@
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::ser::{Serialize, Serializer};
+use std::hash::{Hash, Hasher};
 use std::result;
+use std::time::Duration;
 
+use crate::endpoint::{CreatePlaylist, DeletePlaylist, GetPlaylist, GetPlaylists, UpdatePlaylist};
 use crate::query::Query;
-use crate::{Client, Error, Media, Result, Song};
+use crate::{Client, CoverId, HasCoverArt, PlaylistId, Result, Song, SongId};
 
 #[derive(Debug)]
 pub struct Playlist {
-    id: u64,
+    id: PlaylistId,
     name: String,
+    comment: String,
+    owner: String,
     duration: u64,
-    cover_id: String,
+    created: String,
+    changed: String,
+    cover_id: Option<CoverId>,
     song_count: u64,
     songs: Vec<Song>,
 }
 
 impl Playlist {
+    /// Returns the ID of the playlist.
+    pub fn id(&self) -> &PlaylistId {
+        &self.id
+    }
+
+    /// Returns an ISO8601 timestamp of when the playlist was last changed.
+    #[cfg(feature = "mirror")]
+    pub(crate) fn changed(&self) -> &str {
+        &self.changed
+    }
+
+    /// Returns the total length of the songs in the playlist.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration)
+    }
+
     /// Fetches the songs contained in a playlist.
     pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
         if self.songs.len() as u64 != self.song_count {
-            Ok(get_playlist(client, self.id)?.songs)
+            Ok(get_playlist(client, &self.id)?.songs)
         } else {
             Ok(self.songs.clone())
         }
     }
+
+    /// Passes every song in the playlist to `f`, parsing the songs as the
+    /// response arrives.
+    ///
+    /// Use this over [`songs`] for playlists too large to comfortably hold in
+    /// memory twice.
+    ///
+    /// [`songs`]: #method.songs
+    pub fn for_each_song<F>(&self, client: &Client, f: F) -> Result<()>
+    where
+        F: FnMut(Song),
+    {
+        client.get_streamed(
+            "getPlaylist",
+            Query::with("id", &self.id),
+            &["playlist", "entry"],
+            f,
+        )
+    }
 }
 
 impl<'de> Deserialize<'de> for Playlist {
@@ -34,7 +76,7 @@ impl<'de> Deserialize<'de> for Playlist {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Playlist {
-            id: String,
+            id: PlaylistId,
             name: String,
             #[serde(default)]
             comment: String,
@@ -43,7 +85,7 @@ impl<'de> Deserialize<'de> for Playlist {
             duration: u64,
             created: String,
             changed: String,
-            cover_art: String,
+            cover_art: Option<String>,
             #[serde(default)]
             songs: Vec<Song>,
         }
@@ -51,69 +93,101 @@ impl<'de> Deserialize<'de> for Playlist {
         let raw = _Playlist::deserialize(de)?;
 
         Ok(Playlist {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
+            comment: raw.comment,
+            owner: raw.owner,
             duration: raw.duration,
-            cover_id: raw.cover_art,
+            created: raw.created,
+            changed: raw.changed,
+            cover_id: raw.cover_art.filter(|s| !s.is_empty()).map(CoverId::from),
             song_count: raw.song_count,
             songs: raw.songs,
         })
     }
 }
 
-impl Media for Playlist {
-    fn has_cover_art(&self) -> bool {
-        !self.cover_id.is_empty()
+impl Serialize for Playlist {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Playlist<'a> {
+            id: &'a PlaylistId,
+            name: &'a str,
+            comment: &'a str,
+            owner: &'a str,
+            song_count: u64,
+            duration: u64,
+            created: &'a str,
+            changed: &'a str,
+            cover_art: Option<&'a CoverId>,
+            songs: &'a [Song],
+        }
+
+        _Playlist {
+            id: &self.id,
+            name: &self.name,
+            comment: &self.comment,
+            owner: &self.owner,
+            song_count: self.song_count,
+            duration: self.duration,
+            created: &self.created,
+            changed: &self.changed,
+            cover_art: self.cover_id.as_ref(),
+            songs: &self.songs,
+        }
+        .serialize(se)
     }
+}
 
-    fn cover_id(&self) -> Option<&str> {
-        Some(self.cover_id.as_ref())
+impl PartialEq for Playlist {
+    fn eq(&self, other: &Playlist) -> bool {
+        self.id == other.id
     }
+}
 
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+impl Eq for Playlist {}
 
-        client.get_bytes("getCoverArt", query)
+impl Hash for Playlist {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
+}
 
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+impl HasCoverArt for Playlist {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
     }
 }
 
-fn get_playlists(client: &Client, user: Option<String>) -> Result<Vec<Playlist>> {
-    let playlist = client.get("getPlaylists", Query::with("username", user))?;
-    Ok(get_list_as!(playlist, Playlist))
+pub(crate) fn get_playlists(client: &Client, user: Option<String>) -> Result<Vec<Playlist>> {
+    client.call(&GetPlaylists { username: user })
 }
 
-fn get_playlist(client: &Client, id: u64) -> Result<Playlist> {
-    let res = client.get("getPlaylist", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Playlist>(res)?)
+pub(crate) fn get_playlist(client: &Client, id: &PlaylistId) -> Result<Playlist> {
+    client.call(&GetPlaylist { id: id.clone() })
 }
 
 /// Creates a playlist with the given name.
 ///
 /// Since API version 1.14.0, the newly created playlist is returned. In earlier
 /// versions, an empty response is returned.
-fn create_playlist(client: &Client, name: String, songs: &[u64]) -> Result<Option<Playlist>> {
-    let args = Query::new()
-        .arg("name", name)
-        .arg_list("songId", songs)
-        .build();
-
-    let res = client.get("createPlaylist", args)?;
+pub(crate) fn create_playlist(
+    client: &Client,
+    name: String,
+    songs: &[SongId],
+) -> Result<Option<Playlist>> {
+    let playlist = client.call(&CreatePlaylist {
+        name,
+        songs: songs.to_vec(),
+    })?;
 
     // TODO API is private
     // if client.api >= "1.14.0".into() {
-    Ok(Some(serde_json::from_value(res)?))
+    Ok(Some(playlist))
     // } else {
     // Ok(None)
     // }
@@ -122,39 +196,35 @@ fn create_playlist(client: &Client, name: String, songs: &[u64]) -> Result<Optio
 /// Updates a playlist. Only the owner of the playlist is privileged to do so.
 fn update_playlist<'a, B, S>(
     client: &Client,
-    id: u64,
+    id: &PlaylistId,
     name: S,
     comment: S,
     public: B,
-    to_add: &[u64],
+    to_add: &[SongId],
     to_remove: &[u64],
 ) -> Result<()>
 where
     S: Into<Option<&'a str>>,
     B: Into<Option<bool>>,
 {
-    let args = Query::new()
-        .arg("id", id)
-        .arg("name", name.into())
-        .arg("comment", comment.into())
-        .arg("public", public.into())
-        .arg_list("songIdToAdd", to_add)
-        .arg_list("songIndexToRemove", to_remove)
-        .build();
-
-    client.get("updatePlaylist", args)?;
-    Ok(())
+    client.call(&UpdatePlaylist {
+        id: id.clone(),
+        name: name.into().map(String::from),
+        comment: comment.into().map(String::from),
+        public: public.into(),
+        songs_to_add: to_add.to_vec(),
+        songs_to_remove: to_remove.to_vec(),
+    })
 }
 
-fn delete_playlist(client: &Client, id: u64) -> Result<()> {
-    client.get("deletePlaylist", Query::with("id", id))?;
-    Ok(())
+pub(crate) fn delete_playlist(client: &Client, id: &PlaylistId) -> Result<()> {
+    client.call(&DeletePlaylist { id: id.clone() })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
 
     // The demo playlist exists, but can't be accessed
     #[test]
@@ -164,12 +234,20 @@ mod tests {
         let songs = parsed.songs(&mut srv);
 
         match songs {
-            Err(::error::Error::Api(::error::ApiError::NotAuthorized(_))) => assert!(true),
+            Err(ref e) if matches!(e.api_error(), Some(crate::ApiError::NotAuthorized(_))) => {
+                assert!(true)
+            }
             Err(e) => panic!("unexpected error: {}", e),
             Ok(_) => panic!("test should have failed; insufficient privilege"),
         }
     }
 
+    #[test]
+    fn playlist_round_trip() {
+        let parsed = serde_json::from_value::<Playlist>(raw()).unwrap();
+        test_util::assert_round_trip(&parsed);
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
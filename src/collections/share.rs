@@ -0,0 +1,111 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::endpoint::{CreateShare, DeleteShare, GetShares};
+use crate::query::IntoArg;
+use crate::{Client, Result, Song};
+
+/// A public link to songs, albums or folders on the server, which anyone can
+/// open without an account until it expires.
+///
+/// What the link serves is up to the server; most serve a page with a
+/// player for the shared songs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Share {
+    /// Unique identifier for the share.
+    pub id: String,
+    /// The public URL of the share.
+    pub url: String,
+    /// The description given when the share was created.
+    pub description: Option<String>,
+    /// The user who created the share.
+    pub username: String,
+    /// An ISO8601 timestamp of when the share was created.
+    pub created: String,
+    /// An ISO8601 timestamp of when the share expires, if it does.
+    pub expires: Option<String>,
+    /// An ISO8601 timestamp of when the share was last opened.
+    pub last_visited: Option<String>,
+    /// The number of times the share has been opened.
+    #[serde(default)]
+    pub visit_count: u64,
+    /// The songs shared.
+    #[serde(default, rename = "entry")]
+    pub songs: Vec<Song>,
+}
+
+impl Share {
+    /// Shares the songs, albums or folders with the IDs `ids`, and returns
+    /// the share.
+    ///
+    /// The share expires at `expires`, if given; otherwise it lasts until it
+    /// is deleted.
+    ///
+    /// # Errors
+    ///
+    /// Aside from other errors the `Client` may cause, the server will error
+    /// if the user isn't allowed to share, or sharing is turned off.
+    pub fn create<A, E>(
+        client: &Client,
+        ids: &[A],
+        description: Option<&str>,
+        expires: E,
+    ) -> Result<Share>
+    where
+        A: IntoArg + Clone,
+        E: Into<Option<SystemTime>>,
+    {
+        let expires = expires.into().map(|t| {
+            t.duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0)
+        });
+        client.call(&CreateShare {
+            ids: ids
+                .iter()
+                .map(|a| a.clone().into_arg().to_string())
+                .collect(),
+            description: description.map(String::from),
+            expires,
+        })
+    }
+
+    /// Lists the shares the user has created.
+    pub fn list(client: &Client) -> Result<Vec<Share>> {
+        client.call(&GetShares)
+    }
+
+    /// Deletes the share, so its link stops working.
+    pub fn delete(&self, client: &Client) -> Result<()> {
+        client.call(&DeleteShare {
+            id: self.id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_share() {
+        let share = serde_json::from_value::<Share>(serde_json::json!({
+            "id": "12",
+            "url": "http://localhost/share/abc",
+            "username": "admin",
+            "created": "2024-01-01T00:00:00.000Z",
+            "expires": "2024-01-01T01:00:00.000Z",
+            "visitCount": 3,
+            "entry": [{
+                "id": "27", "title": "Bellevue", "size": 1, "contentType": "audio/mpeg",
+                "suffix": "mp3", "path": "a/b.mp3", "created": "2018-01-01T00:00:00.000Z",
+                "type": "music", "isDir": false,
+            }],
+        }))
+        .unwrap();
+        assert_eq!(share.url, "http://localhost/share/abc");
+        assert_eq!(share.description, None);
+        assert_eq!(share.visit_count, 3);
+        assert_eq!(share.songs[0].title, "Bellevue");
+    }
+}
@@ -0,0 +1,702 @@
+//! Helpers for deserializing the lists returned by the server.
+
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::{self, Display};
+use std::io::Read;
+use std::marker::PhantomData;
+use std::result;
+
+#[cfg(feature = "rayon")]
+use crate::name;
+use crate::{ApiError, Client, Error, Result};
+
+/// Deserializes the list stored under `field` in a response.
+///
+/// Servers don't agree on the shape of lists: some send a one-element list as
+/// a bare object, leave out empty lists entirely, or return the list without
+/// the object wrapping it. All of these are accepted.
+///
+/// By default a single malformed element fails the whole list. If the client
+/// has [lenient lists] enabled, malformed elements are skipped instead and
+/// logged, along with their raw JSON, as warnings.
+///
+/// [lenient lists]: ../struct.Client.html#method.with_lenient_lists
+pub(crate) fn list<T>(client: &Client, value: Value, field: &'static str) -> Result<Vec<T>>
+where
+    T: Element,
+{
+    let items = match value {
+        Value::Object(mut map) => map.remove(field).unwrap_or(Value::Null),
+        Value::Array(items) => Value::Array(items),
+        Value::Null => Value::Null,
+        _ => return Err(<serde_json::Error as de::Error>::missing_field(field).into()),
+    };
+
+    let items = one_or_many(items);
+    client.intern(|| deserialize_items(client, items, field))
+}
+
+/// A type that can be deserialized as an element of a list.
+///
+/// With the `rayon` feature, elements must also be `Send`, so that large
+/// lists can be deserialized in parallel.
+#[cfg(not(feature = "rayon"))]
+pub(crate) trait Element: DeserializeOwned {}
+
+#[cfg(not(feature = "rayon"))]
+impl<T: DeserializeOwned> Element for T {}
+
+/// A type that can be deserialized as an element of a list.
+///
+/// With the `rayon` feature, elements must also be `Send`, so that large
+/// lists can be deserialized in parallel.
+#[cfg(feature = "rayon")]
+pub(crate) trait Element: DeserializeOwned + Send {}
+
+#[cfg(feature = "rayon")]
+impl<T: DeserializeOwned + Send> Element for T {}
+
+/// The number of elements from which lists are deserialized in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 1024;
+
+/// Deserializes the elements of a list, skipping or failing on malformed
+/// elements as configured on the client.
+///
+/// With the `rayon` feature, large lists are deserialized in parallel, unless
+/// names are being interned: the interner is only available to the thread
+/// that set it up.
+fn deserialize_items<T>(client: &Client, items: Vec<Value>, field: &str) -> Result<Vec<T>>
+where
+    T: Element,
+{
+    let lenient = client.lenient_lists();
+    let checked = lenient || client.json_capture().is_some();
+
+    #[cfg(feature = "rayon")]
+    {
+        if items.len() >= PARALLEL_THRESHOLD && !name::interning() {
+            use rayon::prelude::*;
+
+            if !checked {
+                return items
+                    .into_par_iter()
+                    .map(|item| Ok(serde_json::from_value(item)?))
+                    .collect();
+            }
+            let total = items.len();
+            let results = items
+                .into_par_iter()
+                .map(|item| T::deserialize(&item).map_err(|e| (e, item)))
+                .collect::<Vec<_>>();
+            return collect_checked(client, results, total, field);
+        }
+    }
+
+    if !checked {
+        return items
+            .into_iter()
+            .map(|item| Ok(serde_json::from_value(item)?))
+            .collect();
+    }
+    let total = items.len();
+    let results = items
+        .into_iter()
+        .map(|item| T::deserialize(&item).map_err(|e| (e, item)));
+    collect_checked(client, results, total, field)
+}
+
+/// Collects the results of deserializing each element of a list, skipping
+/// malformed elements in lenient mode and failing on them otherwise.
+fn collect_checked<T, I>(client: &Client, results: I, total: usize, field: &str) -> Result<Vec<T>>
+where
+    I: IntoIterator<Item = result::Result<T, (serde_json::Error, Value)>>,
+{
+    let lenient = client.lenient_lists();
+    let mut list = Vec::with_capacity(total);
+    for result in results {
+        match result {
+            Ok(t) => list.push(t),
+            Err((e, item)) if lenient => {
+                warn!("Skipping invalid element of `{}`: {}: {}", field, e, item)
+            }
+            Err((e, item)) => return Err(error(client, e, &item)),
+        }
+    }
+
+    if list.len() < total {
+        warn!(
+            "Skipped {} of {} elements of `{}`",
+            total - list.len(),
+            total,
+            field
+        );
+    }
+
+    Ok(list)
+}
+
+/// Deserializes a response, interning names and capturing JSON on failure as
+/// configured on the client.
+pub(crate) fn from_value<T>(client: &Client, value: Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    client.intern(|| {
+        if client.json_capture().is_none() {
+            return Ok(serde_json::from_value(value)?);
+        }
+        T::deserialize(&value).map_err(|e| error(client, e, &value))
+    })
+}
+
+/// Converts a deserialization error, attaching the JSON that caused it if the
+/// client captures JSON.
+pub(crate) fn error<J>(client: &Client, source: serde_json::Error, json: J) -> Error
+where
+    J: Display,
+{
+    match client.json_capture() {
+        Some(max_len) => Error::Deserialize {
+            source,
+            json: truncate(json.to_string(), max_len),
+        },
+        None => Error::Serde(source),
+    }
+}
+
+/// Truncates a string to at most `max_len` bytes, marking where it was cut.
+fn truncate(mut s: String, max_len: usize) -> String {
+    if s.len() > max_len {
+        let mut end = max_len;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        s.push_str("...");
+    }
+    s
+}
+
+/// Normalizes a value that should be a list. A missing list is empty, and a
+/// bare object is a list of one.
+fn one_or_many(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        item => vec![item],
+    }
+}
+
+/// Deserializes a list from a response as it is read, passing each element
+/// to `f` without holding the whole response in memory.
+///
+/// `path` gives the keys leading to the list from the body of the response,
+/// such as `["artists", "index", "artist"]` for the artists of `getArtists`.
+/// Every level below the first may be a list, a single object or missing, as
+/// with [`list`]. Other keys of the body are ignored.
+///
+/// [`list`]: fn.list.html
+pub(crate) fn stream<R, T, F>(
+    client: &Client,
+    reader: R,
+    path: &'static [&'static str],
+    f: F,
+) -> Result<()>
+where
+    R: Read,
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    let mut sink = Sink {
+        client,
+        field: path.last().cloned().unwrap_or(""),
+        lenient: client.lenient_lists(),
+        f,
+        error: None,
+        api_error: None,
+        total: 0,
+        skipped: 0,
+        _element: PhantomData,
+    };
+
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let result = client.intern(|| {
+        let seed = ResponseSeed {
+            sink: &mut sink,
+            path,
+        };
+        seed.deserialize(&mut de)
+    });
+    if let Some(e) = sink.error {
+        return Err(e);
+    }
+    result?;
+    de.end()?;
+
+    if let Some(e) = sink.api_error {
+        return Err(e.into());
+    }
+    if sink.skipped > 0 {
+        warn!(
+            "Skipped {} of {} elements of `{}`",
+            sink.skipped, sink.total, sink.field
+        );
+    }
+    Ok(())
+}
+
+/// Receives the elements of a streamed list.
+struct Sink<'c, T, F> {
+    client: &'c Client,
+    field: &'static str,
+    lenient: bool,
+    f: F,
+    /// An error to return instead of the one reported by the deserializer.
+    error: Option<Error>,
+    api_error: Option<ApiError>,
+    total: usize,
+    skipped: usize,
+    _element: PhantomData<fn(T)>,
+}
+
+impl<'c, T, F> Sink<'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    fn element<'de, D>(&mut self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.total += 1;
+        if !self.lenient && self.client.json_capture().is_none() {
+            (self.f)(T::deserialize(de)?);
+            return Ok(());
+        }
+
+        let item = Value::deserialize(de)?;
+        match T::deserialize(&item) {
+            Ok(t) => (self.f)(t),
+            Err(e) if self.lenient => {
+                self.skipped += 1;
+                warn!(
+                    "Skipping invalid element of `{}`: {}: {}",
+                    self.field, e, item
+                );
+            }
+            Err(e) => {
+                self.error = Some(error(self.client, e, &item));
+                return Err(de::Error::custom("invalid list element"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The top level of a response: `{"subsonic-response": {...}}`.
+struct ResponseSeed<'s, 'c, T, F> {
+    sink: &'s mut Sink<'c, T, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, 's, 'c, T, F> DeserializeSeed<'de> for ResponseSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, 's, 'c, T, F> Visitor<'de> for ResponseSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Subsonic response")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> result::Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "subsonic-response" {
+                map.next_value_seed(BodySeed {
+                    sink: &mut *self.sink,
+                    path: self.path,
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The body of a response, holding its status and the requested object.
+struct BodySeed<'s, 'c, T, F> {
+    sink: &'s mut Sink<'c, T, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, 's, 'c, T, F> DeserializeSeed<'de> for BodySeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, 's, 'c, T, F> Visitor<'de> for BodySeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the body of a Subsonic response")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> result::Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "error" {
+                self.sink.api_error = Some(map.next_value()?);
+            } else if key == self.path[0] {
+                map.next_value_seed(LevelSeed {
+                    sink: &mut *self.sink,
+                    path: &self.path[1..],
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An object holding the next level of the path.
+struct LevelSeed<'s, 'c, T, F> {
+    sink: &'s mut Sink<'c, T, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, 's, 'c, T, F> DeserializeSeed<'de> for LevelSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, 's, 'c, T, F> Visitor<'de> for LevelSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an object holding `{}`", self.path[0])
+    }
+
+    fn visit_map<A>(self, mut map: A) -> result::Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.path[0] {
+                map.next_value_seed(ItemsSeed {
+                    sink: &mut *self.sink,
+                    path: &self.path[1..],
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One or many elements. If the path continues, each element is an object
+/// holding the next level; otherwise the elements are passed to the sink.
+struct ItemsSeed<'s, 'c, T, F> {
+    sink: &'s mut Sink<'c, T, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, 's, 'c, T, F> DeserializeSeed<'de> for ItemsSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_any(self)
+    }
+}
+
+impl<'de, 's, 'c, T, F> Visitor<'de> for ItemsSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list or a single object")
+    }
+
+    fn visit_unit<E>(self) -> result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> result::Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let ItemsSeed { sink, path } = self;
+        while let Some(()) = seq.next_element_seed(ItemSeed {
+            sink: &mut *sink,
+            path,
+        })? {}
+        Ok(())
+    }
+
+    fn visit_map<A>(self, map: A) -> result::Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        if self.path.is_empty() {
+            self.sink.element(MapAccessDeserializer::new(map))
+        } else {
+            LevelSeed {
+                sink: self.sink,
+                path: self.path,
+            }
+            .visit_map(map)
+        }
+    }
+}
+
+/// A single element of a list.
+struct ItemSeed<'s, 'c, T, F> {
+    sink: &'s mut Sink<'c, T, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, 's, 'c, T, F> DeserializeSeed<'de> for ItemSeed<'s, 'c, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.path.is_empty() {
+            self.sink.element(de)
+        } else {
+            de.deserialize_map(LevelSeed {
+                sink: self.sink,
+                path: self.path,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MusicFolder;
+    use serde_json::json;
+
+    fn raw() -> Value {
+        serde_json::from_str(
+            r#"{"musicFolder" : [
+                { "id" : "0", "name" : "Music" },
+                { "id" : "broken" },
+                { "id" : "1", "name" : "Podcasts" }
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn lenient_list() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        assert!(list::<MusicFolder>(&client, raw(), "musicFolder").is_err());
+
+        let client = client.with_lenient_lists(true);
+        let folders = list::<MusicFolder>(&client, raw(), "musicFolder").unwrap();
+        assert_eq!(folders.len(), 2);
+        assert_eq!(folders[1].name, "Podcasts");
+    }
+
+    #[test]
+    fn captured_json() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        match list::<MusicFolder>(&client, raw(), "musicFolder") {
+            Err(Error::Serde(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let client = client.with_json_capture(12);
+        match list::<MusicFolder>(&client, raw(), "musicFolder") {
+            Err(Error::Deserialize { json, .. }) => assert_eq!(json, r#"{"id":"broke..."#),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn streamed_list() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let body = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "musicFolders": {
+                "musicFolder": [
+                    { "id": "0", "name": "Music" },
+                    { "id": "1", "name": "Podcasts" }
+                ]
+            },
+            "extension": {
+                "musicFolder": [{ "id": "2", "name": "Audiobooks" }]
+            }
+        }}"#;
+
+        let mut names = Vec::new();
+        stream(
+            &client,
+            body.as_bytes(),
+            &["musicFolders", "musicFolder"],
+            |f: MusicFolder| names.push(f.name),
+        )
+        .unwrap();
+        assert_eq!(names, vec!["Music", "Podcasts"]);
+    }
+
+    #[test]
+    fn streamed_nested_list() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let body = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "artists": {
+                "ignoredArticles": "The",
+                "index": [
+                    { "name": "A", "artist": { "id": "0", "name": "Music" } },
+                    { "name": "B" },
+                    { "name": "C", "artist": [{ "id": "1" }, { "id": "2", "name": "Podcasts" }] }
+                ]
+            }
+        }}"#;
+
+        let mut names = Vec::new();
+        let path = &["artists", "index", "artist"];
+        let push = |f: MusicFolder| names.push(f.name);
+        assert!(stream(&client, body.as_bytes(), path, push).is_err());
+
+        let client = client.with_lenient_lists(true);
+        let push = |f: MusicFolder| names.push(f.name);
+        stream(&client, body.as_bytes(), path, push).unwrap();
+        assert_eq!(names, vec!["Music", "Music", "Podcasts"]);
+    }
+
+    #[test]
+    fn streamed_api_error() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let body = r#"{"subsonic-response": {
+            "status": "failed",
+            "version": "1.16.1",
+            "error": { "code": 70, "message": "Playlist not found" }
+        }}"#;
+
+        match stream(
+            &client,
+            body.as_bytes(),
+            &["playlist", "entry"],
+            |_: MusicFolder| (),
+        ) {
+            Err(Error::Api(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_list() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let folders = (0..PARALLEL_THRESHOLD * 2)
+            .map(|i| json!({ "id": i.to_string(), "name": format!("Folder {}", i) }))
+            .collect::<Vec<_>>();
+        let mut raw = json!({ "musicFolder": folders });
+
+        let parsed = list::<MusicFolder>(&client, raw.clone(), "musicFolder").unwrap();
+        assert_eq!(parsed.len(), PARALLEL_THRESHOLD * 2);
+        assert_eq!(parsed[1500].name, "Folder 1500");
+
+        raw["musicFolder"][1500] = json!({ "id": "1500" });
+        assert!(list::<MusicFolder>(&client, raw.clone(), "musicFolder").is_err());
+        let client = client.with_lenient_lists(true);
+        let parsed = list::<MusicFolder>(&client, raw, "musicFolder").unwrap();
+        assert_eq!(parsed.len(), PARALLEL_THRESHOLD * 2 - 1);
+        assert_eq!(parsed[1500].name, "Folder 1501");
+    }
+
+    #[test]
+    fn list_shapes() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let single = json!({ "musicFolder": { "id": "0", "name": "Music" } });
+        let bare = json!([{ "id": "0", "name": "Music" }]);
+
+        let folders = list::<MusicFolder>(&client, single, "musicFolder").unwrap();
+        assert_eq!(folders[0].name, "Music");
+        let folders = list::<MusicFolder>(&client, bare, "musicFolder").unwrap();
+        assert_eq!(folders[0].name, "Music");
+        assert!(list::<MusicFolder>(&client, json!({}), "musicFolder")
+            .unwrap()
+            .is_empty());
+        assert!(list::<MusicFolder>(&client, Value::Null, "musicFolder")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+//! Decoding songs into PCM samples with [symphonia].
+//!
+//! A [`PcmDecoder`] streams a song from the server and decodes it packet by
+//! packet into interleaved `f32` samples, for players that feed an audio
+//! backend themselves. Songs in a format the decoder doesn't understand are
+//! transcoded to MP3 by the server first.
+//!
+//! The stream is read through a [`StreamReader`], so the decoder can seek.
+//! Seeking back is immediate. Seeking far ahead requests the stream again
+//! from there if the server serves byte ranges of the song, as it does for
+//! songs it doesn't transcode; otherwise it waits for the stream to arrive up
+//! to that point.
+//!
+//! [symphonia]: https://docs.rs/symphonia
+//! [`PcmDecoder`]: struct.PcmDecoder.html
+//! [`StreamReader`]: ../struct.StreamReader.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let song = Song::get(&client, 1)?;
+//!
+//! let mut decoder = song.decode(&client)?;
+//! while let Some(samples) = decoder.next_samples()? {
+//!     // Hand `samples` to the audio backend.
+//! }
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::TimeBase;
+
+use crate::song::Song;
+use crate::{Client, Error, Result, StreamReader};
+
+impl MediaSource for StreamReader {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        StreamReader::byte_len(self)
+    }
+}
+
+/// Decodes a song streamed from the server into PCM samples.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: index.html
+pub struct PcmDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    samples: Option<SampleBuffer<f32>>,
+    spec: Option<SignalSpec>,
+    /// Frames to drop before the position last seeked to.
+    skip: u64,
+}
+
+impl PcmDecoder {
+    /// Decodes the media read by `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the format of the media isn't recognised, or it holds no
+    /// audio that can be decoded.
+    pub fn new(reader: StreamReader) -> Result<PcmDecoder> {
+        let mut hint = Hint::new();
+        if let Some(mime) = reader.content_type() {
+            hint.mime_type(mime);
+        }
+        let stream = MediaSourceStream::new(Box::new(reader), Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(decode_error)?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| Error::Decode("no audio track found".into()))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(decode_error)?;
+
+        Ok(PcmDecoder {
+            track_id: track.id,
+            time_base: track.codec_params.time_base,
+            format,
+            decoder,
+            samples: None,
+            spec: None,
+            skip: 0,
+        })
+    }
+
+    /// Returns the sample rate of the audio, once the first samples have been
+    /// decoded, or if the container states it.
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.spec
+            .map(|s| s.rate)
+            .or(self.decoder.codec_params().sample_rate)
+    }
+
+    /// Returns the number of channels the samples are interleaved from, once
+    /// the first samples have been decoded, or if the container states it.
+    pub fn channels(&self) -> Option<usize> {
+        self.spec
+            .map(|s| s.channels.count())
+            .or_else(|| self.decoder.codec_params().channels.map(|c| c.count()))
+    }
+
+    /// Returns the length of the song, if the container states it.
+    pub fn duration(&self) -> Option<Duration> {
+        let params = self.decoder.codec_params();
+        let frames = params.n_frames?;
+        let time = self.time_base?.calc_time(frames);
+        Some(time.into())
+    }
+
+    /// Decodes the next packet of the song, and returns its samples with the
+    /// channels interleaved. Returns `None` at the end of the song.
+    ///
+    /// Packets that fail to decode are skipped, as decoders recover from
+    /// corrupt data at the next packet.
+    pub fn next_samples(&mut self) -> Result<Option<&[f32]>> {
+        let start = loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(None)
+                }
+                Err(e) => return Err(decode_error(e)),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(e)) => {
+                    warn!("Skipping packet that failed to decode: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(decode_error(e)),
+            };
+
+            let spec = *decoded.spec();
+            let capacity = decoded.capacity() as u64;
+            let fits = self
+                .samples
+                .as_ref()
+                .is_some_and(|s| s.capacity() as u64 >= capacity * spec.channels.count() as u64);
+            if self.spec != Some(spec) || !fits {
+                self.samples = Some(SampleBuffer::new(capacity, spec));
+                self.spec = Some(spec);
+            }
+            let samples = self.samples.as_mut().unwrap();
+            samples.copy_interleaved_ref(decoded);
+
+            let channels = spec.channels.count();
+            let frames = (samples.len() / channels) as u64;
+            let skipped = self.skip.min(frames);
+            self.skip -= skipped;
+            if skipped < frames {
+                break skipped as usize * channels;
+            }
+        };
+        let samples = self.samples.as_ref().unwrap();
+        Ok(Some(&samples.samples()[start..]))
+    }
+
+    /// Seeks to `to` from the start of the song, and returns the position
+    /// seeked to, which is `to` unless it is past the end.
+    ///
+    /// Seeking far ahead requests the stream again from the new position if
+    /// the server serves byte ranges of it, and waits for the stream to
+    /// arrive up to there otherwise.
+    pub fn seek(&mut self, to: Duration) -> Result<Duration> {
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: to.into(),
+                    track_id: Some(self.track_id),
+                },
+            )
+            .map_err(decode_error)?;
+        self.decoder.reset();
+        // Seeks land at the start of a packet; the frames before the time
+        // asked for are dropped as they are decoded.
+        self.skip = seeked.required_ts.saturating_sub(seeked.actual_ts);
+        Ok(match self.time_base {
+            Some(base) => base.calc_time(seeked.required_ts).into(),
+            None => to,
+        })
+    }
+}
+
+impl fmt::Debug for PcmDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PcmDecoder")
+            .field("track_id", &self.track_id)
+            .field("spec", &self.spec)
+            .finish()
+    }
+}
+
+impl Song {
+    /// Streams the song, and returns a decoder over its samples.
+    ///
+    /// The song is streamed as the server sends it if it can be decoded, and
+    /// transcoded to MP3 otherwise.
+    pub fn decode(&self, client: &Client) -> Result<PcmDecoder> {
+        PcmDecoder::new(self.decodable_reader(client)?)
+    }
+}
+
+fn decode_error(err: SymphoniaError) -> Error {
+    match err {
+        SymphoniaError::IoError(e) => Error::Io(e),
+        e => Error::Decode(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A second of a 440 Hz tone as 16-bit mono WAV.
+    fn wav() -> Vec<u8> {
+        let rate = 8000u32;
+        let data = (0..rate)
+            .flat_map(|i| {
+                let t = f64::from(i) / f64::from(rate);
+                let v = ((t * 440.0 * 2.0 * std::f64::consts::PI).sin() * 8000.0) as i16;
+                v.to_le_bytes().to_vec()
+            })
+            .collect::<Vec<u8>>();
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+        wav
+    }
+
+    fn decoder() -> PcmDecoder {
+        let wav = wav();
+        let len = wav.len() as u64;
+        let reader = StreamReader::new(Cursor::new(wav), Some(len), Some("audio/wav".into()));
+        PcmDecoder::new(reader).unwrap()
+    }
+
+    #[test]
+    fn decodes_wav() {
+        let mut decoder = decoder();
+        assert_eq!(decoder.duration(), Some(Duration::from_secs(1)));
+
+        let mut total = 0;
+        while let Some(samples) = decoder.next_samples().unwrap() {
+            assert!(samples.iter().all(|s| s.abs() <= 1.0));
+            total += samples.len();
+        }
+        assert_eq!(total, 8000);
+        assert_eq!(decoder.sample_rate(), Some(8000));
+        assert_eq!(decoder.channels(), Some(1));
+    }
+
+    #[test]
+    fn seeks() {
+        let mut decoder = decoder();
+        let seeked = decoder.seek(Duration::from_millis(500)).unwrap();
+        assert_eq!(seeked, Duration::from_millis(500));
+
+        let mut total = 0;
+        while let Some(samples) = decoder.next_samples().unwrap() {
+            total += samples.len();
+        }
+        assert_eq!(total, 4000);
+    }
+
+    #[test]
+    fn rejects_unknown_format() {
+        let reader = StreamReader::new(Cursor::new(vec![0; 64]), Some(64), None);
+        assert!(PcmDecoder::new(reader).is_err());
+    }
+}
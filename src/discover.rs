@@ -0,0 +1,259 @@
+//! Fresh mixes of songs the user is likely to enjoy but rarely plays.
+//!
+//! [`mix`] starts from what the user starred, asks the server for songs
+//! similar to it and for the top songs of the artists involved, and keeps
+//! those that haven't been played much. The result can be kept on the server
+//! as a playlist with [`save`].
+//!
+//! [`mix`]: fn.mix.html
+//! [`save`]: fn.save.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::discover::{self, MixOptions};
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! let songs = discover::mix(&client, &MixOptions::default())?;
+//! discover::save(&client, "Discover", &songs)?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::SliceRandom;
+
+use crate::collections::create_playlist;
+use crate::endpoint::{GetSimilarSongs2, GetTopSongs};
+use crate::{ArtistId, Client, Playlist, Result, Song, SongId};
+
+/// What a [`mix`] should contain.
+///
+/// [`mix`]: fn.mix.html
+#[derive(Debug, Clone)]
+pub struct MixOptions {
+    /// The number of songs in the mix. Defaults to 30.
+    pub size: usize,
+    /// Leave out songs played more than this many times. Defaults to 2.
+    pub max_plays: Option<u64>,
+    /// The number of starred artists the mix is based on. Defaults to 5.
+    pub seeds: usize,
+    /// The most songs by any one artist. Defaults to 3.
+    pub per_artist: usize,
+    /// Only use starred content in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Default for MixOptions {
+    fn default() -> MixOptions {
+        MixOptions {
+            size: 30,
+            max_plays: Some(2),
+            seeds: 5,
+            per_artist: 3,
+            folder_id: None,
+        }
+    }
+}
+
+/// An artist a mix is based on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Seed {
+    id: ArtistId,
+    name: String,
+}
+
+/// Creates a mix of songs related to what the user starred, in random order.
+///
+/// Up to [`seeds`] artists are picked at random among those the user starred,
+/// or whose albums or songs the user starred. Songs similar to each of them
+/// are fetched with `getSimilarSongs2`, and their most popular songs with
+/// `getTopSongs`. Songs the user starred, or played more than [`max_plays`]
+/// times, are left out. Seeds the server fails to find songs for are skipped
+/// and logged.
+///
+/// The mix may be shorter than [`size`] if there aren't enough such songs,
+/// and is empty if the user hasn't starred anything.
+///
+/// [`seeds`]: struct.MixOptions.html#structfield.seeds
+/// [`max_plays`]: struct.MixOptions.html#structfield.max_plays
+/// [`size`]: struct.MixOptions.html#structfield.size
+pub fn mix(client: &Client, options: &MixOptions) -> Result<Vec<Song>> {
+    let starred = client.starred_id3(options.folder_id)?;
+
+    let mut seeds = Vec::new();
+    let artists = starred
+        .artists
+        .iter()
+        .map(|a| (Some(&a.id), Some(&*a.name)));
+    let albums = starred
+        .albums
+        .iter()
+        .map(|a| (a.artist_id(), a.artist.as_deref()));
+    let songs = starred
+        .songs
+        .iter()
+        .map(|s| (s.artist_id(), s.artist.as_deref()));
+    for (id, name) in artists.chain(albums).chain(songs) {
+        if let (Some(id), Some(name)) = (id, name) {
+            let seed = Seed {
+                id: id.clone(),
+                name: name.to_string(),
+            };
+            if !seeds.contains(&seed) {
+                seeds.push(seed);
+            }
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    seeds.shuffle(&mut rng);
+    seeds.truncate(options.seeds);
+
+    let mut candidates = Vec::new();
+    for seed in &seeds {
+        let similar = GetSimilarSongs2 {
+            id: seed.id.clone(),
+            count: Some(options.size),
+        };
+        match client.call(&similar) {
+            Ok(songs) => candidates.extend(songs),
+            Err(e) => warn!("Skipping songs similar to {}: {}", seed.name, e),
+        }
+
+        let top = GetTopSongs {
+            artist: seed.name.to_string(),
+            count: Some(options.size),
+        };
+        match client.call(&top) {
+            Ok(songs) => candidates.extend(songs),
+            Err(e) => warn!("Skipping top songs of {}: {}", seed.name, e),
+        }
+    }
+    candidates.shuffle(&mut rng);
+
+    let starred = starred.songs.into_iter().map(|s| s.id).collect();
+    Ok(pick(candidates, &starred, options))
+}
+
+/// Saves a mix as a playlist on the server.
+///
+/// Since API version 1.14.0, the newly created playlist is returned. In
+/// earlier versions, an empty response is returned.
+pub fn save(client: &Client, name: &str, songs: &[Song]) -> Result<Option<Playlist>> {
+    let ids = songs.iter().map(|s| s.id.clone()).collect::<Vec<_>>();
+    create_playlist(client, name.to_string(), &ids)
+}
+
+/// Picks the songs of a mix from `candidates`, in order.
+fn pick(candidates: Vec<Song>, starred: &HashSet<SongId>, options: &MixOptions) -> Vec<Song> {
+    let mut seen = HashSet::new();
+    let mut by_artist = HashMap::new();
+    let mut songs = Vec::new();
+
+    for song in candidates {
+        if songs.len() == options.size {
+            break;
+        }
+        let played = song.play_count.unwrap_or(0);
+        if starred.contains(&song.id)
+            || options.max_plays.is_some_and(|max| played > max)
+            || seen.contains(&song.id)
+        {
+            continue;
+        }
+
+        let artist = song.artist.as_deref().map(str::to_lowercase);
+        let count = by_artist.entry(artist).or_insert(0);
+        if *count == options.per_artist {
+            continue;
+        }
+        *count += 1;
+
+        seen.insert(song.id.clone());
+        songs.push(song);
+    }
+
+    songs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::test_util;
+
+    fn song(id: u64, artist: &str, plays: u64) -> Song {
+        test_util::song_with(
+            &id.to_string(),
+            serde_json::json!({
+                "title": format!("Song {}", id), "artist": artist, "playCount": plays,
+            }),
+        )
+    }
+
+    #[test]
+    fn pick_fresh_songs() {
+        let candidates = vec![
+            song(1, "Misteur Valaire", 0),
+            song(2, "Misteur Valaire", 9),
+            song(3, "misteur valaire", 1),
+            song(1, "Misteur Valaire", 0),
+            song(4, "Misteur Valaire", 0),
+            song(5, "Pomplamoose", 0),
+            song(6, "Pomplamoose", 0),
+        ];
+        let starred = [SongId::from(6)].iter().cloned().collect();
+        let options = MixOptions {
+            size: 3,
+            per_artist: 2,
+            ..Default::default()
+        };
+
+        let ids = pick(candidates, &starred, &options)
+            .into_iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [SongId::from(1), SongId::from(3), SongId::from(5)]);
+    }
+
+    #[test]
+    fn mix_skips_failed_seeds() {
+        let artist = serde_json::json!({ "id": "1", "name": "Misteur Valaire", "albumCount": 1 });
+        let top = [song(7, "Misteur Valaire", 0), song(8, "Misteur Valaire", 5)];
+        let client = test_util::replay_client(&[
+            (
+                "getStarred2",
+                Query::none(),
+                serde_json::json!({ "starred2": { "artist": [artist] } }),
+            ),
+            (
+                "getSimilarSongs2",
+                Query::with("id", "1").arg("count", 30).build(),
+                serde_json::json!({
+                    "status": "failed",
+                    "error": { "code": 70, "message": "Artist not found" },
+                }),
+            ),
+            (
+                "getTopSongs",
+                Query::with("artist", "Misteur Valaire")
+                    .arg("count", 30)
+                    .build(),
+                serde_json::json!({ "topSongs": { "song": top } }),
+            ),
+        ]);
+        let songs = mix(&client, &MixOptions::default()).unwrap();
+        let ids = songs.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids, [SongId::from(7)]);
+    }
+}
@@ -0,0 +1,139 @@
+use serde_json::Value;
+
+use super::Endpoint;
+use crate::query::Query;
+use crate::{AlbumId, ArtistId, Client, Result};
+
+/// `star`: attaches a star to songs, albums and artists.
+#[derive(Debug, Clone, Default)]
+pub struct Star {
+    /// The IDs of songs, or of album and artist folders.
+    pub ids: Vec<String>,
+    /// The IDs of albums organised by ID3 tags.
+    pub album_ids: Vec<AlbumId>,
+    /// The IDs of artists organised by ID3 tags.
+    pub artist_ids: Vec<ArtistId>,
+}
+
+impl Star {
+    /// Returns a request starring one item, by the parameter `key` the item
+    /// is starred by: `"albumId"`, `"artistId"` or `"id"`.
+    pub(crate) fn one(key: &str, id: &str) -> Star {
+        let mut star = Star::default();
+        match key {
+            "albumId" => star.album_ids.push(AlbumId::new(id)),
+            "artistId" => star.artist_ids.push(ArtistId::new(id)),
+            _ => star.ids.push(id.to_string()),
+        }
+        star
+    }
+}
+
+impl Endpoint for Star {
+    type Output = ();
+    const NAME: &'static str = "star";
+
+    fn query(&self) -> Query {
+        Query::new()
+            .arg_list("id", &self.ids)
+            .arg_list("albumId", &self.album_ids)
+            .arg_list("artistId", &self.artist_ids)
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `unstar`: removes the star from songs, albums and artists.
+#[derive(Debug, Clone, Default)]
+pub struct Unstar {
+    /// The IDs of songs, or of album and artist folders.
+    pub ids: Vec<String>,
+    /// The IDs of albums organised by ID3 tags.
+    pub album_ids: Vec<AlbumId>,
+    /// The IDs of artists organised by ID3 tags.
+    pub artist_ids: Vec<ArtistId>,
+}
+
+/// Removes the stars a `Star` would attach.
+impl From<Star> for Unstar {
+    fn from(star: Star) -> Unstar {
+        Unstar {
+            ids: star.ids,
+            album_ids: star.album_ids,
+            artist_ids: star.artist_ids,
+        }
+    }
+}
+
+impl Endpoint for Unstar {
+    type Output = ();
+    const NAME: &'static str = "unstar";
+
+    fn query(&self) -> Query {
+        Query::new()
+            .arg_list("id", &self.ids)
+            .arg_list("albumId", &self.album_ids)
+            .arg_list("artistId", &self.artist_ids)
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `setRating`: rates a song, album or artist.
+#[derive(Debug, Clone)]
+pub struct SetRating {
+    /// The ID of the song, album or artist.
+    pub id: String,
+    /// The rating, from 1 to 5, or 0 to remove the rating.
+    pub rating: u8,
+}
+
+impl Endpoint for SetRating {
+    type Output = ();
+    const NAME: &'static str = "setRating";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id.as_str())
+            .arg("rating", self.rating)
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `scrobble`: registers that a song was played, or has started playing.
+#[derive(Debug, Clone)]
+pub struct Scrobble {
+    /// The ID of the song.
+    pub id: String,
+    /// When the song was played, as a number of milliseconds since the Unix
+    /// epoch; the server uses the time it receives the request if unset.
+    pub time: Option<String>,
+    /// Whether the song was played, rather than having started playing; the
+    /// server takes it to have been played if unset.
+    pub submission: Option<bool>,
+}
+
+impl Endpoint for Scrobble {
+    type Output = ();
+    const NAME: &'static str = "scrobble";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id.as_str())
+            .arg("time", self.time.as_deref())
+            .arg("submission", self.submission)
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
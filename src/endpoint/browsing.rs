@@ -0,0 +1,389 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::collections::{ArtistIndex, Indexes};
+use crate::query::Query;
+use crate::song::Song;
+#[cfg(feature = "video")]
+use crate::video::{Video, VideoInfo};
+use crate::{
+    Album, AlbumId, AlbumInfo, Artist, ArtistId, ArtistInfo, Client, Directory, Genre, MusicFolder,
+    Result, SongId,
+};
+
+/// `getMusicFolders`: returns the top-level music folders.
+#[derive(Debug, Clone, Default)]
+pub struct GetMusicFolders;
+
+impl Endpoint for GetMusicFolders {
+    type Output = Vec<MusicFolder>;
+    const NAME: &'static str = "getMusicFolders";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<MusicFolder>> {
+        list(client, value, "musicFolder")
+    }
+}
+
+/// `getIndexes`: lists the top-level folders of the library, for browsing it
+/// by folder.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GetIndexes {
+    /// Only list the folders in this music folder.
+    pub folder_id: Option<usize>,
+    /// Only list anything if the library changed after this many
+    /// milliseconds since the Unix epoch.
+    pub if_modified_since: Option<u64>,
+}
+
+impl Endpoint for GetIndexes {
+    type Output = Indexes;
+    const NAME: &'static str = "getIndexes";
+
+    fn query(&self) -> Query {
+        Query::with("musicFolderId", self.folder_id)
+            .arg("ifModifiedSince", self.if_modified_since)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Indexes> {
+        object(client, value)
+    }
+}
+
+/// `getMusicDirectory`: returns a folder, with the files and folders inside
+/// it.
+#[derive(Debug, Clone)]
+pub struct GetMusicDirectory {
+    /// The ID of the folder.
+    pub id: String,
+}
+
+impl Endpoint for GetMusicDirectory {
+    type Output = Directory;
+    const NAME: &'static str = "getMusicDirectory";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id.as_str())
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Directory> {
+        object(client, value)
+    }
+}
+
+/// `getGenres`: returns every genre.
+#[derive(Debug, Clone, Default)]
+pub struct GetGenres;
+
+impl Endpoint for GetGenres {
+    type Output = Vec<Genre>;
+    const NAME: &'static str = "getGenres";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Genre>> {
+        list(client, value, "genre")
+    }
+}
+
+/// `getArtists`: returns every artist, organised by ID3 tags.
+#[derive(Debug, Clone, Default)]
+pub struct GetArtists {
+    /// Only return artists in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetArtists {
+    type Output = ArtistIndex;
+    const NAME: &'static str = "getArtists";
+
+    fn query(&self) -> Query {
+        Query::with("musicFolderId", self.folder_id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<ArtistIndex> {
+        object(client, value)
+    }
+}
+
+/// `getArtist`: returns an artist, with their albums.
+#[derive(Debug, Clone)]
+pub struct GetArtist {
+    /// The ID of the artist.
+    pub id: ArtistId,
+}
+
+impl Endpoint for GetArtist {
+    type Output = Artist;
+    const NAME: &'static str = "getArtist";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Artist> {
+        object(client, value)
+    }
+}
+
+/// `getAlbum`: returns an album, with its songs.
+#[derive(Debug, Clone)]
+pub struct GetAlbum {
+    /// The ID of the album.
+    pub id: AlbumId,
+}
+
+impl Endpoint for GetAlbum {
+    type Output = Album;
+    const NAME: &'static str = "getAlbum";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Album> {
+        object(client, value)
+    }
+}
+
+/// `getSong`: returns a song.
+#[derive(Debug, Clone)]
+pub struct GetSong {
+    /// The ID of the song.
+    pub id: SongId,
+}
+
+impl Endpoint for GetSong {
+    type Output = Song;
+    const NAME: &'static str = "getSong";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Song> {
+        object(client, value)
+    }
+}
+
+/// `getVideos`: returns every video.
+#[cfg(feature = "video")]
+#[derive(Debug, Clone, Default)]
+pub struct GetVideos;
+
+#[cfg(feature = "video")]
+impl Endpoint for GetVideos {
+    type Output = Vec<Video>;
+    const NAME: &'static str = "getVideos";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Video>> {
+        list(client, value, "video")
+    }
+}
+
+/// `getVideoInfo`: returns the captions, audio tracks and conversions of a
+/// video.
+#[cfg(feature = "video")]
+#[derive(Debug, Clone)]
+pub struct GetVideoInfo {
+    /// The ID of the video.
+    pub id: usize,
+    /// The format to describe the conversion to, such as `"mp4"`.
+    pub format: Option<String>,
+}
+
+#[cfg(feature = "video")]
+impl Endpoint for GetVideoInfo {
+    type Output = VideoInfo;
+    const NAME: &'static str = "getVideoInfo";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id)
+            .arg("format", self.format.as_deref())
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<VideoInfo> {
+        object(client, value)
+    }
+}
+
+/// `getArtistInfo`: returns an artist's biography, images and similar
+/// artists from last.fm, for an artist folder.
+#[derive(Debug, Clone)]
+pub struct GetArtistInfo {
+    /// The ID of the artist's folder.
+    pub id: ArtistId,
+    /// The most similar artists to return; the server returns 20 if unset.
+    pub count: Option<usize>,
+    /// Whether to include similar artists that aren't in the library.
+    pub include_not_present: Option<bool>,
+}
+
+impl Endpoint for GetArtistInfo {
+    type Output = ArtistInfo;
+    const NAME: &'static str = "getArtistInfo";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+            .arg("count", self.count)
+            .arg("includeNotPresent", self.include_not_present)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<ArtistInfo> {
+        object(client, value)
+    }
+}
+
+/// `getArtistInfo2`: returns an artist's biography, images and similar
+/// artists from last.fm, for an artist organised by ID3 tags.
+#[derive(Debug, Clone)]
+pub struct GetArtistInfo2 {
+    /// The ID of the artist.
+    pub id: ArtistId,
+    /// The most similar artists to return; the server returns 20 if unset.
+    pub count: Option<usize>,
+    /// Whether to include similar artists that aren't in the library.
+    pub include_not_present: Option<bool>,
+}
+
+impl Endpoint for GetArtistInfo2 {
+    type Output = ArtistInfo;
+    const NAME: &'static str = "getArtistInfo2";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+            .arg("count", self.count)
+            .arg("includeNotPresent", self.include_not_present)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<ArtistInfo> {
+        object(client, value)
+    }
+}
+
+/// `getAlbumInfo`: returns an album's notes and images from last.fm, for an
+/// album folder.
+#[derive(Debug, Clone)]
+pub struct GetAlbumInfo {
+    /// The ID of the album's folder.
+    pub id: AlbumId,
+}
+
+impl Endpoint for GetAlbumInfo {
+    type Output = AlbumInfo;
+    const NAME: &'static str = "getAlbumInfo";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<AlbumInfo> {
+        object(client, value)
+    }
+}
+
+/// `getAlbumInfo2`: returns an album's notes and images from last.fm, for an
+/// album organised by ID3 tags.
+#[derive(Debug, Clone)]
+pub struct GetAlbumInfo2 {
+    /// The ID of the album.
+    pub id: AlbumId,
+}
+
+impl Endpoint for GetAlbumInfo2 {
+    type Output = AlbumInfo;
+    const NAME: &'static str = "getAlbumInfo2";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<AlbumInfo> {
+        object(client, value)
+    }
+}
+
+/// `getSimilarSongs`: returns random songs similar to a song, or by an
+/// artist and similar ones, from last.fm.
+#[derive(Debug, Clone)]
+pub struct GetSimilarSongs {
+    /// The ID of a song, or of an artist or album folder.
+    pub id: String,
+    /// The most songs to return; the server returns 50 if unset.
+    pub count: Option<usize>,
+}
+
+impl Endpoint for GetSimilarSongs {
+    type Output = Vec<Song>;
+    const NAME: &'static str = "getSimilarSongs";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id.as_str())
+            .arg("count", self.count)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Song>> {
+        list(client, value, "song")
+    }
+}
+
+/// `getSimilarSongs2`: returns random songs by an artist and similar ones,
+/// from last.fm, for an artist organised by ID3 tags.
+#[derive(Debug, Clone)]
+pub struct GetSimilarSongs2 {
+    /// The ID of the artist.
+    pub id: ArtistId,
+    /// The most songs to return; the server returns 50 if unset.
+    pub count: Option<usize>,
+}
+
+impl Endpoint for GetSimilarSongs2 {
+    type Output = Vec<Song>;
+    const NAME: &'static str = "getSimilarSongs2";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id).arg("count", self.count).build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Song>> {
+        list(client, value, "song")
+    }
+}
+
+/// `getTopSongs`: returns an artist's most popular songs, from last.fm.
+#[derive(Debug, Clone)]
+pub struct GetTopSongs {
+    /// The name of the artist; the server looks the artist up by name.
+    pub artist: String,
+    /// The most songs to return; the server returns 50 if unset.
+    pub count: Option<usize>,
+}
+
+impl Endpoint for GetTopSongs {
+    type Output = Vec<Song>;
+    const NAME: &'static str = "getTopSongs";
+
+    fn query(&self) -> Query {
+        Query::with("artist", self.artist.as_str())
+            .arg("count", self.count)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Song>> {
+        list(client, value, "song")
+    }
+}
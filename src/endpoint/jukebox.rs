@@ -0,0 +1,58 @@
+use serde_json::Value;
+
+use super::{object, Endpoint};
+use crate::query::Query;
+use crate::{Client, JukeboxPlaylist, JukeboxStatus, Result, SongId};
+
+/// `jukeboxControl`: controls the server's jukebox, and returns its status.
+///
+/// See [`GetJukeboxPlaylist`] for the `get` action, which returns the
+/// jukebox's playlist as well.
+///
+/// [`GetJukeboxPlaylist`]: struct.GetJukeboxPlaylist.html
+#[derive(Debug, Clone, Default)]
+pub struct JukeboxControl {
+    /// What to do, such as `"start"`, `"add"` or `"setGain"`.
+    pub action: String,
+    /// The position in the playlist, from zero, for `skip` and `remove`.
+    pub index: Option<usize>,
+    /// The songs to `add`, or to `set` the playlist to.
+    pub ids: Vec<SongId>,
+    /// The volume, from 0 to 1, for `setGain`.
+    pub gain: Option<f32>,
+}
+
+impl Endpoint for JukeboxControl {
+    type Output = JukeboxStatus;
+    const NAME: &'static str = "jukeboxControl";
+
+    fn query(&self) -> Query {
+        Query::with("action", self.action.as_str())
+            .arg("index", self.index)
+            .arg_list("id", &self.ids)
+            .arg("gain", self.gain)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<JukeboxStatus> {
+        object(client, value)
+    }
+}
+
+/// `jukeboxControl` with the action `get`: returns the jukebox's playlist
+/// and status.
+#[derive(Debug, Clone, Default)]
+pub struct GetJukeboxPlaylist;
+
+impl Endpoint for GetJukeboxPlaylist {
+    type Output = JukeboxPlaylist;
+    const NAME: &'static str = "jukeboxControl";
+
+    fn query(&self) -> Query {
+        Query::with("action", "get")
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<JukeboxPlaylist> {
+        object(client, value)
+    }
+}
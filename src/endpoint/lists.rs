@@ -0,0 +1,236 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::query::Query;
+use crate::search::SearchResult;
+use crate::song::Song;
+use crate::{Album, Client, ListType, NowPlaying, Result};
+
+/// `getAlbumList2`: returns a page of albums organised by ID3 tags, in the
+/// order given by `list_type`.
+///
+/// See [`GetAlbumsByYear`] and [`GetAlbumsByGenre`] for the lists that take
+/// more arguments.
+///
+/// [`GetAlbumsByYear`]: struct.GetAlbumsByYear.html
+/// [`GetAlbumsByGenre`]: struct.GetAlbumsByGenre.html
+#[derive(Debug, Clone, Default)]
+pub struct GetAlbumList2 {
+    /// How the albums are chosen and ordered.
+    pub list_type: ListType,
+    /// The most albums to return, up to 500; the server returns 10 if unset.
+    pub size: Option<usize>,
+    /// The number of albums to skip.
+    pub offset: Option<usize>,
+    /// Only return albums in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetAlbumList2 {
+    type Output = Vec<Album>;
+    const NAME: &'static str = "getAlbumList2";
+
+    fn query(&self) -> Query {
+        Query::with("type", self.list_type)
+            .arg("size", self.size)
+            .arg("offset", self.offset)
+            .arg("musicFolderId", self.folder_id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Album>> {
+        list(client, value, "album")
+    }
+}
+
+/// `getAlbumList2` with the type `byYear`: returns a page of the albums
+/// released in a range of years.
+///
+/// Albums are listed from `from_year` to `to_year`, so a range given newest
+/// first lists the newest albums first.
+#[derive(Debug, Clone, Default)]
+pub struct GetAlbumsByYear {
+    /// The year to list albums from.
+    pub from_year: usize,
+    /// The year to list albums to.
+    pub to_year: usize,
+    /// The most albums to return, up to 500; the server returns 10 if unset.
+    pub size: Option<usize>,
+    /// The number of albums to skip.
+    pub offset: Option<usize>,
+    /// Only return albums in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetAlbumsByYear {
+    type Output = Vec<Album>;
+    const NAME: &'static str = "getAlbumList2";
+
+    fn query(&self) -> Query {
+        Query::with("type", "byYear")
+            .arg("fromYear", self.from_year)
+            .arg("toYear", self.to_year)
+            .arg("size", self.size)
+            .arg("offset", self.offset)
+            .arg("musicFolderId", self.folder_id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Album>> {
+        list(client, value, "album")
+    }
+}
+
+/// `getAlbumList2` with the type `byGenre`: returns a page of the albums in
+/// a genre.
+#[derive(Debug, Clone, Default)]
+pub struct GetAlbumsByGenre {
+    /// The genre, as the server names it.
+    pub genre: String,
+    /// The most albums to return, up to 500; the server returns 10 if unset.
+    pub size: Option<usize>,
+    /// The number of albums to skip.
+    pub offset: Option<usize>,
+    /// Only return albums in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetAlbumsByGenre {
+    type Output = Vec<Album>;
+    const NAME: &'static str = "getAlbumList2";
+
+    fn query(&self) -> Query {
+        Query::with("type", "byGenre")
+            .arg("genre", self.genre.as_str())
+            .arg("size", self.size)
+            .arg("offset", self.offset)
+            .arg("musicFolderId", self.folder_id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Album>> {
+        list(client, value, "album")
+    }
+}
+
+/// `getRandomSongs`: returns random songs.
+#[derive(Debug, Clone, Default)]
+pub struct GetRandomSongs {
+    /// The most songs to return; the server returns 10 if unset.
+    pub size: Option<usize>,
+    /// Only return songs of this genre.
+    pub genre: Option<String>,
+    /// Only return songs released in or after this year.
+    pub from_year: Option<usize>,
+    /// Only return songs released in or before this year.
+    pub to_year: Option<usize>,
+    /// Only return songs in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetRandomSongs {
+    type Output = Vec<Song>;
+    const NAME: &'static str = "getRandomSongs";
+
+    fn query(&self) -> Query {
+        Query::with("size", self.size)
+            .arg("genre", self.genre.as_deref())
+            .arg("fromYear", self.from_year)
+            .arg("toYear", self.to_year)
+            .arg("musicFolderId", self.folder_id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Song>> {
+        list(client, value, "song")
+    }
+}
+
+/// `getSongsByGenre`: returns a page of the songs in a genre.
+#[derive(Debug, Clone, Default)]
+pub struct GetSongsByGenre {
+    /// The genre, as the server names it.
+    pub genre: String,
+    /// The most songs to return, up to 500; the server returns 10 if unset.
+    pub count: Option<usize>,
+    /// The number of songs to skip.
+    pub offset: Option<usize>,
+    /// Only return songs in this music folder.
+    pub folder_id: Option<u64>,
+}
+
+impl Endpoint for GetSongsByGenre {
+    type Output = Vec<Song>;
+    const NAME: &'static str = "getSongsByGenre";
+
+    fn query(&self) -> Query {
+        Query::with("genre", self.genre.as_str())
+            .arg("count", self.count)
+            .arg("offset", self.offset)
+            .arg("musicFolderId", self.folder_id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Song>> {
+        list(client, value, "song")
+    }
+}
+
+/// `getNowPlaying`: returns what every user is playing.
+#[derive(Debug, Clone, Default)]
+pub struct GetNowPlaying;
+
+impl Endpoint for GetNowPlaying {
+    type Output = Vec<NowPlaying>;
+    const NAME: &'static str = "getNowPlaying";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<NowPlaying>> {
+        list(client, value, "entry")
+    }
+}
+
+/// `getStarred`: returns the starred artists, albums and songs, organised by
+/// folder.
+#[derive(Debug, Clone, Default)]
+pub struct GetStarred {
+    /// Only return content in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetStarred {
+    type Output = SearchResult;
+    const NAME: &'static str = "getStarred";
+
+    fn query(&self) -> Query {
+        Query::with("musicFolderId", self.folder_id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<SearchResult> {
+        object(client, value)
+    }
+}
+
+/// `getStarred2`: returns the starred artists, albums and songs, organised
+/// by ID3 tags.
+#[derive(Debug, Clone, Default)]
+pub struct GetStarred2 {
+    /// Only return content in this music folder.
+    pub folder_id: Option<usize>,
+}
+
+impl Endpoint for GetStarred2 {
+    type Output = SearchResult;
+    const NAME: &'static str = "getStarred2";
+
+    fn query(&self) -> Query {
+        Query::with("musicFolderId", self.folder_id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<SearchResult> {
+        object(client, value)
+    }
+}
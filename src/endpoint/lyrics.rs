@@ -0,0 +1,58 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::query::Query;
+use crate::song::{Lyrics, StructuredLyrics};
+use crate::{Client, Result, SongId};
+
+/// `getLyrics`: searches for the lyrics of a song by its artist and title.
+/// Returns `None` if no lyrics are found.
+#[derive(Debug, Clone, Default)]
+pub struct GetLyrics {
+    /// The artist of the song.
+    pub artist: Option<String>,
+    /// The title of the song.
+    pub title: Option<String>,
+}
+
+impl Endpoint for GetLyrics {
+    type Output = Option<Lyrics>;
+    const NAME: &'static str = "getLyrics";
+
+    fn query(&self) -> Query {
+        Query::with("artist", self.artist.as_deref())
+            .arg("title", self.title.as_deref())
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Option<Lyrics>> {
+        if value.get("value").is_some() {
+            object(client, value).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// `getLyricsBySongId`: returns the lyrics of a song from the server's own
+/// library, with timestamps for each line if the server has them.
+///
+/// This is part of the OpenSubsonic `songLyrics` extension.
+#[derive(Debug, Clone)]
+pub struct GetLyricsBySongId {
+    /// The ID of the song.
+    pub id: SongId,
+}
+
+impl Endpoint for GetLyricsBySongId {
+    type Output = Vec<StructuredLyrics>;
+    const NAME: &'static str = "getLyricsBySongId";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<StructuredLyrics>> {
+        list(client, value, "structuredLyrics")
+    }
+}
@@ -0,0 +1,243 @@
+//! Typed descriptions of the methods of the API.
+//!
+//! An [`Endpoint`] describes one method of the [Subsonic API]: its name, its
+//! arguments and how to read its response. [`Client::call`] sends any
+//! endpoint through the same path as the rest of the crate, with its
+//! caching, `POST` threshold and error handling, so a method sunk doesn't
+//! wrap yet only takes a small struct to use.
+//!
+//! Every method of the crate that reads a JSON response is described by an
+//! endpoint here, and goes through [`Client::call`]. The exceptions are the
+//! methods that read something else, such as streams, downloads and cover
+//! art, and those that read a list as it arrives, such as
+//! [`Artist::for_each`]; these use the same request path without an
+//! endpoint.
+//!
+//! [`Endpoint`]: trait.Endpoint.html
+//! [`Artist::for_each`]: ../struct.Artist.html#method.for_each
+//! [Subsonic API]: http://www.subsonic.org/pages/api.jsp
+//! [`Client::call`]: ../struct.Client.html#method.call
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate serde_json;
+//! extern crate sunk;
+//! use sunk::endpoint::{self, Endpoint};
+//! use sunk::{Client, Query};
+//!
+//! /// Lists the bookmarks the user saved.
+//! struct GetBookmarks;
+//!
+//! impl Endpoint for GetBookmarks {
+//!     type Output = Vec<serde_json::Value>;
+//!     const NAME: &'static str = "getBookmarks";
+//!
+//!     fn query(&self) -> Query {
+//!         Query::new()
+//!     }
+//!
+//!     fn parse(client: &Client, value: serde_json::Value) -> sunk::Result<Self::Output> {
+//!         endpoint::list(client, value, "bookmark")
+//!     }
+//! }
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+//! let bookmarks = client.call(&GetBookmarks)?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::de;
+use crate::query::Query;
+use crate::{Client, RawResponse, Result};
+
+mod annotation;
+mod browsing;
+#[cfg(feature = "jukebox")]
+mod jukebox;
+mod lists;
+mod lyrics;
+mod playlists;
+#[cfg(feature = "podcasts")]
+mod podcasts;
+mod radio;
+mod scanning;
+mod searching;
+#[cfg(feature = "sharing")]
+mod sharing;
+mod system;
+mod users;
+
+pub use self::annotation::{Scrobble, SetRating, Star, Unstar};
+pub(crate) use self::browsing::GetIndexes;
+pub use self::browsing::{
+    GetAlbum, GetAlbumInfo, GetAlbumInfo2, GetArtist, GetArtistInfo, GetArtistInfo2, GetArtists,
+    GetGenres, GetMusicDirectory, GetMusicFolders, GetSimilarSongs, GetSimilarSongs2, GetSong,
+    GetTopSongs,
+};
+#[cfg(feature = "video")]
+pub use self::browsing::{GetVideoInfo, GetVideos};
+#[cfg(feature = "jukebox")]
+pub use self::jukebox::{GetJukeboxPlaylist, JukeboxControl};
+pub use self::lists::{
+    GetAlbumList2, GetAlbumsByGenre, GetAlbumsByYear, GetNowPlaying, GetRandomSongs,
+    GetSongsByGenre, GetStarred, GetStarred2,
+};
+pub use self::lyrics::{GetLyrics, GetLyricsBySongId};
+pub use self::playlists::{
+    CreatePlaylist, DeletePlaylist, GetPlaylist, GetPlaylists, UpdatePlaylist,
+};
+#[cfg(feature = "podcasts")]
+pub use self::podcasts::{GetNewestPodcasts, GetPodcasts};
+pub use self::radio::{
+    CreateInternetRadioStation, DeleteInternetRadioStation, GetInternetRadioStations,
+    UpdateInternetRadioStation,
+};
+pub use self::scanning::{GetScanStatus, StartScan};
+pub use self::searching::Search3;
+#[cfg(feature = "sharing")]
+pub use self::sharing::{CreateShare, DeleteShare, GetShares};
+pub use self::system::{GetLicense, GetOpenSubsonicExtensions, Ping};
+pub use self::users::{ChangePassword, CreateUser, DeleteUser, GetUser, GetUsers, UpdateUser};
+
+/// A method of the API.
+///
+/// See the [module-level documentation](./index.html) for more information.
+pub trait Endpoint {
+    /// What the method returns.
+    type Output;
+
+    /// The name of the method, such as `"getAlbum"`.
+    const NAME: &'static str;
+
+    /// Returns the arguments of the request.
+    fn query(&self) -> Query;
+
+    /// Reads the content of a response, such as the `album` object of a
+    /// response to `getAlbum`.
+    ///
+    /// [`object`] and [`list`] read the common shapes of responses.
+    ///
+    /// [`object`]: fn.object.html
+    /// [`list`]: fn.list.html
+    fn parse(client: &Client, value: Value) -> Result<Self::Output>;
+}
+
+impl Client {
+    /// Calls a method of the API.
+    ///
+    /// See the [`endpoint`] module for more information.
+    ///
+    /// [`endpoint`]: ./endpoint/index.html
+    pub fn call<E: Endpoint>(&self, endpoint: &E) -> Result<E::Output> {
+        let value = self.get(E::NAME, endpoint.query())?;
+        E::parse(self, value)
+    }
+
+    /// Calls a method that lists the library by ID3 tags.
+    ///
+    /// Returns `None` instead if the server can only be browsed by folder;
+    /// see `get_id3`.
+    pub(crate) fn call_id3<E: Endpoint>(&self, endpoint: &E) -> Result<Option<E::Output>> {
+        match self.get_id3(E::NAME, endpoint.query())? {
+            Some(value) => E::parse(self, value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Calls a method of the API by name, such as an extension of a
+    /// particular server, and reads the content of its response as `T`.
+    ///
+    /// Use `serde_json::Value` as `T` to get the content as is.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate serde_json;
+    /// extern crate sunk;
+    /// use sunk::{Client, Query};
+    ///
+    /// # fn run() -> sunk::Result<()> {
+    /// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+    /// let args = Query::with("id", "42");
+    /// let info: serde_json::Value = client.custom("getAlbumInfo2", args)?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    pub fn custom<T: DeserializeOwned>(&self, endpoint: &str, args: Query) -> Result<T> {
+        let value = self.get(endpoint, args)?;
+        object(self, value)
+    }
+
+    /// Calls a method of the API by name and returns its response as is,
+    /// such as for an extension that sends a file.
+    pub fn custom_raw(&self, endpoint: &str, args: Query) -> Result<RawResponse> {
+        self.get_raw(endpoint, args)
+    }
+}
+
+/// Reads the content of a response as a single object.
+pub fn object<T: DeserializeOwned>(client: &Client, value: Value) -> Result<T> {
+    de::from_value(client, value)
+}
+
+/// Reads the list at `field` in the content of a response, such as the
+/// `song` list of a response to `getRandomSongs`.
+///
+/// A missing list is read as empty. If the client has [lenient lists],
+/// elements that can't be read are skipped.
+///
+/// [lenient lists]: ../struct.Client.html#method.with_lenient_lists
+pub fn list<T>(client: &Client, value: Value, field: &'static str) -> Result<Vec<T>>
+where
+    T: DeserializeOwned + Send,
+{
+    de::list(client, value, field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_songs_query() {
+        let random = GetRandomSongs {
+            size: Some(20),
+            genre: Some("Jazz".into()),
+            ..Default::default()
+        };
+        assert_eq!(random.query().to_string(), "size=20&genre=Jazz");
+    }
+
+    #[test]
+    fn custom_payload() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let body = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "nowPlayingCount": { "count": 3 }
+        }}"#;
+        let value = client.parse_body(body).unwrap();
+        assert_eq!(object::<Value>(&client, value.clone()).unwrap()["count"], 3);
+
+        #[derive(Deserialize)]
+        struct Count {
+            count: usize,
+        }
+        assert_eq!(object::<Count>(&client, value).unwrap().count, 3);
+    }
+
+    #[test]
+    fn parse_song_list() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let value = serde_json::json!({ "song": [] });
+        assert!(GetRandomSongs::parse(&client, value).unwrap().is_empty());
+    }
+}
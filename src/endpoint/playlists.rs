@@ -0,0 +1,132 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::query::Query;
+use crate::{Client, Playlist, PlaylistId, Result, SongId};
+
+/// `getPlaylists`: returns the playlists a user may play, without their
+/// songs.
+#[derive(Debug, Clone, Default)]
+pub struct GetPlaylists {
+    /// Return the playlists of this user rather than the current one; only
+    /// administrators may ask for another user's.
+    pub username: Option<String>,
+}
+
+impl Endpoint for GetPlaylists {
+    type Output = Vec<Playlist>;
+    const NAME: &'static str = "getPlaylists";
+
+    fn query(&self) -> Query {
+        Query::with("username", self.username.as_deref())
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Playlist>> {
+        list(client, value, "playlist")
+    }
+}
+
+/// `getPlaylist`: returns a playlist, with its songs.
+#[derive(Debug, Clone)]
+pub struct GetPlaylist {
+    /// The ID of the playlist.
+    pub id: PlaylistId,
+}
+
+impl Endpoint for GetPlaylist {
+    type Output = Playlist;
+    const NAME: &'static str = "getPlaylist";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Playlist> {
+        object(client, value)
+    }
+}
+
+/// `createPlaylist`: creates a playlist of songs, and returns it.
+///
+/// Servers older than API version 1.14.0 return nothing, which fails to be
+/// read as a playlist even though the playlist was created.
+#[derive(Debug, Clone, Default)]
+pub struct CreatePlaylist {
+    /// The name of the playlist.
+    pub name: String,
+    /// The songs of the playlist, in order.
+    pub songs: Vec<SongId>,
+}
+
+impl Endpoint for CreatePlaylist {
+    type Output = Playlist;
+    const NAME: &'static str = "createPlaylist";
+
+    fn query(&self) -> Query {
+        Query::with("name", self.name.as_str())
+            .arg_list("songId", &self.songs)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Playlist> {
+        object(client, value)
+    }
+}
+
+/// `updatePlaylist`: changes a playlist. Only its owner may do so.
+///
+/// Fields left unset are left as they are.
+#[derive(Debug, Clone)]
+pub struct UpdatePlaylist {
+    /// The ID of the playlist.
+    pub id: PlaylistId,
+    /// The new name of the playlist.
+    pub name: Option<String>,
+    /// The new comment on the playlist.
+    pub comment: Option<String>,
+    /// Whether other users may see the playlist.
+    pub public: Option<bool>,
+    /// Songs to add to the end of the playlist.
+    pub songs_to_add: Vec<SongId>,
+    /// The positions of songs to remove from the playlist, from zero.
+    pub songs_to_remove: Vec<u64>,
+}
+
+impl Endpoint for UpdatePlaylist {
+    type Output = ();
+    const NAME: &'static str = "updatePlaylist";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+            .arg("name", self.name.as_deref())
+            .arg("comment", self.comment.as_deref())
+            .arg("public", self.public)
+            .arg_list("songIdToAdd", &self.songs_to_add)
+            .arg_list("songIndexToRemove", &self.songs_to_remove)
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `deletePlaylist`: deletes a playlist.
+#[derive(Debug, Clone)]
+pub struct DeletePlaylist {
+    /// The ID of the playlist.
+    pub id: PlaylistId,
+}
+
+impl Endpoint for DeletePlaylist {
+    type Output = ();
+    const NAME: &'static str = "deletePlaylist";
+
+    fn query(&self) -> Query {
+        Query::with("id", &self.id)
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
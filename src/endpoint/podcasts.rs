@@ -0,0 +1,52 @@
+use serde_json::Value;
+
+use super::{list, Endpoint};
+use crate::podcast::{Episode, Podcast};
+use crate::query::Query;
+use crate::{Client, Result};
+
+/// `getPodcasts`: returns the podcasts the server subscribes to.
+#[derive(Debug, Clone, Default)]
+pub struct GetPodcasts {
+    /// Only return the podcast with this ID.
+    pub id: Option<usize>,
+    /// Whether to return the episodes of each podcast; the server does if
+    /// unset.
+    pub include_episodes: Option<bool>,
+}
+
+impl Endpoint for GetPodcasts {
+    type Output = Vec<Podcast>;
+    const NAME: &'static str = "getPodcasts";
+
+    fn query(&self) -> Query {
+        Query::with("includeEpisodes", self.include_episodes)
+            .arg("id", self.id)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Podcast>> {
+        list(client, value, "channel")
+    }
+}
+
+/// `getNewestPodcasts`: returns the newest episodes of the podcasts the
+/// server subscribes to.
+#[derive(Debug, Clone, Default)]
+pub struct GetNewestPodcasts {
+    /// The most episodes to return; the server returns 20 if unset.
+    pub count: Option<usize>,
+}
+
+impl Endpoint for GetNewestPodcasts {
+    type Output = Vec<Episode>;
+    const NAME: &'static str = "getNewestPodcasts";
+
+    fn query(&self) -> Query {
+        Query::with("count", self.count)
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Episode>> {
+        list(client, value, "episode")
+    }
+}
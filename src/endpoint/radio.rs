@@ -0,0 +1,99 @@
+use serde_json::Value;
+
+use super::{list, Endpoint};
+use crate::query::Query;
+use crate::{Client, RadioStation, Result};
+
+/// `getInternetRadioStations`: returns every internet radio station.
+#[derive(Debug, Clone, Default)]
+pub struct GetInternetRadioStations;
+
+impl Endpoint for GetInternetRadioStations {
+    type Output = Vec<RadioStation>;
+    const NAME: &'static str = "getInternetRadioStations";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<RadioStation>> {
+        list(client, value, "internetRadioStation")
+    }
+}
+
+/// `createInternetRadioStation`: adds an internet radio station.
+#[derive(Debug, Clone, Default)]
+pub struct CreateInternetRadioStation {
+    /// The name of the station.
+    pub name: String,
+    /// The URL of the station's stream.
+    pub stream_url: String,
+    /// The URL of the station's home page.
+    pub homepage_url: Option<String>,
+}
+
+impl Endpoint for CreateInternetRadioStation {
+    type Output = ();
+    const NAME: &'static str = "createInternetRadioStation";
+
+    fn query(&self) -> Query {
+        Query::with("name", self.name.as_str())
+            .arg("streamUrl", self.stream_url.as_str())
+            .arg("homepageUrl", self.homepage_url.as_deref())
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `updateInternetRadioStation`: changes an internet radio station.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateInternetRadioStation {
+    /// The ID of the station.
+    pub id: usize,
+    /// The new URL of the station's stream.
+    pub stream_url: String,
+    /// The new name of the station.
+    pub name: String,
+    /// The new URL of the station's home page.
+    pub homepage_url: Option<String>,
+}
+
+impl Endpoint for UpdateInternetRadioStation {
+    type Output = ();
+    const NAME: &'static str = "updateInternetRadioStation";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id)
+            .arg("streamUrl", self.stream_url.as_str())
+            .arg("name", self.name.as_str())
+            .arg("homepageUrl", self.homepage_url.as_deref())
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `deleteInternetRadioStation`: removes an internet radio station.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteInternetRadioStation {
+    /// The ID of the station.
+    pub id: usize,
+}
+
+impl Endpoint for DeleteInternetRadioStation {
+    type Output = ();
+    const NAME: &'static str = "deleteInternetRadioStation";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id)
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use serde_json::Value;
+
+use super::Endpoint;
+use crate::query::Query;
+use crate::{Client, Result};
+
+/// The status of a scan, as sent by `getScanStatus` and `startScan`.
+#[derive(Deserialize)]
+struct ScanStatus {
+    count: u64,
+    scanning: bool,
+}
+
+/// `getScanStatus`: returns whether the server is scanning its media
+/// libraries, and the number of files scanned so far.
+#[derive(Debug, Clone, Default)]
+pub struct GetScanStatus;
+
+impl Endpoint for GetScanStatus {
+    type Output = (bool, u64);
+    const NAME: &'static str = "getScanStatus";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(_: &Client, value: Value) -> Result<(bool, u64)> {
+        let status = serde_json::from_value::<ScanStatus>(value)?;
+        Ok((status.scanning, status.count))
+    }
+}
+
+/// `startScan`: starts a scan of the media libraries.
+///
+/// Returns the status of the scan as [`GetScanStatus`] does, or `None` if
+/// the server doesn't send one.
+///
+/// [`GetScanStatus`]: struct.GetScanStatus.html
+#[derive(Debug, Clone, Default)]
+pub struct StartScan;
+
+impl Endpoint for StartScan {
+    type Output = Option<(bool, u64)>;
+    const NAME: &'static str = "startScan";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Option<(bool, u64)>> {
+        Ok(GetScanStatus::parse(client, value).ok())
+    }
+}
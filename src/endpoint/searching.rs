@@ -0,0 +1,43 @@
+use serde_json::Value;
+
+use super::{object, Endpoint};
+use crate::query::Query;
+use crate::search::{SearchPage, SearchResult};
+use crate::{Client, Result};
+
+/// `search3`: returns the artists, albums and songs matching a query,
+/// organised by ID3 tags.
+///
+/// Each kind of result is paged separately; a page with a `count` of 0
+/// leaves that kind out.
+#[derive(Debug, Clone)]
+pub struct Search3 {
+    /// The text to search for.
+    pub query: String,
+    /// The page of artists to return.
+    pub artists: SearchPage,
+    /// The page of albums to return.
+    pub albums: SearchPage,
+    /// The page of songs to return.
+    pub songs: SearchPage,
+}
+
+impl Endpoint for Search3 {
+    type Output = SearchResult;
+    const NAME: &'static str = "search3";
+
+    fn query(&self) -> Query {
+        Query::with("query", self.query.as_str())
+            .arg("artistCount", self.artists.count)
+            .arg("artistOffset", self.artists.offset)
+            .arg("albumCount", self.albums.count)
+            .arg("albumOffset", self.albums.offset)
+            .arg("songCount", self.songs.count)
+            .arg("songOffset", self.songs.offset)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<SearchResult> {
+        object(client, value)
+    }
+}
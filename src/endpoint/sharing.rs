@@ -0,0 +1,74 @@
+use serde_json::Value;
+
+use super::{list, Endpoint};
+use crate::query::Query;
+use crate::{Client, Error, Result, Share};
+
+/// `getShares`: returns the shares the user has created.
+#[derive(Debug, Clone, Default)]
+pub struct GetShares;
+
+impl Endpoint for GetShares {
+    type Output = Vec<Share>;
+    const NAME: &'static str = "getShares";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Share>> {
+        list(client, value, "share")
+    }
+}
+
+/// `createShare`: shares songs, albums or folders, and returns the share.
+#[derive(Debug, Clone, Default)]
+pub struct CreateShare {
+    /// The IDs of the songs, albums or folders to share.
+    pub ids: Vec<String>,
+    /// A description shown to those who open the share.
+    pub description: Option<String>,
+    /// When the share expires, as a number of milliseconds since the Unix
+    /// epoch; the share lasts until it is deleted if unset.
+    pub expires: Option<u64>,
+}
+
+impl Endpoint for CreateShare {
+    type Output = Share;
+    const NAME: &'static str = "createShare";
+
+    fn query(&self) -> Query {
+        Query::new()
+            .arg_list("id", &self.ids)
+            .arg("description", self.description.as_deref())
+            .arg("expires", self.expires)
+            .build()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Share> {
+        list(client, value, "share")?
+            .into_iter()
+            .next()
+            .ok_or(Error::Other("server returned no share"))
+    }
+}
+
+/// `deleteShare`: deletes a share, so its link stops working.
+#[derive(Debug, Clone)]
+pub struct DeleteShare {
+    /// The ID of the share.
+    pub id: String,
+}
+
+impl Endpoint for DeleteShare {
+    type Output = ();
+    const NAME: &'static str = "deleteShare";
+
+    fn query(&self) -> Query {
+        Query::with("id", self.id.as_str())
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
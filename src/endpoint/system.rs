@@ -0,0 +1,58 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::client::License;
+use crate::query::Query;
+use crate::{Client, Extension, Result};
+
+/// `ping`: tests the connection with the server.
+#[derive(Debug, Clone, Default)]
+pub struct Ping;
+
+impl Endpoint for Ping {
+    type Output = ();
+    const NAME: &'static str = "ping";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `getLicense`: returns details about the server's license.
+#[derive(Debug, Clone, Default)]
+pub struct GetLicense;
+
+impl Endpoint for GetLicense {
+    type Output = License;
+    const NAME: &'static str = "getLicense";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<License> {
+        object(client, value)
+    }
+}
+
+/// `getOpenSubsonicExtensions`: returns the OpenSubsonic extensions the
+/// server supports.
+#[derive(Debug, Clone, Default)]
+pub struct GetOpenSubsonicExtensions;
+
+impl Endpoint for GetOpenSubsonicExtensions {
+    type Output = Vec<Extension>;
+    const NAME: &'static str = "getOpenSubsonicExtensions";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<Extension>> {
+        list(client, value, "openSubsonicExtensions")
+    }
+}
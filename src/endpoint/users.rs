@@ -0,0 +1,128 @@
+use serde_json::Value;
+
+use super::{list, object, Endpoint};
+use crate::query::Query;
+use crate::{Client, Result, User, UserBuilder};
+
+/// `getUser`: returns a user's details and permissions.
+#[derive(Debug, Clone)]
+pub struct GetUser {
+    /// The name of the user.
+    pub username: String,
+}
+
+impl Endpoint for GetUser {
+    type Output = User;
+    const NAME: &'static str = "getUser";
+
+    fn query(&self) -> Query {
+        Query::with("username", self.username.as_str())
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<User> {
+        object(client, value)
+    }
+}
+
+/// `getUsers`: returns every user. Only administrators may ask.
+#[derive(Debug, Clone, Default)]
+pub struct GetUsers;
+
+impl Endpoint for GetUsers {
+    type Output = Vec<User>;
+    const NAME: &'static str = "getUsers";
+
+    fn query(&self) -> Query {
+        Query::none()
+    }
+
+    fn parse(client: &Client, value: Value) -> Result<Vec<User>> {
+        list(client, value, "user")
+    }
+}
+
+/// `createUser`: creates a user as described by a [`UserBuilder`].
+///
+/// [`UserBuilder`]: ../struct.UserBuilder.html
+#[derive(Debug, Clone)]
+pub struct CreateUser<'a> {
+    /// The user to create.
+    pub user: &'a UserBuilder,
+}
+
+impl<'a> Endpoint for CreateUser<'a> {
+    type Output = ();
+    const NAME: &'static str = "createUser";
+
+    fn query(&self) -> Query {
+        self.user.args()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `updateUser`: sets a user's email and permissions to those of a `User`.
+#[derive(Debug, Clone)]
+pub struct UpdateUser<'a> {
+    /// The user, as it should be.
+    pub user: &'a User,
+}
+
+impl<'a> Endpoint for UpdateUser<'a> {
+    type Output = ();
+    const NAME: &'static str = "updateUser";
+
+    fn query(&self) -> Query {
+        self.user.args()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `deleteUser`: removes a user.
+#[derive(Debug, Clone)]
+pub struct DeleteUser {
+    /// The name of the user.
+    pub username: String,
+}
+
+impl Endpoint for DeleteUser {
+    type Output = ();
+    const NAME: &'static str = "deleteUser";
+
+    fn query(&self) -> Query {
+        Query::with("username", self.username.as_str())
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `changePassword`: changes a user's password.
+#[derive(Debug, Clone)]
+pub struct ChangePassword {
+    /// The name of the user.
+    pub username: String,
+    /// The new password.
+    pub password: String,
+}
+
+impl Endpoint for ChangePassword {
+    type Output = ();
+    const NAME: &'static str = "changePassword";
+
+    fn query(&self) -> Query {
+        Query::with("username", self.username.as_str())
+            .arg("password", self.password.as_str())
+            .build()
+    }
+
+    fn parse(_: &Client, _: Value) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 use reqwest;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
 use serde::de::{Deserialize, Deserializer};
 use serde_json;
 use std::convert::From;
+use std::time::{Duration, SystemTime};
 use std::{fmt, io, num, result};
 use thiserror;
 use url;
@@ -16,9 +19,25 @@ pub enum Error {
     #[error("Unable to connect to server: received {}", _0)]
     Connection(reqwest::StatusCode),
 
+    /// The server, or a proxy in front of it, is overloaded or restarting,
+    /// and answered `429 Too Many Requests` or `503 Service Unavailable`.
+    ///
+    /// The request may succeed if sent again later; see
+    /// [`Client::with_retries`] to have the client do so.
+    ///
+    /// [`Client::with_retries`]: ./struct.Client.html#method.with_retries
+    #[error("Server unavailable: received {}{}", .status, retry_hint(.retry_after))]
+    Unavailable {
+        /// The status the server answered with.
+        status: reqwest::StatusCode,
+        /// How long the server asked to wait before trying again, from its
+        /// `Retry-After` header.
+        retry_after: Option<Duration>,
+    },
+
     /// Unable to parse the URL provided in `Client` setup.
     #[error("Invalid URL: {}", _0)]
-    UrlParseError(url::ParseError),
+    UrlParseError(#[source] url::ParseError),
 
     /// The Subsonic server returned an error.
     #[error("{}", _0)]
@@ -47,12 +66,193 @@ pub enum Error {
     /// An error occurred in serialization.
     #[error("Error serialising: {}", _0)]
     Serde(#[from] serde_json::Error),
+    /// A response could not be deserialized, along with the offending JSON.
+    ///
+    /// This is only returned if the `Client` has been set to capture JSON
+    /// with [`with_json_capture`]; otherwise such errors are `Serde` errors.
+    /// The JSON is truncated to the length set there.
+    ///
+    /// [`with_json_capture`]: ./struct.Client.html#method.with_json_capture
+    #[error("Error deserialising: {} in {}", source, json)]
+    Deserialize {
+        /// The underlying error.
+        source: serde_json::Error,
+        /// The JSON that failed to deserialize.
+        json: String,
+    },
+
+    /// Media from the server, such as a song or cover art, could not be
+    /// decoded.
+    #[error("Unable to decode media: {}", _0)]
+    Decode(String),
+
+    /// A listen could not be submitted to ListenBrainz or Last.fm.
+    #[error("Unable to submit listen: {}", _0)]
+    Scrobble(String),
+
+    /// The settings of a client could not be loaded.
+    #[error("Invalid configuration: {}", _0)]
+    Config(String),
+
+    /// A request to the server failed.
+    ///
+    /// Every error that comes from a request is wrapped in this, so that it
+    /// can be traced back to the method that failed. [`api_error`] and the
+    /// other methods of `Error` look through it; match on the result of
+    /// [`api_error`] or [`inner`] rather than on `Error::Api` directly, as an
+    /// error from the server is only ever returned wrapped in this.
+    ///
+    /// [`api_error`]: #method.api_error
+    /// [`inner`]: #method.inner
+    #[error("{}{}{} failed: {}", .endpoint, sep(.args), .args, .source)]
+    Request {
+        /// The name of the method, such as `"getSong"`.
+        endpoint: String,
+        /// The arguments of the request, with passwords and other secrets
+        /// hidden.
+        args: String,
+        /// The underlying error.
+        #[source]
+        source: Box<Error>,
+    },
 
     /// For general, one-off errors.
     #[error("{}", _0)]
     Other(&'static str),
 }
 
+impl Error {
+    /// Attaches the method and arguments of the request that caused the
+    /// error.
+    pub(crate) fn request(self, endpoint: &str, args: String) -> Error {
+        match self {
+            Error::Request { .. } => self,
+            e => Error::Request {
+                endpoint: endpoint.into(),
+                args,
+                source: Box::new(e),
+            },
+        }
+    }
+
+    /// Returns the error without the request it came from.
+    pub fn inner(&self) -> &Error {
+        match *self {
+            Error::Request { ref source, .. } => source.inner(),
+            ref e => e,
+        }
+    }
+
+    /// Returns the name of the method that failed, if the error came from a
+    /// request.
+    pub fn endpoint(&self) -> Option<&str> {
+        match *self {
+            Error::Request { ref endpoint, .. } => Some(endpoint),
+            _ => None,
+        }
+    }
+
+    /// Returns the error the server returned, if any.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match *self.inner() {
+            Error::Api(ref e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the request may succeed if it's sent again, such as
+    /// after a timeout or when the server is temporarily unavailable.
+    pub fn is_retryable(&self) -> bool {
+        match *self.inner() {
+            Error::Unavailable { .. } => true,
+            Error::Connection(status) => {
+                status.is_server_error()
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT
+            }
+            Error::Reqwest(ref e) => e.is_timeout() || e.is_connect(),
+            Error::Io(ref e) => matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns how long the server asked to wait before sending the request
+    /// again, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match *self.inner() {
+            Error::Unavailable { retry_after, .. } => retry_after,
+            _ => None,
+        }
+    }
+
+    /// Returns the error for a response with an unsuccessful status.
+    pub(crate) fn from_status(status: StatusCode, headers: &HeaderMap) -> Error {
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            let retry_after = headers
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| parse_retry_after(v, SystemTime::now()));
+            Error::Unavailable {
+                status,
+                retry_after,
+            }
+        } else {
+            Error::Connection(status)
+        }
+    }
+
+    /// Returns `true` if the server rejected the credentials of the client,
+    /// or the way they were sent.
+    ///
+    /// A user that lacks permission for an operation is not an
+    /// authentication error; see [`ApiError::NotAuthorized`].
+    ///
+    /// [`ApiError::NotAuthorized`]: ./enum.ApiError.html#variant.NotAuthorized
+    pub fn is_auth_error(&self) -> bool {
+        match *self.inner() {
+            Error::Api(ApiError::WrongAuth) | Error::Api(ApiError::Ldap) => true,
+            Error::Connection(status) => status == reqwest::StatusCode::UNAUTHORIZED,
+            _ => false,
+        }
+    }
+}
+
+/// Parses a `Retry-After` header, which is either a number of seconds or a
+/// date, into how long to wait from `now`.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date already past means the request can be sent straight away.
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Describes how long the server asked to wait, if it did.
+fn retry_hint(retry_after: &Option<Duration>) -> String {
+    match *retry_after {
+        Some(wait) => format!(" (retry after {}s)", wait.as_secs()),
+        None => String::new(),
+    }
+}
+
+/// Separates the method and arguments of a failed request.
+fn sep(args: &str) -> &'static str {
+    if args.is_empty() {
+        ""
+    } else {
+        "?"
+    }
+}
+
 /// The possible errors a Subsonic server may return.
 #[derive(Debug, thiserror::Error, Clone)]
 pub enum ApiError {
@@ -124,7 +324,7 @@ impl<'de> Deserialize<'de> for ApiError {
         use self::ApiError::*;
 
         match raw.code {
-            10 => Ok(Generic(raw.message)),
+            10 => Ok(MissingParameter),
             20 => Ok(ClientMustUpgrade),
             30 => Ok(ServerMustUpgrade),
             40 => Ok(WrongAuth),
@@ -132,7 +332,7 @@ impl<'de> Deserialize<'de> for ApiError {
             50 => Ok(NotAuthorized(raw.message)),
             60 => Ok(TrialExpired),
             70 => Ok(NotFound),
-            _ => unimplemented!(),
+            _ => Ok(Generic(raw.message)),
         }
     }
 }
@@ -159,3 +359,78 @@ impl From<url::ParseError> for Error {
         Error::UrlParseError(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_context() {
+        let err = Error::Api(ApiError::NotFound).request("getSong", "id=42".into());
+        assert_eq!(
+            err.to_string(),
+            "getSong?id=42 failed: Requested data not found"
+        );
+        assert_eq!(err.endpoint(), Some("getSong"));
+        assert!(matches!(err.api_error(), Some(ApiError::NotFound)));
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err = Error::Connection(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+            .request("ping", String::new());
+        assert_eq!(
+            err.to_string(),
+            "ping failed: Unable to connect to server: received 503 Service Unavailable"
+        );
+        assert!(err.is_retryable());
+        assert!(!err.is_auth_error());
+    }
+
+    #[test]
+    fn api_error_codes() {
+        let parse = |code| {
+            serde_json::from_value::<ApiError>(serde_json::json!({ "code": code, "message": "m" }))
+                .unwrap()
+        };
+        assert!(matches!(parse(10), ApiError::MissingParameter));
+        assert!(matches!(parse(44), ApiError::Generic(_)));
+        assert!(Error::Api(parse(40)).is_auth_error());
+        assert!(!Error::Api(parse(50)).is_auth_error());
+    }
+
+    #[test]
+    fn unavailable() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        let err = Error::from_status(StatusCode::TOO_MANY_REQUESTS, &headers)
+            .request("ping", String::new());
+        assert_eq!(
+            err.to_string(),
+            "ping failed: Server unavailable: received 429 Too Many Requests (retry after 120s)"
+        );
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(120)));
+
+        let err = Error::from_status(StatusCode::SERVICE_UNAVAILABLE, &HeaderMap::new());
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), None);
+
+        let err = Error::from_status(StatusCode::NOT_FOUND, &headers);
+        assert!(matches!(err, Error::Connection(StatusCode::NOT_FOUND)));
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn retry_after_dates() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(parse_retry_after(" 5 ", now), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
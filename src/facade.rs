@@ -0,0 +1,314 @@
+//! Namespaced access to the API from a [`Client`].
+//!
+//! Requests are spread over the types they return: random songs come from
+//! [`Song::random`], playlists from [`Playlist`], and so on. The accessors
+//! here gather them by topic on the client instead, which makes them easier
+//! to discover:
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! let songs = client.songs().random(20)?;
+//! let playlists = client.playlists().list()?;
+//! client.library().scan()?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+//!
+//! Each accessor only calls the functions it mirrors, so the two styles can
+//! be mixed freely.
+//!
+//! [`Client`]: ../struct.Client.html
+//! [`Song::random`]: ../song/struct.Song.html#method.random
+//! [`Playlist`]: ../struct.Playlist.html
+
+use std::ops::RangeInclusive;
+
+use crate::collections::{create_playlist, delete_playlist, get_playlist, get_playlists};
+use crate::library::{self, AllSongs};
+use crate::search::{SearchPage, SearchResult};
+use crate::song::{self, GenreSongs, Radio, RadioConstraints, RandomSongs};
+use crate::{
+    Album, AlbumId, Artist, ArtistId, Client, Genre, ListType, MusicFolder, NowPlaying, Playlist,
+    PlaylistId, RandomAlbum, Result, Song, SongId,
+};
+
+impl Client {
+    /// Requests about songs.
+    pub fn songs(&self) -> Songs<'_> {
+        Songs { client: self }
+    }
+
+    /// Requests about albums.
+    pub fn albums(&self) -> Albums<'_> {
+        Albums { client: self }
+    }
+
+    /// Requests about artists.
+    pub fn artists(&self) -> Artists<'_> {
+        Artists { client: self }
+    }
+
+    /// Requests about playlists.
+    pub fn playlists(&self) -> Playlists<'_> {
+        Playlists { client: self }
+    }
+
+    /// Requests about the library as a whole.
+    pub fn library(&self) -> Library<'_> {
+        Library { client: self }
+    }
+}
+
+/// Requests about songs, from [`Client::songs`].
+///
+/// [`Client::songs`]: ../struct.Client.html#method.songs
+#[derive(Debug, Clone, Copy)]
+pub struct Songs<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Songs<'a> {
+    /// Returns a song. See [`Song::get`].
+    ///
+    /// [`Song::get`]: ../song/struct.Song.html#method.get
+    pub fn get<I: Into<SongId>>(&self, id: I) -> Result<Song> {
+        Song::get(self.client, id)
+    }
+
+    /// Returns a number of random songs. See [`Song::random`].
+    ///
+    /// [`Song::random`]: ../song/struct.Song.html#method.random
+    pub fn random<U: Into<Option<usize>>>(&self, size: U) -> Result<Vec<Song>> {
+        Song::random(self.client, size)
+    }
+
+    /// Creates a builder to request random songs. See [`Song::random_with`].
+    ///
+    /// [`Song::random_with`]: ../song/struct.Song.html#method.random_with
+    pub fn random_with(&self) -> RandomSongs<'a> {
+        Song::random_with(self.client)
+    }
+
+    /// Lists the songs in a genre. See [`Song::list_in_genre`].
+    ///
+    /// [`Song::list_in_genre`]: ../song/struct.Song.html#method.list_in_genre
+    pub fn in_genre<U>(&self, genre: &str, page: SearchPage, folder_id: U) -> Result<Vec<Song>>
+    where
+        U: Into<Option<u64>>,
+    {
+        Song::list_in_genre(self.client, genre, page, folder_id)
+    }
+
+    /// Lists every song in a genre, a page at a time. See
+    /// [`Song::all_in_genre`].
+    ///
+    /// [`Song::all_in_genre`]: ../song/struct.Song.html#method.all_in_genre
+    pub fn all_in_genre<U: Into<Option<u64>>>(&self, genre: &str, folder_id: U) -> GenreSongs<'a> {
+        Song::all_in_genre(self.client, genre, folder_id)
+    }
+
+    /// Lists every song in the library. See [`library::all_songs`].
+    ///
+    /// [`library::all_songs`]: ../library/fn.all_songs.html
+    pub fn all(&self) -> AllSongs<'a> {
+        library::all_songs(self.client)
+    }
+
+    /// Creates an endless stream of songs. See [`song::radio`].
+    ///
+    /// [`song::radio`]: ../song/fn.radio.html
+    pub fn radio(&self, constraints: RadioConstraints) -> Radio<'a> {
+        song::radio(self.client, constraints)
+    }
+}
+
+/// Requests about albums, from [`Client::albums`].
+///
+/// [`Client::albums`]: ../struct.Client.html#method.albums
+#[derive(Debug, Clone, Copy)]
+pub struct Albums<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Albums<'a> {
+    /// Returns an album. See [`Album::get`].
+    ///
+    /// [`Album::get`]: ../struct.Album.html#method.get
+    pub fn get<I: Into<AlbumId>>(&self, id: I) -> Result<Album> {
+        Album::get(self.client, id)
+    }
+
+    /// Lists albums. See [`Album::list`].
+    ///
+    /// [`Album::list`]: ../struct.Album.html#method.list
+    pub fn list(&self, list_type: ListType, page: SearchPage, folder: usize) -> Result<Vec<Album>> {
+        Album::list(self.client, list_type, page, folder)
+    }
+
+    /// Lists the albums released in a range of years. See [`Album::by_year`].
+    ///
+    /// [`Album::by_year`]: ../struct.Album.html#method.by_year
+    pub fn by_year(
+        &self,
+        years: RangeInclusive<u32>,
+        newest_first: bool,
+        page: SearchPage,
+    ) -> Result<Vec<Album>> {
+        Album::by_year(self.client, years, newest_first, page)
+    }
+
+    /// Picks a random album. See [`Album::random`].
+    ///
+    /// [`Album::random`]: ../struct.Album.html#method.random
+    pub fn random(&self) -> Result<Option<Album>> {
+        Album::random(self.client)
+    }
+
+    /// Creates a builder to pick a random album. See [`Album::random_with`].
+    ///
+    /// [`Album::random_with`]: ../struct.Album.html#method.random_with
+    pub fn random_with(&self) -> RandomAlbum<'a> {
+        Album::random_with(self.client)
+    }
+}
+
+/// Requests about artists, from [`Client::artists`].
+///
+/// [`Client::artists`]: ../struct.Client.html#method.artists
+#[derive(Debug, Clone, Copy)]
+pub struct Artists<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Artists<'a> {
+    /// Returns an artist. See [`Artist::get`].
+    ///
+    /// [`Artist::get`]: ../struct.Artist.html#method.get
+    pub fn get<I: Into<ArtistId>>(&self, id: I) -> Result<Artist> {
+        Artist::get(self.client, id)
+    }
+
+    /// Lists every artist. See [`Artist::list`].
+    ///
+    /// [`Artist::list`]: ../struct.Artist.html#method.list
+    pub fn list<U: Into<Option<usize>>>(&self, folder_id: U) -> Result<Vec<Artist>> {
+        Artist::list(self.client, folder_id)
+    }
+
+    /// Passes every artist to `f`. See [`Artist::for_each`].
+    ///
+    /// [`Artist::for_each`]: ../struct.Artist.html#method.for_each
+    pub fn for_each<U, F>(&self, folder_id: U, f: F) -> Result<()>
+    where
+        U: Into<Option<usize>>,
+        F: FnMut(Artist),
+    {
+        Artist::for_each(self.client, folder_id, f)
+    }
+}
+
+/// Requests about playlists, from [`Client::playlists`].
+///
+/// [`Client::playlists`]: ../struct.Client.html#method.playlists
+#[derive(Debug, Clone, Copy)]
+pub struct Playlists<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Playlists<'a> {
+    /// Lists the playlists the user can play.
+    pub fn list(&self) -> Result<Vec<Playlist>> {
+        get_playlists(self.client, None)
+    }
+
+    /// Lists the playlists another user can play. Only admins may do so.
+    pub fn list_for(&self, user: &str) -> Result<Vec<Playlist>> {
+        get_playlists(self.client, Some(user.to_string()))
+    }
+
+    /// Returns a playlist, with its songs.
+    pub fn get<I: Into<PlaylistId>>(&self, id: I) -> Result<Playlist> {
+        get_playlist(self.client, &id.into())
+    }
+
+    /// Creates a playlist of songs.
+    ///
+    /// Since API version 1.14.0, the newly created playlist is returned. In
+    /// earlier versions, an empty response is returned.
+    pub fn create(&self, name: &str, songs: &[SongId]) -> Result<Option<Playlist>> {
+        create_playlist(self.client, name.to_string(), songs)
+    }
+
+    /// Deletes a playlist. Only the owner of the playlist may do so.
+    pub fn delete(&self, id: &PlaylistId) -> Result<()> {
+        delete_playlist(self.client, id)
+    }
+}
+
+/// Requests about the library as a whole, from [`Client::library`].
+///
+/// [`Client::library`]: ../struct.Client.html#method.library
+#[derive(Debug, Clone, Copy)]
+pub struct Library<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Library<'a> {
+    /// Starts a scan of the library. See [`Client::scan_library`].
+    ///
+    /// [`Client::scan_library`]: ../struct.Client.html#method.scan_library
+    pub fn scan(&self) -> Result<()> {
+        self.client.scan_library()
+    }
+
+    /// Returns whether a scan is running, and the number of files scanned.
+    /// See [`Client::scan_status`].
+    ///
+    /// [`Client::scan_status`]: ../struct.Client.html#method.scan_status
+    pub fn scan_status(&self) -> Result<(bool, u64)> {
+        self.client.scan_status()
+    }
+
+    /// Returns the music folders.
+    pub fn folders(&self) -> Result<Vec<MusicFolder>> {
+        self.client.music_folders()
+    }
+
+    /// Returns the genres.
+    pub fn genres(&self) -> Result<Vec<Genre>> {
+        self.client.genres()
+    }
+
+    /// Returns the starred artists, albums and songs.
+    pub fn starred<U: Into<Option<usize>>>(&self, folder_id: U) -> Result<SearchResult> {
+        self.client.starred(folder_id)
+    }
+
+    /// Returns what every user is playing.
+    pub fn now_playing(&self) -> Result<Vec<NowPlaying>> {
+        self.client.now_playing()
+    }
+
+    /// Searches the library. See [`Client::search`].
+    ///
+    /// [`Client::search`]: ../struct.Client.html#method.search
+    pub fn search(
+        &self,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> Result<SearchResult> {
+        self.client
+            .search(query, artist_page, album_page, song_page)
+    }
+}
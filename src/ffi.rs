@@ -0,0 +1,373 @@
+//! A C interface, for players written in other languages.
+//!
+//! The functions here cover what a player needs from a Subsonic server:
+//! connecting, searching for songs, streaming and downloading them, and
+//! fetching cover art. They are declared for C in `include/sunk.h`.
+//!
+//! Build the crate as a shared library with the `ffi` feature:
+//!
+//! ```text
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! ```
+//!
+//! and link against `libsunk.so` (`sunk.dll`, `libsunk.dylib`) in
+//! `target/release`.
+//!
+//! # Conventions
+//!
+//! - Strings are UTF-8 and nul-terminated, both ways.
+//! - Functions that fail return `NULL`, or `-1` where they return an `int`,
+//!   and leave a message for [`sunk_last_error`] on the calling thread.
+//! - Everything returned is owned by the caller, and freed with the
+//!   matching `sunk_*_free` function.
+//! - A client may be used from one thread at a time.
+//!
+//! [`sunk_last_error`]: fn.sunk_last_error.html
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::ptr;
+
+use crate::query::Query;
+use crate::search::{self, SearchPage};
+use crate::storage::LocalStorage;
+use crate::{Client, Error, HasCoverArt, Result, Song};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A connection to a Subsonic server.
+#[derive(Debug)]
+pub struct SunkClient {
+    client: Client,
+}
+
+/// A song found by a search.
+///
+/// Strings other than `id` and `title` are `NULL` when the server doesn't
+/// send them.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SunkSong {
+    /// The song's ID, to pass to the other functions.
+    pub id: *mut c_char,
+    /// The song's title.
+    pub title: *mut c_char,
+    /// The song's artist.
+    pub artist: *mut c_char,
+    /// The song's album.
+    pub album: *mut c_char,
+    /// The ID of the song's cover art, for `sunk_cover_art`.
+    pub cover_id: *mut c_char,
+    /// The length of the song in seconds, or 0 if unknown.
+    pub duration: u64,
+}
+
+/// The songs found by a search.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SunkSongList {
+    /// The songs, `len` of them.
+    pub songs: *mut SunkSong,
+    /// The number of songs.
+    pub len: usize,
+}
+
+/// Returns the message of the last error on this thread, or `NULL` if there
+/// hasn't been one.
+///
+/// The message is owned by the library, and lasts until the next call that
+/// fails on this thread.
+#[no_mangle]
+pub extern "C" fn sunk_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Connects to the server at `url` as `user`, and returns the client, or
+/// `NULL` on error.
+///
+/// No request is made; the credentials are checked by the first call that
+/// uses the client.
+///
+/// # Safety
+///
+/// The arguments must be valid nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_client_new(
+    url: *const c_char,
+    user: *const c_char,
+    password: *const c_char,
+) -> *mut SunkClient {
+    let res = (|| Client::new(str_arg(url)?, str_arg(user)?, str_arg(password)?))();
+    match res {
+        Ok(client) => Box::into_raw(Box::new(SunkClient { client })),
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+/// Frees a client. Does nothing if `client` is `NULL`.
+///
+/// # Safety
+///
+/// `client` must have been returned by `sunk_client_new`, and not freed
+/// before.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_client_free(client: *mut SunkClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Searches the server for up to `count` songs matching `query`, and returns
+/// them, or `NULL` on error.
+///
+/// # Safety
+///
+/// `client` must be a live client, and `query` a valid nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_search(
+    client: *const SunkClient,
+    query: *const c_char,
+    count: usize,
+) -> *mut SunkSongList {
+    let res = (|| {
+        let page = SearchPage::new().with_size(count);
+        client_arg(client)?.search(str_arg(query)?, search::NONE, search::NONE, page)
+    })();
+    match res {
+        Ok(found) => {
+            let songs = found.songs.iter().map(c_song).collect::<Box<[_]>>();
+            let len = songs.len();
+            let songs = Box::into_raw(songs) as *mut SunkSong;
+            Box::into_raw(Box::new(SunkSongList { songs, len }))
+        }
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+/// Frees a list of songs. Does nothing if `list` is `NULL`.
+///
+/// # Safety
+///
+/// `list` must have been returned by `sunk_search`, and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_song_list_free(list: *mut SunkSongList) {
+    if list.is_null() {
+        return;
+    }
+    let list = Box::from_raw(list);
+    let songs = Box::from_raw(ptr::slice_from_raw_parts_mut(list.songs, list.len));
+    for song in songs.iter() {
+        for s in &[song.id, song.title, song.artist, song.album, song.cover_id] {
+            sunk_string_free(*s);
+        }
+    }
+}
+
+/// Returns the URL streaming the song with the ID `song_id`, or `NULL` on
+/// error.
+///
+/// The URL holds the client's credentials, as a token where the client
+/// uses them, so should be handled like a password.
+///
+/// # Safety
+///
+/// `client` must be a live client, and `song_id` a valid nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_stream_url(
+    client: *const SunkClient,
+    song_id: *const c_char,
+) -> *mut c_char {
+    let res = (|| client_arg(client)?.build_url("stream", Query::with("id", str_arg(song_id)?)))();
+    match res {
+        Ok(url) => c_string(url.as_str()),
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+/// Downloads the song with the ID `song_id` to the file `path`, in its
+/// original format. Returns 0 on success, or -1 on error.
+///
+/// The file is replaced only once the whole song has arrived.
+///
+/// # Safety
+///
+/// `client` must be a live client, and `song_id` and `path` valid
+/// nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_download(
+    client: *const SunkClient,
+    song_id: *const c_char,
+    path: *const c_char,
+) -> c_int {
+    let res = (|| {
+        let query = Query::with("id", str_arg(song_id)?);
+        let path = Path::new(str_arg(path)?);
+        // An empty root leaves `path` as it is, relative or not.
+        let storage = LocalStorage::new("");
+        client_arg(client)?.get_to_storage("download", query, &storage, path, |_, _| ())
+    })();
+    match res {
+        Ok(_) => 0,
+        Err(e) => fail(e, -1),
+    }
+}
+
+/// Fetches the cover art with the ID `cover_id`, scaled to `size` pixels
+/// if not 0, and returns the image, or `NULL` on error. The length of the
+/// image is written to `len`.
+///
+/// # Safety
+///
+/// `client` must be a live client, `cover_id` a valid nul-terminated string
+/// and `len` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_cover_art(
+    client: *const SunkClient,
+    cover_id: *const c_char,
+    size: usize,
+    len: *mut usize,
+) -> *mut u8 {
+    let res = (|| {
+        let size = if size == 0 { None } else { Some(size) };
+        let query = Query::with("id", str_arg(cover_id)?)
+            .arg("size", size)
+            .build();
+        client_arg(client)?.get_cover_art(query)
+    })();
+    match res {
+        Ok(res) => {
+            let bytes = res.bytes.into_boxed_slice();
+            *len = bytes.len();
+            Box::into_raw(bytes) as *mut u8
+        }
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+/// Frees an image returned by `sunk_cover_art`. Does nothing if `bytes` is
+/// `NULL`.
+///
+/// # Safety
+///
+/// `bytes` must have been returned by `sunk_cover_art` along with `len`, and
+/// not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_bytes_free(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+    }
+}
+
+/// Frees a string returned by the library. Does nothing if `s` is `NULL`.
+///
+/// # Safety
+///
+/// `s` must have been returned by the library, and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn sunk_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Keeps `err` for `sunk_last_error`, and returns `ret`.
+fn fail<T>(err: Error, ret: T) -> T {
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(c_owned(&err.to_string())));
+    ret
+}
+
+/// Reads a string passed in from C.
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(Error::Other("null string passed"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Error::Other("string passed is not UTF-8"))
+}
+
+/// Reads a client passed in from C.
+unsafe fn client_arg<'a>(client: *const SunkClient) -> Result<&'a Client> {
+    client
+        .as_ref()
+        .map(|c| &c.client)
+        .ok_or(Error::Other("null client passed"))
+}
+
+/// Returns `s` as a string owned by C, dropping any nul bytes.
+fn c_owned(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+fn c_string(s: &str) -> *mut c_char {
+    c_owned(s).into_raw()
+}
+
+fn c_song(song: &Song) -> SunkSong {
+    let opt = |s: Option<&str>| s.map_or(ptr::null_mut(), c_string);
+    SunkSong {
+        id: c_string(song.id.as_str()),
+        title: c_string(&song.title),
+        artist: opt(song.artist.as_deref()),
+        album: opt(song.album.as_deref()),
+        cover_id: opt(song.cover_id().map(|c| c.as_str())),
+        duration: song.duration.unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    #[test]
+    fn client_and_urls() {
+        unsafe {
+            let (url, user, pass) = (c("http://localhost"), c("user"), c("pass"));
+            let client = sunk_client_new(url.as_ptr(), user.as_ptr(), pass.as_ptr());
+            assert!(!client.is_null());
+
+            let stream = sunk_stream_url(client, c("27").as_ptr());
+            let stream_str = CStr::from_ptr(stream).to_str().unwrap();
+            assert!(stream_str.starts_with("http://localhost/rest/stream?"));
+            assert!(stream_str.contains("id=27"));
+            sunk_string_free(stream);
+
+            assert!(sunk_stream_url(client, ptr::null()).is_null());
+            let err = CStr::from_ptr(sunk_last_error()).to_str().unwrap();
+            assert_eq!(err, "null string passed");
+            sunk_client_free(client);
+
+            let bad = sunk_client_new(c("localhost").as_ptr(), user.as_ptr(), pass.as_ptr());
+            assert!(bad.is_null());
+        }
+    }
+
+    #[test]
+    fn song_lists() {
+        let fields = serde_json::json!({ "title": "Bellevue", "artist": "Misteur Valaire" });
+        let song = test_util::song_with("27", fields);
+        let songs = vec![c_song(&song)].into_boxed_slice();
+        let len = songs.len();
+        let list = Box::into_raw(Box::new(SunkSongList {
+            songs: Box::into_raw(songs) as *mut SunkSong,
+            len,
+        }));
+        unsafe {
+            let first = &*(*list).songs;
+            assert_eq!(CStr::from_ptr(first.title).to_str().unwrap(), "Bellevue");
+            assert!(first.album.is_null());
+            sunk_song_list_free(list);
+        }
+    }
+}
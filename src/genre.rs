@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::Genre;
+
+/// Aliases applied by [`GenreMap::default`], as `(alias, canonical)` pairs.
+///
+/// [`GenreMap::default`]: ./struct.GenreMap.html#method.default
+const DEFAULT_ALIASES: &[(&str, &str)] = &[
+    ("Hip Hop", "Hip-Hop"),
+    ("R and B", "R&B"),
+    ("RnB", "R&B"),
+    ("Rhythm and Blues", "R&B"),
+    ("Rock and Roll", "Rock & Roll"),
+    ("Rock n Roll", "Rock & Roll"),
+    ("Drum and Bass", "Drum & Bass"),
+    ("Drum n Bass", "Drum & Bass"),
+    ("DnB", "Drum & Bass"),
+    ("Synth Pop", "Synthpop"),
+    ("Lo Fi", "Lo-Fi"),
+    ("Post Rock", "Post-Rock"),
+    ("Post Punk", "Post-Punk"),
+    ("Electronica", "Electronic"),
+    ("Soundtracks", "Soundtrack"),
+    ("OST", "Soundtrack"),
+];
+
+/// A normalization map that merges spelling variants of the same genre.
+///
+/// Real libraries mix "Hip Hop", "Hip-Hop" and "hiphop". Genres are compared
+/// by a key that ignores case, whitespace and punctuation, so those three are
+/// already the same genre; aliases can then merge genres that are spelled
+/// differently altogether, such as "RnB" and "R&B".
+///
+/// Set a map on the [`Client`] with [`with_genre_map`] to have
+/// [`Client::genres`] and [`Song::list_in_genre`] merge variants.
+///
+/// [`Client`]: ./struct.Client.html
+/// [`with_genre_map`]: ./struct.Client.html#method.with_genre_map
+/// [`Client::genres`]: ./struct.Client.html#method.genres
+/// [`Song::list_in_genre`]: ./song/struct.Song.html#method.list_in_genre
+///
+/// # Examples
+///
+/// ```
+/// use sunk::GenreMap;
+///
+/// let map = GenreMap::default().alias("Trip Hop", "Trip-Hop");
+///
+/// assert_eq!(map.canonical("hip hop"), "Hip-Hop");
+/// assert_eq!(map.canonical("TRIP HOP"), "Trip-Hop");
+/// assert!(map.same("RnB", "r&b"));
+/// assert_eq!(map.canonical("Shoegaze"), "Shoegaze");
+/// ```
+#[derive(Debug, Clone)]
+pub struct GenreMap {
+    aliases: HashMap<String, String>,
+}
+
+impl GenreMap {
+    /// Creates a map without any aliases. Genres are still merged if they
+    /// differ only by case, whitespace or punctuation.
+    pub fn new() -> GenreMap {
+        GenreMap {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Adds an alias, so that `alias` is treated as the genre `canonical`.
+    ///
+    /// The canonical genre is also registered under its own key, so that
+    /// variants of it are displayed with its spelling.
+    pub fn alias(mut self, alias: &str, canonical: &str) -> GenreMap {
+        self.aliases.insert(key(alias), canonical.to_string());
+        self.aliases.insert(key(canonical), canonical.to_string());
+        self
+    }
+
+    /// Returns the canonical name of a genre.
+    ///
+    /// Genres without an alias are returned unchanged.
+    pub fn canonical<'a>(&self, genre: &'a str) -> Cow<'a, str> {
+        match self.aliases.get(&key(genre)) {
+            Some(c) => Cow::Owned(c.clone()),
+            None => Cow::Borrowed(genre),
+        }
+    }
+
+    /// Returns whether two genres are the same after normalization.
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        key(&self.canonical(a)) == key(&self.canonical(b))
+    }
+
+    /// Merges genres that normalize to the same genre, summing their song and
+    /// album counts. The merged genre takes the canonical name, or the name of
+    /// its first variant if there is no alias for it.
+    pub fn merge(&self, genres: Vec<Genre>) -> Vec<Genre> {
+        let mut merged: Vec<Genre> = Vec::with_capacity(genres.len());
+        for genre in genres {
+            match merged.iter_mut().find(|g| self.same(&g.name, &genre.name)) {
+                Some(g) => {
+                    g.song_count += genre.song_count;
+                    g.album_count += genre.album_count;
+                }
+                None => {
+                    let mut genre = genre;
+                    genre.name = self.canonical(&genre.name).into_owned();
+                    merged.push(genre);
+                }
+            }
+        }
+        merged
+    }
+}
+
+impl Default for GenreMap {
+    /// Creates a map with aliases for common spelling variants, such as
+    /// "Hip Hop" and "RnB".
+    fn default() -> GenreMap {
+        DEFAULT_ALIASES
+            .iter()
+            .fold(GenreMap::new(), |map, &(a, c)| map.alias(a, c))
+    }
+}
+
+/// The comparison key of a genre: lowercase, without whitespace or
+/// punctuation.
+fn key(genre: &str) -> String {
+    genre
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genre(name: &str, song_count: u64) -> Genre {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "songCount": song_count,
+            "albumCount": 1
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn canonical_genres() {
+        let map = GenreMap::default();
+
+        assert_eq!(map.canonical("Hip Hop"), "Hip-Hop");
+        assert_eq!(map.canonical("hiphop"), "Hip-Hop");
+        assert_eq!(map.canonical("Drum n' Bass"), "Drum & Bass");
+        assert_eq!(map.canonical("Jazz"), "Jazz");
+        assert!(map.same("Jazz", "jazz"));
+        assert!(!GenreMap::new().same("RnB", "R&B"));
+    }
+
+    #[test]
+    fn merge_genres() {
+        let map = GenreMap::default();
+        let genres = vec![
+            genre("Hip Hop", 3),
+            genre("Jazz", 2),
+            genre("Hip-Hop", 4),
+            genre("hiphop", 1),
+        ];
+        let merged = map.merge(genres);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].name, "Hip-Hop");
+        assert_eq!(merged[0].song_count, 8);
+        assert_eq!(merged[0].album_count, 3);
+        assert_eq!(merged[1].name, "Jazz");
+    }
+}
@@ -0,0 +1,223 @@
+//! Watching whether the server can be reached.
+//!
+//! A [`KeepAlive`] pings the server from a background thread at a fixed
+//! interval, and keeps the outcome in a [`ServerHealth`] that can be read at
+//! any time, such as when drawing a connection indicator. A callback is
+//! called whenever the server goes away or comes back.
+//!
+//! [`KeepAlive`]: struct.KeepAlive.html
+//! [`ServerHealth`]: struct.ServerHealth.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use sunk::health::{KeepAlive, Status};
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Arc::new(Client::new(site, user, password)?);
+//! let keep_alive = KeepAlive::start(client, Duration::from_secs(30), |health| {
+//!     match health.status {
+//!         Status::Up => println!("Connected"),
+//!         Status::Down => println!("Lost connection: {:?}", health.error),
+//!         Status::Unknown => (),
+//!     }
+//! });
+//!
+//! // Later, when showing connection state:
+//! let connected = keep_alive.health().is_up();
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{Client, Result};
+
+/// Whether the server could be reached when last pinged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Status {
+    /// The server hasn't been pinged yet.
+    #[default]
+    Unknown,
+    /// The server answered the last ping.
+    Up,
+    /// The last ping failed.
+    Down,
+}
+
+/// What is known about the server's health from the latest pings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerHealth {
+    /// Whether the server answered the last ping.
+    pub status: Status,
+    /// When the server was last pinged.
+    pub checked: Option<SystemTime>,
+    /// How long the last successful ping took.
+    pub latency: Option<Duration>,
+    /// Why the last ping failed, if it did.
+    pub error: Option<String>,
+    /// The number of pings that have failed in a row.
+    pub failures: u32,
+}
+
+impl ServerHealth {
+    /// Returns whether the server answered the last ping.
+    pub fn is_up(&self) -> bool {
+        self.status == Status::Up
+    }
+
+    /// Records the outcome of a ping that took `elapsed`, and returns whether
+    /// the status changed.
+    pub(crate) fn record(&mut self, res: Result<()>, elapsed: Duration) -> bool {
+        let old = self.status;
+        self.checked = Some(SystemTime::now());
+        match res {
+            Ok(()) => {
+                self.status = Status::Up;
+                self.latency = Some(elapsed);
+                self.error = None;
+                self.failures = 0;
+            }
+            Err(e) => {
+                self.status = Status::Down;
+                self.error = Some(e.to_string());
+                self.failures += 1;
+            }
+        }
+        self.status != old
+    }
+}
+
+/// Pings the server in the background until stopped or dropped.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: index.html
+#[derive(Debug)]
+pub struct KeepAlive {
+    health: Arc<Mutex<ServerHealth>>,
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KeepAlive {
+    /// Starts pinging the server every `interval`, beginning straight away.
+    ///
+    /// `on_change` is called from the background thread whenever the status
+    /// changes, including after the first ping.
+    pub fn start<F>(client: Arc<Client>, interval: Duration, mut on_change: F) -> KeepAlive
+    where
+        F: FnMut(&ServerHealth) + Send + 'static,
+    {
+        let health = Arc::new(Mutex::new(ServerHealth::default()));
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let thread = {
+            let health = health.clone();
+            let stop = stop.clone();
+            thread::spawn(move || loop {
+                let started = Instant::now();
+                let res = client.ping();
+                let update = {
+                    let mut health = health.lock().unwrap();
+                    if health.record(res, started.elapsed()) {
+                        Some(health.clone())
+                    } else {
+                        None
+                    }
+                };
+                // Called without the lock held, so the callback can read the
+                // health itself.
+                if let Some(update) = update {
+                    on_change(&update);
+                }
+
+                let (ref stopped, ref wake) = *stop;
+                let stopped = stopped.lock().unwrap();
+                let (stopped, _) = wake.wait_timeout_while(stopped, interval, |s| !*s).unwrap();
+                if *stopped {
+                    return;
+                }
+            })
+        };
+
+        KeepAlive {
+            health,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the server's health as of the latest ping.
+    pub fn health(&self) -> ServerHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    /// Stops pinging the server, waiting for a ping in progress to finish.
+    pub fn stop(self) {}
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        let (ref stopped, ref wake) = *self.stop;
+        *stopped.lock().unwrap() = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::sync::mpsc;
+
+    #[test]
+    fn record_transitions() {
+        let mut health = ServerHealth::default();
+        let ms = Duration::from_millis(5);
+
+        assert!(health.record(Ok(()), ms));
+        assert!(health.is_up());
+        assert_eq!(health.latency, Some(ms));
+        assert!(!health.record(Ok(()), ms));
+
+        assert!(health.record(Err(Error::Other("gone")), ms));
+        assert_eq!(health.status, Status::Down);
+        assert_eq!(health.error.as_deref(), Some("gone"));
+        assert!(!health.record(Err(Error::Other("still gone")), ms));
+        assert_eq!(health.failures, 2);
+
+        assert!(health.record(Ok(()), ms));
+        assert_eq!(health.failures, 0);
+        assert_eq!(health.error, None);
+    }
+
+    #[test]
+    fn reports_unreachable_server() {
+        // Nothing listens on the discard port, so pings fail straight away.
+        let client = Arc::new(Client::new("http://127.0.0.1:9", "user", "pass").unwrap());
+        let (tx, rx) = mpsc::channel();
+        let keep_alive = KeepAlive::start(client, Duration::from_secs(60), move |h| {
+            let _ = tx.send(h.status);
+        });
+
+        let status = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(status, Status::Down);
+        assert_eq!(keep_alive.health().failures, 1);
+        // Stopping interrupts the wait for the next ping.
+        keep_alive.stop();
+    }
+}
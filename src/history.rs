@@ -0,0 +1,268 @@
+//! A local record of what was played.
+//!
+//! The Subsonic API counts plays, but has no way to ask what was played when.
+//! A [`History`] given to the client with [`Client::with_history`] records
+//! every song the client reports as playing or played, so that it can later
+//! answer questions such as "what did I play last Tuesday?".
+//!
+//! [`History`]: struct.History.html
+//! [`Client::with_history`]: ../struct.Client.html#method.with_history
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::time::{Duration, SystemTime};
+//! use sunk::history::History;
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let history = History::open("history.jsonl")?;
+//! let client = Client::new(site, user, password)?.with_history(history.clone());
+//!
+//! let song = Song::get(&client, 27)?;
+//! song.scrobble_at(&client, None)?;
+//!
+//! let day = Duration::from_secs(24 * 60 * 60);
+//! for play in history.between(SystemTime::now() - day, SystemTime::now()) {
+//!     println!("{}", play.title);
+//! }
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::{Result, Song, SongId};
+
+/// How a play was reported to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlayKind {
+    /// The song started playing.
+    NowPlaying,
+    /// The song was played, and scrobbled.
+    Scrobble,
+}
+
+/// A song the client reported to the server as playing or played.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Play {
+    /// The song played.
+    pub song: SongId,
+    /// The song's title.
+    pub title: String,
+    /// The song's artist, if known.
+    pub artist: Option<String>,
+    /// The song's album, if known.
+    pub album: Option<String>,
+    /// How the play was reported.
+    pub kind: PlayKind,
+    /// When the song was played.
+    pub time: SystemTime,
+}
+
+impl Play {
+    /// Creates a play of a song.
+    pub(crate) fn new(song: &Song, kind: PlayKind, time: SystemTime) -> Play {
+        Play {
+            song: song.id.clone(),
+            title: song.title.clone(),
+            artist: song.artist.as_deref().map(str::to_string),
+            album: song.album.as_deref().map(str::to_string),
+            kind,
+            time,
+        }
+    }
+}
+
+/// A record of the songs played through a client.
+///
+/// A history is a handle: clones share the same record, so one can be given
+/// to the client while another is kept to query it.
+///
+/// See the [module-level documentation](./index.html) for more information.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    inner: Arc<Mutex<Record>>,
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    plays: Vec<Play>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Creates an empty history, kept in memory only.
+    pub fn new() -> History {
+        History::default()
+    }
+
+    /// Opens a history kept in a file, creating it if it doesn't exist.
+    ///
+    /// Plays are appended to the file as they are recorded, one JSON object
+    /// per line. Lines that can't be read, such as one cut short by a crash,
+    /// are skipped and logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<History> {
+        let path = path.as_ref();
+        let mut plays = Vec::new();
+        match File::open(path) {
+            Ok(file) => {
+                for (n, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(&line) {
+                        Ok(play) => plays.push(play),
+                        Err(e) => warn!(
+                            "Skipping malformed line {} of {}: {}",
+                            n + 1,
+                            path.display(),
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+        // Plays are appended as they are recorded, which isn't always the
+        // order they were played in.
+        plays.sort_by_key(|p: &Play| p.time);
+
+        let record = Record {
+            plays,
+            path: Some(path.to_path_buf()),
+        };
+        Ok(History {
+            inner: Arc::new(Mutex::new(record)),
+        })
+    }
+
+    /// Returns every play recorded, oldest first.
+    pub fn plays(&self) -> Vec<Play> {
+        self.lock().plays.clone()
+    }
+
+    /// Returns the plays from `from` up to, but not including, `to`, oldest
+    /// first.
+    pub fn between(&self, from: SystemTime, to: SystemTime) -> Vec<Play> {
+        self.lock()
+            .plays
+            .iter()
+            .filter(|p| p.time >= from && p.time < to)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the plays on a day, in UTC, oldest first.
+    #[cfg(feature = "chrono")]
+    pub fn on(&self, day: chrono::NaiveDate) -> Vec<Play> {
+        let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let end = start + chrono::Duration::days(1);
+        self.between(start.into(), end.into())
+    }
+
+    /// Records a play, in order of when it was played.
+    ///
+    /// A play that can't be written to the history's file is still kept in
+    /// memory; the error is logged rather than failing the request that was
+    /// played.
+    pub(crate) fn record(&self, play: Play) {
+        let mut record = self.lock();
+        if let Some(ref path) = record.path {
+            if let Err(e) = append(path, &play) {
+                warn!("Failed to record play in {}: {}", path.display(), e);
+            }
+        }
+        let at = record.plays.partition_point(|p| p.time <= play.time);
+        record.plays.insert(at, play);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Record> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Appends a play to a history file.
+fn append(path: &Path, play: &Play) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_vec(play)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn play(secs: u64) -> Play {
+        Play {
+            song: SongId::from(27),
+            title: "Bellevue Avenue".into(),
+            artist: Some("Misteur Valaire".into()),
+            album: None,
+            kind: PlayKind::Scrobble,
+            time: UNIX_EPOCH + Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn plays_between() {
+        let history = History::new();
+        let handle = history.clone();
+        for secs in &[100, 200, 300] {
+            handle.record(play(*secs));
+        }
+
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(history.plays().len(), 3);
+        assert_eq!(history.between(at(100), at(300)), [play(100), play(200)]);
+    }
+
+    #[test]
+    fn history_file() {
+        let path = std::env::temp_dir().join(format!("sunk-history-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let history = History::open(&path).unwrap();
+        history.record(play(100));
+        history.record(play(200));
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{{\"song\": \"27\", \"time\"").unwrap();
+        history.record(play(300));
+        history.record(play(150));
+
+        let reopened = History::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let plays = [play(100), play(150), play(200), play(300)];
+        assert_eq!(reopened.plays(), plays);
+    }
+
+    #[test]
+    fn plays_in_time_order() {
+        let history = History::new();
+        history.record(play(300));
+        history.record(play(100));
+        history.record(play(200));
+
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(history.plays(), [play(100), play(200), play(300)]);
+        assert_eq!(history.between(at(0), at(250)), [play(100), play(200)]);
+    }
+}
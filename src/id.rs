@@ -0,0 +1,233 @@
+//! Typed identifiers for entities on a Subsonic server.
+//!
+//! Subsonic treats IDs as opaque strings. Older servers happen to use numbers,
+//! but OpenSubsonic servers are free to use any string (Navidrome, for
+//! example, uses hashes). Each kind of entity gets its own ID type so that an
+//! album's ID can't be handed to a method expecting a song's.
+//!
+//! Cover art IDs are shared between entities and are namespaced by a prefix
+//! instead: `ar-1` is the cover of artist 1, `al-1` of album 1, and a plain
+//! `1` usually the cover embedded in song 1. [`CoverId`] keeps the prefix
+//! intact so that the ID can be handed back to the server unchanged.
+//!
+//! [`CoverId`]: ../struct.CoverId.html
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::str::FromStr;
+use std::{fmt, result};
+
+use crate::query::{Arg, IntoArg};
+use crate::{Album, Artist, Error, Playlist, Result, Song};
+
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(String);
+
+        impl $name {
+            /// Creates an ID from its raw form.
+            pub fn new<S: Into<String>>(id: S) -> $name {
+                $name(id.into())
+            }
+
+            /// Returns the raw form of the ID, as sent by the server.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<$name> {
+                if s.is_empty() {
+                    Err(Error::Other("empty ID"))
+                } else {
+                    Ok($name(s.to_string()))
+                }
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(id: u64) -> $name {
+                $name(id.to_string())
+            }
+        }
+
+        impl<'a> From<&'a str> for $name {
+            fn from(id: &'a str) -> $name {
+                $name(id.to_string())
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> $name {
+                $name(id)
+            }
+        }
+
+        impl<'a> From<&'a $name> for $name {
+            fn from(id: &'a $name) -> $name {
+                id.clone()
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Ok($name(String::deserialize(de)?))
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                se.serialize_str(&self.0)
+            }
+        }
+
+        impl IntoArg for $name {
+            fn into_arg(self) -> Arg {
+                self.0.into_arg()
+            }
+        }
+
+        impl<'a> IntoArg for &'a $name {
+            fn into_arg(self) -> Arg {
+                self.as_str().into_arg()
+            }
+        }
+    };
+}
+
+id_type!(
+    /// The ID of a song.
+    SongId
+);
+id_type!(
+    /// The ID of an album.
+    AlbumId
+);
+id_type!(
+    /// The ID of an artist.
+    ArtistId
+);
+id_type!(
+    /// The ID of a playlist.
+    PlaylistId
+);
+id_type!(
+    /// The ID of a piece of cover art.
+    CoverId
+);
+
+/// The kind of entity a [`CoverId`] belongs to, as given by its prefix.
+///
+/// [`CoverId`]: ./struct.CoverId.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoverKind {
+    /// An artist's cover (`ar-`).
+    Artist,
+    /// An album's cover (`al-`).
+    Album,
+    /// A playlist's cover (`pl-`).
+    Playlist,
+    /// A podcast channel's cover (`pod-`).
+    Podcast,
+    /// A cover without a prefix, or with one the crate doesn't know about.
+    /// These are usually covers embedded in a song or video file.
+    Other,
+}
+
+impl CoverId {
+    /// Returns the namespace prefix of the ID (such as `"al"`), if it has
+    /// one.
+    pub fn prefix(&self) -> Option<&str> {
+        let mut parts = self.0.splitn(2, '-');
+        let prefix = parts.next()?;
+        parts.next()?;
+
+        if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+            Some(prefix)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the kind of entity the cover belongs to.
+    pub fn kind(&self) -> CoverKind {
+        match self.prefix() {
+            Some("ar") => CoverKind::Artist,
+            Some("al") => CoverKind::Album,
+            Some("pl") => CoverKind::Playlist,
+            Some("pod") => CoverKind::Podcast,
+            _ => CoverKind::Other,
+        }
+    }
+}
+
+impl<'a> From<&'a Song> for SongId {
+    fn from(song: &'a Song) -> SongId {
+        song.id.clone()
+    }
+}
+
+impl<'a> From<&'a Album> for AlbumId {
+    fn from(album: &'a Album) -> AlbumId {
+        album.id.clone()
+    }
+}
+
+impl<'a> From<&'a Artist> for ArtistId {
+    fn from(artist: &'a Artist) -> ArtistId {
+        artist.id.clone()
+    }
+}
+
+impl<'a> From<&'a Playlist> for PlaylistId {
+    fn from(playlist: &'a Playlist) -> PlaylistId {
+        playlist.id().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_opaque() {
+        let numeric = serde_json::from_str::<SongId>(r#""27""#).unwrap();
+        let hashed = serde_json::from_str::<SongId>(r#""2f8a6c1e9b""#).unwrap();
+
+        assert_eq!(numeric, SongId::from(27));
+        assert_eq!(hashed.as_str(), "2f8a6c1e9b");
+        assert_eq!(serde_json::to_string(&hashed).unwrap(), r#""2f8a6c1e9b""#);
+        assert!("".parse::<AlbumId>().is_err());
+    }
+
+    #[test]
+    fn cover_id_prefix() {
+        let ids = ["ar-1", "al-1", "pl-3", "1880", "mf-2f8a6c1e9b_6548a1b2"];
+        let ids = ids.iter().map(|&s| CoverId::from(s)).collect::<Vec<_>>();
+
+        assert_eq!(ids[0].kind(), CoverKind::Artist);
+        assert_eq!(ids[1].kind(), CoverKind::Album);
+        assert_eq!(ids[2].kind(), CoverKind::Playlist);
+        assert_eq!(ids[3].prefix(), None);
+        assert_eq!(ids[3].kind(), CoverKind::Other);
+        assert_eq!(ids[4].prefix(), Some("mf"));
+        assert_eq!(ids[2].to_string(), "pl-3");
+    }
+}
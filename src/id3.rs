@@ -0,0 +1,278 @@
+//! Decoding of ID3v1 numeric genres.
+//!
+//! Files tagged with ID3v1 store their genre as an index into a fixed table,
+//! and some servers pass that index straight through as `"(17)"` instead of
+//! `"Rock"`. ID3v2.3 also allows the index to be followed by a refinement,
+//! as in `"(4)Eurodisco"`.
+
+use std::borrow::Cow;
+
+/// The ID3v1 genre table, including the Winamp extensions.
+static GENRES: [&str; 192] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "Alternative Rock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychedelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+    "Folk",
+    "Folk-Rock",
+    "National Folk",
+    "Swing",
+    "Fast Fusion",
+    "Bebop",
+    "Latin",
+    "Revival",
+    "Celtic",
+    "Bluegrass",
+    "Avantgarde",
+    "Gothic Rock",
+    "Progressive Rock",
+    "Psychedelic Rock",
+    "Symphonic Rock",
+    "Slow Rock",
+    "Big Band",
+    "Chorus",
+    "Easy Listening",
+    "Acoustic",
+    "Humour",
+    "Speech",
+    "Chanson",
+    "Opera",
+    "Chamber Music",
+    "Sonata",
+    "Symphony",
+    "Booty Bass",
+    "Primus",
+    "Porn Groove",
+    "Satire",
+    "Slow Jam",
+    "Club",
+    "Tango",
+    "Samba",
+    "Folklore",
+    "Ballad",
+    "Power Ballad",
+    "Rhythmic Soul",
+    "Freestyle",
+    "Duet",
+    "Punk Rock",
+    "Drum Solo",
+    "A Cappella",
+    "Euro-House",
+    "Dance Hall",
+    "Goa",
+    "Drum & Bass",
+    "Club-House",
+    "Hardcore",
+    "Terror",
+    "Indie",
+    "BritPop",
+    "Negerpunk",
+    "Polsk Punk",
+    "Beat",
+    "Christian Gangsta Rap",
+    "Heavy Metal",
+    "Black Metal",
+    "Crossover",
+    "Contemporary Christian",
+    "Christian Rock",
+    "Merengue",
+    "Salsa",
+    "Thrash Metal",
+    "Anime",
+    "JPop",
+    "Synthpop",
+    "Abstract",
+    "Art Rock",
+    "Baroque",
+    "Bhangra",
+    "Big Beat",
+    "Breakbeat",
+    "Chillout",
+    "Downtempo",
+    "Dub",
+    "EBM",
+    "Eclectic",
+    "Electro",
+    "Electroclash",
+    "Emo",
+    "Experimental",
+    "Garage",
+    "Global",
+    "IDM",
+    "Illbient",
+    "Industro-Goth",
+    "Jam Band",
+    "Krautrock",
+    "Leftfield",
+    "Lounge",
+    "Math Rock",
+    "New Romantic",
+    "Nu-Breakz",
+    "Post-Punk",
+    "Post-Rock",
+    "Psytrance",
+    "Shoegaze",
+    "Space Rock",
+    "Trop Rock",
+    "World Music",
+    "Neoclassical",
+    "Audiobook",
+    "Audio Theatre",
+    "Neue Deutsche Welle",
+    "Podcast",
+    "Indie Rock",
+    "G-Funk",
+    "Dubstep",
+    "Garage Rock",
+    "Psybient",
+];
+
+/// Returns the name of the ID3v1 genre at `index`.
+///
+/// Returns `None` for indices outside the table, including `255`, which ID3v1
+/// uses to mean "no genre".
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sunk::id3_genre(17), Some("Rock"));
+/// assert_eq!(sunk::id3_genre(255), None);
+/// ```
+pub fn id3_genre(index: u8) -> Option<&'static str> {
+    GENRES.get(index as usize).cloned()
+}
+
+/// Decodes a genre as reported by the server into a readable name.
+///
+/// Plain genres are returned unchanged. Index notation is looked up in the
+/// ID3v1 table, with a trailing refinement taking precedence over the index.
+/// Returns `None` if the genre is empty or refers to no genre at all.
+pub(crate) fn decode(raw: &str) -> Option<Cow<'_, str>> {
+    let raw = raw.trim();
+    if !raw.starts_with('(') || raw.starts_with("((") {
+        return if raw.is_empty() {
+            None
+        } else {
+            Some(Cow::Borrowed(raw))
+        };
+    }
+
+    let close = match raw.find(')') {
+        Some(i) => i,
+        None => return Some(Cow::Borrowed(raw)),
+    };
+    let (reference, rest) = (&raw[1..close], raw[close + 1..].trim());
+    if !rest.is_empty() && !rest.starts_with('(') {
+        return Some(Cow::Borrowed(rest));
+    }
+
+    match reference {
+        "RX" => Some(Cow::Borrowed("Remix")),
+        "CR" => Some(Cow::Borrowed("Cover")),
+        n => match n.parse::<u8>() {
+            Ok(i) => id3_genre(i).map(Cow::Borrowed),
+            Err(_) => Some(Cow::Borrowed(raw)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_genres() {
+        assert_eq!(decode("Electronic").as_deref(), Some("Electronic"));
+        assert_eq!(decode("(17)").as_deref(), Some("Rock"));
+        assert_eq!(decode("(4)Eurodisco").as_deref(), Some("Eurodisco"));
+        assert_eq!(decode("(52)(18)").as_deref(), Some("Electronic"));
+        assert_eq!(decode("(RX)").as_deref(), Some("Remix"));
+        assert_eq!(decode("(255)"), None);
+        assert_eq!(decode(""), None);
+        assert_eq!(decode("(Live)").as_deref(), Some("(Live)"));
+    }
+
+    #[test]
+    fn genre_table() {
+        assert_eq!(id3_genre(0), Some("Blues"));
+        assert_eq!(id3_genre(79), Some("Hard Rock"));
+        assert_eq!(id3_genre(191), Some("Psybient"));
+        assert_eq!(id3_genre(192), None);
+    }
+}
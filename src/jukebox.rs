@@ -1,9 +1,9 @@
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::ser::{Serialize, Serializer};
 use std::result;
 
-use crate::query::Query;
-use crate::{Client, Result, Song};
+use crate::endpoint::{GetJukeboxPlaylist, JukeboxControl};
+use crate::{Client, Result, Song, SongId};
 
 /// A wrapper on a `Client` to control just the jukebox.
 ///
@@ -15,7 +15,7 @@ pub struct Jukebox<'a> {
 }
 
 /// A representation of the jukebox's current status.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct JukeboxStatus {
     /// Current index in the playlist (zero-indexed). `-1` means that the
     /// jukebox has had its playlist cleared and has not since been played.
@@ -66,22 +66,48 @@ impl<'de> Deserialize<'de> for JukeboxPlaylist {
     }
 }
 
+impl Serialize for JukeboxPlaylist {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Playlist<'a> {
+            #[serde(rename = "currentIndex")]
+            index: isize,
+            playing: bool,
+            gain: f32,
+            position: usize,
+            entry: &'a [Song],
+        }
+
+        _Playlist {
+            index: self.status.index,
+            playing: self.status.playing,
+            gain: self.status.volume,
+            position: self.status.position,
+            entry: &self.songs,
+        }
+        .serialize(se)
+    }
+}
+
 impl<'a> Jukebox<'a> {
     /// Creates a new handler to the jukebox of the client.
     pub fn start(client: &'a Client) -> Jukebox {
         Jukebox { client }
     }
 
-    fn send_action_with<U>(&self, action: &str, index: U, ids: &[usize]) -> Result<JukeboxStatus>
+    fn send_action_with<U>(&self, action: &str, index: U, ids: &[SongId]) -> Result<JukeboxStatus>
     where
         U: Into<Option<usize>>,
     {
-        let args = Query::with("action", action)
-            .arg("index", index.into())
-            .arg_list("id", ids)
-            .build();
-        let res = self.client.get("jukeboxControl", args)?;
-        Ok(serde_json::from_value(res)?)
+        self.client.call(&JukeboxControl {
+            action: action.to_string(),
+            index: index.into(),
+            ids: ids.to_vec(),
+            gain: None,
+        })
     }
 
     fn send_action(&self, action: &str) -> Result<JukeboxStatus> {
@@ -92,10 +118,7 @@ impl<'a> Jukebox<'a> {
     /// status is also returned as it contains the position of the jukebox
     /// in its playlist.
     pub fn playlist(&self) -> Result<JukeboxPlaylist> {
-        let res = self
-            .client
-            .get("jukeboxControl", Query::with("action", "get"))?;
-        Ok(serde_json::from_value::<JukeboxPlaylist>(res)?)
+        self.client.call(&GetJukeboxPlaylist)
     }
 
     /// Returns the status of the jukebox.
@@ -124,7 +147,7 @@ impl<'a> Jukebox<'a> {
 
     /// Adds the song to the jukebox's playlist.
     pub fn add(&self, song: &Song) -> Result<JukeboxStatus> {
-        self.send_action_with("add", None, &[song.id as usize])
+        self.send_action_with("add", None, std::slice::from_ref(&song.id))
     }
 
     /// Adds a song matching the provided ID to the playlist.
@@ -133,8 +156,11 @@ impl<'a> Jukebox<'a> {
     ///
     /// The method will return an error if a song matching the provided ID
     /// cannot be found.
-    pub fn add_id(&self, id: usize) -> Result<JukeboxStatus> {
-        self.send_action_with("add", None, &[id])
+    pub fn add_id<I>(&self, id: I) -> Result<JukeboxStatus>
+    where
+        I: Into<SongId>,
+    {
+        self.send_action_with("add", None, &[id.into()])
     }
 
     /// Adds all the songs to the jukebox's playlist.
@@ -142,7 +168,16 @@ impl<'a> Jukebox<'a> {
         self.send_action_with(
             "add",
             None,
-            &songs.iter().map(|s| s.id as usize).collect::<Vec<_>>(),
+            &songs.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
+        )
+    }
+
+    /// Replaces the jukebox's playlist with the songs.
+    pub fn set_all(&self, songs: &[Song]) -> Result<JukeboxStatus> {
+        self.send_action_with(
+            "set",
+            None,
+            &songs.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
         )
     }
 
@@ -152,7 +187,7 @@ impl<'a> Jukebox<'a> {
     ///
     /// The method will return an error if at least one ID cannot be matched to
     /// a song.
-    pub fn add_all_ids(&self, ids: &[usize]) -> Result<JukeboxStatus> {
+    pub fn add_all_ids(&self, ids: &[SongId]) -> Result<JukeboxStatus> {
         self.send_action_with("add", None, ids)
     }
 
@@ -175,15 +210,18 @@ impl<'a> Jukebox<'a> {
     ///
     /// Seting the volume above `1.0` will have no effect.
     pub fn set_volume(&self, volume: f32) -> Result<JukeboxStatus> {
-        let args = Query::with("action", "setGain").arg("gain", volume).build();
-        let res = self.client.get("jukeboxControl", args)?;
-        Ok(serde_json::from_value(res)?)
+        self.client.call(&JukeboxControl {
+            action: "setGain".to_string(),
+            gain: Some(volume),
+            ..Default::default()
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util;
 
     #[test]
     fn parse_playlist() {
@@ -251,5 +289,6 @@ mod tests {
         assert_eq!(parsed.songs.len(), 2);
         assert!(!parsed.status.playing);
         assert_eq!(parsed.status.volume, 0.75);
+        test_util::assert_round_trip(&parsed);
     }
 }
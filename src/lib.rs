@@ -14,7 +14,7 @@
 //! ```no_run
 //! extern crate sunk;
 //! use sunk::song::Song;
-//! use sunk::{Album, Artist, Client, Streamable};
+//! use sunk::{Album, Artist, Bitrate, Client, Streamable};
 //!
 //! # fn run() -> sunk::Result<()> {
 //! let site = "http://subsonic.example.com";
@@ -25,7 +25,7 @@
 //!
 //! let random_songs = Song::random(&client, 20)?;
 //! for mut song in random_songs {
-//!     song.set_max_bit_rate(320);
+//!     song.set_max_bit_rate(Bitrate::HIGHEST);
 //!     let bytes = song.stream(&client)?;
 //!     // Use another library to stream the `bytes`!
 //! }
@@ -45,7 +45,7 @@
 //!
 //! ```no_run
 //! # extern crate sunk;
-//! # use sunk::{Client, Album, Artist, Streamable};
+//! # use sunk::{Client, Album, Artist, Downloadable, Streamable};
 //! # use sunk::song::Song;
 //! # fn run() -> sunk::Result<()> {
 //! # let site = "http://subsonic.example.com";
@@ -83,6 +83,20 @@
 //! This has the result of many methods requiring an active connection to a
 //! `Client` to fetch more information.
 //!
+//! # Async
+//!
+//! `Client` sends its requests with a blocking HTTP client, and sunk doesn't
+//! depend on any async runtime. Async applications, whether they run on
+//! tokio, async-std or anything else, can use sunk as a protocol layer
+//! instead: [`Client::http_request`] builds the request for an endpoint, which
+//! the application sends with its own HTTP client, and
+//! [`Client::parse_response`] reads the response. See the [`protocol`]
+//! module.
+//!
+//! [`Client::http_request`]: ./struct.Client.html#method.http_request
+//! [`Client::parse_response`]: ./struct.Client.html#method.parse_response
+//! [`protocol`]: ./protocol/index.html
+//!
 //! # Debugging
 //!
 //! The crate uses [`log`] as its debugging backend. If your crate uses log,
@@ -91,6 +105,69 @@
 //!
 //! [`log`]: https://doc.rust-lang.org/log/log/index.html
 //!
+//! # Features
+//!
+//! The default features can be turned off for a smaller build, such as for
+//! an embedded player:
+//!
+//! - `default-tls` (default): connects over HTTPS with the platform's TLS
+//!   library.
+//! - `rustls-tls`: connects over HTTPS with [`rustls`] instead.
+//! - `cache` (default): the [`cache`] module, for caching responses and
+//!   cover art.
+//! - `mirror` (default): the [`mirror`] module, a local copy of the library.
+//! - `jukebox` (default): controlling the server's [`Jukebox`].
+//! - `podcasts` (default): the [`podcast`] module.
+//! - `sharing` (default): creating a [`Share`] of media, and the [`cast`]
+//!   module, for handing media to cast devices.
+//! - `video` (default): the [`video`] module.
+//!
+//! The rest are off by default:
+//!
+//! - `config`: reads a [`Config`] from a TOML file.
+//! - `decode`: the [`decode`] module, decoding songs with [`symphonia`].
+//! - `ffi`: the [`ffi`] module, a C interface for players written in other
+//!   languages.
+//! - `image`: the [`palette`] module, picking theme colours from cover art
+//!   with [`image`].
+//! - `playback`: the [`playback`] module, playing songs with [`rodio`].
+//! - `scrobble`: the [`scrobbler`] module, submitting listens to
+//!   ListenBrainz and Last.fm directly.
+//! - `unicode`: normalizes search terms, as set with
+//!   [`Client::with_query_normalization`].
+//! - `chrono`: parses the ISO8601 timestamps the server sends (such as when a
+//!   song was added or starred) into [`chrono`] types.
+//! - `rayon`: deserializes lists of more than a thousand elements, such as
+//!   the songs of a large library, in parallel with [`rayon`].
+//! - `shared-strings`: stores artist, album and genre names as shared
+//!   [`Interned`] strings rather than `String`s, and adds an [`Interner`] to
+//!   share equal names, reducing the memory used by large libraries. See
+//!   [`Name`].
+//!
+//! [`rustls`]: https://docs.rs/rustls
+//! [`rodio`]: https://docs.rs/rodio
+//! [`symphonia`]: https://docs.rs/symphonia
+//! [`image`]: https://docs.rs/image
+//! [`decode`]: ./decode/index.html
+//! [`ffi`]: ./ffi/index.html
+//! [`palette`]: ./palette/index.html
+//! [`playback`]: ./playback/index.html
+//! [`scrobbler`]: ./scrobbler/index.html
+//! [`Config`]: ./config/struct.Config.html
+//! [`Client::with_query_normalization`]: ./struct.Client.html#method.with_query_normalization
+//! [`cache`]: ./cache/index.html
+//! [`mirror`]: ./mirror/index.html
+//! [`Jukebox`]: ./struct.Jukebox.html
+//! [`podcast`]: ./podcast/index.html
+//! [`Share`]: ./struct.Share.html
+//! [`cast`]: ./cast/index.html
+//! [`video`]: ./video/index.html
+//! [`Name`]: ./type.Name.html
+//! [`Interned`]: ./struct.Interned.html
+//! [`Interner`]: ./struct.Interner.html
+//! [`chrono`]: https://docs.rs/chrono
+//! [`rayon`]: https://docs.rs/rayon
+//!
 //! # Development
 //!
 //! The crate is still under active development. Methods and paths may change,
@@ -111,36 +188,105 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 
-#[macro_use]
-mod macros;
 mod client;
+mod coalesce;
+mod de;
+#[cfg(feature = "decode")]
+pub mod decode;
+pub mod discover;
+pub mod endpoint;
 mod error;
+pub mod facade;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod genre;
+pub mod health;
+pub mod history;
+mod id;
+mod id3;
 
 mod collections;
+pub mod config;
 mod media;
+mod name;
 
+pub mod analysis;
 mod annotate;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "sharing")]
+pub mod cast;
+#[cfg(feature = "jukebox")]
 mod jukebox;
+pub mod library;
+#[cfg(feature = "mirror")]
+pub mod mirror;
+#[cfg(feature = "image")]
+pub mod palette;
+#[cfg(feature = "playback")]
+pub mod playback;
+pub mod player;
+pub mod progress;
+pub mod protocol;
 mod query;
+mod quirks;
 mod response;
+#[cfg(feature = "scrobble")]
+pub mod scrobbler;
 pub mod search;
+pub mod sort;
+pub mod stats;
+pub mod storage;
+mod timestamp;
 mod user;
+pub mod vcr;
 mod version;
+pub mod warm;
+pub mod watch;
 
 #[cfg(test)]
 mod test_util;
 
-pub use self::client::Client;
+pub use self::annotate::Annotatable;
+pub use self::client::{Client, Extension};
 pub use self::collections::Playlist;
-pub use self::collections::{Album, AlbumInfo, ListType};
-pub use self::collections::{Artist, ArtistInfo};
-pub use self::collections::{Genre, MusicFolder};
+#[cfg(feature = "sharing")]
+pub use self::collections::Share;
+pub use self::collections::{
+    Album, AlbumInfo, Disc, DiscTitle, ExplicitStatus, ItemDate, ListType, RandomAlbum,
+};
+pub use self::collections::{Artist, ArtistIndex, ArtistInfo, ArtistRef, SimilarityGraph};
+pub use self::collections::{Directory, Folder};
+pub use self::collections::{Genre, ImageSize, ImageUrls, MusicFolder};
 pub use self::error::{ApiError, Error, Result};
+pub use self::genre::GenreMap;
+pub use self::id::{AlbumId, ArtistId, CoverId, CoverKind, PlaylistId, SongId};
+pub use self::id3::id3_genre;
+#[cfg(feature = "jukebox")]
 pub use self::jukebox::{Jukebox, JukeboxPlaylist, JukeboxStatus};
-pub use self::media::{podcast, song, video};
-pub use self::media::{Hls, HlsPlaylist, Media, NowPlaying, RadioStation, Streamable};
+pub use self::media::format::Bitrate;
+#[cfg(feature = "podcasts")]
+pub use self::media::podcast;
+#[cfg(feature = "video")]
+pub use self::media::video;
+/// The former name of [`HasCoverArt`].
+///
+/// [`HasCoverArt`]: ./trait.HasCoverArt.html
+pub use self::media::HasCoverArt as Media;
+pub use self::media::{format, song};
+pub use self::media::{format_duration, MediaType, MediaTypeFilter};
+pub use self::media::{Downloadable, HasCoverArt, MediaItem, Streamable};
+pub use self::media::{Hls, HlsPlaylist, NowPlaying, RadioStation, StreamReader};
+pub use self::name::Name;
+#[cfg(feature = "shared-strings")]
+pub use self::name::{Interned, Interner};
+pub use self::query::{Arg, IntoArg, Query};
+pub use self::quirks::{Quirks, ServerKind};
+pub use self::response::RawResponse;
 pub use self::user::{User, UserBuilder};
 pub use self::version::Version;
+pub use url::Url;
 
 use self::song::{Lyrics, RandomSongs, Song};
+#[cfg(feature = "video")]
 use self::video::Video;
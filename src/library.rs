@@ -0,0 +1,238 @@
+//! Walking the whole library.
+//!
+//! Backups, statistics and the like need every song on the server, but the
+//! Subsonic API has no endpoint to list them. [`all_songs`] lists them
+//! anyway, a page or an album at a time, so that even large libraries don't
+//! have to be held in memory at once.
+//!
+//! [`all_songs`]: fn.all_songs.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::{library, Client};
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! let mut size = 0;
+//! for song in library::all_songs(&client) {
+//!     size += song?.size;
+//! }
+//! println!("the library takes {} bytes", size);
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::vec;
+
+use crate::search::{self, SearchPage};
+use crate::{Album, Artist, Client, Result, Song};
+
+/// The number of songs requested at a time from servers that list the library
+/// through `search3`.
+const SEARCH_PAGE: usize = 500;
+
+/// Lists every song in the library.
+///
+/// Servers that match every song with an empty search (see
+/// [`Quirks::empty_search`]) are paged through with `search3`. Otherwise,
+/// every artist is listed, then each of their albums; this takes a request
+/// per album, but works on any server, including those browsed by folder.
+///
+/// Songs are fetched as the iterator is advanced. The iterator ends after
+/// yielding the first error.
+///
+/// [`Quirks::empty_search`]: ../struct.Quirks.html#method.empty_search
+pub fn all_songs(client: &Client) -> AllSongs<'_> {
+    let walk = if client.quirks().empty_search() {
+        Walk::Search { offset: Some(0) }
+    } else {
+        Walk::Artists {
+            artists: None,
+            albums: Vec::new().into_iter(),
+        }
+    };
+
+    AllSongs {
+        client,
+        walk,
+        buffer: Vec::new().into_iter(),
+        done: false,
+    }
+}
+
+/// An iterator over every song in the library, created by [`all_songs`].
+///
+/// [`all_songs`]: fn.all_songs.html
+#[derive(Debug)]
+pub struct AllSongs<'a> {
+    client: &'a Client,
+    walk: Walk,
+    buffer: vec::IntoIter<Song>,
+    done: bool,
+}
+
+/// How the library is being walked, and how far along the walk is.
+#[derive(Debug)]
+enum Walk {
+    /// Paging through `search3`; `None` once the last page has been read.
+    Search { offset: Option<usize> },
+    /// Listing the albums of each artist; `artists` is `None` until they
+    /// have been listed.
+    Artists {
+        artists: Option<vec::IntoIter<Artist>>,
+        albums: vec::IntoIter<Album>,
+    },
+}
+
+impl<'a> AllSongs<'a> {
+    /// Fetches the next batch of songs into the buffer. Returns `false` once
+    /// the whole library has been walked.
+    fn fill(&mut self) -> Result<bool> {
+        let client = self.client;
+        match self.walk {
+            Walk::Search { ref mut offset } => {
+                let page = match *offset {
+                    Some(offset) => SearchPage {
+                        count: SEARCH_PAGE,
+                        offset,
+                    },
+                    None => return Ok(false),
+                };
+                let songs = client.search("", search::NONE, search::NONE, page)?.songs;
+                *offset = if songs.len() < SEARCH_PAGE {
+                    None
+                } else {
+                    Some(page.offset + SEARCH_PAGE)
+                };
+                self.buffer = songs.into_iter();
+                Ok(true)
+            }
+            Walk::Artists {
+                ref mut artists,
+                ref mut albums,
+            } => loop {
+                if let Some(album) = albums.next() {
+                    self.buffer = album.songs(client)?.into_iter();
+                    return Ok(true);
+                }
+                if artists.is_none() {
+                    *artists = Some(Artist::list(client, None)?.into_iter());
+                }
+                match artists.as_mut().and_then(Iterator::next) {
+                    Some(artist) => *albums = artist.albums(client)?.into_iter(),
+                    None => return Ok(false),
+                }
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for AllSongs<'a> {
+    type Item = Result<Song>;
+
+    fn next(&mut self) -> Option<Result<Song>> {
+        loop {
+            if let Some(song) = self.buffer.next() {
+                return Some(Ok(song));
+            }
+            if self.done {
+                return None;
+            }
+            match self.fill() {
+                Ok(true) => (),
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::test_util;
+    use crate::Quirks;
+    use serde_json::{json, Value};
+
+    fn songs(ids: std::ops::Range<usize>) -> Vec<Value> {
+        ids.map(|id| serde_json::to_value(test_util::song(&id.to_string())).unwrap())
+            .collect()
+    }
+
+    fn album(id: &str) -> Value {
+        json!({
+            "id": id, "name": id, "songCount": 2, "duration": 1,
+            "created": "2017-03-12T11:07:25.000Z",
+        })
+    }
+
+    #[test]
+    fn walks_artists_and_albums() {
+        let artist = json!({ "id": "1", "name": "Misteur Valaire", "albumCount": 2 });
+        let mut with_albums = artist.clone();
+        with_albums["album"] = json!([album("1"), album("2")]);
+        let mut first = album("1");
+        first["song"] = songs(0..2).into();
+        let client = test_util::replay_client(&[
+            (
+                "getArtists",
+                Query::none(),
+                json!({ "artists": { "index": [{ "artist": [artist] }] } }),
+            ),
+            (
+                "getArtist",
+                Query::with("id", "1"),
+                json!({ "artist": with_albums }),
+            ),
+            (
+                "getAlbum",
+                Query::with("id", "1"),
+                json!({ "album": first }),
+            ),
+        ]);
+
+        let mut songs = all_songs(&client);
+        assert_eq!(songs.next().unwrap().unwrap().id.as_str(), "0");
+        assert_eq!(songs.next().unwrap().unwrap().id.as_str(), "1");
+        // The second album was never recorded, so fetching it fails.
+        assert!(songs.next().unwrap().is_err());
+        assert!(songs.next().is_none());
+    }
+
+    #[test]
+    fn pages_through_search() {
+        let page = |offset: usize| {
+            Query::with("query", "")
+                .arg("artistCount", 0)
+                .arg("artistOffset", 0)
+                .arg("albumCount", 0)
+                .arg("albumOffset", 0)
+                .arg("songCount", SEARCH_PAGE)
+                .arg("songOffset", offset)
+                .build()
+        };
+        let full = json!({ "searchResult3": { "song": songs(0..SEARCH_PAGE) } });
+        let last = json!({ "searchResult3": { "song": songs(SEARCH_PAGE..SEARCH_PAGE + 1) } });
+        let client = test_util::replay_client(&[
+            ("search3", page(0), full),
+            ("search3", page(SEARCH_PAGE), last),
+        ])
+        .with_quirks(Quirks::default().with_empty_search(true));
+
+        let ids = all_songs(&client)
+            .map(|s| s.unwrap().id.as_str().parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (0..=SEARCH_PAGE).collect::<Vec<_>>());
+    }
+}
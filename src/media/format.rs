@@ -1,52 +1,211 @@
-use query::{Arg, IntoArg};
-use std::fmt;
+//! Audio and video encoding formats and bit rates.
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected};
+use serde::ser::{Serialize, Serializer};
+use std::str::FromStr;
+use std::{fmt, result};
+
+use crate::query::{Arg, IntoArg};
+use crate::{Error, Result};
 
 /// Audio encoding format.
 ///
-/// Recognises all of Subsonic's default transcoding formats.
-#[derive(Debug)]
+/// Recognises all of Subsonic's default transcoding formats. Formats produced
+/// by custom transcoders (such as `mka` or `webm`) are kept as `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AudioFormat {
+    /// Advanced Audio Coding.
     Aac,
+    /// Audio Interchange File Format, short suffix.
     Aif,
+    /// Audio Interchange File Format.
     Aiff,
+    /// Monkey's Audio.
     Ape,
+    /// Free Lossless Audio Codec.
     Flac,
+    /// Flash Video audio.
     Flv,
+    /// MPEG-4 audio.
     M4a,
+    /// MPEG-1 Audio Layer III.
     Mp3,
+    /// Musepack.
     Mpc,
+    /// Ogg audio.
     Oga,
+    /// Ogg Vorbis.
     Ogg,
+    /// Ogg multiplexed.
     Ogx,
+    /// Opus.
     Opus,
+    /// Shorten.
     Shn,
+    /// Waveform Audio.
     Wav,
+    /// Windows Media Audio.
     Wma,
+    /// The original file, without transcoding.
     Raw,
+    /// A format not in Subsonic's defaults, stored as its lowercase suffix.
+    Other(String),
+}
+
+impl AudioFormat {
+    /// Returns the file suffix of the format, as used by the server.
+    pub fn as_str(&self) -> &str {
+        use self::AudioFormat::*;
+        match *self {
+            Aac => "aac",
+            Aif => "aif",
+            Aiff => "aiff",
+            Ape => "ape",
+            Flac => "flac",
+            Flv => "flv",
+            M4a => "m4a",
+            Mp3 => "mp3",
+            Mpc => "mpc",
+            Oga => "oga",
+            Ogg => "ogg",
+            Ogx => "ogx",
+            Opus => "opus",
+            Shn => "shn",
+            Wav => "wav",
+            Wma => "wma",
+            Raw => "raw",
+            Other(ref s) => s,
+        }
+    }
+
+    /// Finds the format from a MIME type, such as the `contentType` of a song.
+    ///
+    /// Unknown MIME types are kept as `Other`, using the subtype (`webm` for
+    /// `audio/webm`).
+    pub fn from_content_type(mime: &str) -> AudioFormat {
+        use self::AudioFormat::*;
+        let mime = mime.trim().to_lowercase();
+        match mime.as_str() {
+            "audio/aac" | "audio/aacp" => Aac,
+            "audio/aiff" | "audio/x-aiff" => Aiff,
+            "audio/ape" | "audio/x-ape" | "audio/x-monkeys-audio" => Ape,
+            "audio/flac" | "audio/x-flac" => Flac,
+            "video/x-flv" => Flv,
+            "audio/mp4" | "audio/m4a" | "audio/x-m4a" => M4a,
+            "audio/mpeg" | "audio/mp3" | "audio/x-mp3" => Mp3,
+            "audio/x-musepack" => Mpc,
+            "audio/ogg" | "audio/x-ogg" | "application/ogg" => Ogg,
+            "audio/opus" => Opus,
+            "audio/x-shorten" => Shn,
+            "audio/wav" | "audio/x-wav" | "audio/wave" => Wav,
+            "audio/x-ms-wma" => Wma,
+            _ => {
+                let sub = mime.rsplit('/').next().unwrap_or("");
+                Other(sub.trim_start_matches("x-").to_string())
+            }
+        }
+    }
+
+    /// Finds the format of a file from its `suffix` and `contentType`, as
+    /// given on songs and videos.
+    ///
+    /// The suffix is preferred; the content type is only used when the
+    /// suffix is empty.
+    pub fn from_fields(suffix: &str, content_type: &str) -> AudioFormat {
+        if suffix.is_empty() {
+            AudioFormat::from_content_type(content_type)
+        } else {
+            suffix.parse().unwrap()
+        }
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<AudioFormat> {
+        use self::AudioFormat::*;
+        let s = s.trim().trim_start_matches('.').to_lowercase();
+        Ok(match s.as_str() {
+            "aac" => Aac,
+            "aif" => Aif,
+            "aiff" => Aiff,
+            "ape" => Ape,
+            "flac" => Flac,
+            "flv" => Flv,
+            "m4a" => M4a,
+            "mp3" => Mp3,
+            "mpc" => Mpc,
+            "oga" => Oga,
+            "ogg" => Ogg,
+            "ogx" => Ogx,
+            "opus" => Opus,
+            "shn" => Shn,
+            "wav" => Wav,
+            "wma" => Wma,
+            "raw" => Raw,
+            _ => Other(s),
+        })
+    }
 }
 
 impl fmt::Display for AudioFormat {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", format!("{:?}", self).to_lowercase())
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AudioFormat {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(de)?;
+        Ok(raw.parse().unwrap())
+    }
+}
+
+impl Serialize for AudioFormat {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_str(self.as_str())
     }
 }
 
 impl IntoArg for AudioFormat {
-    fn into_arg(self) -> Arg { self.to_string().into_arg() }
+    fn into_arg(self) -> Arg {
+        self.to_string().into_arg()
+    }
 }
 
+/// Video encoding format.
+///
+/// Recognises all of Subsonic's default video formats.
 #[derive(Debug)]
 pub enum VideoFormat {
+    /// Audio Video Interleave.
     Avi,
+    /// MPEG video, short suffix.
     Mpg,
+    /// MPEG video.
     Mpeg,
+    /// MPEG-4 video.
     Mp4,
+    /// MPEG-4 video (Apple).
     M4v,
+    /// Matroska.
     Mkv,
+    /// QuickTime.
     Mov,
+    /// Windows Media Video.
     Wmv,
+    /// Ogg video.
     Ogv,
+    /// DivX.
     Divx,
+    /// MPEG-2 transport stream.
     M2ts,
 }
 
@@ -57,5 +216,213 @@ impl fmt::Display for VideoFormat {
 }
 
 impl IntoArg for VideoFormat {
-    fn into_arg(self) -> Arg { self.to_string().into_arg() }
+    fn into_arg(self) -> Arg {
+        self.to_string().into_arg()
+    }
+}
+
+/// A bit rate limit for streaming, measured in Kbps.
+///
+/// Subsonic only accepts a fixed set of bit rates; anything else is rejected
+/// by the server or silently ignored. A `Bitrate` can only be built from one
+/// of those values, so a limit that reaches the server is always honoured.
+///
+/// # Examples
+///
+/// ```
+/// use sunk::Bitrate;
+///
+/// assert_eq!(Bitrate::new(192).unwrap().kbps(), 192);
+/// assert!(Bitrate::new(200).is_err());
+/// assert_eq!(Bitrate::nearest(200), Bitrate::new(192).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Bitrate(u16);
+
+impl Bitrate {
+    /// Every bit rate accepted by Subsonic, in ascending order.
+    pub const ACCEPTED: [u16; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+
+    /// No limit; media streams at its original bit rate.
+    pub const UNLIMITED: Bitrate = Bitrate(0);
+    /// 64 Kbps, suitable for speech or poor connections.
+    pub const LOW: Bitrate = Bitrate(64);
+    /// 128 Kbps.
+    pub const MEDIUM: Bitrate = Bitrate(128);
+    /// 192 Kbps.
+    pub const HIGH: Bitrate = Bitrate(192);
+    /// 320 Kbps, the highest limit Subsonic supports.
+    pub const HIGHEST: Bitrate = Bitrate(320);
+
+    /// Creates a bit rate limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kbps` is not one of the bit rates Subsonic
+    /// accepts. See [`ACCEPTED`](#associatedconstant.ACCEPTED).
+    pub fn new(kbps: u32) -> Result<Bitrate> {
+        Bitrate::ACCEPTED
+            .iter()
+            .find(|&&b| u32::from(b) == kbps)
+            .map(|&b| Bitrate(b))
+            .ok_or(Error::Other("unsupported bit rate"))
+    }
+
+    /// Returns the highest accepted bit rate that doesn't exceed `kbps`.
+    ///
+    /// Anything above 320 Kbps is capped to 320 Kbps. Note that `0` means
+    /// unlimited, so values between `1` and `31` round down to the lowest
+    /// limit instead.
+    pub fn nearest(kbps: u32) -> Bitrate {
+        if kbps == 0 {
+            return Bitrate::UNLIMITED;
+        }
+        let b = Bitrate::ACCEPTED[1..]
+            .iter()
+            .rev()
+            .find(|&&b| u32::from(b) <= kbps)
+            .unwrap_or(&Bitrate::ACCEPTED[1]);
+        Bitrate(*b)
+    }
+
+    /// Returns the limit in Kbps. A limit of `0` means unlimited.
+    pub fn kbps(self) -> u32 {
+        u32::from(self.0)
+    }
+
+    /// Returns whether the bit rate imposes no limit.
+    pub fn is_unlimited(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Default for Bitrate {
+    fn default() -> Bitrate {
+        Bitrate::UNLIMITED
+    }
+}
+
+impl fmt::Display for Bitrate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses a bit rate in Kbps, or the name of one of the constants, such as
+/// `"high"`.
+impl FromStr for Bitrate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Bitrate> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "unlimited" => Ok(Bitrate::UNLIMITED),
+            "low" => Ok(Bitrate::LOW),
+            "medium" => Ok(Bitrate::MEDIUM),
+            "high" => Ok(Bitrate::HIGH),
+            "highest" => Ok(Bitrate::HIGHEST),
+            kbps => Bitrate::new(kbps.parse()?),
+        }
+    }
+}
+
+impl IntoArg for Bitrate {
+    fn into_arg(self) -> Arg {
+        self.0.into_arg()
+    }
+}
+
+impl<'de> Deserialize<'de> for Bitrate {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Rounding would hide the server's value, and send a different one
+        // back should the value be saved again, so refuse it like `new`.
+        let kbps = u32::deserialize(de)?;
+        Bitrate::new(kbps).map_err(|_| {
+            de::Error::invalid_value(
+                Unexpected::Unsigned(u64::from(kbps)),
+                &"a bit rate Subsonic accepts",
+            )
+        })
+    }
+}
+
+impl Serialize for Bitrate {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_u16(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitrate_validates() {
+        assert_eq!(Bitrate::new(0).unwrap(), Bitrate::UNLIMITED);
+        assert_eq!(Bitrate::new(320).unwrap(), Bitrate::HIGHEST);
+        assert!(Bitrate::new(100).is_err());
+        assert!("1000".parse::<Bitrate>().is_err());
+        assert_eq!("160".parse::<Bitrate>().unwrap().to_string(), "160");
+    }
+
+    #[test]
+    fn bitrate_serde() {
+        assert_eq!(
+            serde_json::from_str::<Bitrate>("96").unwrap(),
+            Bitrate::new(96).unwrap()
+        );
+        assert!(serde_json::from_str::<Bitrate>("100").is_err());
+        assert!(serde_json::from_str::<Bitrate>("96000").is_err());
+        assert_eq!(serde_json::to_string(&Bitrate::HIGH).unwrap(), "192");
+    }
+
+    #[test]
+    fn bitrate_nearest() {
+        assert_eq!(Bitrate::nearest(0), Bitrate::UNLIMITED);
+        assert_eq!(Bitrate::nearest(8).kbps(), 32);
+        assert_eq!(Bitrate::nearest(100).kbps(), 96);
+        assert_eq!(Bitrate::nearest(1411), Bitrate::HIGHEST);
+    }
+
+    #[test]
+    fn parse_audio_format() {
+        assert_eq!("mp3".parse::<AudioFormat>().unwrap(), AudioFormat::Mp3);
+        assert_eq!(".FLAC".parse::<AudioFormat>().unwrap(), AudioFormat::Flac);
+        assert_eq!(
+            "mka".parse::<AudioFormat>().unwrap(),
+            AudioFormat::Other("mka".into())
+        );
+        assert_eq!(AudioFormat::Other("webm".into()).to_string(), "webm");
+    }
+
+    #[test]
+    fn audio_format_from_fields() {
+        assert_eq!(
+            AudioFormat::from_fields("", "audio/x-flac"),
+            AudioFormat::Flac
+        );
+        assert_eq!(
+            AudioFormat::from_fields("", "audio/webm"),
+            AudioFormat::Other("webm".into())
+        );
+        assert_eq!(
+            AudioFormat::from_fields("opus", "audio/ogg"),
+            AudioFormat::Opus
+        );
+    }
+
+    #[test]
+    fn audio_format_serde() {
+        let parsed = serde_json::from_str::<Vec<AudioFormat>>(r#"["ogg", "mka"]"#).unwrap();
+
+        assert_eq!(parsed[0], AudioFormat::Ogg);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), r#"["ogg","mka"]"#);
+    }
 }
@@ -0,0 +1,321 @@
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json::Value;
+use std::result;
+use std::time::Duration;
+
+#[cfg(feature = "podcasts")]
+use super::podcast::Episode;
+use super::song::Song;
+#[cfg(feature = "video")]
+use super::video::Video;
+use crate::{Bitrate, Client, CoverId, Downloadable, HasCoverArt, MediaType, Result, Streamable};
+use url::Url;
+
+/// A song, video or podcast episode.
+///
+/// Directory listings and some custom endpoints mix the three. `MediaItem`
+/// lets them be read as one list, and shown with the accessors they share;
+/// the wrapped item is still there to match on for anything else.
+///
+/// When read from the server, items flagged `isVideo` are videos, items with a
+/// `streamId` are podcast episodes, and anything else is a song.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate serde_json;
+/// extern crate sunk;
+/// use sunk::{Client, MediaItem, Query};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let dir: serde_json::Value = client.custom("getMusicDirectory", Query::with("id", 12))?;
+/// let items: Vec<MediaItem> = serde_json::from_value(dir["child"].clone())?;
+/// for item in items.iter().filter(|m| !m.is_song()) {
+///     println!("{} ({:?})", item.title(), item.media_type());
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+// Most items are songs, so boxing them would only add an allocation to each.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum MediaItem {
+    /// A song, or other audio such as an audiobook.
+    Song(Song),
+    /// A video.
+    #[cfg(feature = "video")]
+    Video(Video),
+    /// A podcast episode.
+    #[cfg(feature = "podcasts")]
+    Episode(Episode),
+}
+
+/// Calls `$f` on the item wrapped by a `MediaItem`.
+macro_rules! each {
+    ($media:expr, $m:ident => $f:expr) => {
+        match $media {
+            MediaItem::Song($m) => $f,
+            #[cfg(feature = "video")]
+            MediaItem::Video($m) => $f,
+            #[cfg(feature = "podcasts")]
+            MediaItem::Episode($m) => $f,
+        }
+    };
+}
+
+impl MediaItem {
+    /// Returns the ID of the item.
+    pub fn id(&self) -> String {
+        match *self {
+            MediaItem::Song(ref s) => s.id.to_string(),
+            #[cfg(feature = "video")]
+            MediaItem::Video(ref v) => v.id.to_string(),
+            #[cfg(feature = "podcasts")]
+            MediaItem::Episode(ref e) => e.id().to_string(),
+        }
+    }
+
+    /// Returns the title of the item.
+    pub fn title(&self) -> &str {
+        match *self {
+            MediaItem::Song(ref s) => &s.title,
+            #[cfg(feature = "video")]
+            MediaItem::Video(ref v) => &v.title,
+            #[cfg(feature = "podcasts")]
+            MediaItem::Episode(ref e) => e.title(),
+        }
+    }
+
+    /// Returns the length of the item, if the server knows it.
+    pub fn duration(&self) -> Option<Duration> {
+        match *self {
+            MediaItem::Song(ref s) => s.duration.map(Duration::from_secs),
+            #[cfg(feature = "video")]
+            MediaItem::Video(ref v) => Some(v.duration()),
+            #[cfg(feature = "podcasts")]
+            MediaItem::Episode(ref e) => Some(e.duration()),
+        }
+    }
+
+    /// Returns the kind of the item.
+    pub fn media_type(&self) -> MediaType {
+        match *self {
+            MediaItem::Song(ref s) => s.media_type.clone(),
+            #[cfg(feature = "video")]
+            MediaItem::Video(ref v) => v.media_type.clone(),
+            #[cfg(feature = "podcasts")]
+            MediaItem::Episode(_) => MediaType::Podcast,
+        }
+    }
+
+    /// Returns `true` if the item is a song.
+    pub fn is_song(&self) -> bool {
+        self.as_song().is_some()
+    }
+
+    /// Returns the song, if the item is one.
+    pub fn as_song(&self) -> Option<&Song> {
+        match *self {
+            MediaItem::Song(ref s) => Some(s),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Returns the video, if the item is one.
+    #[cfg(feature = "video")]
+    pub fn as_video(&self) -> Option<&Video> {
+        match *self {
+            MediaItem::Video(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the podcast episode, if the item is one.
+    #[cfg(feature = "podcasts")]
+    pub fn as_episode(&self) -> Option<&Episode> {
+        match *self {
+            MediaItem::Episode(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Song> for MediaItem {
+    fn from(song: Song) -> MediaItem {
+        MediaItem::Song(song)
+    }
+}
+
+#[cfg(feature = "video")]
+impl From<Video> for MediaItem {
+    fn from(video: Video) -> MediaItem {
+        MediaItem::Video(video)
+    }
+}
+
+#[cfg(feature = "podcasts")]
+impl From<Episode> for MediaItem {
+    fn from(episode: Episode) -> MediaItem {
+        MediaItem::Episode(episode)
+    }
+}
+
+impl Streamable for MediaItem {
+    fn stream(&self, client: &Client) -> Result<Vec<u8>> {
+        each!(self, m => m.stream(client))
+    }
+
+    fn stream_url(&self, client: &Client) -> Result<Url> {
+        each!(self, m => m.stream_url(client))
+    }
+
+    fn encoding(&self) -> &str {
+        each!(self, m => m.encoding())
+    }
+
+    fn set_max_bit_rate(&mut self, bit_rate: Bitrate) {
+        each!(self, m => m.set_max_bit_rate(bit_rate))
+    }
+
+    fn set_transcoding(&mut self, format: &str) {
+        each!(self, m => m.set_transcoding(format))
+    }
+}
+
+impl Downloadable for MediaItem {
+    fn download(&self, client: &Client) -> Result<Vec<u8>> {
+        each!(self, m => m.download(client))
+    }
+
+    fn download_url(&self, client: &Client) -> Result<Url> {
+        each!(self, m => m.download_url(client))
+    }
+}
+
+impl HasCoverArt for MediaItem {
+    fn cover_id(&self) -> Option<&CoverId> {
+        each!(self, m => m.cover_id())
+    }
+}
+
+impl<'de> Deserialize<'de> for MediaItem {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = Value::deserialize(de)?;
+
+        #[cfg(feature = "video")]
+        {
+            if raw.get("isVideo").and_then(Value::as_bool) == Some(true) {
+                return Video::deserialize(raw)
+                    .map(MediaItem::Video)
+                    .map_err(de::Error::custom);
+            }
+        }
+        #[cfg(feature = "podcasts")]
+        {
+            if raw.get("streamId").is_some() {
+                return Episode::deserialize(raw)
+                    .map(MediaItem::Episode)
+                    .map_err(de::Error::custom);
+            }
+        }
+        Song::deserialize(raw)
+            .map(MediaItem::Song)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for MediaItem {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        each!(self, m => m.serialize(se))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song() -> Value {
+        serde_json::json!({
+            "id": "27",
+            "title": "Bellevue Avenue",
+            "size": 5400185,
+            "contentType": "audio/mpeg",
+            "suffix": "mp3",
+            "duration": 198,
+            "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "created": "2017-03-12T11:07:25.000Z",
+            "type": "music",
+            "isDir": false,
+            "isVideo": false
+        })
+    }
+
+    #[test]
+    fn mixed_listing() {
+        let items = serde_json::from_value::<Vec<MediaItem>>(serde_json::json!([song()])).unwrap();
+        let song = &items[0];
+        assert!(song.is_song());
+        assert_eq!(song.id(), "27");
+        assert_eq!(song.title(), "Bellevue Avenue");
+        assert_eq!(song.duration(), Some(Duration::from_secs(198)));
+        assert_eq!(song.media_type(), MediaType::Music);
+
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let url = song.stream_url(&client).unwrap();
+        assert!(url.query_pairs().any(|(k, v)| k == "id" && v == "27"));
+    }
+
+    #[cfg(feature = "video")]
+    #[test]
+    fn mixed_video() {
+        let video = serde_json::json!({
+            "id": "460", "parent": "24", "isDir": false, "title": "Big Buck Bunny",
+            "size": 52464391, "contentType": "video/mp4", "suffix": "mp4",
+            "duration": 281, "bitRate": 1488, "path": "Movies/Big Buck Bunny.mp4",
+            "isVideo": true, "created": "2017-03-12T11:06:30.000Z", "type": "video",
+        });
+        let items =
+            serde_json::from_value::<Vec<MediaItem>>(serde_json::json!([song(), video])).unwrap();
+        let video = &items[1];
+        assert!(!video.is_song());
+        assert!(video.as_video().is_some());
+        assert_eq!(video.id(), "460");
+        assert_eq!(video.title(), "Big Buck Bunny");
+        assert_eq!(video.duration(), Some(Duration::from_secs(281)));
+        assert_eq!(video.media_type(), MediaType::Video);
+        assert_eq!(video.encoding(), "video/mp4");
+    }
+
+    #[cfg(feature = "podcasts")]
+    #[test]
+    fn mixed_episode() {
+        let episode = serde_json::json!({
+            "id": "34", "parent": "7", "isDir": false, "title": "Scorpions have no ears",
+            "album": "Dr Karl and the Naked Scientist", "artist": "BBC Radio Five Live",
+            "year": 2011, "coverArt": "24", "size": 78421341, "contentType": "audio/mpeg", "suffix": "mp3",
+            "duration": 3146, "bitRate": 128, "isVideo": false,
+            "created": "2011-02-03T14:46:43", "artistId": "", "type": "podcast", "streamId": "523",
+            "channelId": "1", "description": "", "status": "completed",
+            "publishDate": "2011-02-03T14:46:43",
+        });
+        let items = serde_json::from_value::<Vec<MediaItem>>(serde_json::json!([episode])).unwrap();
+        let episode = &items[0];
+        assert!(episode.as_episode().is_some());
+        assert_eq!(episode.id(), "34");
+        assert_eq!(episode.title(), "Scorpions have no ears");
+        assert_eq!(episode.duration(), Some(Duration::from_secs(3146)));
+        assert_eq!(episode.media_type(), MediaType::Podcast);
+        assert_eq!(episode.cover_id().unwrap().as_str(), "24");
+    }
+}
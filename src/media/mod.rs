@@ -1,31 +1,42 @@
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use std::ops::Index;
-use std::result;
 use std::str::FromStr;
+use std::time::Duration;
+use std::{fmt, result};
 
-use crate::{Client, Error, Result};
+use serde_json::{Map, Value};
 
-// pub mod format;
+use crate::query::Query;
+use crate::{Bitrate, Client, CoverId, Error, RawResponse, Result};
+use url::Url;
+
+pub mod format;
+mod mixed;
+#[cfg(feature = "podcasts")]
 pub mod podcast;
 mod radio;
+mod reader;
 pub mod song;
+#[cfg(feature = "video")]
 pub mod video;
 
+pub use self::mixed::MediaItem;
 pub use self::radio::RadioStation;
+pub use self::reader::StreamReader;
 
 use self::song::Song;
+#[cfg(feature = "video")]
 use self::video::Video;
 // pub use self::podcast::{Podcast, Episode};
 
-// use self::format::{AudioFormat, VideoFormat};
-
 /// A trait for forms of streamable media.
 pub trait Streamable {
     /// Returns the raw bytes of the media.
     ///
     /// Supports transcoding options specified on the media beforehand. See the
-    /// struct-level documentation for available options. The `Media` trait
-    /// provides setting a maximum bit rate and a target transcoding format.
+    /// struct-level documentation for available options. The trait provides
+    /// setting a maximum bit rate and a target transcoding format.
     ///
     /// The method does not provide any information about the encoding of the
     /// media without evaluating the stream itself.
@@ -34,21 +45,12 @@ pub trait Streamable {
     /// Returns a constructed URL for streaming.
     ///
     /// Supports transcoding options specified on the media beforehand. See the
-    /// struct-level documentation for available options. The `Media` trait
-    /// provides setting a maximum bit rate and a target transcoding format.
+    /// struct-level documentation for available options. The trait provides
+    /// setting a maximum bit rate and a target transcoding format.
     ///
     /// This would be used in conjunction with a streaming library to directly
     /// take the URI and stream it.
-    fn stream_url(&self, client: &Client) -> Result<String>;
-
-    /// Returns the raw bytes of the media.
-    ///
-    /// The method does not provide any information about the encoding of the
-    /// media without evaluating the stream itself.
-    fn download(&self, client: &Client) -> Result<Vec<u8>>;
-
-    /// Returns a constructed URL for downloading the song.
-    fn download_url(&self, client: &Client) -> Result<String>;
+    fn stream_url(&self, client: &Client) -> Result<Url>;
 
     /// Returns the default encoding of the media.
     ///
@@ -61,14 +63,12 @@ pub trait Streamable {
 
     /// Sets the maximum bitrate the media will use when streaming.
     ///
-    /// The bit rate is measured in Kbps. Higher bit rate media will be
-    /// downsampled to this bit rate.
+    /// Higher bit rate media will be downsampled to this bit rate.
+    /// [`Bitrate::UNLIMITED`] disables a limit (i.e., uses the original bit
+    /// rate).
     ///
-    /// Supported values are 0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192,
-    /// 224, 256, 320. The method will not error or panic when using a non-legal
-    /// value, but the server may not provide the requested bit rate. A bit rate
-    /// of 0 will disable a limit (i.e., use the original bit rate.)
-    fn set_max_bit_rate(&mut self, bit_rate: usize);
+    /// [`Bitrate::UNLIMITED`]: ./struct.Bitrate.html#associatedconstant.UNLIMITED
+    fn set_max_bit_rate(&mut self, bit_rate: Bitrate);
 
     /// Sets the transcoding format the media will use when streaming.
     ///
@@ -82,23 +82,37 @@ pub trait Streamable {
     fn set_transcoding(&mut self, format: &str);
 }
 
-/// A trait deriving common methods for any form of media.
-pub trait Media {
+/// A trait for media that can be downloaded from the server as it is stored,
+/// without transcoding.
+pub trait Downloadable {
+    /// Returns the raw bytes of the media.
+    ///
+    /// The method does not provide any information about the encoding of the
+    /// media without evaluating the stream itself.
+    fn download(&self, client: &Client) -> Result<Vec<u8>>;
+
+    /// Returns a constructed URL for downloading the media.
+    fn download_url(&self, client: &Client) -> Result<Url>;
+}
+
+/// A trait for media that may have cover art, such as songs, albums, artists
+/// and playlists.
+pub trait HasCoverArt {
     /// Returns whether or not the media has an associated cover.
-    fn has_cover_art(&self) -> bool;
+    fn has_cover_art(&self) -> bool {
+        self.cover_id().is_some()
+    }
 
     /// Returns the cover ID associated with the media, if any.
     ///
-    /// The ID may be a number, an identifier-number pair, or simply empty.
-    /// This is due to the introduction of ID3 tags into the Subsonic API;
-    /// collections of media (such as albums or playlists) will typically
-    /// have an identifier-number ID, while raw media (such as songs or videos)
-    /// will have a numeric or no identifier.
+    /// The ID may be a number or an identifier-number pair. This is due to the
+    /// introduction of ID3 tags into the Subsonic API; collections of media
+    /// (such as albums or playlists) will typically have an identifier-number
+    /// ID, while raw media (such as songs or videos) will have a numeric
+    /// identifier. See [`CoverId`] for inspecting the prefix.
     ///
-    /// Because the method has the potential to return either a string-y or
-    /// numeric ID, the number is coerced into a `&str` to avoid type
-    /// checking workarounds.
-    fn cover_id(&self) -> Option<&str>;
+    /// [`CoverId`]: ./struct.CoverId.html
+    fn cover_id(&self) -> Option<&CoverId>;
 
     /// Returns the raw bytes of the cover art of the media.
     ///
@@ -110,7 +124,29 @@ pub trait Media {
     ///
     /// Aside from errors that the `Client` may cause, the method will error
     /// if the media does not have an associated cover art.
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>>;
+    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
+        Ok(self.cover_art_response(client, size)?.bytes)
+    }
+
+    /// Returns the cover art of the media along with the content type and
+    /// headers the server sent with it.
+    ///
+    /// # Errors
+    ///
+    /// Aside from errors that the `Client` may cause, the method will error
+    /// if the media does not have an associated cover art.
+    fn cover_art_response<U: Into<Option<usize>>>(
+        &self,
+        client: &Client,
+        size: U,
+    ) -> Result<RawResponse> {
+        let cover = self
+            .cover_id()
+            .ok_or_else(|| Error::Other("no cover art found"))?;
+        let query = Query::with("id", cover).arg("size", size.into()).build();
+
+        client.get_cover_art(query)
+    }
 
     /// Returns the URL pointing to the cover art of the media.
     ///
@@ -118,18 +154,145 @@ pub trait Media {
     ///
     /// Aside from errors that the `Client` may cause, the method will error
     /// if the media does not have an associated cover art.
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String>;
+    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Url> {
+        let cover = self
+            .cover_id()
+            .ok_or_else(|| Error::Other("no cover art found"))?;
+        let query = Query::with("id", cover).arg("size", size.into()).build();
+
+        client.build_url("getCoverArt", query)
+    }
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` if it is an hour or longer.
+///
+/// Fractions of a second are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use sunk::format_duration;
+///
+/// assert_eq!(format_duration(Duration::from_secs(198)), "3:18");
+/// assert_eq!(format_duration(Duration::from_secs(3723)), "1:02:03");
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// The kind of content a piece of media holds, as reported by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    /// A music track.
+    Music,
+    /// A podcast episode.
+    Podcast,
+    /// An audiobook chapter.
+    Audiobook,
+    /// A video.
+    Video,
+    /// Any type not covered by the Subsonic specification.
+    Other(String),
+}
+
+impl MediaType {
+    /// Returns the name the server uses for the media type.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            MediaType::Music => "music",
+            MediaType::Podcast => "podcast",
+            MediaType::Audiobook => "audiobook",
+            MediaType::Video => "video",
+            MediaType::Other(ref s) => s,
+        }
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<MediaType> {
+        Ok(match s {
+            "music" => MediaType::Music,
+            "podcast" => MediaType::Podcast,
+            "audiobook" => MediaType::Audiobook,
+            "video" => MediaType::Video,
+            other => MediaType::Other(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for MediaType {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(de)?;
+        Ok(raw.parse().unwrap())
+    }
+}
+
+impl Serialize for MediaType {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_str(self.as_str())
+    }
+}
+
+/// Filtering helpers for lists of media.
+pub trait MediaTypeFilter {
+    /// Keeps only the media of the given type.
+    fn of_type(self, media_type: &MediaType) -> Self;
+
+    /// Keeps only music, dropping podcasts, audiobooks and anything else the
+    /// server returned.
+    fn music_only(self) -> Self
+    where
+        Self: Sized,
+    {
+        self.of_type(&MediaType::Music)
+    }
+}
+
+impl MediaTypeFilter for Vec<Song> {
+    fn of_type(mut self, media_type: &MediaType) -> Self {
+        self.retain(|s| s.media_type == *media_type);
+        self
+    }
+}
+
+#[cfg(feature = "video")]
+impl MediaTypeFilter for Vec<Video> {
+    fn of_type(mut self, media_type: &MediaType) -> Self {
+        self.retain(|v| v.media_type == *media_type);
+        self
+    }
 }
 
 /// Information about currently playing media.
 ///
 /// Due to the "now playing" information possibly containing both audio and
-/// video, compromises are made. `NowPlaying` only stores the ID, title, and
-/// content type of the media. This is most of the information afforded through
-/// the web interface. For more detailed information, `song_info()` or
-/// `video_info()` gives the full `Song` or `Video` struct, though requires
-/// another web request.
-#[derive(Debug)]
+/// video, compromises are made. `NowPlaying` only exposes the ID and content
+/// type of the media; the rest of the server's entry is kept as sent, so that
+/// serializing a `NowPlaying` gives it back whole. For more detailed
+/// information, `song_info()` or `video_info()` gives the full `Song` or
+/// `Video` struct, though requires another web request.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NowPlaying {
     /// The user streaming the current media.
     pub user: String,
@@ -137,8 +300,9 @@ pub struct NowPlaying {
     pub minutes_ago: usize,
     /// The ID of the player.
     pub player_id: usize,
-    id: usize,
+    id: String,
     is_video: bool,
+    media: Map<String, Value>,
 }
 
 impl NowPlaying {
@@ -154,7 +318,7 @@ impl NowPlaying {
         if self.is_video {
             Err(Error::Other("Now Playing info is not a song"))
         } else {
-            Song::get(client, self.id as u64)
+            Song::get(client, self.id.as_str())
         }
     }
 
@@ -166,14 +330,20 @@ impl NowPlaying {
     /// error if the `NowPlaying` is not a video.
     ///
     /// [`Client`]: ../struct.Client.html
+    #[cfg(feature = "video")]
     pub fn video_info(&self, client: &Client) -> Result<Video> {
         if !self.is_video {
             Err(Error::Other("Now Playing info is not a video"))
         } else {
-            Video::get(client, self.id)
+            Video::get(client, self.id.parse()?)
         }
     }
 
+    /// Returns the ID of the currently playing media.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
     /// Returns `true` if the currently playing media is a song.
     pub fn is_song(&self) -> bool {
         !self.is_video
@@ -299,18 +469,9 @@ impl<'de> Deserialize<'de> for NowPlaying {
             minutes_ago: usize,
             player_id: usize,
             id: String,
-            is_dir: bool,
-            title: String,
-            size: usize,
-            content_type: String,
-            suffix: String,
-            transcoded_content_type: Option<String>,
-            transcoded_suffix: Option<String>,
-            path: String,
             is_video: bool,
-            created: String,
-            #[serde(rename = "type")]
-            media_type: String,
+            #[serde(flatten)]
+            media: Map<String, Value>,
         }
 
         let raw = _NowPlaying::deserialize(de)?;
@@ -319,15 +480,99 @@ impl<'de> Deserialize<'de> for NowPlaying {
             user: raw.username,
             minutes_ago: raw.minutes_ago,
             player_id: raw.player_id,
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             is_video: raw.is_video,
+            media: raw.media,
         })
     }
 }
 
+impl Serialize for NowPlaying {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _NowPlaying<'a> {
+            username: &'a str,
+            minutes_ago: usize,
+            player_id: usize,
+            id: &'a str,
+            is_video: bool,
+            #[serde(flatten)]
+            media: &'a Map<String, Value>,
+        }
+
+        _NowPlaying {
+            username: &self.user,
+            minutes_ago: self.minutes_ago,
+            player_id: self.player_id,
+            id: &self.id,
+            is_video: self.is_video,
+            media: &self.media,
+        }
+        .serialize(se)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_duration(Duration::from_millis(59_900)), "0:59");
+        assert_eq!(format_duration(Duration::from_secs(600)), "10:00");
+        assert_eq!(format_duration(Duration::from_secs(36_005)), "10:00:05");
+    }
+
+    #[test]
+    fn parse_media_type() {
+        let parsed = serde_json::from_str::<Vec<MediaType>>(
+            r#"["music", "podcast", "audiobook", "video", "radio"]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                MediaType::Music,
+                MediaType::Podcast,
+                MediaType::Audiobook,
+                MediaType::Video,
+                MediaType::Other("radio".into()),
+            ]
+        );
+        assert_eq!(serde_json::to_string(&parsed[4]).unwrap(), r#""radio""#);
+    }
+
+    #[test]
+    fn now_playing_round_trip() {
+        let entry = serde_json::json!({
+            "username": "guest",
+            "minutesAgo": 2,
+            "playerId": 7,
+            "id": "27",
+            "isDir": false,
+            "title": "Bellevue Avenue",
+            "size": 5400185,
+            "contentType": "audio/mpeg",
+            "suffix": "mp3",
+            "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "isVideo": false,
+            "created": "2017-03-12T11:07:27.000Z",
+            "type": "music",
+        });
+        let parsed = serde_json::from_value::<NowPlaying>(entry.clone()).unwrap();
+
+        assert_eq!(parsed.id(), "27");
+        assert!(parsed.is_song());
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), entry);
+        test_util::assert_round_trip(&parsed);
+    }
 
     #[test]
     fn parse_hls() {
@@ -1,8 +1,12 @@
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use std::result;
+use std::time::Duration;
 
+use crate::endpoint::{GetNewestPodcasts, GetPodcasts};
 use crate::query::Query;
-use crate::{Client, Result};
+use crate::{Bitrate, Client, CoverId, Downloadable, HasCoverArt, Result, Streamable};
+use url::Url;
 
 #[derive(Debug)]
 pub struct Podcast {
@@ -17,7 +21,7 @@ pub struct Podcast {
     error: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Episode {
     id: usize,
     parent: usize,
@@ -26,7 +30,7 @@ pub struct Episode {
     album: String,
     artist: String,
     year: usize,
-    cover_art: String,
+    cover_id: Option<CoverId>,
     size: usize,
     content_type: String,
     suffix: String,
@@ -41,6 +45,8 @@ pub struct Episode {
     description: String,
     status: String,
     publish_date: String,
+    stream_br: Option<Bitrate>,
+    stream_tc: Option<String>,
 }
 
 impl Podcast {
@@ -49,8 +55,11 @@ impl Podcast {
     where
         U: Into<Option<usize>>,
     {
-        let channel = client.get("getPodcasts", Query::with("id", id.into()))?;
-        Ok(get_list_as!(channel, Podcast).remove(0))
+        let mut channels = client.call(&GetPodcasts {
+            id: id.into(),
+            include_episodes: None,
+        })?;
+        Ok(channels.remove(0))
     }
     /// Returns a list of all podcasts the server subscribes to and,
     /// optionally, their episodes.
@@ -59,11 +68,10 @@ impl Podcast {
         B: Into<Option<bool>>,
         U: Into<Option<usize>>,
     {
-        let channel = client.get(
-            "getPodcasts",
-            Query::with("includeEpisodes", include_episodes.into()),
-        )?;
-        Ok(get_list_as!(channel, Podcast))
+        client.call(&GetPodcasts {
+            id: None,
+            include_episodes: include_episodes.into(),
+        })
     }
 }
 
@@ -74,8 +82,71 @@ impl Episode {
     where
         U: Into<Option<usize>>,
     {
-        let episode = client.get("getNewestPodcasts", Query::with("count", count.into()))?;
-        Ok(get_list_as!(episode, Episode))
+        client.call(&GetNewestPodcasts {
+            count: count.into(),
+        })
+    }
+
+    /// Returns the ID of the episode.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the title of the episode.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the length of the episode.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration as u64)
+    }
+
+    fn stream_args(&self) -> Query {
+        Query::with("id", self.stream_id.as_str())
+            .arg("maxBitRate", self.stream_br)
+            .arg("format", self.stream_tc.as_deref())
+            .build()
+    }
+}
+
+/// Episodes are streamed and downloaded by their stream ID, once the server
+/// has downloaded them.
+impl Streamable for Episode {
+    fn stream(&self, client: &Client) -> Result<Vec<u8>> {
+        client.get_bytes("stream", self.stream_args())
+    }
+
+    fn stream_url(&self, client: &Client) -> Result<Url> {
+        client.build_url("stream", self.stream_args())
+    }
+
+    fn encoding(&self) -> &str {
+        &self.content_type
+    }
+
+    fn set_max_bit_rate(&mut self, bit_rate: Bitrate) {
+        self.stream_br = Some(bit_rate);
+    }
+
+    fn set_transcoding(&mut self, format: &str) {
+        self.stream_tc = Some(format.to_string());
+    }
+}
+
+impl Downloadable for Episode {
+    fn download(&self, client: &Client) -> Result<Vec<u8>> {
+        client.get_bytes("download", Query::with("id", self.stream_id.as_str()))
+    }
+
+    fn download_url(&self, client: &Client) -> Result<Url> {
+        client.build_url("download", Query::with("id", self.stream_id.as_str()))
+    }
+}
+
+impl HasCoverArt for Episode {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
     }
 }
 
@@ -135,7 +206,8 @@ impl<'de> Deserialize<'de> for Episode {
             album: String,
             artist: String,
             year: usize,
-            cover_art: String,
+            #[serde(default)]
+            cover_art: Option<String>,
             size: usize,
             content_type: String,
             suffix: String,
@@ -163,7 +235,7 @@ impl<'de> Deserialize<'de> for Episode {
             album: raw.album,
             artist: raw.artist,
             year: raw.year,
-            cover_art: raw.cover_art,
+            cover_id: raw.cover_art.filter(|s| !s.is_empty()).map(CoverId::from),
             size: raw.size,
             content_type: raw.content_type,
             suffix: raw.suffix,
@@ -178,6 +250,151 @@ impl<'de> Deserialize<'de> for Episode {
             description: raw.description,
             status: raw.status,
             publish_date: raw.publish_date,
+            stream_br: None,
+            stream_tc: None,
         })
     }
 }
+
+impl Serialize for Podcast {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Podcast<'a> {
+            id: String,
+            url: &'a str,
+            title: &'a str,
+            description: &'a str,
+            cover_art: &'a str,
+            image_url: &'a str,
+            status: &'a str,
+            episode: &'a [Episode],
+            error_message: &'a str,
+        }
+
+        _Podcast {
+            id: self.id.to_string(),
+            url: &self.url,
+            title: &self.title,
+            description: &self.description,
+            cover_art: &self.cover_art,
+            image_url: &self.image_url,
+            status: &self.status,
+            episode: &self.episodes,
+            error_message: self.error.as_deref().unwrap_or(""),
+        }
+        .serialize(se)
+    }
+}
+
+impl Serialize for Episode {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Episode<'a> {
+            id: String,
+            parent: String,
+            is_dir: bool,
+            title: &'a str,
+            album: &'a str,
+            artist: &'a str,
+            year: usize,
+            cover_art: Option<&'a CoverId>,
+            size: usize,
+            content_type: &'a str,
+            suffix: &'a str,
+            duration: usize,
+            bit_rate: usize,
+            is_video: bool,
+            created: &'a str,
+            artist_id: &'a str,
+            #[serde(rename = "type")]
+            media_type: &'a str,
+            stream_id: &'a str,
+            channel_id: &'a str,
+            description: &'a str,
+            status: &'a str,
+            publish_date: &'a str,
+        }
+
+        _Episode {
+            id: self.id.to_string(),
+            parent: self.parent.to_string(),
+            is_dir: self.is_dir,
+            title: &self.title,
+            album: &self.album,
+            artist: &self.artist,
+            year: self.year,
+            cover_art: self.cover_id.as_ref(),
+            size: self.size,
+            content_type: &self.content_type,
+            suffix: &self.suffix,
+            duration: self.duration,
+            bit_rate: self.bitrate,
+            is_video: self.is_video,
+            created: &self.created,
+            artist_id: &self.artist_id,
+            media_type: &self.media_type,
+            stream_id: &self.stream_id,
+            channel_id: &self.channel_id,
+            description: &self.description,
+            status: &self.status,
+            publish_date: &self.publish_date,
+        }
+        .serialize(se)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn episode_media() {
+        let raw = serde_json::json!({
+            "id": "34",
+            "parent": "7",
+            "isDir": false,
+            "title": "Scorpions have no ears",
+            "album": "Dr Karl and the Naked Scientist",
+            "artist": "BBC Radio Five Live",
+            "year": 2011,
+            "coverArt": "24",
+            "size": 78421341,
+            "contentType": "audio/mpeg",
+            "suffix": "mp3",
+            "duration": 3146,
+            "bitRate": 128,
+            "isVideo": false,
+            "created": "2011-02-03T14:46:43",
+            "artistId": "",
+            "type": "podcast",
+            "streamId": "523",
+            "channelId": "1",
+            "description": "Dr Karl and the Naked Scientist answer questions.",
+            "status": "completed",
+            "publishDate": "2011-02-03T14:46:43",
+        });
+        let mut episode = serde_json::from_value::<Episode>(raw).unwrap();
+        assert_eq!(episode.cover_id().map(CoverId::as_str), Some("24"));
+
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        episode.set_max_bit_rate(Bitrate::HIGHEST);
+        let url = episode.stream_url(&client).unwrap();
+        assert!(url.query_pairs().any(|(k, v)| k == "id" && v == "523"));
+        assert!(url.query_pairs().any(|(k, _)| k == "maxBitRate"));
+
+        let mut raw = serde_json::to_value(&episode).unwrap();
+        raw.as_object_mut().unwrap().remove("coverArt");
+        let uncovered = serde_json::from_value::<Episode>(raw).unwrap();
+        assert!(uncovered.cover_id().is_none());
+        test_util::assert_round_trip(&uncovered);
+    }
+}
@@ -1,7 +1,11 @@
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use std::result;
 
-use crate::query::Query;
+use crate::endpoint::{
+    CreateInternetRadioStation, DeleteInternetRadioStation, GetInternetRadioStations,
+    UpdateInternetRadioStation,
+};
 use crate::{Client, Result};
 
 #[derive(Debug)]
@@ -35,41 +39,57 @@ impl<'de> Deserialize<'de> for RadioStation {
     }
 }
 
+impl Serialize for RadioStation {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Station<'a> {
+            id: String,
+            name: &'a str,
+            stream_url: &'a str,
+            homepage_url: Option<&'a str>,
+        }
+
+        _Station {
+            id: self.id.to_string(),
+            name: &self.name,
+            stream_url: &self.stream_url,
+            homepage_url: self.homepage_url.as_deref(),
+        }
+        .serialize(se)
+    }
+}
+
 impl RadioStation {
     pub fn id(&self) -> usize {
         self.id
     }
 
     pub fn list(client: &Client) -> Result<Vec<RadioStation>> {
-        #[allow(non_snake_case)]
-        let internetRadioStation = client.get("getInternetRadioStations", Query::none())?;
-        Ok(get_list_as!(internetRadioStation, RadioStation))
+        client.call(&GetInternetRadioStations)
     }
 
     pub fn create(client: &Client, name: &str, url: &str, homepage: Option<&str>) -> Result<()> {
-        let args = Query::with("name", name)
-            .arg("streamUrl", url)
-            .arg("homepageUrl", homepage)
-            .build();
-        client.get("createInternetRadioStation", args)?;
-        Ok(())
+        client.call(&CreateInternetRadioStation {
+            name: name.to_string(),
+            stream_url: url.to_string(),
+            homepage_url: homepage.map(String::from),
+        })
     }
 
     pub fn update(&self, client: &Client) -> Result<()> {
-        let args = Query::with("id", self.id)
-            .arg("streamUrl", self.stream_url.as_str())
-            .arg("name", self.name.as_str())
-            .arg(
-                "homepageUrl",
-                self.homepage_url.as_ref().map(|s| s.as_str()),
-            )
-            .build();
-        client.get("updateInternetRadioStation", args)?;
-        Ok(())
+        client.call(&UpdateInternetRadioStation {
+            id: self.id,
+            stream_url: self.stream_url.clone(),
+            name: self.name.clone(),
+            homepage_url: self.homepage_url.clone(),
+        })
     }
 
     pub fn delete(&self, client: &Client) -> Result<()> {
-        client.get("deleteInternetRadioStation", Query::with("id", self.id))?;
-        Ok(())
+        client.call(&DeleteInternetRadioStation { id: self.id })
     }
 }
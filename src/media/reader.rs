@@ -0,0 +1,399 @@
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+
+/// Media read from the server as it arrives, such as a song being streamed.
+///
+/// The bytes already read are kept, so the reader can seek back to them.
+/// Seeking ahead reads up to the new position, and seeking from the end
+/// reads the rest of the stream unless the server sent its length. This lets
+/// decoders that need to seek work on a stream, at the cost of holding it in
+/// memory.
+///
+/// If the server serves byte ranges of the media, as it does for files it
+/// sends untranscoded, reading far ahead of what has arrived requests the
+/// stream again from there instead of reading through the rest. The bytes
+/// kept then start at that position, and reading before it requests the
+/// stream again too.
+///
+/// Created by [`Song::stream_reader`].
+///
+/// By default the stream is only read from the server as the reader is read.
+/// [`prefetch`] reads ahead in the background instead, so that reads don't
+/// wait on the network.
+///
+/// [`Song::stream_reader`]: ./song/struct.Song.html#method.stream_reader
+/// [`prefetch`]: #method.prefetch
+pub struct StreamReader {
+    // Only ever used through `get_mut`, so never locked; the mutex makes the
+    // reader `Sync`, which decoders ask for, without asking it of `inner`.
+    inner: Mutex<Box<dyn Read + Send>>,
+    reopen: Option<Mutex<Reopen>>,
+    prefetch: Option<usize>,
+    buf: Vec<u8>,
+    /// The position in the media of the first byte of `buf`.
+    base: u64,
+    pos: u64,
+    len: Option<u64>,
+    content_type: Option<String>,
+    done: bool,
+}
+
+impl StreamReader {
+    /// Wraps `inner`, which holds `len` bytes if known.
+    pub(crate) fn new<R>(inner: R, len: Option<u64>, content_type: Option<String>) -> StreamReader
+    where
+        R: Read + Send + 'static,
+    {
+        StreamReader {
+            inner: Mutex::new(Box::new(inner)),
+            reopen: None,
+            prefetch: None,
+            buf: Vec::new(),
+            base: 0,
+            pos: 0,
+            len,
+            content_type,
+            done: false,
+        }
+    }
+
+    /// Requests the media again from a position with `reopen` when reading
+    /// far from what has been kept.
+    pub(crate) fn with_ranges(self, reopen: Reopen) -> StreamReader {
+        let mut reader = self;
+        reader.reopen = Some(Mutex::new(reopen));
+        reader
+    }
+
+    /// Returns the length of the media in bytes, if the server sent it or
+    /// the whole stream has been read.
+    pub fn byte_len(&self) -> Option<u64> {
+        if self.done {
+            Some(self.end())
+        } else {
+            self.len
+        }
+    }
+
+    /// Returns the MIME type the server sent the media as, if any.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns how far into the media the bytes read from the server reach.
+    pub fn buffered(&self) -> u64 {
+        self.end()
+    }
+
+    /// Reads from the server until the first `end` bytes are buffered, or the
+    /// stream ends, and returns the number of bytes buffered.
+    ///
+    /// Later reads up to `end` are then answered without waiting on the
+    /// network.
+    pub fn buffer_to(&mut self, end: u64) -> io::Result<u64> {
+        self.fill_to(end)?;
+        Ok(self.buffered())
+    }
+
+    /// Reads the stream from the server in the background, keeping up to
+    /// about `buffer_size` bytes ahead of what has been read.
+    ///
+    /// The background thread stops when the stream ends or the reader is
+    /// dropped.
+    pub fn prefetch(self, buffer_size: usize) -> StreamReader {
+        let mut reader = self;
+        reader.prefetch = Some(buffer_size);
+        if reader.done {
+            return reader;
+        }
+
+        let inner = reader.inner.into_inner().unwrap_or_else(|e| e.into_inner());
+        reader.inner = Mutex::new(Box::new(Prefetched::spawn(inner, buffer_size)));
+        reader
+    }
+
+    /// Returns the position in the media of the end of the bytes kept.
+    fn end(&self) -> u64 {
+        self.base + self.buf.len() as u64
+    }
+
+    /// Reads from the server until `end` bytes are kept, or the stream ends.
+    fn fill_to(&mut self, end: u64) -> io::Result<()> {
+        let inner = self.inner.get_mut().unwrap_or_else(|e| e.into_inner());
+        let mut chunk = [0; CHUNK_SIZE];
+        while !self.done && self.base + (self.buf.len() as u64) < end {
+            match inner.read(&mut chunk) {
+                Ok(0) => self.done = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests the stream again from the current position if the server
+    /// serves ranges and the position is before the bytes kept, or further
+    /// ahead of them than is worth reading through.
+    ///
+    /// If the request fails ahead of the bytes kept, ranges stop being used
+    /// and the stream is read through instead.
+    fn jump(&mut self) -> io::Result<()> {
+        let behind = self.pos < self.base;
+        let ahead = !self.done && self.pos > self.end() + RANGE_THRESHOLD;
+        let past_end = self.len.is_some_and(|len| self.pos >= len);
+        let reopen = match self.reopen {
+            Some(ref mut reopen) if (behind || ahead) && !past_end => {
+                reopen.get_mut().unwrap_or_else(|e| e.into_inner())
+            }
+            _ => return Ok(()),
+        };
+
+        let inner = match reopen(self.pos) {
+            Ok(inner) => inner,
+            Err(e) if !behind => {
+                warn!(
+                    "Reading through the stream, as the range request failed: {}",
+                    e
+                );
+                self.reopen = None;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        self.inner = Mutex::new(match self.prefetch {
+            Some(size) => Box::new(Prefetched::spawn(inner, size)),
+            None => inner,
+        });
+        self.buf.clear();
+        self.base = self.pos;
+        self.done = false;
+        Ok(())
+    }
+}
+
+/// Requests media again from a byte offset.
+pub(crate) type Reopen = Box<dyn FnMut(u64) -> io::Result<Box<dyn Read + Send>> + Send>;
+
+/// How much of the stream is read from the server at a time.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// How far ahead of the bytes kept a read has to be for the stream to be
+/// requested again from there, where the server allows it.
+const RANGE_THRESHOLD: u64 = 256 * 1024;
+
+/// The stream, as read ahead by a background thread.
+struct Prefetched {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Prefetched {
+    /// Reads `inner` in a background thread, keeping up to about
+    /// `buffer_size` bytes ahead. The thread stops when the stream ends or
+    /// this is dropped.
+    fn spawn(inner: Box<dyn Read + Send>, buffer_size: usize) -> Prefetched {
+        let (tx, rx) = mpsc::sync_channel(buffer_size / CHUNK_SIZE);
+        thread::spawn(move || {
+            let mut inner = inner;
+            loop {
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let res = match inner.read(&mut chunk) {
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        Ok(chunk)
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let last = !matches!(res, Ok(ref c) if !c.is_empty());
+                if tx.send(res).is_err() || last {
+                    return;
+                }
+            }
+        });
+        Prefetched {
+            chunks: rx,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for Prefetched {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            self.chunk = match self.chunks.recv() {
+                Ok(chunk) => chunk?,
+                // The thread has stopped after the end of the stream.
+                Err(_) => return Ok(0),
+            };
+            self.pos = 0;
+        }
+        let n = out.len().min(self.chunk.len() - self.pos);
+        out[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.jump()?;
+        self.fill_to(self.pos + out.len() as u64)?;
+        let start = (self.pos.saturating_sub(self.base) as usize).min(self.buf.len());
+        let n = out.len().min(self.buf.len() - start);
+        out[..n].copy_from_slice(&self.buf[start..start + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        let pos = match to {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => {
+                if self.byte_len().is_none() {
+                    self.fill_to(u64::MAX)?;
+                }
+                self.byte_len().and_then(|len| len.checked_add_signed(d))
+            }
+        };
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of stream")
+        })?;
+        Ok(self.pos)
+    }
+}
+
+impl fmt::Debug for StreamReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("pos", &self.pos)
+            .field("buffered", &self.end())
+            .field("len", &self.len)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    /// Hands out at most a few bytes at a time, like a network stream.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = out.len().min(3);
+            self.0.read(&mut out[..n])
+        }
+    }
+
+    fn reader(len: Option<u64>) -> StreamReader {
+        let data = (0..100).collect::<Vec<u8>>();
+        StreamReader::new(Trickle(Cursor::new(data)), len, None)
+    }
+
+    #[test]
+    fn seeks_within_stream() {
+        let mut r = reader(Some(100));
+        let mut buf = [0; 4];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3]);
+
+        r.seek(SeekFrom::Start(50)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [50, 51, 52, 53]);
+        assert!(r.buffered() < 100);
+
+        r.seek(SeekFrom::Current(-10)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [44, 45, 46, 47]);
+
+        assert_eq!(r.seek(SeekFrom::End(-2)).unwrap(), 98);
+        let mut rest = Vec::new();
+        r.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [98, 99]);
+        assert!(r.seek(SeekFrom::Current(-200)).is_err());
+    }
+
+    #[test]
+    fn buffers_ahead() {
+        let mut r = reader(None);
+        assert_eq!(r.buffer_to(10).unwrap(), 12);
+        assert_eq!(r.buffer_to(500).unwrap(), 100);
+        assert_eq!(r.byte_len(), Some(100));
+    }
+
+    #[test]
+    fn prefetches() {
+        let mut r = reader(Some(100)).prefetch(64 * 1024);
+        let mut all = Vec::new();
+        r.read_to_end(&mut all).unwrap();
+        assert_eq!(all, (0..100).collect::<Vec<u8>>());
+
+        r.seek(SeekFrom::Start(10)).unwrap();
+        let mut buf = [0; 2];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [10, 11]);
+    }
+
+    #[test]
+    fn unknown_length() {
+        let mut r = reader(None);
+        assert_eq!(r.byte_len(), None);
+        assert_eq!(r.seek(SeekFrom::End(0)).unwrap(), 100);
+        assert_eq!(r.byte_len(), Some(100));
+
+        r.seek(SeekFrom::Start(200)).unwrap();
+        assert_eq!(r.read(&mut [0; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn requests_ranges() {
+        let data = (0..1_000_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let (all, log) = (data.clone(), requested.clone());
+        let mut r = StreamReader::new(Cursor::new(data.clone()), Some(1_000_000), None)
+            .with_ranges(Box::new(move |from| {
+                log.lock().unwrap().push(from);
+                let rest = all[from as usize..].to_vec();
+                Ok(Box::new(Cursor::new(rest)) as Box<dyn Read + Send>)
+            }));
+        let mut buf = [0; 4];
+
+        r.seek(SeekFrom::Start(800_000)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[800_000..800_004]);
+        r.seek(SeekFrom::Start(10)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[10..14]);
+        r.seek(SeekFrom::Start(100_000)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[100_000..100_004]);
+        assert_eq!(*requested.lock().unwrap(), [800_000, 10]);
+    }
+
+    #[test]
+    fn reads_through_failed_ranges() {
+        let data = (0..1_000_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let mut r = StreamReader::new(Cursor::new(data.clone()), Some(1_000_000), None)
+            .with_ranges(Box::new(|_| Err(io::Error::other("no"))));
+        let mut buf = [0; 4];
+
+        r.seek(SeekFrom::Start(800_000)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[800_000..800_004]);
+        assert!(r.reopen.is_none());
+    }
+}
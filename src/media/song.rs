@@ -1,54 +1,128 @@
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
-use std::fmt;
-use std::ops::Range;
+use serde::ser::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::ops::{Range, RangeInclusive};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fmt, result};
 
+use crate::endpoint::{
+    GetLyricsBySongId, GetRandomSongs, GetSimilarSongs, GetSimilarSongs2, GetSong, GetSongsByGenre,
+    Scrobble,
+};
+use crate::history::PlayKind;
+use crate::id3;
+use crate::media::format::AudioFormat;
+use crate::name;
+use crate::progress::{self, Progress, ProgressObserver};
 use crate::query::Query;
 use crate::search::SearchPage;
-use crate::{Client, Error, HlsPlaylist, Media, Result, Streamable};
+use crate::storage::{LocalStorage, Storage};
+use crate::{
+    Album, AlbumId, Artist, ArtistId, ArtistRef, Bitrate, Client, CoverId, Directory, HasCoverArt,
+    HlsPlaylist, MediaType, Result,
+};
+use crate::{Downloadable, Error, Name, SongId, StreamReader, Streamable};
+#[cfg(feature = "chrono")]
+use crate::timestamp;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use url::Url;
 
 /// A work of music contained on a Subsonic server.
+///
+/// Two songs are equal if they have the same ID. See
+/// [`sort::song_order`] to sort songs.
+///
+/// [`sort::song_order`]: ../sort/fn.song_order.html
 #[derive(Debug, Clone)]
 pub struct Song {
     /// Unique identifier for the song.
-    pub id: u64,
+    pub id: SongId,
     /// Title of the song. Prefers the song's ID3 tags, but will fall back to
     /// the file name.
     pub title: String,
     /// Album the song belongs to. Reads from the song's ID3 tags.
-    pub album: Option<String>,
+    pub album: Option<Name>,
     /// The ID of the released album.
-    album_id: Option<u64>,
+    album_id: Option<AlbumId>,
     /// Credited artist for the song. Reads from the song's ID3 tags.
-    pub artist: Option<String>,
+    pub artist: Option<Name>,
     /// The ID of the releasing artist.
-    artist_id: Option<u64>,
+    artist_id: Option<ArtistId>,
+    /// All artists credited on the song. Only provided by OpenSubsonic
+    /// servers; see `artist` for older servers.
+    pub artists: Vec<ArtistRef>,
+    /// The full credit for the song's artists, as it should be displayed.
+    pub display_artist: Option<Name>,
+    /// All artists credited on the song's album. Only provided by OpenSubsonic
+    /// servers.
+    pub album_artists: Vec<ArtistRef>,
+    /// The full credit for the album's artists, as it should be displayed.
+    pub display_album_artist: Option<Name>,
     /// Position of the song in the album.
     pub track: Option<u64>,
     /// Year the song was released.
     pub year: Option<u64>,
-    /// Genre of the song.
-    pub genre: Option<String>,
+    /// Genre of the song. ID3v1 references such as `"(17)"` are decoded into
+    /// their names; see [`raw_genre`] for the value the server sent.
+    ///
+    /// [`raw_genre`]: #method.raw_genre
+    pub genre: Option<Name>,
+    raw_genre: Option<String>,
+    /// All genres of the song. Only provided by OpenSubsonic servers; use
+    /// [`all_genres`] to get the genres regardless of the server.
+    ///
+    /// [`all_genres`]: #method.all_genres
+    pub genres: Vec<Name>,
     /// ID of the song's cover art. Defaults to the parent album's cover.
-    cover_id: Option<String>,
+    cover_id: Option<CoverId>,
     /// File size of the song, in bytes.
     pub size: u64,
     /// An audio MIME type.
-    content_type: String,
+    pub content_type: Name,
     /// The file extension of the song.
-    suffix: String,
+    pub suffix: Name,
     /// The MIME type that the song will be transcoded to.
     transcoded_content_type: Option<String>,
     /// The file extension that the song will be transcoded to.
     transcoded_suffix: Option<String>,
-    /// Duration of the song, in seconds.
+    /// Duration of the song, in seconds. See [`duration()`] for a
+    /// [`Duration`].
+    ///
+    /// [`duration()`]: #method.duration
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
     pub duration: Option<u64>,
+    /// Bit rate of the original file, in Kbps.
+    pub bit_rate: Option<u64>,
+    /// Disc the song is on, for albums spanning multiple discs.
+    pub disc_number: Option<u64>,
+    /// The ID of the directory containing the song. See [`directory()`].
+    ///
+    /// [`directory()`]: #method.directory
+    pub parent: Option<String>,
+    /// Whether the media is a video rather than audio.
+    pub is_video: bool,
+    /// Number of times the song has been played.
+    pub play_count: Option<u64>,
+    /// An ISO8601 timestamp of when the song was added to the server.
+    pub created: String,
+    /// An ISO8601 timestamp of when the song was starred, if it has been.
+    pub starred: Option<String>,
+    /// The rating the user has given the song, from 1 to 5.
+    pub user_rating: Option<u8>,
+    /// The song's [MusicBrainz](https://musicbrainz.org/) recording ID.
+    pub musicbrainz_id: Option<String>,
+    /// ReplayGain metadata for the song. Only provided by OpenSubsonic
+    /// servers.
+    pub replay_gain: Option<ReplayGain>,
     /// The absolute path of the song in the server database.
     path: String,
-    /// Will always be "song".
-    media_type: String,
+    /// The kind of content the song holds.
+    pub media_type: MediaType,
     /// Bit rate the song will be downsampled to.
-    stream_br: Option<usize>,
+    stream_br: Option<Bitrate>,
     /// Format the song will be transcoded to.
     stream_tc: Option<String>,
 }
@@ -60,26 +134,112 @@ impl Song {
     ///
     /// Aside from other errors the `Client` may cause, the server will return
     /// an error if there is no song matching the provided ID.
-    pub fn get(client: &Client, id: u64) -> Result<Song> {
-        let res = client.get("getSong", Query::with("id", id))?;
-        Ok(serde_json::from_value(res)?)
+    pub fn get<I>(client: &Client, id: I) -> Result<Song>
+    where
+        I: Into<SongId>,
+    {
+        client.call(&GetSong { id: id.into() })
+    }
+
+    /// Returns the ID of the album the song is on, if the server knows it.
+    pub fn album_id(&self) -> Option<&AlbumId> {
+        self.album_id.as_ref()
+    }
+
+    /// Returns the ID of the song's artist, if the server knows it.
+    pub fn artist_id(&self) -> Option<&ArtistId> {
+        self.artist_id.as_ref()
+    }
+
+    /// Fetches the album the song is on. Returns `None` if the server doesn't
+    /// know which album that is.
+    pub fn album(&self, client: &Client) -> Result<Option<Album>> {
+        self.album_id
+            .as_ref()
+            .map(|id| Album::get(client, id))
+            .transpose()
+    }
+
+    /// Fetches the song's artist. Returns `None` if the server doesn't know
+    /// who that is.
+    pub fn artist(&self, client: &Client) -> Result<Option<Artist>> {
+        self.artist_id
+            .as_ref()
+            .map(|id| Artist::get(client, id))
+            .transpose()
+    }
+
+    /// Fetches the directory containing the song, to browse the folder it is
+    /// in. Returns `None` if the server doesn't say which directory that is.
+    pub fn directory(&self, client: &Client) -> Result<Option<Directory>> {
+        self.parent
+            .as_ref()
+            .map(|id| Directory::get(client, id))
+            .transpose()
+    }
+
+    /// Tells the server that the song has started playing.
+    ///
+    /// The song appears on the server's "Now Playing" page, and in
+    /// [`Client::now_playing`], but its play count isn't changed.
+    ///
+    /// [`Client::now_playing`]: ../struct.Client.html#method.now_playing
+    pub fn now_playing(&self, client: &Client) -> Result<()> {
+        client.call(&Scrobble {
+            id: self.id.to_string(),
+            time: None,
+            submission: Some(false),
+        })?;
+        client.record_play(self, PlayKind::NowPlaying, SystemTime::now());
+        Ok(())
+    }
+
+    /// Registers that the song has been played, typically once the player
+    /// has finished it.
+    ///
+    /// The song's play count is updated, and it is scrobbled to last.fm if
+    /// the user has configured their last.fm credentials on the server.
+    /// `time` is when the song was played, and defaults to the time the
+    /// server receives the request.
+    ///
+    /// Unlike [`Annotatable::scrobble`], `time` is a `SystemTime` rather
+    /// than a string, and the song is always submitted as played.
+    ///
+    /// [`Annotatable::scrobble`]: ../trait.Annotatable.html#tymethod.scrobble
+    pub fn scrobble_at<T>(&self, client: &Client, time: T) -> Result<()>
+    where
+        T: Into<Option<SystemTime>>,
+    {
+        let time = time.into();
+        client.call(&Scrobble {
+            id: self.id.to_string(),
+            time: time.map(|t| epoch_millis(t).to_string()),
+            submission: Some(true),
+        })?;
+        let time = time.unwrap_or_else(SystemTime::now);
+        client.record_play(self, PlayKind::Scrobble, time);
+        Ok(())
     }
 
-    /// Returns a number of random songs similar to this one.
+    /// Returns a number of random songs similar to this one, such as to start
+    /// a radio station from it.
     ///
     /// last.fm suggests a number of similar songs to the one the method is
     /// called on. Optionally takes a `count` to specify the maximum number of
-    /// results to return.
+    /// results to return (defaults to 50). See [`Artist::similar_songs`] for
+    /// songs similar to an artist.
+    ///
+    /// [`Artist::similar_songs`]: ../struct.Artist.html#method.similar_songs
     pub fn similar<U>(&self, client: &Client, count: U) -> Result<Vec<Song>>
     where
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("count", count.into())
-            .build();
-
-        let song = client.get("getSimilarSongs2", args)?;
-        Ok(get_list_as!(song, Song))
+        // `getSimilarSongs2` only takes an artist ID, but `getSimilarSongs`
+        // takes the ID of a song as well.
+        client.call(&GetSimilarSongs {
+            id: self.id.to_string(),
+            count: count.into(),
+        })
     }
 
     /// Returns a number of random songs. Optionally accepts a maximum number
@@ -93,9 +253,10 @@ impl Song {
     where
         U: Into<Option<usize>>,
     {
-        let arg = Query::with("size", size.into().unwrap_or(10));
-        let song = client.get("getRandomSongs", arg)?;
-        Ok(get_list_as!(song, Song))
+        client.call(&GetRandomSongs {
+            size: Some(size.into().unwrap_or(10)),
+            ..Default::default()
+        })
     }
 
     /// Creates a new builder to request a set of random songs.
@@ -113,7 +274,12 @@ impl Song {
     ///
     /// See the [struct level documentation] about paging for more.
     ///
+    /// If the client has a [genre map], the page is taken from the songs of
+    /// every variant of the genre on the server, one variant after another.
+    /// Reaching a later page then means fetching the songs before it.
+    ///
     /// [struct level documentation]: ../search/struct.SearchPage.html
+    /// [genre map]: ../struct.Client.html#method.with_genre_map
     pub fn list_in_genre<U>(
         client: &Client,
         genre: &str,
@@ -123,14 +289,38 @@ impl Song {
     where
         U: Into<Option<u64>>,
     {
-        let args = Query::with("genre", genre)
-            .arg("count", page.count)
-            .arg("offset", page.offset)
-            .arg("musicFolderId", folder_id.into())
-            .build();
+        let folder_id = folder_id.into();
+        let variants = genre_variants(client, genre)?;
+        if variants.len() == 1 {
+            return list_songs_in_genre(client, &variants[0], &page, folder_id);
+        }
+
+        let mut songs = Song::all_in_genre(client, genre, folder_id);
+        songs.variants = Some(variants.into_iter());
+        songs.skip(page.offset).take(page.count).collect()
+    }
 
-        let song = client.get("getSongsByGenre", args)?;
-        Ok(get_list_as!(song, Song))
+    /// Lists every song in a provided genre, fetching them from the server a
+    /// page at a time as the iterator is advanced.
+    ///
+    /// Genre variants are handled as in [`list_in_genre`]. The iterator ends
+    /// after yielding the first error.
+    ///
+    /// [`list_in_genre`]: #method.list_in_genre
+    pub fn all_in_genre<'a, U>(client: &'a Client, genre: &str, folder_id: U) -> GenreSongs<'a>
+    where
+        U: Into<Option<u64>>,
+    {
+        GenreSongs {
+            client,
+            genre: genre.to_string(),
+            folder_id: folder_id.into(),
+            variants: None,
+            current: None,
+            offset: 0,
+            buffer: Vec::new().into_iter(),
+            done: false,
+        }
     }
 
     /// Creates an HLS (HTTP Live Streaming) playlist used for streaming video
@@ -148,43 +338,491 @@ impl Song {
     /// empty array) to disable adaptive streaming, or given a single value to
     /// force streaming at that bit rate.
     pub fn hls(&self, client: &Client, bit_rates: &[u64]) -> Result<HlsPlaylist> {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", &self.id)
             .arg_list("bitrate", bit_rates)
             .build();
 
         let raw = client.get_raw("hls", args)?;
-        Ok(raw.parse::<HlsPlaylist>()?)
+        raw.text()?.parse::<HlsPlaylist>()
+    }
+
+    /// Returns the genre exactly as the server reported it.
+    ///
+    /// The `genre` field has ID3v1 references such as `"(17)"` decoded into
+    /// names; this returns the undecoded value.
+    pub fn raw_genre(&self) -> Option<&str> {
+        self.raw_genre.as_deref()
+    }
+
+    /// Returns the path of the song's file on the server, relative to its
+    /// music folder.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Downloads the song into a directory or to a path named by a template,
+    /// and returns the path of the file written.
+    ///
+    /// Given a directory, the file is laid out as it is on the server (see
+    /// [`path`]) under it. Given a path with placeholders in braces, such as
+    /// `"music/{artist}/{album}/{track} - {title}.{suffix}"`, the file is
+    /// written there, with the placeholders filled in from the song:
+    ///
+    /// - `{artist}`, `{album}`, `{title}` and `{genre}`, with `/` and `\`
+    ///   replaced by `_`;
+    /// - `{track}` and `{disc}`, the track padded to two digits;
+    /// - `{year}`, `{suffix}` and `{id}`.
+    ///
+    /// Folders are created as needed, and any file already there is replaced.
+    ///
+    /// The song is downloaded in its original format, so the file keeps the
+    /// tags it has on the server; none are rewritten. The file is checked
+    /// against the size the server reports for the song, and isn't kept if
+    /// they differ.
+    ///
+    /// # Errors
+    ///
+    /// Aside from errors the `Client` may cause, fails if the template has a
+    /// placeholder other than those above, or if the download is cut short.
+    ///
+    /// [`path`]: #method.path
+    pub fn download_to<P>(&self, client: &Client, dir_or_template: P) -> Result<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let target = dir_or_template.as_ref();
+        let templated = |c: &Component| c.as_os_str().to_string_lossy().contains('{');
+        let components = target.components().collect::<Vec<_>>();
+        let start = match components.iter().position(templated) {
+            Some(start) => start,
+            None => {
+                let storage = LocalStorage::new(target);
+                let path = self.download_to_storage(client, &storage)?;
+                return Ok(storage.root().join(path));
+            }
+        };
+
+        let root = components[..start].iter().collect::<PathBuf>();
+        let mut path = PathBuf::new();
+        for c in &components[start..] {
+            path.push(self.fill_template(&c.as_os_str().to_string_lossy())?);
+        }
+        let storage = LocalStorage::new(root);
+        self.download_into(client, &storage, &path, |_, _| ())?;
+        Ok(storage.root().join(path))
+    }
+
+    /// Fills in the placeholders of one component of a `download_to`
+    /// template.
+    fn fill_template(&self, template: &str) -> Result<String> {
+        let text = |s: Option<&str>, default: &str| s.unwrap_or(default).replace(['/', '\\'], "_");
+        let number = |n: Option<u64>| n.map(|n| n.to_string()).unwrap_or_default();
+
+        let mut filled = String::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            filled.push_str(&rest[..open]);
+            let close = rest[open..]
+                .find('}')
+                .ok_or(Error::Other("unclosed placeholder in download template"))?;
+            let value = match &rest[open + 1..open + close] {
+                "artist" => text(self.artist.as_deref(), "Unknown Artist"),
+                "album" => text(self.album.as_deref(), "Unknown Album"),
+                "title" => text(Some(self.title.as_str()), ""),
+                "genre" => text(self.genre.as_deref(), "Unknown Genre"),
+                "track" => self.track.map(|t| format!("{:02}", t)).unwrap_or_default(),
+                "disc" => number(self.disc_number),
+                "year" => number(self.year),
+                "suffix" => self.suffix.to_string(),
+                "id" => self.id.to_string(),
+                _ => return Err(Error::Other("unknown placeholder in download template")),
+            };
+            filled.push_str(&value);
+            rest = &rest[open + close + 1..];
+        }
+        filled.push_str(rest);
+
+        if filled.is_empty() || filled == "." || filled == ".." {
+            return Err(Error::Other("download template names no file"));
+        }
+        Ok(filled)
+    }
+
+    /// Downloads the song into `storage`, and returns the path it is stored
+    /// at, relative to the storage.
+    ///
+    /// The path is the song's path on the server (see [`path`]), and any
+    /// file already stored there is replaced. See the [`storage`] module.
+    ///
+    /// [`path`]: #method.path
+    /// [`storage`]: ../storage/index.html
+    pub fn download_to_storage<S>(&self, client: &Client, storage: &S) -> Result<PathBuf>
+    where
+        S: Storage + ?Sized,
+    {
+        let path = self
+            .local_path(Path::new(""))
+            .ok_or(Error::Other("song has no usable path"))?;
+        self.download_into(client, storage, &path, |_, _| ())?;
+        Ok(path)
+    }
+
+    /// Starts streaming the song, and returns a reader over it as soon as the
+    /// server starts sending it.
+    ///
+    /// The maximum bit rate and transcoding format set through [`Streamable`]
+    /// apply. The reader can seek, so can be handed to decoders that expect
+    /// a file; see [`StreamReader`].
+    ///
+    /// [`Streamable`]: ../trait.Streamable.html
+    /// [`StreamReader`]: ../struct.StreamReader.html
+    pub fn stream_reader(&self, client: &Client) -> Result<StreamReader> {
+        self.stream_reader_as(client, self.stream_tc.as_deref())
+    }
+
+    /// Starts streaming the song, transcoded to `format` if given.
+    fn stream_reader_as(&self, client: &Client, format: Option<&str>) -> Result<StreamReader> {
+        let mut q = Query::with("id", &self.id);
+        q.arg("maxBitRate", self.stream_br);
+        q.arg("format", format);
+        client.get_reader("stream", q)
+    }
+
+    /// Starts streaming the song in a format the decoders sunk builds with
+    /// understand, having the server transcode it to MP3 if they don't
+    /// understand the format it streams the song in.
+    #[cfg(any(feature = "playback", feature = "decode"))]
+    pub(crate) fn decodable_reader(&self, client: &Client) -> Result<StreamReader> {
+        match self.transcode_for_decoding() {
+            Some(format) => self.stream_reader_as(client, Some(format.as_str())),
+            None => self.stream_reader(client),
+        }
+    }
+
+    /// Returns the format to transcode the song to for it to be decoded, or
+    /// `None` if it can be decoded as the server streams it.
+    #[cfg(any(feature = "playback", feature = "decode"))]
+    fn transcode_for_decoding(&self) -> Option<AudioFormat> {
+        use crate::media::format::AudioFormat::*;
+        let streamed = match self.stream_tc.as_deref() {
+            Some("raw") => self.format(),
+            Some(format) => format.parse().unwrap_or_else(|_| self.format()),
+            None => self.transcoded_format().unwrap_or_else(|| self.format()),
+        };
+        match streamed {
+            Aac | Flac | M4a | Mp3 | Oga | Ogg | Wav => None,
+            _ => Some(Mp3),
+        }
+    }
+
+    /// Returns about how many bytes `duration` of the song takes to stream,
+    /// going by its bit rate and any limit set on it. Songs of unknown bit
+    /// rate are taken to be 320 Kbps.
+    pub(crate) fn stream_bytes(&self, duration: Duration) -> u64 {
+        let original = self.bit_rate.unwrap_or(320);
+        let kbps = match self.stream_br {
+            Some(limit) if !limit.is_unlimited() => original.min(u64::from(limit.kbps())),
+            _ => original,
+        };
+        (kbps as f64 * 125.0 * duration.as_secs_f64()) as u64
+    }
+
+    /// Returns where the song is downloaded to under `dir`, mirroring its
+    /// path on the server.
+    ///
+    /// Only the plain components of the server path are kept, so a path
+    /// can't escape `dir`.
+    pub(crate) fn local_path(&self, dir: &Path) -> Option<PathBuf> {
+        let relative = Path::new(&self.path)
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part),
+                _ => None,
+            })
+            .collect::<PathBuf>();
+
+        if relative.file_name().is_none() {
+            None
+        } else {
+            Some(dir.join(relative))
+        }
+    }
+
+    /// Downloads the song to `path` in `storage`, and returns its size in
+    /// bytes.
+    ///
+    /// `progress` is called as the file arrives, as with `Client::get_to`.
+    /// The file is only stored if its size matches the size the server
+    /// reports for the song, where it reports one.
+    pub(crate) fn download_into<S, F>(
+        &self,
+        client: &Client,
+        storage: &S,
+        path: &Path,
+        progress: F,
+    ) -> Result<u64>
+    where
+        S: Storage + ?Sized,
+        F: FnMut(u64, Option<u64>),
+    {
+        info!("Downloading {} to {}", self.id, path.display());
+        let query = Query::with("id", &self.id);
+        let mut writer = storage.create(path)?;
+        let written = client.get_to("download", query, &mut writer, progress)?;
+        if self.size != 0 && written != self.size {
+            // Dropping the writer discards what was written.
+            warn!(
+                "Downloaded {} bytes of song {}, expected {}",
+                written, self.id, self.size
+            );
+            return Err(Error::Other(
+                "downloaded file doesn't match the song's size",
+            ));
+        }
+        writer.finish()?;
+        Ok(written)
+    }
+
+    /// Fetches the lyrics of the song. Returns `None` if no lyrics are found.
+    ///
+    /// Servers with the OpenSubsonic `songLyrics` extension are asked for the
+    /// lyrics of this exact song, without timestamps; see
+    /// [`structured_lyrics`] to keep them. Otherwise, or if they have none,
+    /// the lyrics are searched for by the song's artist and title.
+    ///
+    /// The artist and title of the lyrics are filled in from the song if the
+    /// server leaves them out.
+    ///
+    /// [`structured_lyrics`]: #method.structured_lyrics
+    pub fn lyrics(&self, client: &Client) -> Result<Option<Lyrics>> {
+        if client.has_extension("songLyrics")? {
+            let structured = self.structured_lyrics(client)?;
+            if let Some(lyrics) = structured.into_iter().find(|l| !l.lines.is_empty()) {
+                return Ok(Some(self.fill_lyrics(lyrics.into())));
+            }
+        }
+
+        let lyrics = client.lyrics(self.artist.as_deref(), Some(self.title.as_str()))?;
+        Ok(lyrics.map(|lyrics| self.fill_lyrics(lyrics)))
+    }
+
+    /// Fills in the artist and title of lyrics from the song, where missing.
+    fn fill_lyrics(&self, mut lyrics: Lyrics) -> Lyrics {
+        if lyrics.title.is_empty() {
+            lyrics.title = self.title.clone();
+        }
+        if lyrics.artist.is_empty() {
+            lyrics.artist = self.artist.as_deref().unwrap_or_default().to_string();
+        }
+        lyrics
+    }
+
+    /// Fetches the lyrics of the song from the server's own library, with
+    /// timestamps for each line if the server has them.
+    ///
+    /// Servers may have lyrics in several languages, or both synced and
+    /// unsynced versions, so all of them are returned.
+    ///
+    /// # Errors
+    ///
+    /// This requires the OpenSubsonic `songLyrics` extension; an error is
+    /// returned if the server doesn't support it. Use [`lyrics`] on other
+    /// servers.
+    ///
+    /// [`lyrics`]: #method.lyrics
+    pub fn structured_lyrics(&self, client: &Client) -> Result<Vec<StructuredLyrics>> {
+        if !client.has_extension("songLyrics")? {
+            return Err(Error::Other("server does not support structured lyrics"));
+        }
+
+        client.call(&GetLyricsBySongId {
+            id: self.id.clone(),
+        })
+    }
+
+    /// Returns every genre of the song.
+    ///
+    /// OpenSubsonic servers may list several genres for a song; older servers
+    /// only ever provide one. This returns whichever is available.
+    pub fn all_genres(&self) -> Vec<&str> {
+        if self.genres.is_empty() {
+            self.genre.iter().map(|g| &**g).collect()
+        } else {
+            self.genres.iter().map(|g| &**g).collect()
+        }
+    }
+
+    /// Returns the length of the song, if the server knows it.
+    ///
+    /// The raw number of seconds is available as the `duration` field.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration.map(Duration::from_secs)
     }
+
+    /// Returns the audio format of the original file.
+    pub fn format(&self) -> AudioFormat {
+        AudioFormat::from_fields(&self.suffix, &self.content_type)
+    }
+
+    /// Returns the audio format the server transcodes the song to by default,
+    /// if it transcodes it at all.
+    pub fn transcoded_format(&self) -> Option<AudioFormat> {
+        match (&self.transcoded_suffix, &self.transcoded_content_type) {
+            (None, None) => None,
+            (suffix, content_type) => Some(AudioFormat::from_fields(
+                suffix.as_ref().map_or("", |s| s.as_str()),
+                content_type.as_ref().map_or("", |s| s.as_str()),
+            )),
+        }
+    }
+
+    /// Returns when the song was added to the server.
+    ///
+    /// Returns `None` if the server sent a timestamp that couldn't be parsed.
+    #[cfg(feature = "chrono")]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        timestamp::parse(&self.created)
+    }
+
+    /// Returns when the song was starred, if it has been.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<DateTime<Utc>> {
+        self.starred.as_ref().and_then(|s| timestamp::parse(s))
+    }
+}
+
+/// Returns the names the server uses for `genre`.
+///
+/// Without a genre map this is just `genre`; with one, it is every genre on
+/// the server the map considers the same, or `genre` if there are none.
+fn genre_variants(client: &Client, genre: &str) -> Result<Vec<String>> {
+    let map = match client.genre_map() {
+        Some(map) => map,
+        None => return Ok(vec![genre.to_string()]),
+    };
+
+    let mut variants = client
+        .server_genres()?
+        .into_iter()
+        .filter(|g| map.same(&g.name, genre))
+        .map(|g| g.name)
+        .collect::<Vec<_>>();
+    if variants.is_empty() {
+        variants.push(genre.to_string());
+    }
+    Ok(variants)
+}
+
+fn list_songs_in_genre(
+    client: &Client,
+    genre: &str,
+    page: &SearchPage,
+    folder_id: Option<u64>,
+) -> Result<Vec<Song>> {
+    client.call(&GetSongsByGenre {
+        genre: genre.to_string(),
+        count: Some(page.count),
+        offset: Some(page.offset),
+        folder_id,
+    })
+}
+
+/// Downloads `songs` into `storage`, laid out as they are on the server, and
+/// returns the paths of the files written, relative to the storage.
+///
+/// Songs whose file already exists are skipped unless `overwrite` is set.
+/// `observer` is told the songs done, skipped ones included, and the bytes
+/// downloaded, against the song count and sizes the server reports.
+pub(crate) fn download_songs<S, O>(
+    client: &Client,
+    songs: &[Song],
+    storage: &S,
+    overwrite: bool,
+    mut observer: O,
+) -> Result<Vec<PathBuf>>
+where
+    S: Storage + ?Sized,
+    O: ProgressObserver,
+{
+    let mut progress = Progress {
+        total_items: Some(songs.len() as u64),
+        total_bytes: Some(songs.iter().map(|s| s.size).sum()),
+        ..Progress::default()
+    };
+    observer.started(&progress);
+    let mut written = Vec::new();
+    let res = download_each(
+        client,
+        songs,
+        storage,
+        overwrite,
+        &mut written,
+        &mut progress,
+        &mut observer,
+    );
+    progress::finish(&mut observer, &progress, res.map(|_| written))
+}
+
+fn download_each<S: Storage + ?Sized, O: ProgressObserver>(
+    client: &Client,
+    songs: &[Song],
+    storage: &S,
+    overwrite: bool,
+    written: &mut Vec<PathBuf>,
+    progress: &mut Progress,
+    observer: &mut O,
+) -> Result<()> {
+    for song in songs {
+        let path = song.local_path(Path::new(""));
+        if path.is_none() {
+            warn!(
+                "Skipping song {} with unusable path {:?}",
+                song.id,
+                song.path()
+            );
+        }
+        let path = match path {
+            Some(path) if overwrite || !storage.exists(&path)? => Some(path),
+            _ => None,
+        };
+        match path {
+            Some(path) => {
+                let before = progress.bytes;
+                let size = song.download_into(client, storage, &path, |n, _| {
+                    progress.bytes = before + n;
+                    observer.progress(progress);
+                })?;
+                progress.bytes = before + size;
+                written.push(path);
+            }
+            None => progress.bytes += song.size,
+        }
+        progress.items += 1;
+        observer.progress(progress);
+    }
+    Ok(())
 }
 
 impl Streamable for Song {
     fn stream(&self, client: &Client) -> Result<Vec<u8>> {
-        let mut q = Query::with("id", self.id);
+        let mut q = Query::with("id", &self.id);
         q.arg("maxBitRate", self.stream_br);
         client.get_bytes("stream", q)
     }
 
-    fn stream_url(&self, client: &Client) -> Result<String> {
-        let mut q = Query::with("id", self.id);
+    fn stream_url(&self, client: &Client) -> Result<Url> {
+        let mut q = Query::with("id", &self.id);
         q.arg("maxBitRate", self.stream_br);
         client.build_url("stream", q)
     }
 
-    fn download(&self, client: &Client) -> Result<Vec<u8>> {
-        client.get_bytes("download", Query::with("id", self.id))
-    }
-
-    fn download_url(&self, client: &Client) -> Result<String> {
-        client.build_url("download", Query::with("id", self.id))
-    }
-
     fn encoding(&self) -> &str {
         self.transcoded_content_type
-            .as_ref()
+            .as_deref()
             .unwrap_or(&self.content_type)
     }
 
-    fn set_max_bit_rate(&mut self, bit_rate: usize) {
+    fn set_max_bit_rate(&mut self, bit_rate: Bitrate) {
         self.stream_br = Some(bit_rate);
     }
 
@@ -193,31 +831,33 @@ impl Streamable for Song {
     }
 }
 
-impl Media for Song {
-    fn has_cover_art(&self) -> bool {
-        self.cover_id.is_some()
+impl Downloadable for Song {
+    fn download(&self, client: &Client) -> Result<Vec<u8>> {
+        client.get_bytes("download", Query::with("id", &self.id))
     }
 
-    fn cover_id(&self) -> Option<&str> {
-        self.cover_id.as_ref().map(|s| s.as_str())
+    fn download_url(&self, client: &Client) -> Result<Url> {
+        client.build_url("download", Query::with("id", &self.id))
     }
+}
 
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+impl HasCoverArt for Song {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
+    }
+}
 
-        client.get_bytes("getCoverArt", query)
+impl PartialEq for Song {
+    fn eq(&self, other: &Song) -> bool {
+        self.id == other.id
     }
+}
 
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+impl Eq for Song {}
 
-        client.build_url("getCoverArt", query)
+impl Hash for Song {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
 }
 
@@ -246,60 +886,93 @@ impl fmt::Display for Song {
 }
 
 impl<'de> Deserialize<'de> for Song {
-    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Song {
-            id: String,
-            parent: String,
+            id: SongId,
+            parent: Option<String>,
             is_dir: bool,
             title: String,
-            album: Option<String>,
-            artist: Option<String>,
+            album: Option<Name>,
+            artist: Option<Name>,
+            #[serde(default)]
+            artists: Vec<ArtistRef>,
+            display_artist: Option<Name>,
+            #[serde(default)]
+            album_artists: Vec<ArtistRef>,
+            display_album_artist: Option<Name>,
             track: Option<u64>,
             year: Option<u64>,
             genre: Option<String>,
-            cover_art: Option<String>,
+            #[serde(default)]
+            genres: Vec<_Genre>,
+            cover_art: Option<CoverId>,
             size: u64,
-            content_type: String,
-            suffix: String,
+            content_type: Name,
+            suffix: Name,
             transcoded_content_type: Option<String>,
             transcoded_suffix: Option<String>,
             duration: Option<u64>,
             bit_rate: Option<u64>,
             path: String,
             is_video: Option<bool>,
-            play_count: u64,
+            play_count: Option<u64>,
             disc_number: Option<u64>,
             created: String,
-            album_id: Option<String>,
-            artist_id: Option<String>,
+            starred: Option<String>,
+            user_rating: Option<u8>,
+            music_brainz_id: Option<String>,
+            replay_gain: Option<ReplayGain>,
+            album_id: Option<AlbumId>,
+            artist_id: Option<ArtistId>,
             #[serde(rename = "type")]
-            media_type: String,
+            media_type: MediaType,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct _Genre {
+            name: Name,
         }
 
         let raw = _Song::deserialize(de)?;
 
         Ok(Song {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             title: raw.title,
             album: raw.album,
-            album_id: raw.album_id.map(|i| i.parse().unwrap()),
+            album_id: raw.album_id,
             artist: raw.artist,
-            artist_id: raw.artist_id.map(|i| i.parse().unwrap()),
+            artist_id: raw.artist_id,
+            artists: raw.artists,
+            display_artist: raw.display_artist,
+            album_artists: raw.album_artists,
+            display_album_artist: raw.display_album_artist,
             cover_id: raw.cover_art,
             track: raw.track,
             year: raw.year,
-            genre: raw.genre,
+            genre: raw.genre.as_deref().and_then(id3::decode).map(name::shared),
+            raw_genre: raw.genre,
+            genres: raw.genres.into_iter().map(|g| g.name).collect(),
             size: raw.size,
             content_type: raw.content_type,
             suffix: raw.suffix,
             transcoded_content_type: raw.transcoded_content_type,
             transcoded_suffix: raw.transcoded_suffix,
             duration: raw.duration,
+            bit_rate: raw.bit_rate,
+            disc_number: raw.disc_number,
+            parent: raw.parent,
+            is_video: raw.is_video.unwrap_or(false),
+            play_count: raw.play_count,
+            created: raw.created,
+            starred: raw.starred,
+            user_rating: raw.user_rating,
+            musicbrainz_id: raw.music_brainz_id.filter(|s| !s.is_empty()),
+            replay_gain: raw.replay_gain,
             path: raw.path,
             media_type: raw.media_type,
             stream_br: None,
@@ -308,16 +981,330 @@ impl<'de> Deserialize<'de> for Song {
     }
 }
 
+impl Serialize for Song {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Song<'a> {
+            id: &'a SongId,
+            parent: Option<String>,
+            is_dir: bool,
+            title: &'a str,
+            album: Option<&'a str>,
+            artist: Option<&'a str>,
+            artists: &'a [ArtistRef],
+            display_artist: Option<&'a str>,
+            album_artists: &'a [ArtistRef],
+            display_album_artist: Option<&'a str>,
+            track: Option<u64>,
+            year: Option<u64>,
+            genre: Option<&'a str>,
+            genres: Vec<_Genre<'a>>,
+            cover_art: Option<&'a CoverId>,
+            size: u64,
+            content_type: &'a str,
+            suffix: &'a str,
+            transcoded_content_type: Option<&'a str>,
+            transcoded_suffix: Option<&'a str>,
+            duration: Option<u64>,
+            bit_rate: Option<u64>,
+            path: &'a str,
+            is_video: bool,
+            play_count: Option<u64>,
+            disc_number: Option<u64>,
+            created: &'a str,
+            starred: Option<&'a str>,
+            user_rating: Option<u8>,
+            music_brainz_id: Option<&'a str>,
+            replay_gain: Option<ReplayGain>,
+            album_id: Option<&'a AlbumId>,
+            artist_id: Option<&'a ArtistId>,
+            #[serde(rename = "type")]
+            media_type: &'a MediaType,
+        }
+
+        #[derive(Serialize)]
+        struct _Genre<'a> {
+            name: &'a str,
+        }
+
+        _Song {
+            id: &self.id,
+            parent: self.parent.clone(),
+            is_dir: false,
+            title: &self.title,
+            album: self.album.as_deref(),
+            artist: self.artist.as_deref(),
+            artists: &self.artists,
+            display_artist: self.display_artist.as_deref(),
+            album_artists: &self.album_artists,
+            display_album_artist: self.display_album_artist.as_deref(),
+            track: self.track,
+            year: self.year,
+            genre: self.raw_genre.as_deref(),
+            genres: self.genres.iter().map(|g| _Genre { name: g }).collect(),
+            cover_art: self.cover_id.as_ref(),
+            size: self.size,
+            content_type: &self.content_type,
+            suffix: &self.suffix,
+            transcoded_content_type: self.transcoded_content_type.as_deref(),
+            transcoded_suffix: self.transcoded_suffix.as_deref(),
+            duration: self.duration,
+            bit_rate: self.bit_rate,
+            path: &self.path,
+            is_video: self.is_video,
+            play_count: self.play_count,
+            disc_number: self.disc_number,
+            created: &self.created,
+            starred: self.starred.as_deref(),
+            user_rating: self.user_rating,
+            music_brainz_id: self.musicbrainz_id.as_deref(),
+            replay_gain: self.replay_gain,
+            album_id: self.album_id.as_ref(),
+            artist_id: self.artist_id.as_ref(),
+            media_type: &self.media_type,
+        }
+        .serialize(se)
+    }
+}
+
+/// ReplayGain loudness metadata, used to normalise playback volume.
+///
+/// Gains are measured in decibels, and peaks as a linear amplitude where `1.0`
+/// is full scale. Use [`ReplayGain::to_amplitude`] to turn a gain into a
+/// factor that samples can be multiplied by.
+///
+/// [`ReplayGain::to_amplitude`]: #method.to_amplitude
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayGain {
+    /// The gain to apply when playing the song on its own.
+    pub track_gain: Option<f32>,
+    /// The gain to apply when playing the song as part of its album.
+    pub album_gain: Option<f32>,
+    /// The peak amplitude of the song.
+    pub track_peak: Option<f32>,
+    /// The peak amplitude of the song's album.
+    pub album_peak: Option<f32>,
+    /// The reference level the gains were calculated against.
+    pub base_gain: Option<f32>,
+    /// A gain suggested by the server for songs with no ReplayGain tags.
+    pub fallback_gain: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Converts a gain in decibels into a linear amplitude factor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sunk::song::ReplayGain;
+    ///
+    /// assert_eq!(ReplayGain::to_amplitude(0.0), 1.0);
+    /// assert!((ReplayGain::to_amplitude(-6.0) - 0.501).abs() < 0.001);
+    /// ```
+    pub fn to_amplitude(gain: f32) -> f32 {
+        10f32.powf(gain / 20.0)
+    }
+
+    /// Returns the linear amplitude factor of the track gain, falling back to
+    /// the server's fallback gain.
+    pub fn track_factor(&self) -> Option<f32> {
+        self.track_gain
+            .or(self.fallback_gain)
+            .map(ReplayGain::to_amplitude)
+    }
+
+    /// Returns the linear amplitude factor of the album gain, falling back to
+    /// the track gain where the album has none.
+    pub fn album_factor(&self) -> Option<f32> {
+        self.album_gain
+            .map(ReplayGain::to_amplitude)
+            .or_else(|| self.track_factor())
+    }
+}
+
 /// A struct matching a lyric search result.
-#[derive(Debug, Deserialize)]
+///
+/// Displaying a `Lyrics` prints the text of the lyrics.
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Lyrics {
-    /// Title of the song.
+    /// Title of the song. Empty if the server didn't report it.
+    #[serde(default)]
     pub title: String,
-    /// Artist that performed the song.
+    /// Artist that performed the song. Empty if the server didn't report it.
+    #[serde(default)]
     pub artist: String,
     /// Lyrics to the song.
     #[serde(rename = "value")]
-    pub lyrics: String,
+    pub text: String,
+}
+
+impl fmt::Display for Lyrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Lyrics of a song split into lines, as returned by the OpenSubsonic
+/// `songLyrics` extension.
+#[derive(Debug, Clone)]
+pub struct StructuredLyrics {
+    /// The artist to display with the lyrics.
+    pub display_artist: Option<String>,
+    /// The title to display with the lyrics.
+    pub display_title: Option<String>,
+    /// The language of the lyrics, as an ISO 639 code. `"und"` or `"xxx"`
+    /// if unknown.
+    pub lang: String,
+    /// Whether the lines have timestamps.
+    pub synced: bool,
+    /// How far to shift the timestamps, in milliseconds. Positive offsets
+    /// make the lines appear sooner.
+    pub offset: i64,
+    /// The lines of the lyrics.
+    pub lines: Vec<LyricLine>,
+}
+
+/// A line of [`StructuredLyrics`].
+///
+/// [`StructuredLyrics`]: ./struct.StructuredLyrics.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    /// When the line starts, from the start of the song. Always zero for
+    /// unsynced lyrics.
+    pub start: Duration,
+    /// The text of the line.
+    pub text: String,
+}
+
+impl StructuredLyrics {
+    /// Returns the line being sung at `position` in the song, taking the
+    /// offset into account.
+    ///
+    /// Returns `None` for unsynced lyrics, and before the first line starts.
+    pub fn line_at(&self, position: Duration) -> Option<&LyricLine> {
+        if !self.synced {
+            return None;
+        }
+
+        let position = position.as_millis() as i128 + i128::from(self.offset);
+        self.lines
+            .iter()
+            .take_while(|l| l.start.as_millis() as i128 <= position)
+            .last()
+    }
+}
+
+impl From<StructuredLyrics> for Lyrics {
+    /// Drops the timestamps of the lyrics, keeping one line of text for each
+    /// line.
+    fn from(lyrics: StructuredLyrics) -> Lyrics {
+        let text = lyrics
+            .lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Lyrics {
+            title: lyrics.display_title.unwrap_or_default(),
+            artist: lyrics.display_artist.unwrap_or_default(),
+            text,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StructuredLyrics {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _StructuredLyrics {
+            display_artist: Option<String>,
+            display_title: Option<String>,
+            lang: String,
+            synced: bool,
+            #[serde(default)]
+            offset: i64,
+            #[serde(default)]
+            line: Vec<_Line>,
+        }
+
+        #[derive(Deserialize)]
+        struct _Line {
+            start: Option<u64>,
+            value: String,
+        }
+
+        let raw = _StructuredLyrics::deserialize(de)?;
+
+        Ok(StructuredLyrics {
+            display_artist: raw.display_artist,
+            display_title: raw.display_title,
+            lang: raw.lang,
+            synced: raw.synced,
+            offset: raw.offset,
+            lines: raw
+                .line
+                .into_iter()
+                .map(|l| LyricLine {
+                    start: Duration::from_millis(l.start.unwrap_or(0)),
+                    text: l.value,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl Serialize for StructuredLyrics {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _StructuredLyrics<'a> {
+            display_artist: Option<&'a str>,
+            display_title: Option<&'a str>,
+            lang: &'a str,
+            synced: bool,
+            offset: i64,
+            line: Vec<_Line<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct _Line<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            start: Option<u64>,
+            value: &'a str,
+        }
+
+        _StructuredLyrics {
+            display_artist: self.display_artist.as_deref(),
+            display_title: self.display_title.as_deref(),
+            lang: &self.lang,
+            synced: self.synced,
+            offset: self.offset,
+            line: self
+                .lines
+                .iter()
+                .map(|l| _Line {
+                    start: if self.synced {
+                        Some(l.start.as_millis() as u64)
+                    } else {
+                        None
+                    },
+                    value: &l.text,
+                })
+                .collect(),
+        }
+        .serialize(se)
+    }
 }
 
 /// A builder struct for a query of random songs.
@@ -351,7 +1338,7 @@ pub struct Lyrics {
 /// // Get 25 songs from the last 10 years
 /// let random = Song::random_with(&client)
 ///     .size(25)
-///     .in_years(2008 .. 2018)
+///     .years(2008..=2018)
 ///     .request()?;
 /// # Ok(())
 /// # }
@@ -419,6 +1406,15 @@ impl<'a> RandomSongs<'a> {
         self
     }
 
+    /// Sets both the lower and upper year bounds using an inclusive range.
+    ///
+    /// `1990..=1999` returns songs released in the nineties.
+    pub fn years(&mut self, years: RangeInclusive<usize>) -> &mut RandomSongs<'a> {
+        self.from_year = Some(*years.start());
+        self.to_year = Some(*years.end());
+        self
+    }
+
     /// Sets the folder index that songs must be in.
     ///
     /// Music folders are zero-indexed, and there will always be index `0`
@@ -431,35 +1427,664 @@ impl<'a> RandomSongs<'a> {
         self
     }
 
+    // An alias of `in_folder`, hidden so that only one is documented.
+    #[doc(hidden)]
+    pub fn folder(&mut self, id: usize) -> &mut RandomSongs<'a> {
+        self.in_folder(id)
+    }
+
     /// Issues the query to the Subsonic server. Returns a list of random
     /// songs, modified by the builder.
     pub fn request(&mut self) -> Result<Vec<Song>> {
-        let args = Query::with("size", self.size)
-            .arg("genre", self.genre)
-            .arg("fromYear", self.from_year)
-            .arg("toYear", self.to_year)
-            .arg("musicFolderId", self.folder_id)
-            .build();
+        self.client.call(&GetRandomSongs {
+            size: Some(self.size),
+            genre: self.genre.map(String::from),
+            from_year: self.from_year,
+            to_year: self.to_year,
+            folder_id: self.folder_id,
+        })
+    }
+}
+
+/// The number of songs fetched at a time by [`GenreSongs`].
+const GENRE_PAGE: usize = 500;
+
+/// An iterator over every song in a genre, created by
+/// [`Song::all_in_genre`].
+///
+/// Songs are requested from the server 500 at a time, as they are
+/// needed.
+///
+/// [`Song::all_in_genre`]: ./struct.Song.html#method.all_in_genre
+#[derive(Debug)]
+pub struct GenreSongs<'a> {
+    client: &'a Client,
+    genre: String,
+    folder_id: Option<u64>,
+    variants: Option<std::vec::IntoIter<String>>,
+    current: Option<String>,
+    offset: usize,
+    buffer: std::vec::IntoIter<Song>,
+    done: bool,
+}
+
+impl<'a> GenreSongs<'a> {
+    /// Fetches the next page of songs into the buffer. Returns `false` once
+    /// every variant of the genre has been exhausted.
+    fn fill(&mut self) -> Result<bool> {
+        if self.variants.is_none() {
+            self.variants = Some(genre_variants(self.client, &self.genre)?.into_iter());
+        }
+        let variant = match self.current.take() {
+            Some(variant) => variant,
+            None => match self.variants.as_mut().and_then(Iterator::next) {
+                Some(variant) => variant,
+                None => return Ok(false),
+            },
+        };
+
+        let page = SearchPage {
+            count: GENRE_PAGE,
+            offset: self.offset,
+        };
+        let songs = list_songs_in_genre(self.client, &variant, &page, self.folder_id)?;
+        if songs.len() < GENRE_PAGE {
+            self.offset = 0;
+        } else {
+            self.offset += GENRE_PAGE;
+            self.current = Some(variant);
+        }
+        self.buffer = songs.into_iter();
+        Ok(true)
+    }
+}
+
+impl<'a> Iterator for GenreSongs<'a> {
+    type Item = Result<Song>;
+
+    fn next(&mut self) -> Option<Result<Song>> {
+        loop {
+            if let Some(song) = self.buffer.next() {
+                return Some(Ok(song));
+            }
+            if self.done {
+                return None;
+            }
+            match self.fill() {
+                Ok(true) => (),
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Constraints on the songs played by a [`Radio`].
+///
+/// Every constraint is optional; the default plays anything in the library.
+///
+/// [`Radio`]: ./struct.Radio.html
+#[derive(Debug, Clone, Default)]
+pub struct RadioConstraints {
+    /// Only play songs of this genre.
+    pub genre: Option<String>,
+    /// Only play songs released in or after this year.
+    pub from_year: Option<usize>,
+    /// Only play songs released in or before this year.
+    pub to_year: Option<usize>,
+    /// Only play songs in this music folder.
+    pub folder_id: Option<usize>,
+    /// Play songs by this artist and similar ones, using `getSimilarSongs2`.
+    ///
+    /// Once the server runs out of fresh similar songs, the radio falls back
+    /// to random songs matching the other constraints.
+    pub seed: Option<ArtistId>,
+}
+
+/// The number of songs a [`Radio`] requests at a time.
+const RADIO_REFILL: usize = 50;
+
+/// The number of times a [`Radio`] asks for more songs when every song it is
+/// given was played recently, before it gives up and repeats them.
+const RADIO_ATTEMPTS: usize = 3;
 
-        let song = self.client.get("getRandomSongs", args)?;
-        Ok(get_list_as!(song, Song))
+/// Creates an endless iterator of songs matching `constraints`.
+///
+/// See [`Radio`] for details.
+///
+/// [`Radio`]: ./struct.Radio.html
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use sunk::song::{self, RadioConstraints};
+/// use sunk::Client;
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// let jazz = RadioConstraints {
+///     genre: Some("Jazz".into()),
+///     ..Default::default()
+/// };
+/// for song in song::radio(&client, jazz).take(100) {
+///     println!("{}", song?);
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn radio(client: &Client, constraints: RadioConstraints) -> Radio<'_> {
+    Radio {
+        client,
+        constraints,
+        queue: VecDeque::new(),
+        recent: VecDeque::new(),
+        window: 100,
+        done: false,
     }
 }
 
+/// An endless, shuffled stream of songs, created by [`radio`].
+///
+/// Songs are requested from the server in batches as the iterator is
+/// advanced. The last played songs are remembered, and songs among them are
+/// skipped when a batch is requested so that they don't repeat too soon; if
+/// the library is too small to avoid that, they are played again anyway.
+///
+/// The iterator only ends if the server has no songs matching the
+/// constraints, or after yielding an error.
+///
+/// [`radio`]: ./fn.radio.html
+#[derive(Debug)]
+pub struct Radio<'a> {
+    client: &'a Client,
+    constraints: RadioConstraints,
+    queue: VecDeque<Song>,
+    recent: VecDeque<SongId>,
+    window: usize,
+    done: bool,
+}
+
+impl<'a> Radio<'a> {
+    /// Sets how many of the last played songs are kept from repeating.
+    /// Defaults to 100.
+    pub fn with_window(mut self, window: usize) -> Radio<'a> {
+        self.window = window;
+        self.recent.truncate(window);
+        self
+    }
+
+    /// Returns whether a song was played recently or is already queued.
+    fn is_recent(&self, song: &Song) -> bool {
+        self.recent.contains(&song.id) || self.queue.iter().any(|s| s.id == song.id)
+    }
+
+    /// Remembers a song as played, forgetting the oldest one if the window is
+    /// full.
+    fn remember(&mut self, id: SongId) {
+        if self.window == 0 {
+            return;
+        }
+        if self.recent.len() == self.window {
+            self.recent.pop_back();
+        }
+        self.recent.push_front(id);
+    }
+
+    /// Requests a batch of songs from the server.
+    fn fetch(&self, similar: bool) -> Result<Vec<Song>> {
+        match &self.constraints.seed {
+            Some(seed) if similar => self.client.call(&GetSimilarSongs2 {
+                id: seed.clone(),
+                count: Some(RADIO_REFILL),
+            }),
+            _ => RandomSongs {
+                client: self.client,
+                size: RADIO_REFILL,
+                genre: self.constraints.genre.as_deref(),
+                from_year: self.constraints.from_year,
+                to_year: self.constraints.to_year,
+                folder_id: self.constraints.folder_id,
+            }
+            .request(),
+        }
+    }
+
+    /// Queues another batch of songs. Returns `false` if the server has no
+    /// songs to give.
+    fn refill(&mut self) -> Result<bool> {
+        let mut similar = self.constraints.seed.is_some();
+        let mut last = Vec::new();
+        for _ in 0..RADIO_ATTEMPTS {
+            let songs = self.fetch(similar)?;
+            if songs.is_empty() && !similar {
+                break;
+            }
+            if songs.iter().all(|s| self.is_recent(s)) {
+                // Similar songs are the same every time, so only random ones
+                // are worth asking for again.
+                similar = false;
+                if !songs.is_empty() {
+                    last = songs;
+                }
+                continue;
+            }
+            for song in songs {
+                if !self.is_recent(&song) {
+                    self.queue.push_back(song);
+                }
+            }
+            return Ok(true);
+        }
+
+        self.queue.extend(last);
+        Ok(!self.queue.is_empty())
+    }
+}
+
+impl<'a> Iterator for Radio<'a> {
+    type Item = Result<Song>;
+
+    fn next(&mut self) -> Option<Result<Song>> {
+        if self.done {
+            return None;
+        }
+        if self.queue.is_empty() {
+            match self.refill() {
+                Ok(true) => (),
+                Ok(false) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let song = self.queue.pop_front()?;
+        self.remember(song.id.clone());
+        Some(Ok(song))
+    }
+}
+
+/// Returns the number of milliseconds since the Unix epoch, as the API takes
+/// times.
+fn epoch_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
+    use crate::MediaTypeFilter;
+
+    #[test]
+    #[cfg(any(feature = "playback", feature = "decode"))]
+    fn transcodes_for_decoding() {
+        let song = |suffix: &str, transcoded: Option<&str>| {
+            let mut raw = raw();
+            raw["suffix"] = suffix.into();
+            if let Some(t) = transcoded {
+                raw["transcodedSuffix"] = t.into();
+            }
+            serde_json::from_value::<Song>(raw).unwrap()
+        };
+        assert_eq!(song("flac", None).transcode_for_decoding(), None);
+        assert_eq!(
+            song("ape", None).transcode_for_decoding(),
+            Some(AudioFormat::Mp3)
+        );
+        assert_eq!(
+            song("opus", None).transcode_for_decoding(),
+            Some(AudioFormat::Mp3)
+        );
+        assert_eq!(song("wma", Some("ogg")).transcode_for_decoding(), None);
+
+        let mut chosen = song("flac", None);
+        chosen.set_transcoding("opus");
+        assert_eq!(chosen.transcode_for_decoding(), Some(AudioFormat::Mp3));
+    }
+
+    #[test]
+    fn estimates_stream_bytes() {
+        let mut raw = raw();
+        raw["bitRate"] = 256.into();
+        let mut song = serde_json::from_value::<Song>(raw).unwrap();
+        let ten = Duration::from_secs(10);
+        assert_eq!(song.stream_bytes(ten), 320_000);
+
+        song.set_max_bit_rate(Bitrate::LOW);
+        assert_eq!(song.stream_bytes(ten), 80_000);
+        song.set_max_bit_rate(Bitrate::UNLIMITED);
+        assert_eq!(song.stream_bytes(ten), 320_000);
+    }
+
+    #[test]
+    fn download_paths() {
+        let song = |path: &str| {
+            let mut raw = raw();
+            raw["path"] = path.into();
+            serde_json::from_value::<Song>(raw).unwrap()
+        };
+        let dir = Path::new("backup");
+
+        assert_eq!(
+            song("Misteur Valaire/Bellevue/01.mp3").local_path(dir),
+            Some(dir.join("Misteur Valaire").join("Bellevue").join("01.mp3"))
+        );
+        assert_eq!(
+            song("/music/../../etc/01.mp3").local_path(dir),
+            Some(dir.join("music").join("etc").join("01.mp3"))
+        );
+        assert_eq!(song("..").local_path(dir), None);
+    }
+
+    #[test]
+    fn download_templates() {
+        let mut raw = raw();
+        raw["title"] = "Bellevue / Avenue".into();
+        raw["track"] = 1.into();
+        raw.as_object_mut().unwrap().remove("album");
+        let song = serde_json::from_value::<Song>(raw).unwrap();
+
+        assert_eq!(
+            song.fill_template("{track} - {title}.{suffix}").unwrap(),
+            "01 - Bellevue _ Avenue.mp3"
+        );
+        assert_eq!(song.fill_template("{album}").unwrap(), "Unknown Album");
+        assert!(song.fill_template("{composer}").is_err());
+        assert!(song.fill_template("{title").is_err());
+        assert!(song.fill_template("{disc}").is_err());
+    }
+
+    #[test]
+    fn radio_window() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let song = |id: u64| {
+            let mut raw = raw();
+            raw["id"] = id.to_string().into();
+            serde_json::from_value::<Song>(raw).unwrap()
+        };
+
+        let mut radio = radio(&client, RadioConstraints::default()).with_window(2);
+        radio.queue.extend((1..=3).map(song));
+        assert!(radio.is_recent(&song(3)));
+
+        assert_eq!(radio.by_ref().take(3).filter(Result::is_ok).count(), 3);
+        assert!(!radio.is_recent(&song(1)));
+        assert!(radio.is_recent(&song(2)));
+        assert!(radio.is_recent(&song(3)));
+    }
+
+    #[test]
+    fn equality_and_order() {
+        use crate::sort;
+        use std::collections::HashSet;
+
+        let song = |id: &str, disc: Option<u64>, track: u64| {
+            let mut raw = raw();
+            raw["id"] = id.into();
+            raw["discNumber"] = disc.into();
+            raw["track"] = track.into();
+            serde_json::from_value::<Song>(raw).unwrap()
+        };
+
+        let mut renamed = song("27", None, 1);
+        renamed.title = "Bellevue Avenue (Live)".into();
+        assert_eq!(song("27", None, 1), renamed);
+        assert_ne!(song("27", None, 1), song("28", None, 1));
+
+        let mut songs = [
+            song("3", Some(2), 1),
+            song("4", None, 1),
+            song("2", Some(1), 2),
+            song("1", Some(1), 1),
+            song("2", Some(1), 2),
+        ];
+        let unique = songs.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 4);
+
+        songs.sort_by(sort::song_order);
+        let ids = songs.iter().map(|s| s.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["1", "2", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn scrobble_time() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_500_000_000_123);
+        assert_eq!(epoch_millis(time), 1_500_000_000_123);
+        assert_eq!(epoch_millis(UNIX_EPOCH - Duration::from_secs(1)), 0);
+    }
+
+    #[test]
+    fn song_related_ids() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        assert_eq!(parsed.album_id(), Some(&AlbumId::from(1)));
+        assert_eq!(parsed.artist_id(), Some(&ArtistId::from(1)));
+
+        let mut raw = raw();
+        raw.as_object_mut().unwrap().remove("albumId");
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        assert!(parsed.album(&client).unwrap().is_none());
+    }
 
     #[test]
     fn parse_song() {
         let parsed = serde_json::from_value::<Song>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 27);
+        assert_eq!(parsed.id.as_str(), "27");
         assert_eq!(parsed.title, String::from("Bellevue Avenue"));
         assert_eq!(parsed.track, Some(1));
     }
 
+    #[test]
+    fn song_round_trip() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        test_util::assert_round_trip(&parsed);
+
+        let mut raw = raw();
+        raw["genres"] = serde_json::json!([{ "name": "Electronic" }]);
+        raw["artists"] = serde_json::json!([{ "id": "1", "name": "Misteur Valaire" }]);
+        raw["replayGain"] = serde_json::json!({ "trackGain": -6.0 });
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        test_util::assert_round_trip(&parsed);
+    }
+
+    #[test]
+    fn song_format() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+
+        assert_eq!(parsed.format(), AudioFormat::Mp3);
+        assert_eq!(parsed.transcoded_format(), None);
+    }
+
+    #[test]
+    fn filter_music_only() {
+        let mut podcast = raw();
+        podcast["type"] = serde_json::json!("podcast");
+        let songs = vec![
+            serde_json::from_value::<Song>(raw()).unwrap(),
+            serde_json::from_value::<Song>(podcast).unwrap(),
+        ];
+
+        assert_eq!(songs[1].media_type, MediaType::Podcast);
+        let music = songs.music_only();
+        assert_eq!(music.len(), 1);
+        assert_eq!(music[0].media_type, MediaType::Music);
+    }
+
+    #[test]
+    fn parse_song_details() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+
+        assert_eq!(&*parsed.suffix, "mp3");
+        assert_eq!(&*parsed.content_type, "audio/mpeg");
+        assert_eq!(parsed.bit_rate, Some(216));
+        assert_eq!(parsed.disc_number, None);
+        assert_eq!(parsed.parent.as_deref(), Some("25"));
+        assert!(!parsed.is_video);
+        assert_eq!(parsed.play_count, Some(706));
+        assert_eq!(parsed.created, "2017-03-12T11:07:27.000Z");
+        assert_eq!(
+            parsed.starred,
+            Some(String::from("2017-06-01T19:48:25.635Z"))
+        );
+        assert_eq!(parsed.user_rating, None);
+    }
+
+    #[test]
+    fn parse_song_musicbrainz_id() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        assert_eq!(parsed.musicbrainz_id, None);
+
+        let mut raw = raw();
+        raw["musicBrainzId"] = "b9c0a4f5-5d5c-4b0e-8a43-1ad4fb8ed4b8".into();
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        assert_eq!(
+            parsed.musicbrainz_id,
+            Some(String::from("b9c0a4f5-5d5c-4b0e-8a43-1ad4fb8ed4b8"))
+        );
+    }
+
+    #[test]
+    fn parse_multiple_artists() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        assert!(parsed.artists.is_empty());
+        assert_eq!(parsed.display_artist, None);
+
+        let mut raw = raw();
+        raw["artists"] = serde_json::json!([
+            { "id": "1", "name": "Misteur Valaire" },
+            { "id": "4", "name": "Milk & Bone" }
+        ]);
+        raw["displayArtist"] = "Misteur Valaire feat. Milk & Bone".into();
+        raw["albumArtists"] = serde_json::json!([
+            { "id": "1", "name": "Misteur Valaire" }
+        ]);
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+
+        assert_eq!(parsed.artists.len(), 2);
+        assert_eq!(parsed.artists[1].id, Some(ArtistId::from(4)));
+        assert_eq!(&*parsed.album_artists[0].name, "Misteur Valaire");
+        assert_eq!(
+            parsed.display_artist.as_deref(),
+            Some("Misteur Valaire feat. Milk & Bone")
+        );
+        assert_eq!(parsed.artist.as_deref(), Some("Misteur Valaire"));
+    }
+
+    #[test]
+    fn parse_genres() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        assert!(parsed.genres.is_empty());
+        assert!(parsed.all_genres().is_empty());
+        assert_eq!(parsed.raw_genre(), Some("(255)"));
+
+        let mut decoded = raw();
+        decoded["genre"] = "(52)".into();
+        let parsed = serde_json::from_value::<Song>(decoded).unwrap();
+        assert_eq!(parsed.genre.as_deref(), Some("Electronic"));
+        test_util::assert_round_trip(&parsed);
+
+        let mut raw = raw();
+        raw["genre"] = "Electronic".into();
+        raw["genres"] = serde_json::json!([
+            { "name": "Electronic" },
+            { "name": "Hip-Hop" }
+        ]);
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        assert_eq!(parsed.all_genres(), vec!["Electronic", "Hip-Hop"]);
+    }
+
+    #[test]
+    fn parse_replay_gain() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+        assert!(parsed.replay_gain.is_none());
+
+        let mut raw = raw();
+        raw["replayGain"] = serde_json::json!({
+            "trackGain": -6.0,
+            "trackPeak": 0.98,
+            "albumPeak": 1.0
+        });
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        let gain = parsed.replay_gain.unwrap();
+
+        assert_eq!(gain.track_gain, Some(-6.0));
+        assert_eq!(gain.album_gain, None);
+        assert_eq!(gain.track_peak, Some(0.98));
+        assert_eq!(gain.track_factor(), gain.album_factor());
+        assert!((gain.track_factor().unwrap() - 0.501).abs() < 0.001);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn song_timestamps() {
+        let parsed = serde_json::from_value::<Song>(raw()).unwrap();
+
+        let created = parsed.created_at().unwrap();
+        let starred = parsed.starred_at().unwrap();
+        assert!(created < starred);
+    }
+
+    #[test]
+    fn parse_lyrics() {
+        let lyrics = serde_json::from_value::<Lyrics>(serde_json::json!({
+            "artist": "Misteur Valaire",
+            "value": "Bellevue, Bellevue"
+        }))
+        .unwrap();
+
+        assert_eq!(lyrics.artist, "Misteur Valaire");
+        assert_eq!(lyrics.title, "");
+        assert_eq!(lyrics.to_string(), "Bellevue, Bellevue");
+    }
+
+    #[test]
+    fn parse_structured_lyrics() {
+        let lyrics = serde_json::from_value::<StructuredLyrics>(serde_json::json!({
+            "displayArtist": "Misteur Valaire",
+            "displayTitle": "Bellevue Avenue",
+            "lang": "eng",
+            "offset": 250,
+            "synced": true,
+            "line": [
+                { "start": 0, "value": "Bellevue" },
+                { "start": 2000, "value": "Bellevue Avenue" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(lyrics.lines[1].start, Duration::from_secs(2));
+        assert_eq!(
+            lyrics.line_at(Duration::from_millis(1500)).unwrap().text,
+            "Bellevue"
+        );
+        assert_eq!(
+            lyrics.line_at(Duration::from_millis(1800)).unwrap().text,
+            "Bellevue Avenue"
+        );
+        test_util::assert_round_trip(&lyrics);
+
+        let plain = Lyrics::from(lyrics);
+        assert_eq!(plain.text, "Bellevue\nBellevue Avenue");
+        assert_eq!(plain.artist, "Misteur Valaire");
+    }
+
     #[test]
     fn get_hls() {
         let mut srv = test_util::demo_site().unwrap();
@@ -469,6 +2094,61 @@ mod tests {
         assert_eq!(hls.len(), 20)
     }
 
+    #[test]
+    fn get_directory() {
+        let srv = test_util::demo_site().unwrap();
+        let mut song = serde_json::from_value::<Song>(raw()).unwrap();
+
+        let dir = song.directory(&srv).unwrap().unwrap();
+        assert_eq!(dir.name, "Bellevue");
+        assert!(dir.folders().unwrap().is_empty());
+        assert_eq!(dir.songs(&srv).unwrap()[0], song);
+
+        song.parent = None;
+        assert!(song.directory(&srv).unwrap().is_none());
+    }
+
+    #[test]
+    fn page_genre_variants() {
+        use crate::GenreMap;
+
+        let songs = |genre: &str, ids: &[&str]| {
+            let mut raw = raw();
+            let song = ids
+                .iter()
+                .map(|id| {
+                    raw["id"] = (*id).into();
+                    raw["genre"] = genre.into();
+                    raw.clone()
+                })
+                .collect::<Vec<_>>();
+            let args = Query::with("genre", genre)
+                .arg("count", 500)
+                .arg("offset", 0)
+                .build();
+            let body = serde_json::json!({ "songsByGenre": { "song": song } });
+            ("getSongsByGenre", args, body)
+        };
+        let genre =
+            |name: &str| serde_json::json!({ "name": name, "songCount": 2, "albumCount": 1 });
+        let genres = serde_json::json!({
+            "genres": { "genre": [genre("Hip Hop"), genre("Jazz"), genre("Hip-Hop")] },
+        });
+        let client = test_util::replay_client(&[
+            ("getGenres", Query::none(), genres),
+            songs("Hip Hop", &["1", "2"]),
+            songs("Hip-Hop", &["3", "4"]),
+        ])
+        .with_genre_map(GenreMap::new());
+        let page = SearchPage {
+            offset: 1,
+            count: 2,
+        };
+        let songs = Song::list_in_genre(&client, "hip hop", page, None).unwrap();
+        let ids = songs.iter().map(|s| s.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["2", "3"]);
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
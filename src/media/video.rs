@@ -1,18 +1,24 @@
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::ser::{Serialize, Serializer};
 use std::result;
+use std::time::Duration;
 
+use crate::endpoint::{GetVideoInfo, GetVideos};
 use crate::query::Query;
-use crate::{Client, Error, Media, Result, Streamable};
+use crate::{
+    Bitrate, Client, CoverId, Downloadable, Error, HasCoverArt, MediaType, RawResponse, Result,
+    Streamable,
+};
+use url::Url;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Video {
     pub id: usize,
     parent: usize,
     is_dir: bool,
     pub title: String,
     pub album: Option<String>,
-    cover_id: Option<String>,
+    cover_id: Option<CoverId>,
     pub size: usize,
     content_type: String,
     suffix: String,
@@ -24,11 +30,11 @@ pub struct Video {
     is_video: bool,
     created: String,
     play_count: Option<u64>,
-    pub media_type: String,
+    pub media_type: MediaType,
     bookmark_position: Option<u64>,
     original_height: Option<u64>,
     original_width: Option<u64>,
-    stream_br: Option<usize>,
+    stream_br: Option<Bitrate>,
     stream_size: Option<(usize, usize)>,
     stream_offset: usize,
     stream_tc: Option<String>,
@@ -43,31 +49,38 @@ impl Video {
     }
 
     pub fn list(client: &Client) -> Result<Vec<Video>> {
-        let video = client.get("getVideos", Query::none())?;
-        Ok(get_list_as!(video, Video))
+        client.call(&GetVideos)
     }
 
     pub fn info<'a, S>(&self, client: &Client, format: S) -> Result<VideoInfo>
     where
         S: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
-            .arg("format", format.into())
-            .build();
-        let res = client.get("getVideoInfo", args)?;
-        Ok(serde_json::from_value(res)?)
+        client.call(&GetVideoInfo {
+            id: self.id,
+            format: format.into().map(String::from),
+        })
+    }
+
+    /// Returns the length of the video.
+    ///
+    /// The raw number of seconds is available as the `duration` field.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration as u64)
     }
 
     /// Returns the raw video captions.
-    pub fn captions<'a, S>(&self, client: &Client, format: S) -> Result<String>
+    ///
+    /// The format of the captions, such as `"text/vtt"`, is given by the
+    /// content type of the response.
+    pub fn captions<'a, S>(&self, client: &Client, format: S) -> Result<RawResponse>
     where
         S: Into<Option<&'a str>>,
     {
         let args = Query::with("id", self.id)
             .arg("format", format.into())
             .build();
-        let res = client.get_raw("getCaptions", args)?;
-        Ok(res)
+        client.get_raw("getCaptions", args)
     }
 
     /// Sets the size that the video will stream at, measured in pixels.
@@ -98,7 +111,7 @@ impl Streamable for Video {
         client.get_bytes("stream", args)
     }
 
-    fn stream_url(&self, client: &Client) -> Result<String> {
+    fn stream_url(&self, client: &Client) -> Result<Url> {
         let args = Query::with("id", self.id)
             .arg("maxBitRate", self.stream_br)
             .arg(
@@ -110,21 +123,13 @@ impl Streamable for Video {
         client.build_url("stream", args)
     }
 
-    fn download(&self, client: &Client) -> Result<Vec<u8>> {
-        client.get_bytes("download", Query::with("id", self.id))
-    }
-
-    fn download_url(&self, client: &Client) -> Result<String> {
-        client.build_url("download", Query::with("id", self.id))
-    }
-
     fn encoding(&self) -> &str {
         self.transcoded_content_type
             .as_ref()
             .unwrap_or(&self.content_type)
     }
 
-    fn set_max_bit_rate(&mut self, bit_rate: usize) {
+    fn set_max_bit_rate(&mut self, bit_rate: Bitrate) {
         self.stream_br = Some(bit_rate);
     }
 
@@ -133,36 +138,24 @@ impl Streamable for Video {
     }
 }
 
-impl Media for Video {
-    fn has_cover_art(&self) -> bool {
-        self.cover_id.is_some()
-    }
-
-    fn cover_id(&self) -> Option<&str> {
-        self.cover_id.as_ref().map(|s| s.as_str())
+impl Downloadable for Video {
+    fn download(&self, client: &Client) -> Result<Vec<u8>> {
+        client.get_bytes("download", Query::with("id", self.id))
     }
 
-    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.get_bytes("getCoverArt", query)
+    fn download_url(&self, client: &Client) -> Result<Url> {
+        client.build_url("download", Query::with("id", self.id))
     }
+}
 
-    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
-        let cover = self
-            .cover_id()
-            .ok_or_else(|| Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+impl HasCoverArt for Video {
+    fn cover_id(&self) -> Option<&CoverId> {
+        self.cover_id.as_ref()
     }
 }
 
 impl<'de> Deserialize<'de> for Video {
-    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -174,7 +167,7 @@ impl<'de> Deserialize<'de> for Video {
             is_dir: bool,
             title: String,
             album: Option<String>,
-            cover_art: Option<String>,
+            cover_art: Option<CoverId>,
             size: usize,
             content_type: String,
             suffix: String,
@@ -187,7 +180,7 @@ impl<'de> Deserialize<'de> for Video {
             play_count: Option<u64>,
             created: String,
             #[serde(rename = "type")]
-            media_type: String,
+            media_type: MediaType,
             bookmark_position: Option<u64>,
             original_height: Option<u64>,
             original_width: Option<u64>,
@@ -225,6 +218,65 @@ impl<'de> Deserialize<'de> for Video {
     }
 }
 
+impl Serialize for Video {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Video<'a> {
+            id: String,
+            parent: String,
+            is_dir: bool,
+            title: &'a str,
+            album: Option<&'a str>,
+            cover_art: Option<&'a CoverId>,
+            size: usize,
+            content_type: &'a str,
+            suffix: &'a str,
+            transcoded_suffix: Option<&'a str>,
+            transcoded_content_type: Option<&'a str>,
+            duration: usize,
+            bit_rate: usize,
+            path: &'a str,
+            is_video: bool,
+            play_count: Option<u64>,
+            created: &'a str,
+            #[serde(rename = "type")]
+            media_type: &'a MediaType,
+            bookmark_position: Option<u64>,
+            original_height: Option<u64>,
+            original_width: Option<u64>,
+        }
+
+        _Video {
+            id: self.id.to_string(),
+            parent: self.parent.to_string(),
+            is_dir: self.is_dir,
+            title: &self.title,
+            album: self.album.as_deref(),
+            cover_art: self.cover_id.as_ref(),
+            size: self.size,
+            content_type: &self.content_type,
+            suffix: &self.suffix,
+            transcoded_suffix: self.transcoded_suffix.as_deref(),
+            transcoded_content_type: self.transcoded_content_type.as_deref(),
+            duration: self.duration,
+            bit_rate: self.bitrate,
+            path: &self.path,
+            is_video: self.is_video,
+            play_count: self.play_count,
+            created: &self.created,
+            media_type: &self.media_type,
+            bookmark_position: self.bookmark_position,
+            original_height: self.original_height,
+            original_width: self.original_width,
+        }
+        .serialize(se)
+    }
+}
+
 #[derive(Debug)]
 pub struct VideoInfo {
     pub id: usize,
@@ -257,6 +309,30 @@ impl<'de> Deserialize<'de> for VideoInfo {
     }
 }
 
+impl Serialize for VideoInfo {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _VideoInfo<'a> {
+            id: String,
+            captions: Option<&'a Captions>,
+            #[serde(rename = "audioTrack")]
+            audio_tracks: &'a [AudioTrack],
+            conversion: Option<&'a Conversion>,
+        }
+
+        _VideoInfo {
+            id: self.id.to_string(),
+            captions: self.captions.as_ref(),
+            audio_tracks: &self.audio_tracks,
+            conversion: self.conversion.as_ref(),
+        }
+        .serialize(se)
+    }
+}
+
 #[derive(Debug)]
 pub struct AudioTrack {
     pub id: usize,
@@ -285,6 +361,28 @@ impl<'de> Deserialize<'de> for AudioTrack {
     }
 }
 
+impl Serialize for AudioTrack {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _AudioTrack<'a> {
+            id: String,
+            name: &'a str,
+            #[serde(rename = "languageCode")]
+            language_code: &'a str,
+        }
+
+        _AudioTrack {
+            id: self.id.to_string(),
+            name: &self.name,
+            language_code: &self.language_code,
+        }
+        .serialize(se)
+    }
+}
+
 #[derive(Debug)]
 pub struct Captions {
     pub id: usize,
@@ -309,6 +407,25 @@ impl<'de> Deserialize<'de> for Captions {
     }
 }
 
+impl Serialize for Captions {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Captions<'a> {
+            id: String,
+            name: &'a str,
+        }
+
+        _Captions {
+            id: self.id.to_string(),
+            name: &self.name,
+        }
+        .serialize(se)
+    }
+}
+
 #[derive(Debug)]
 pub struct Conversion {
     pub id: usize,
@@ -334,9 +451,30 @@ impl<'de> Deserialize<'de> for Conversion {
     }
 }
 
+impl Serialize for Conversion {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Conversion {
+            id: String,
+            #[serde(rename = "bitRate")]
+            bitrate: String,
+        }
+
+        _Conversion {
+            id: self.id.to_string(),
+            bitrate: self.bitrate.to_string(),
+        }
+        .serialize(se)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util;
 
     #[test]
     fn parse_video() {
@@ -355,6 +493,15 @@ mod tests {
         assert_eq!(parsed.audio_tracks.len(), 5);
     }
 
+    #[test]
+    fn video_round_trip() {
+        let parsed = serde_json::from_value::<Video>(raw()).unwrap();
+        test_util::assert_round_trip(&parsed);
+
+        let info = serde_json::from_value::<VideoInfo>(raw_info()).unwrap();
+        test_util::assert_round_trip(&info);
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
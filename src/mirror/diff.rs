@@ -0,0 +1,176 @@
+//! Comparing two snapshots of a library.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use super::Mirror;
+use crate::{Result, Song, SongId};
+
+/// How songs of two mirrors are matched up when diffing them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchBy {
+    /// Songs with the same ID are the same song. Use this to compare two
+    /// snapshots of the same server.
+    Id,
+    /// Songs with the same artist, album, disc, track number and title,
+    /// ignoring case, are the same song. Use this to compare two servers,
+    /// which assign their own IDs, such as after migrating from Subsonic to
+    /// Navidrome.
+    Tags,
+}
+
+/// The songs that differ between two mirrors, as found by [`Mirror::diff`].
+///
+/// [`Mirror::diff`]: struct.Mirror.html#method.diff
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// Songs only in the newer mirror, by their ID there.
+    pub added: Vec<SongId>,
+    /// Songs only in the older mirror, by their ID there.
+    pub removed: Vec<SongId>,
+    /// Songs in both mirrors whose tags differ, by their ID in the older
+    /// mirror and then in the newer one.
+    pub retagged: Vec<(SongId, SongId)>,
+}
+
+impl SnapshotDiff {
+    /// Returns whether the mirrors hold the same songs with the same tags.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.retagged.is_empty()
+    }
+}
+
+impl Mirror {
+    /// Saves a snapshot of the mirror as JSON.
+    ///
+    /// The writer isn't buffered; wrap files in a `BufWriter`.
+    pub fn save<W: Write>(&self, writer: W) -> Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Loads a snapshot saved with [`save`].
+    ///
+    /// [`save`]: #method.save
+    pub fn load<R: Read>(reader: R) -> Result<Mirror> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Compares the songs of the mirror with those of a newer one.
+    ///
+    /// Besides the songs added and removed, the songs whose title, artists,
+    /// album, track or disc number, year, genres or MusicBrainz ID changed are
+    /// reported as retagged. Play counts, stars and ratings are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate sunk;
+    /// use std::fs::File;
+    /// use sunk::mirror::{MatchBy, Mirror};
+    /// use sunk::Client;
+    ///
+    /// # fn run() -> sunk::Result<()> {
+    /// let subsonic = Mirror::load(File::open("subsonic.json")?)?;
+    /// let navidrome = Client::new("http://navidrome.example.com", "admin", "hunter2")?;
+    ///
+    /// let diff = subsonic.diff(&Mirror::sync(&navidrome)?, MatchBy::Tags);
+    /// for id in &diff.removed {
+    ///     println!("missing after migration: {}", subsonic.song(id).unwrap());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    pub fn diff(&self, newer: &Mirror, by: MatchBy) -> SnapshotDiff {
+        let key = |song: &Song| match by {
+            MatchBy::Id => song.id.to_string(),
+            MatchBy::Tags => tag_key(song),
+        };
+        let old = self
+            .songs
+            .iter()
+            .map(|s| (key(s), s))
+            .collect::<HashMap<_, _>>();
+        let new = newer
+            .songs
+            .iter()
+            .map(|s| (key(s), s))
+            .collect::<HashMap<_, _>>();
+
+        let mut diff = SnapshotDiff::default();
+        for song in &newer.songs {
+            match old.get(&key(song)) {
+                None => diff.added.push(song.id.clone()),
+                Some(old) if !same_tags(old, song) => {
+                    diff.retagged.push((old.id.clone(), song.id.clone()))
+                }
+                Some(_) => (),
+            }
+        }
+        for song in &self.songs {
+            if !new.contains_key(&key(song)) {
+                diff.removed.push(song.id.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// Returns the tags that identify a song across servers.
+fn tag_key(song: &Song) -> String {
+    format!(
+        "{}\u{0}{}\u{0}{}\u{0}{}\u{0}{}",
+        song.artist.as_deref().unwrap_or("").to_lowercase(),
+        song.album.as_deref().unwrap_or("").to_lowercase(),
+        song.disc_number.unwrap_or(1),
+        song.track.unwrap_or(0),
+        song.title.to_lowercase()
+    )
+}
+
+/// Returns whether two versions of a song are tagged the same.
+fn same_tags(a: &Song, b: &Song) -> bool {
+    a.title == b.title
+        && a.artist == b.artist
+        && a.album == b.album
+        && a.display_album_artist == b.display_album_artist
+        && a.track == b.track
+        && a.disc_number == b.disc_number
+        && a.year == b.year
+        && a.all_genres() == b.all_genres()
+        && a.musicbrainz_id == b.musicbrainz_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::tests::mirror;
+
+    #[test]
+    fn diff_snapshots() {
+        let old = mirror();
+        assert!(old.diff(&mirror(), MatchBy::Id).is_empty());
+
+        let mut new = mirror();
+        new.songs[0].year = Some(2017);
+        new.songs[1].id = SongId::from(13);
+        let diff = old.diff(&new, MatchBy::Id);
+        assert_eq!(diff.added, [SongId::from(13)]);
+        assert_eq!(diff.removed, [SongId::from(11)]);
+        assert_eq!(diff.retagged, [(SongId::from(12), SongId::from(12))]);
+
+        // Another server's IDs don't matter when matching by tags.
+        let diff = old.diff(&new, MatchBy::Tags);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.retagged, [(SongId::from(12), SongId::from(12))]);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut saved = Vec::new();
+        mirror().save(&mut saved).unwrap();
+        let loaded = Mirror::load(&saved[..]).unwrap();
+        assert!(mirror().diff(&loaded, MatchBy::Id).is_empty());
+    }
+}
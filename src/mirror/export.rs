@@ -0,0 +1,198 @@
+//! Exporting the metadata of the library.
+
+use std::borrow::Borrow;
+use std::io::Write;
+
+use super::Mirror;
+use crate::{Result, Song};
+
+/// The format to export song metadata in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line, holding everything the server sent about a
+    /// song, in the same form as serializing the [`Song`].
+    ///
+    /// [`Song`]: ../song/struct.Song.html
+    JsonLines,
+    /// Comma-separated values with a header row, holding the most useful
+    /// fields of each song; see [`CSV_COLUMNS`].
+    ///
+    /// [`CSV_COLUMNS`]: constant.CSV_COLUMNS.html
+    Csv,
+}
+
+/// The columns of a CSV export.
+pub const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "artist",
+    "album",
+    "album_artist",
+    "disc",
+    "track",
+    "year",
+    "genre",
+    "duration",
+    "bit_rate",
+    "size",
+    "suffix",
+    "path",
+    "created",
+    "starred",
+    "user_rating",
+    "play_count",
+    "musicbrainz_id",
+    "album_id",
+    "artist_id",
+];
+
+impl Mirror {
+    /// Writes the metadata of every song in the mirror to `writer`.
+    ///
+    /// The writer isn't buffered; wrap files in a `BufWriter`.
+    pub fn export<W: Write>(&self, format: ExportFormat, writer: W) -> Result<()> {
+        export_songs(self.songs.iter().map(Ok), format, writer)
+    }
+}
+
+/// Writes the metadata of songs to `writer` as they arrive, without holding
+/// them all in memory.
+///
+/// Use this over [`Mirror::export`] for libraries too large to mirror, with
+/// [`library::all_songs`]. Stops at the first error.
+///
+/// [`Mirror::export`]: struct.Mirror.html#method.export
+/// [`library::all_songs`]: ../library/fn.all_songs.html
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use std::fs::File;
+/// use std::io::BufWriter;
+/// use sunk::mirror::{self, ExportFormat};
+/// use sunk::{library, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// let file = BufWriter::new(File::create("library.csv")?);
+/// mirror::export_songs(library::all_songs(&client), ExportFormat::Csv, file)?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn export_songs<S, I, W>(songs: I, format: ExportFormat, mut writer: W) -> Result<()>
+where
+    S: Borrow<Song>,
+    I: IntoIterator<Item = Result<S>>,
+    W: Write,
+{
+    if format == ExportFormat::Csv {
+        write_row(&mut writer, CSV_COLUMNS.iter().map(|c| c.to_string()))?;
+    }
+    for song in songs {
+        let song = song?;
+        let song = song.borrow();
+        match format {
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut writer, song)?;
+                writer.write_all(b"\n")?;
+            }
+            ExportFormat::Csv => write_row(&mut writer, csv_fields(song))?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns the fields of a song in the order of `CSV_COLUMNS`.
+fn csv_fields(song: &Song) -> impl Iterator<Item = String> {
+    fn opt<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    vec![
+        song.id.to_string(),
+        song.title.clone(),
+        opt(song.artist.as_deref()),
+        opt(song.album.as_deref()),
+        opt(song.display_album_artist.as_deref()),
+        opt(song.disc_number),
+        opt(song.track),
+        opt(song.year),
+        song.all_genres().join("; "),
+        opt(song.duration),
+        opt(song.bit_rate),
+        song.size.to_string(),
+        song.suffix.to_string(),
+        song.path().to_string(),
+        song.created.clone(),
+        opt(song.starred.as_deref()),
+        opt(song.user_rating),
+        opt(song.play_count),
+        opt(song.musicbrainz_id.as_deref()),
+        opt(song.album_id()),
+        opt(song.artist_id()),
+    ]
+    .into_iter()
+}
+
+/// Writes a row of CSV, quoting fields that need it.
+fn write_row<W, I>(writer: &mut W, fields: I) -> Result<()>
+where
+    W: Write,
+    I: Iterator<Item = String>,
+{
+    let row = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    writer.write_all(row.as_bytes())?;
+    writer.write_all(b"\r\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::tests::mirror;
+
+    #[test]
+    fn export_csv() {
+        let mut mirror = mirror();
+        mirror.songs[0].title = "Interlude, \"Live\"".into();
+
+        let mut out = Vec::new();
+        mirror.export(ExportFormat::Csv, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].split(',').count(), CSV_COLUMNS.len());
+        assert!(lines[1].starts_with("12,\"Interlude, \"\"Live\"\"\",Misteur Valaire,Bellevue,"));
+    }
+
+    #[test]
+    fn export_json_lines() {
+        let mut out = Vec::new();
+        mirror().export(ExportFormat::JsonLines, &mut out).unwrap();
+
+        let songs = out
+            .split(|&b| b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| serde_json::from_slice::<Song>(l).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(songs.len(), 2);
+        assert_eq!(songs[1].title, "Bellevue Avenue");
+    }
+}
@@ -0,0 +1,833 @@
+//! A local copy of the library.
+//!
+//! A [`Mirror`] holds every artist, album, song and playlist on the server.
+//! Once it has been synced, looking things up and searching in it never
+//! touch the network, so a client can start instantly from a mirror saved on
+//! a previous run, and keep working while offline.
+//! [`Mirror::update_in_background`] brings the saved mirror up to date
+//! meanwhile.
+//!
+//! [`Mirror`]: struct.Mirror.html
+//! [`Mirror::update_in_background`]: struct.Mirror.html#method.update_in_background
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::mirror::Mirror;
+//! use sunk::search::SearchPage;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! let mirror = Mirror::sync(&client)?;
+//! for album in mirror.albums_in_genre("Jazz") {
+//!     println!("{}", album);
+//! }
+//!
+//! // Searches work as with `Client::search`, and forgive typos.
+//! let page = SearchPage::new();
+//! let result = mirror.search("misteur valare", page, page, page);
+//!
+//! // Mirrors can be saved, and loaded on the next run.
+//! mirror.save_file("library.json")?;
+//! let mut mirror = Mirror::open("library.json")?.unwrap();
+//!
+//! // Only what changed since is fetched again.
+//! let diff = mirror.refresh(&client)?;
+//! println!("{} new albums", diff.albums.added.len());
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::result;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod diff;
+mod export;
+mod persist;
+mod search;
+
+pub use self::diff::{MatchBy, SnapshotDiff};
+pub use self::export::{export_songs, ExportFormat, CSV_COLUMNS};
+use self::search::SearchIndex;
+use crate::collections::{get_albums, get_playlists};
+use crate::endpoint::GetIndexes;
+use crate::progress::{self, Progress, ProgressObserver};
+use crate::sort;
+use crate::timestamp;
+use crate::{
+    Album, AlbumId, Artist, ArtistId, Client, ListType, Playlist, PlaylistId, Result, Song, SongId,
+};
+
+/// A local copy of the artists, albums, songs and playlists on a server.
+///
+/// See the [module-level documentation](./index.html) for more information.
+#[derive(Debug)]
+pub struct Mirror {
+    synced: u64,
+    artists: Vec<Artist>,
+    albums: Vec<Album>,
+    songs: Vec<Song>,
+    playlists: Vec<(Playlist, Vec<SongId>)>,
+    artist_index: HashMap<ArtistId, usize>,
+    album_index: HashMap<AlbumId, usize>,
+    song_index: HashMap<SongId, usize>,
+    search_index: SearchIndex,
+}
+
+impl Mirror {
+    /// Copies the whole library from the server.
+    ///
+    /// Every artist is listed, then every album of each artist and every song
+    /// on each album; this takes a request per artist and per album, so can
+    /// take a while on large libraries.
+    pub fn sync(client: &Client) -> Result<Mirror> {
+        Mirror::sync_with_progress(client, ())
+    }
+
+    /// Copies the whole library from the server, as with [`sync`], telling
+    /// `observer` how many artists have been copied so far.
+    ///
+    /// The number of artists is known once they are listed; the playlists
+    /// are copied after the last artist.
+    ///
+    /// [`sync`]: #method.sync
+    pub fn sync_with_progress<O: ProgressObserver>(
+        client: &Client,
+        mut observer: O,
+    ) -> Result<Mirror> {
+        let mut progress = Progress::default();
+        observer.started(&progress);
+        let res = Mirror::sync_each(client, &mut progress, &mut observer);
+        progress::finish(&mut observer, &progress, res)
+    }
+
+    fn sync_each<O: ProgressObserver>(
+        client: &Client,
+        progress: &mut Progress,
+        observer: &mut O,
+    ) -> Result<Mirror> {
+        let synced = now();
+        let artists = Artist::list(client, None)?;
+        info!("Mirroring {} artists", artists.len());
+        progress.total_items = Some(artists.len() as u64);
+        observer.progress(progress);
+
+        let mut albums = Vec::new();
+        let mut songs = Vec::new();
+        for artist in &artists {
+            for album in artist.albums(client)? {
+                // Cloned before the songs are fetched, so that the album
+                // doesn't hold a second copy of them.
+                albums.push(album.clone());
+                songs.extend(album.songs(client)?);
+            }
+            progress.items += 1;
+            observer.progress(progress);
+        }
+
+        let mut playlists = Vec::new();
+        for playlist in get_playlists(client, None)? {
+            let mut ids = Vec::new();
+            playlist.for_each_song(client, |song| ids.push(song.id))?;
+            playlists.push((playlist, ids));
+        }
+
+        info!(
+            "Mirrored {} albums, {} songs and {} playlists",
+            albums.len(),
+            songs.len(),
+            playlists.len()
+        );
+        Ok(Mirror::from_parts(
+            synced, artists, albums, songs, playlists,
+        ))
+    }
+
+    /// Builds a mirror from its contents, indexing them by ID.
+    pub(crate) fn from_parts(
+        synced: u64,
+        artists: Vec<Artist>,
+        albums: Vec<Album>,
+        songs: Vec<Song>,
+        playlists: Vec<(Playlist, Vec<SongId>)>,
+    ) -> Mirror {
+        let mut mirror = Mirror {
+            synced,
+            artists,
+            albums,
+            songs,
+            playlists,
+            artist_index: HashMap::new(),
+            album_index: HashMap::new(),
+            song_index: HashMap::new(),
+            search_index: SearchIndex::default(),
+        };
+        mirror.reindex();
+        mirror
+    }
+
+    /// Brings the mirror up to date with the server, and returns what
+    /// changed.
+    ///
+    /// Rather than copying the whole library again, `getIndexes` is first
+    /// asked whether anything changed since the last sync. If so, the
+    /// artists are listed again, and the albums of artists that are new or
+    /// whose album count changed are listed too; albums added since the last
+    /// sync are found by their `created` timestamp in the list of newest
+    /// albums. Only the songs of new and changed albums are fetched.
+    /// Playlists are listed every time, and their songs fetched again if they
+    /// changed.
+    ///
+    /// Songs retagged on an album whose song count and duration stayed the
+    /// same aren't noticed; [`sync`] again to catch those.
+    ///
+    /// If a request fails, the mirror is left as it was.
+    ///
+    /// [`sync`]: #method.sync
+    pub fn refresh(&mut self, client: &Client) -> Result<MirrorDiff> {
+        let started = now();
+        let mut diff = MirrorDiff::default();
+
+        let playlists = self.fetch_playlists(client, &mut diff.playlists)?;
+        let library = if modified_since(client, self.synced)? {
+            Some(self.fetch_library(client, &mut diff)?)
+        } else {
+            None
+        };
+
+        self.playlists = playlists;
+        if let Some(library) = library {
+            for id in library.gone {
+                self.remove_album(&id, &mut diff.songs);
+            }
+            for (album, songs) in library.fetched {
+                self.replace_album(album, songs, &mut diff.songs);
+            }
+            self.artists = library.artists;
+            self.reindex();
+        }
+        self.synced = started;
+        Ok(diff)
+    }
+
+    /// Lists the playlists again, fetching the songs of those that changed.
+    fn fetch_playlists(
+        &self,
+        client: &Client,
+        changes: &mut Changes<PlaylistId>,
+    ) -> Result<Vec<(Playlist, Vec<SongId>)>> {
+        let mut playlists = Vec::new();
+        for playlist in get_playlists(client, None)? {
+            let old = self.playlists.iter().find(|(p, _)| p.id() == playlist.id());
+            let ids = match old {
+                Some((old, ids)) if old.changed() == playlist.changed() => ids.clone(),
+                _ => {
+                    if old.is_some() {
+                        changes.changed.push(playlist.id().clone());
+                    } else {
+                        changes.added.push(playlist.id().clone());
+                    }
+                    let mut ids = Vec::new();
+                    playlist.for_each_song(client, |song| ids.push(song.id))?;
+                    ids
+                }
+            };
+            playlists.push((playlist, ids));
+        }
+
+        for (old, _) in &self.playlists {
+            if !playlists.iter().any(|(p, _)| p.id() == old.id()) {
+                changes.removed.push(old.id().clone());
+            }
+        }
+        Ok(playlists)
+    }
+
+    /// Finds the artists and albums that changed since the last sync.
+    fn fetch_library(&self, client: &Client, diff: &mut MirrorDiff) -> Result<LibraryUpdate> {
+        let artists = Artist::list(client, None)?;
+        let mut stale = Vec::new();
+        for artist in &artists {
+            match self.artist(&artist.id) {
+                None => diff.artists.added.push(artist.id.clone()),
+                Some(old) if old.name != artist.name || old.album_count != artist.album_count => {
+                    diff.artists.changed.push(artist.id.clone())
+                }
+                Some(_) => continue,
+            }
+            stale.push(artist);
+        }
+        let ids = artists.iter().map(|a| &a.id).collect::<HashSet<_>>();
+        for old in &self.artists {
+            if !ids.contains(&old.id) {
+                diff.artists.removed.push(old.id.clone());
+            }
+        }
+
+        // The albums of removed artists go with them.
+        let mut gone = self
+            .albums
+            .iter()
+            .filter(|a| {
+                a.artist_id()
+                    .is_some_and(|id| diff.artists.removed.contains(id))
+            })
+            .map(|a| a.id.clone())
+            .collect::<Vec<_>>();
+
+        let mut fetch = Vec::new();
+        for artist in stale {
+            let albums = artist.albums(client)?;
+            for old in self.albums_by(&artist.id) {
+                if !albums.iter().any(|a| a.id == old.id) {
+                    gone.push(old.id.clone());
+                }
+            }
+            for album in albums {
+                match self.album(&album.id) {
+                    None => diff.albums.added.push(album.id.clone()),
+                    Some(old) if album_changed(old, &album) => {
+                        diff.albums.changed.push(album.id.clone())
+                    }
+                    Some(_) => continue,
+                }
+                fetch.push(album);
+            }
+        }
+
+        // Albums can be added to an artist without changing its album count,
+        // if one was removed at the same time.
+        for album in newest_since(client, self.synced)? {
+            if fetch.iter().any(|a| a.id == album.id) {
+                continue;
+            }
+            if self.album(&album.id).is_some() {
+                diff.albums.changed.push(album.id.clone());
+            } else {
+                diff.albums.added.push(album.id.clone());
+            }
+            fetch.push(album);
+        }
+        diff.albums.removed.extend(gone.iter().cloned());
+
+        let mut fetched = Vec::new();
+        for album in fetch {
+            let songs = album.songs(client)?;
+            fetched.push((album, songs));
+        }
+        Ok(LibraryUpdate {
+            artists,
+            gone,
+            fetched,
+        })
+    }
+
+    /// Removes an album and its songs.
+    fn remove_album(&mut self, id: &AlbumId, changes: &mut Changes<SongId>) {
+        self.albums.retain(|a| a.id != *id);
+        let (gone, kept) = self
+            .songs
+            .drain(..)
+            .partition::<Vec<_>, _>(|s| s.album_id() == Some(id));
+        changes.removed.extend(gone.into_iter().map(|s| s.id));
+        self.songs = kept;
+    }
+
+    /// Adds an album and its songs, replacing the album if the mirror already
+    /// has it.
+    fn replace_album(&mut self, album: Album, songs: Vec<Song>, changes: &mut Changes<SongId>) {
+        let old = self
+            .songs
+            .iter()
+            .filter(|s| s.album_id() == Some(&album.id))
+            .map(|s| (&s.id, s))
+            .collect::<HashMap<_, _>>();
+        for song in &songs {
+            match old.get(&song.id) {
+                None => changes.added.push(song.id.clone()),
+                Some(old) if !same_song(old, song) => changes.changed.push(song.id.clone()),
+                Some(_) => (),
+            }
+        }
+        for id in old.keys() {
+            if !songs.iter().any(|s| s.id == **id) {
+                changes.removed.push((*id).clone());
+            }
+        }
+
+        self.songs.retain(|s| s.album_id() != Some(&album.id));
+        self.songs.extend(songs);
+        match self.albums.iter().position(|a| a.id == album.id) {
+            Some(i) => self.albums[i] = album,
+            None => self.albums.push(album),
+        }
+    }
+
+    /// Rebuilds the lookup tables from IDs to positions, and the search
+    /// index.
+    fn reindex(&mut self) {
+        self.artist_index = index(&self.artists, |a| a.id.clone());
+        self.album_index = index(&self.albums, |a| a.id.clone());
+        self.song_index = index(&self.songs, |s| s.id.clone());
+        self.search_index = SearchIndex::build(self);
+    }
+
+    /// Returns when the mirror was last synced with the server.
+    pub fn synced_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.synced)
+    }
+
+    /// Returns every artist in the library.
+    pub fn artists(&self) -> &[Artist] {
+        &self.artists
+    }
+
+    /// Returns every album in the library.
+    pub fn albums(&self) -> &[Album] {
+        &self.albums
+    }
+
+    /// Returns every song in the library.
+    pub fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    /// Returns every playlist visible to the user.
+    ///
+    /// Playlists are listed without their songs; see [`playlist_songs`].
+    ///
+    /// [`playlist_songs`]: #method.playlist_songs
+    pub fn playlists(&self) -> impl Iterator<Item = &Playlist> {
+        self.playlists.iter().map(|(playlist, _)| playlist)
+    }
+
+    /// Looks up an artist by ID.
+    pub fn artist(&self, id: &ArtistId) -> Option<&Artist> {
+        self.artist_index.get(id).map(|&i| &self.artists[i])
+    }
+
+    /// Looks up an album by ID.
+    pub fn album(&self, id: &AlbumId) -> Option<&Album> {
+        self.album_index.get(id).map(|&i| &self.albums[i])
+    }
+
+    /// Looks up a song by ID.
+    pub fn song(&self, id: &SongId) -> Option<&Song> {
+        self.song_index.get(id).map(|&i| &self.songs[i])
+    }
+
+    /// Looks up a playlist by ID.
+    pub fn playlist(&self, id: &PlaylistId) -> Option<&Playlist> {
+        self.playlists().find(|p| p.id() == id)
+    }
+
+    /// Returns the artists with the given name, ignoring case.
+    pub fn artists_named(&self, name: &str) -> Vec<&Artist> {
+        self.artists
+            .iter()
+            .filter(|a| a.name.to_lowercase() == name.to_lowercase())
+            .collect()
+    }
+
+    /// Returns the albums with the given name, ignoring case.
+    pub fn albums_named(&self, name: &str) -> Vec<&Album> {
+        self.albums
+            .iter()
+            .filter(|a| a.name.to_lowercase() == name.to_lowercase())
+            .collect()
+    }
+
+    /// Returns the songs with the given title, ignoring case.
+    pub fn songs_named(&self, title: &str) -> Vec<&Song> {
+        self.songs
+            .iter()
+            .filter(|s| s.title.to_lowercase() == title.to_lowercase())
+            .collect()
+    }
+
+    /// Returns the albums of an artist, in the order of
+    /// [`sort::album_order`].
+    ///
+    /// [`sort::album_order`]: ../sort/fn.album_order.html
+    pub fn albums_by(&self, artist: &ArtistId) -> Vec<&Album> {
+        let mut albums = self
+            .albums
+            .iter()
+            .filter(|a| a.artist_id() == Some(artist))
+            .collect::<Vec<_>>();
+        albums.sort_by(|a, b| sort::album_order(a, b));
+        albums
+    }
+
+    /// Returns the songs on an album, by disc and track number.
+    pub fn album_songs(&self, album: &AlbumId) -> Vec<&Song> {
+        let mut songs = self
+            .songs
+            .iter()
+            .filter(|s| s.album_id() == Some(album))
+            .collect::<Vec<_>>();
+        songs.sort_by(|a, b| sort::song_order(a, b));
+        songs
+    }
+
+    /// Returns the songs in a playlist, in order. Returns `None` if there is
+    /// no such playlist.
+    ///
+    /// Songs the mirror doesn't have are left out.
+    pub fn playlist_songs(&self, playlist: &PlaylistId) -> Option<Vec<&Song>> {
+        let (_, ids) = self.playlists.iter().find(|(p, _)| p.id() == playlist)?;
+        Some(ids.iter().filter_map(|id| self.song(id)).collect())
+    }
+
+    /// Returns the albums in a genre, ignoring case.
+    pub fn albums_in_genre(&self, genre: &str) -> Vec<&Album> {
+        self.albums
+            .iter()
+            .filter(|a| {
+                a.genre
+                    .as_deref()
+                    .is_some_and(|g| g.eq_ignore_ascii_case(genre))
+            })
+            .collect()
+    }
+
+    /// Returns the songs in a genre, ignoring case.
+    ///
+    /// Songs with several genres are matched on any of them.
+    pub fn songs_in_genre(&self, genre: &str) -> Vec<&Song> {
+        self.songs
+            .iter()
+            .filter(|s| s.all_genres().iter().any(|g| g.eq_ignore_ascii_case(genre)))
+            .collect()
+    }
+}
+
+/// The changes to the library fetched by a refresh, before they are applied.
+struct LibraryUpdate {
+    /// Every artist on the server.
+    artists: Vec<Artist>,
+    /// The albums no longer on the server.
+    gone: Vec<AlbumId>,
+    /// The albums that are new or changed, with their songs.
+    fetched: Vec<(Album, Vec<Song>)>,
+}
+
+/// The items of one kind that a refresh added, removed or changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changes<I> {
+    /// Items new to the mirror.
+    pub added: Vec<I>,
+    /// Items no longer on the server.
+    pub removed: Vec<I>,
+    /// Items that were fetched again because they changed.
+    pub changed: Vec<I>,
+}
+
+impl<I> Changes<I> {
+    /// Returns whether nothing was added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<I> Default for Changes<I> {
+    fn default() -> Changes<I> {
+        Changes {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+/// What a [`Mirror::refresh`] changed.
+///
+/// [`Mirror::refresh`]: struct.Mirror.html#method.refresh
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorDiff {
+    /// The artists that changed.
+    pub artists: Changes<ArtistId>,
+    /// The albums that changed.
+    pub albums: Changes<AlbumId>,
+    /// The songs that changed.
+    pub songs: Changes<SongId>,
+    /// The playlists that changed.
+    pub playlists: Changes<PlaylistId>,
+}
+
+impl MirrorDiff {
+    /// Returns whether the refresh changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.artists.is_empty()
+            && self.albums.is_empty()
+            && self.songs.is_empty()
+            && self.playlists.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for Mirror {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct _Mirror {
+            synced: u64,
+            artists: Vec<Artist>,
+            albums: Vec<Album>,
+            songs: Vec<Song>,
+            playlists: Vec<(Playlist, Vec<SongId>)>,
+        }
+
+        let raw = _Mirror::deserialize(de)?;
+        Ok(Mirror::from_parts(
+            raw.synced,
+            raw.artists,
+            raw.albums,
+            raw.songs,
+            raw.playlists,
+        ))
+    }
+}
+
+impl Serialize for Mirror {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Mirror<'a> {
+            synced: u64,
+            artists: &'a [Artist],
+            albums: &'a [Album],
+            songs: &'a [Song],
+            playlists: &'a [(Playlist, Vec<SongId>)],
+        }
+
+        _Mirror {
+            synced: self.synced,
+            artists: &self.artists,
+            albums: &self.albums,
+            songs: &self.songs,
+            playlists: &self.playlists,
+        }
+        .serialize(se)
+    }
+}
+
+/// Maps the key of each item to its position.
+fn index<T, K, F>(items: &[T], key: F) -> HashMap<K, usize>
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&T) -> K,
+{
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (key(item), i))
+        .collect()
+}
+
+/// Asks the server whether the library changed after a number of seconds
+/// since the Unix epoch.
+///
+/// `getIndexes` answers with no artists if nothing changed. Servers that
+/// don't support it are assumed to have changed.
+fn modified_since(client: &Client, secs: u64) -> Result<bool> {
+    if !client.quirks().supports("getIndexes") {
+        return Ok(true);
+    }
+    let since = GetIndexes {
+        if_modified_since: Some(secs * 1000),
+        ..Default::default()
+    };
+    match client.call(&since) {
+        Ok(indexes) => Ok(!indexes.is_empty()),
+        Err(ref e) if e.api_error().is_some() => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// Lists the albums added to the server after a number of seconds since the
+/// Unix epoch, newest first.
+fn newest_since(client: &Client, secs: u64) -> Result<Vec<Album>> {
+    const PAGE: usize = 500;
+    let since = UNIX_EPOCH + Duration::from_secs(secs);
+
+    let mut albums = Vec::new();
+    for offset in (0..).step_by(PAGE) {
+        let page = get_albums(client, ListType::Newest, Some(PAGE), Some(offset), None)?;
+        let len = page.len();
+        let before = albums.len();
+        albums.extend(page.into_iter().take_while(|a| is_after(&a.created, since)));
+        if len < PAGE || albums.len() - before < len {
+            break;
+        }
+    }
+    Ok(albums)
+}
+
+/// Returns whether an ISO8601 timestamp is later than a time.
+///
+/// Only whole seconds are compared, as the time the mirror was last synced
+/// is kept to the second. A timestamp that can't be read is taken to be
+/// later, so that the album is fetched rather than missed.
+fn is_after(time: &str, since: SystemTime) -> bool {
+    let seconds = |t: SystemTime| t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    timestamp::parse_system(time).is_none_or(|t| seconds(t) > seconds(since))
+}
+
+/// Returns whether an album's listing differs enough from the mirrored one
+/// that its songs should be fetched again.
+fn album_changed(old: &Album, new: &Album) -> bool {
+    old.name != new.name
+        || old.song_count != new.song_count
+        || old.duration != new.duration
+        || old.year != new.year
+}
+
+/// Returns whether two versions of a song are identical.
+///
+/// Songs compare equal by ID alone, so this compares everything the server
+/// sent instead.
+fn same_song(a: &Song, b: &Song) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Returns the number of seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(super) fn mirror() -> Mirror {
+        let artist = json!({ "id": "1", "name": "Misteur Valaire", "albumCount": 1 });
+        let album = json!({
+            "id": "1", "name": "Bellevue", "artist": "Misteur Valaire", "artistId": "1",
+            "songCount": 2, "duration": 400, "created": "2017-03-12T11:07:25.000Z",
+            "genre": "Electronic"
+        });
+        let song = |id: &str, track: u64, title: &str| {
+            json!({
+                "id": id, "title": title, "album": "Bellevue", "artist": "Misteur Valaire",
+                "albumId": "1", "artistId": "1", "track": track, "genre": "Electronic",
+                "size": 1, "contentType": "audio/mpeg", "suffix": "mp3",
+                "path": format!("Misteur Valaire/Bellevue/{:02}.mp3", track),
+                "created": "2017-03-12T11:07:25.000Z", "type": "music", "isDir": false
+            })
+        };
+        let playlist = json!({
+            "id": "1", "name": "Sleep Hits", "owner": "user", "songCount": 1,
+            "duration": 200, "created": "2018-01-01T14:45:07.464Z",
+            "changed": "2018-01-01T14:45:07.478Z"
+        });
+
+        Mirror::from_parts(
+            1_500_000_000,
+            vec![serde_json::from_value(artist).unwrap()],
+            vec![serde_json::from_value(album).unwrap()],
+            vec![
+                serde_json::from_value(song("12", 2, "Interlude")).unwrap(),
+                serde_json::from_value(song("11", 1, "Bellevue Avenue")).unwrap(),
+            ],
+            vec![(
+                serde_json::from_value(playlist).unwrap(),
+                vec![SongId::from(12), SongId::from(404)],
+            )],
+        )
+    }
+
+    #[test]
+    fn lookups() {
+        let mirror = mirror();
+        assert_eq!(
+            &*mirror.artist(&ArtistId::from(1)).unwrap().name,
+            "Misteur Valaire"
+        );
+        assert!(mirror.album(&AlbumId::from(2)).is_none());
+        assert_eq!(mirror.song(&SongId::from(12)).unwrap().title, "Interlude");
+
+        assert_eq!(mirror.artists_named("misteur valaire").len(), 1);
+        assert_eq!(mirror.albums_named("BELLEVUE").len(), 1);
+        assert_eq!(mirror.songs_named("interlude").len(), 1);
+        assert_eq!(mirror.albums_by(&ArtistId::from(1)).len(), 1);
+        assert_eq!(mirror.albums_in_genre("electronic").len(), 1);
+        assert_eq!(mirror.songs_in_genre("Electronic").len(), 2);
+
+        let songs = mirror.album_songs(&AlbumId::from(1));
+        let titles = songs.iter().map(|s| s.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, ["Bellevue Avenue", "Interlude"]);
+
+        let playlist = mirror.playlist_songs(&PlaylistId::from(1)).unwrap();
+        assert_eq!(playlist.len(), 1);
+        assert!(mirror.playlist_songs(&PlaylistId::from(2)).is_none());
+    }
+
+    #[test]
+    fn replace_and_remove_albums() {
+        let mut mirror = mirror();
+        let mut songs = mirror
+            .album_songs(&AlbumId::from(1))
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let album = mirror.album(&AlbumId::from(1)).unwrap().clone();
+        songs[1].title = "Interlude (Remastered)".into();
+        songs.remove(0);
+
+        let mut changes = Changes::default();
+        mirror.replace_album(album, songs, &mut changes);
+        assert_eq!(changes.added, []);
+        assert_eq!(changes.removed, [SongId::from(11)]);
+        assert_eq!(changes.changed, [SongId::from(12)]);
+        assert_eq!(mirror.albums().len(), 1);
+        assert_eq!(mirror.songs().len(), 1);
+
+        let mut changes = Changes::default();
+        mirror.remove_album(&AlbumId::from(1), &mut changes);
+        assert_eq!(changes.removed, [SongId::from(12)]);
+        assert!(mirror.albums().is_empty());
+        assert!(mirror.songs().is_empty());
+    }
+
+    #[test]
+    fn compare_timestamps() {
+        let since = UNIX_EPOCH + Duration::from_secs(1_489_316_845);
+        assert!(is_after("2017-03-12T11:07:26.000Z", since));
+        assert!(!is_after("2017-03-12T11:07:25.999Z", since));
+        assert!(!is_after("2016-12-31T23:59:59Z", since));
+        // Servers in other time zones, or that leave the offset out.
+        assert!(is_after("2017-03-12T12:07:26+01:00", since));
+        assert!(!is_after("2017-03-12T11:07:25", since));
+    }
+
+    #[test]
+    fn mirror_round_trip() {
+        let mirror = mirror();
+        let saved = serde_json::to_value(&mirror).unwrap();
+        let loaded = serde_json::from_value::<Mirror>(saved).unwrap();
+        assert_eq!(loaded.synced_at(), mirror.synced_at());
+        assert_eq!(loaded.songs().len(), 2);
+        assert_eq!(
+            loaded.song(&SongId::from(11)).unwrap().title,
+            "Bellevue Avenue"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Keeping a mirror in a file between runs.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use super::Mirror;
+use crate::{Client, Result};
+
+impl Mirror {
+    /// Loads a mirror saved with [`save_file`], or returns `None` if there is
+    /// no such file.
+    ///
+    /// [`save_file`]: #method.save_file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Option<Mirror>> {
+        match File::open(path) {
+            Ok(file) => Mirror::load(BufReader::new(file)).map(Some),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves the mirror to a file, replacing any saved before.
+    ///
+    /// The mirror is written next to the file first and then moved over it,
+    /// so the file is never left half-written, even if the program stops
+    /// while saving.
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        self.save(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Brings the mirror saved in a file up to date, and saves it again.
+    ///
+    /// The mirror is [refreshed] if the file exists, and [synced] from scratch
+    /// otherwise.
+    ///
+    /// [refreshed]: #method.refresh
+    /// [synced]: #method.sync
+    pub fn update_file<P: AsRef<Path>>(client: &Client, path: P) -> Result<Mirror> {
+        let path = path.as_ref();
+        let mirror = match Mirror::open(path)? {
+            Some(mut mirror) => {
+                mirror.refresh(client)?;
+                mirror
+            }
+            None => Mirror::sync(client)?,
+        };
+        mirror.save_file(path)?;
+        Ok(mirror)
+    }
+
+    /// Runs [`update_file`] on another thread.
+    ///
+    /// This lets a client start instantly with the mirror saved on its last
+    /// run, and switch to the updated one once it is ready.
+    ///
+    /// [`update_file`]: #method.update_file
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate sunk;
+    /// use std::sync::Arc;
+    /// use sunk::mirror::Mirror;
+    /// use sunk::Client;
+    ///
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    /// let client = Arc::new(Client::new(site, user, password)?);
+    ///
+    /// let saved = Mirror::open("library.json")?;
+    /// let update = Mirror::update_in_background(client.clone(), "library.json");
+    ///
+    /// // Show `saved` while the update runs...
+    ///
+    /// let mirror = update.join().expect("update panicked")?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    pub fn update_in_background<P>(client: Arc<Client>, path: P) -> JoinHandle<Result<Mirror>>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        thread::spawn(move || Mirror::update_file(&client, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::mirror;
+    use super::*;
+
+    #[test]
+    fn save_and_open() {
+        let path = std::env::temp_dir().join(format!("sunk-mirror-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(Mirror::open(&path).unwrap().is_none());
+
+        mirror().save_file(&path).unwrap();
+        let opened = Mirror::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let opened = opened.unwrap();
+        assert_eq!(opened.synced_at(), mirror().synced_at());
+        assert_eq!(opened.songs(), mirror().songs());
+    }
+}
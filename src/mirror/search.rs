@@ -0,0 +1,249 @@
+//! Searching a mirror without the network.
+//!
+//! Every word in the names of artists, the names, artists and genres of
+//! albums, and the titles, artists, albums and genres of songs is indexed.
+//! A search matches the items that have every word of the query, either as
+//! the start of one of their words or, failing that, within a typo or two of
+//! one.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::Mirror;
+use crate::search::{SearchPage, SearchResult};
+
+/// An artist, album or song of the mirror, by position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Item {
+    Artist(usize),
+    Album(usize),
+    Song(usize),
+}
+
+/// How well a word of the query matched a word of an item. Higher is better.
+const EXACT: u32 = 3;
+const PREFIX: u32 = 2;
+const FUZZY: u32 = 1;
+
+/// The items each word appears in.
+#[derive(Debug, Default)]
+pub(super) struct SearchIndex {
+    words: BTreeMap<String, Vec<Item>>,
+}
+
+impl SearchIndex {
+    /// Indexes the artists, albums and songs of a mirror.
+    pub(super) fn build(mirror: &Mirror) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (i, artist) in mirror.artists.iter().enumerate() {
+            index.add(Item::Artist(i), &[&artist.name]);
+        }
+        for (i, album) in mirror.albums.iter().enumerate() {
+            let artist = album.artist.as_deref().unwrap_or("");
+            let genre = album.genre.as_deref().unwrap_or("");
+            index.add(Item::Album(i), &[&album.name, artist, genre]);
+        }
+        for (i, song) in mirror.songs.iter().enumerate() {
+            let mut fields = vec![
+                song.title.as_str(),
+                song.artist.as_deref().unwrap_or(""),
+                song.album.as_deref().unwrap_or(""),
+            ];
+            fields.extend(song.all_genres());
+            index.add(Item::Song(i), &fields);
+        }
+        index
+    }
+
+    /// Indexes the words of an item's fields.
+    fn add(&mut self, item: Item, fields: &[&str]) {
+        for word in fields.iter().flat_map(|f| words(f)) {
+            let items = self.words.entry(word).or_default();
+            // Items are added one at a time, so a repeated word would have
+            // just been added.
+            if items.last() != Some(&item) {
+                items.push(item);
+            }
+        }
+    }
+
+    /// Returns the items matching every word of a query, best matches first.
+    fn matches(&self, query: &[String]) -> Vec<Item> {
+        let mut found = HashMap::<Item, (usize, u32)>::new();
+        for word in query {
+            let mut best = HashMap::<Item, u32>::new();
+            for (score, items) in self.similar(word) {
+                for &item in items {
+                    let entry = best.entry(item).or_insert(score);
+                    *entry = (*entry).max(score);
+                }
+            }
+            for (item, score) in best {
+                let entry = found.entry(item).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += score;
+            }
+        }
+
+        let mut matches = found
+            .into_iter()
+            .filter(|&(_, (words, _))| words == query.len())
+            .map(|(item, (_, score))| (item, score))
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        matches.into_iter().map(|(item, _)| item).collect()
+    }
+
+    /// Returns the indexed words that start with `word`, or failing that are
+    /// within a typo or two of it, with how well they match.
+    fn similar(&self, word: &str) -> Vec<(u32, &[Item])> {
+        let prefixed = self
+            .words
+            .range(word.to_string()..)
+            .take_while(|(w, _)| w.starts_with(word))
+            .map(|(w, items)| (if w == word { EXACT } else { PREFIX }, &items[..]))
+            .collect::<Vec<_>>();
+        if !prefixed.is_empty() {
+            return prefixed;
+        }
+
+        let typos = match word.chars().count() {
+            0..=3 => return Vec::new(),
+            4..=7 => 1,
+            _ => 2,
+        };
+        self.words
+            .iter()
+            .filter(|(w, _)| distance(w, word) <= typos)
+            .map(|(_, items)| (FUZZY, &items[..]))
+            .collect()
+    }
+}
+
+impl Mirror {
+    /// Searches the mirror for artists, albums and songs, as
+    /// [`Client::search`] does on the server.
+    ///
+    /// Words of the query match the start of words in names, titles, artists
+    /// and genres, ignoring case; a word that matches nothing matches words
+    /// within a typo or two of it instead. Results are ordered by how well
+    /// they match. An empty query matches everything.
+    ///
+    /// [`Client::search`]: ../struct.Client.html#method.search
+    pub fn search(
+        &self,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> SearchResult {
+        let query = words(query).collect::<Vec<_>>();
+        let items = if query.is_empty() {
+            let artists = (0..self.artists.len()).map(Item::Artist);
+            let albums = (0..self.albums.len()).map(Item::Album);
+            let songs = (0..self.songs.len()).map(Item::Song);
+            artists.chain(albums).chain(songs).collect()
+        } else {
+            self.search_index.matches(&query)
+        };
+
+        let mut result = SearchResult {
+            artists: Vec::new(),
+            albums: Vec::new(),
+            songs: Vec::new(),
+        };
+        let mut seen = (0, 0, 0);
+        for item in items {
+            match item {
+                Item::Artist(i) if on_page(&mut seen.0, artist_page) => {
+                    result.artists.push(self.artists[i].clone())
+                }
+                Item::Album(i) if on_page(&mut seen.1, album_page) => {
+                    result.albums.push(self.albums[i].clone())
+                }
+                Item::Song(i) if on_page(&mut seen.2, song_page) => {
+                    result.songs.push(self.songs[i].clone())
+                }
+                _ => (),
+            }
+        }
+        result
+    }
+}
+
+/// Counts another result, and returns whether it falls on the page.
+fn on_page(seen: &mut usize, page: SearchPage) -> bool {
+    let i = *seen;
+    *seen += 1;
+    i >= page.offset && i - page.offset < page.count
+}
+
+/// Splits text into lowercase words.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Returns the number of characters that must be inserted, removed or
+/// replaced to turn one word into another.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let replace = prev[j] + usize::from(ca != cb);
+            row.push(replace.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::tests::mirror;
+    use crate::search;
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("valaire", "valaire"), 0);
+        assert_eq!(distance("valare", "valaire"), 1);
+        assert_eq!(distance("misteur", "mystery"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn search_mirror() {
+        let mirror = mirror();
+        let all = search::ALL;
+
+        let result = mirror.search("misteur valare", all, all, all);
+        assert_eq!(result.artists.len(), 1);
+        assert_eq!(result.albums.len(), 1);
+        assert_eq!(result.songs.len(), 2);
+
+        let result = mirror.search("inter", all, all, all);
+        assert!(result.artists.is_empty());
+        assert_eq!(result.songs.len(), 1);
+        assert_eq!(result.songs[0].title, "Interlude");
+
+        let result = mirror.search("bellevue", all, all, all);
+        assert_eq!(result.songs.len(), 2);
+        let result = mirror.search("bellevue ave", all, all, all);
+        assert_eq!(result.songs.len(), 1);
+        assert_eq!(result.songs[0].title, "Bellevue Avenue");
+
+        let result = mirror.search("", search::NONE, search::NONE, SearchPage::new());
+        assert!(result.artists.is_empty());
+        assert_eq!(result.songs.len(), 2);
+
+        let page = SearchPage {
+            count: 1,
+            offset: 1,
+        };
+        let result = mirror.search("electronic", all, all, page);
+        assert_eq!(result.songs.len(), 1);
+    }
+}
@@ -0,0 +1,449 @@
+//! The string type used for names that repeat across a library.
+//!
+//! A large library repeats the same artist, album and genre names on
+//! thousands of songs. By default these are plain `String`s. With the
+//! `shared-strings` feature they are [`Interned`] handles instead: reference
+//! counted strings that are cheap to clone into indexes and caches, and that
+//! share a single allocation between every song using the same name when
+//! deserialized with an [`Interner`].
+//!
+//! Either way, a [`Name`] dereferences to `str`, so code that only reads
+//! names works unchanged with both.
+//!
+//! [`Name`]: ../type.Name.html
+//! [`Interned`]: ../struct.Interned.html
+//! [`Interner`]: ../struct.Interner.html
+
+#[cfg(feature = "shared-strings")]
+use serde::de::{Deserialize, Deserializer};
+#[cfg(feature = "shared-strings")]
+use serde::ser::{Serialize, Serializer};
+#[cfg(feature = "shared-strings")]
+use std::borrow::Borrow;
+use std::borrow::Cow;
+#[cfg(feature = "shared-strings")]
+use std::cell::RefCell;
+#[cfg(feature = "shared-strings")]
+use std::collections::HashSet;
+#[cfg(feature = "shared-strings")]
+use std::ops::Deref;
+#[cfg(feature = "shared-strings")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "shared-strings")]
+use std::{fmt, mem, result};
+
+/// A name that is likely to repeat across many entities, such as an artist,
+/// album or genre name.
+///
+/// This is a `String`, or an [`Interned`] handle with the `shared-strings`
+/// feature.
+///
+/// [`Interned`]: ./struct.Interned.html
+#[cfg(not(feature = "shared-strings"))]
+pub type Name = String;
+
+/// A name that is likely to repeat across many entities, such as an artist,
+/// album or genre name.
+///
+/// This is an [`Interned`] handle, or a `String` without the `shared-strings`
+/// feature.
+///
+/// [`Interned`]: ./struct.Interned.html
+#[cfg(feature = "shared-strings")]
+pub type Name = Interned;
+
+/// Converts a string into a [`Name`], interning it if an interner is in scope.
+///
+/// [`Name`]: ../type.Name.html
+#[cfg(not(feature = "shared-strings"))]
+pub(crate) fn shared(s: Cow<'_, str>) -> Name {
+    s.into_owned()
+}
+
+/// Converts a string into a [`Name`], interning it if an interner is in scope.
+///
+/// [`Name`]: ../type.Name.html
+#[cfg(feature = "shared-strings")]
+pub(crate) fn shared(s: Cow<'_, str>) -> Name {
+    SCOPE.with(|scope| match *scope.borrow_mut() {
+        Some(Scope::Owned(ref mut interner)) => interner.intern(&s),
+        Some(Scope::Shared(ref interner)) => match interner.lock() {
+            Ok(mut interner) => interner.intern(&s),
+            Err(_) => Interned::from(s),
+        },
+        None => Interned::from(s),
+    })
+}
+
+/// Returns whether names deserialized on this thread are being interned.
+#[cfg(all(feature = "rayon", not(feature = "shared-strings")))]
+pub(crate) fn interning() -> bool {
+    false
+}
+
+/// Returns whether names deserialized on this thread are being interned.
+#[cfg(all(feature = "rayon", feature = "shared-strings"))]
+pub(crate) fn interning() -> bool {
+    SCOPE.with(|scope| scope.borrow().is_some())
+}
+
+/// A shared, immutable string.
+///
+/// Cloning an `Interned` only bumps a reference count. Handles created by the
+/// same [`Interner`] for equal strings share one allocation.
+///
+/// [`Interner`]: ./struct.Interner.html
+#[cfg(feature = "shared-strings")]
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned(Arc<str>);
+
+#[cfg(feature = "shared-strings")]
+impl Interned {
+    /// Returns whether two handles share the same allocation.
+    pub fn ptr_eq(a: &Interned, b: &Interned) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl<'a> From<&'a str> for Interned {
+    fn from(s: &'a str) -> Interned {
+        Interned(s.into())
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl From<String> for Interned {
+    fn from(s: String) -> Interned {
+        Interned(s.into())
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl<'a> From<Cow<'a, str>> for Interned {
+    fn from(s: Cow<'a, str>) -> Interned {
+        Interned(s.into())
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl<'a> PartialEq<&'a str> for Interned {
+    fn eq(&self, other: &&'a str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl<'de> Deserialize<'de> for Interned {
+    /// Deserializes a string, sharing it with equal strings if an
+    /// [`Interner`] is in scope.
+    ///
+    /// [`Interner`]: ./struct.Interner.html
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(shared(Cow::<str>::deserialize(de)?))
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl Serialize for Interned {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+thread_local! {
+    /// The interner used by `Interned::deserialize` on this thread.
+    static SCOPE: RefCell<Option<Scope>> = const { RefCell::new(None) };
+}
+
+/// The interner in scope on a thread.
+#[cfg(feature = "shared-strings")]
+enum Scope {
+    /// An interner moved in by [`Interner::scope`] for the length of the call.
+    Owned(Interner),
+    /// An interner shared between threads, locked for each name interned.
+    Shared(Arc<Mutex<Interner>>),
+}
+
+/// Puts `scope` in scope on this thread while `f` runs, and returns it
+/// afterwards along with the result of `f`.
+#[cfg(feature = "shared-strings")]
+fn with_scope<F, R>(scope: Scope, f: F) -> (Option<Scope>, R)
+where
+    F: FnOnce() -> R,
+{
+    /// Puts the outer scope back if `f` panics.
+    struct Restore(Option<Option<Scope>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(outer) = self.0.take() {
+                SCOPE.with(|scope| *scope.borrow_mut() = outer);
+            }
+        }
+    }
+
+    let outer = SCOPE.with(|s| s.borrow_mut().replace(scope));
+    let mut restore = Restore(Some(outer));
+    let result = f();
+    let outer = restore.0.take().unwrap_or_default();
+    let inner = SCOPE.with(|s| mem::replace(&mut *s.borrow_mut(), outer));
+    (inner, result)
+}
+
+/// Runs `f`, interning every [`Name`] deserialized on this thread while it
+/// runs with the shared `interner`.
+///
+/// The interner is locked for each name rather than for the whole of `f`, so
+/// other threads can deserialize with it at the same time.
+///
+/// [`Name`]: ../type.Name.html
+#[cfg(feature = "shared-strings")]
+pub(crate) fn scope_shared<F, R>(interner: &Arc<Mutex<Interner>>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    with_scope(Scope::Shared(interner.clone()), f).1
+}
+
+/// The number of names an [`Interner`] holds before it drops the ones no
+/// longer in use.
+#[cfg(feature = "shared-strings")]
+const DEFAULT_LIMIT: usize = 1 << 16;
+
+/// A table of strings, used to share the storage of equal names.
+///
+/// Names deserialized inside [`scope`] are looked up in the table, and equal
+/// names share a single allocation. Reuse one interner for related requests,
+/// such as all pages of a library being mirrored, and drop it afterwards.
+///
+/// The table is bounded: once it holds its [limit] of names, the names no
+/// longer used outside of it are dropped, and if most are still in use it is
+/// cleared. Names interned before that keep working; they just stop being
+/// shared with names interned after.
+///
+/// Set an interner on the [`Client`] with [`with_interner`] to intern the
+/// names of every list and search result it fetches.
+///
+/// [`scope`]: #method.scope
+/// [limit]: #method.with_limit
+/// [`Client`]: ./struct.Client.html
+/// [`with_interner`]: ./struct.Client.html#method.with_interner
+///
+/// # Examples
+///
+/// ```
+/// use sunk::{Interned, Interner};
+///
+/// let mut interner = Interner::new();
+/// let names: Vec<Interned> = interner.scope(|| {
+///     serde_json::from_str(r#"["Misteur Valaire", "Misteur Valaire"]"#).unwrap()
+/// });
+///
+/// assert!(Interned::ptr_eq(&names[0], &names[1]));
+/// assert_eq!(interner.len(), 1);
+/// ```
+#[cfg(feature = "shared-strings")]
+#[derive(Debug)]
+pub struct Interner {
+    names: HashSet<Arc<str>>,
+    limit: usize,
+}
+
+#[cfg(feature = "shared-strings")]
+impl Default for Interner {
+    fn default() -> Interner {
+        Interner {
+            names: HashSet::new(),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+#[cfg(feature = "shared-strings")]
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Sets the number of names the table holds before it is pruned. The
+    /// default is 65536.
+    pub fn with_limit(self, limit: usize) -> Interner {
+        let mut interner = self;
+        interner.limit = limit.max(1);
+        interner
+    }
+
+    /// Returns a handle to `name`, sharing the storage of an equal name if
+    /// one has been interned before.
+    pub fn intern(&mut self, name: &str) -> Interned {
+        if let Some(shared) = self.names.get(name) {
+            return Interned(shared.clone());
+        }
+        if self.names.len() >= self.limit {
+            self.prune();
+        }
+        let shared: Arc<str> = name.into();
+        self.names.insert(shared.clone());
+        Interned(shared)
+    }
+
+    /// Drops the names only the table holds, or every name if more than half
+    /// of the limit are still in use, so that pruning isn't repeated on every
+    /// new name.
+    fn prune(&mut self) {
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+        if self.names.len() > self.limit / 2 {
+            self.names.clear();
+        }
+    }
+
+    /// Returns the number of distinct names interned.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns whether no names have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Runs `f`, interning every [`Name`] deserialized on this thread while
+    /// it runs.
+    ///
+    /// [`Name`]: ./type.Name.html
+    pub fn scope<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let taken = mem::take(self);
+        let limit = taken.limit;
+        let (inner, result) = with_scope(Scope::Owned(taken), f);
+        *self = match inner {
+            Some(Scope::Owned(interner)) => interner,
+            _ => Interner::new().with_limit(limit),
+        };
+        result
+    }
+}
+
+#[cfg(all(test, feature = "shared-strings"))]
+mod tests {
+    use super::*;
+    use crate::Song;
+
+    #[test]
+    fn intern_song_names() {
+        let song = serde_json::json!({
+            "id": "27",
+            "isDir": false,
+            "title": "Bellevue Avenue",
+            "album": "Bellevue",
+            "artist": "Misteur Valaire",
+            "size": 8707387,
+            "genre": "(52)",
+            "contentType": "audio/mpeg",
+            "suffix": "mp3",
+            "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "created": "2017-03-12T11:07:25.000Z",
+            "type": "music"
+        });
+
+        let mut interner = Interner::new();
+        let songs: Vec<Song> = interner
+            .scope(|| serde_json::from_value(serde_json::json!([song.clone(), song])).unwrap());
+        let (a, b) = (songs[0].artist.as_ref(), songs[1].artist.as_ref());
+        assert!(Interned::ptr_eq(a.unwrap(), b.unwrap()));
+        assert_eq!(interner.len(), 5);
+
+        let unshared: Song = serde_json::from_value(serde_json::json!(songs[0])).unwrap();
+        assert!(!Interned::ptr_eq(
+            unshared.album.as_ref().unwrap(),
+            songs[0].album.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn prune_unused_names() {
+        let mut interner = Interner::new().with_limit(2);
+        let kept = interner.intern("Bellevue");
+        interner.intern("Misteur Valaire");
+        interner.intern("Electronic");
+        assert_eq!(interner.len(), 2);
+        assert!(Interned::ptr_eq(&kept, &interner.intern("Bellevue")));
+    }
+
+    #[test]
+    fn shared_scope() {
+        let interner = Arc::new(Mutex::new(Interner::new()));
+        let threads = (0..2)
+            .map(|_| {
+                let interner = interner.clone();
+                std::thread::spawn(move || {
+                    scope_shared(&interner, || {
+                        assert!(interner.try_lock().is_ok());
+                        serde_json::from_str::<Interned>(r#""Bellevue""#).unwrap()
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let names = threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>();
+        assert!(Interned::ptr_eq(&names[0], &names[1]));
+        assert_eq!(interner.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,296 @@
+//! Picking theme colours from cover art.
+//!
+//! Players often tint their now-playing screen to match the album playing.
+//! [`extract_palette`] decodes a cover, as returned by
+//! [`HasCoverArt::cover_art`], and picks its dominant colour, for
+//! backgrounds, and an accent colour that stands out from it, for
+//! highlights.
+//!
+//! [`extract_palette`]: fn.extract_palette.html
+//! [`HasCoverArt::cover_art`]: ../trait.HasCoverArt.html#method.cover_art
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::palette::extract_palette;
+//! use sunk::{Client, HasCoverArt};
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let album = client.albums().get("1")?;
+//!
+//! let palette = extract_palette(&album.cover_art(&client, 300)?)?;
+//! println!("background {}, accent {}", palette.dominant, palette.accent);
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Error, Result};
+
+/// The side, in pixels, covers are shrunk to before their colours are
+/// counted. Palettes of larger images are no better, just slower.
+const SAMPLE_SIZE: u32 = 64;
+
+/// Bits kept of each channel when grouping similar colours.
+const BITS: u32 = 4;
+
+/// How far apart, in RGB, the accent colour must be from the dominant one.
+const MIN_ACCENT_DISTANCE: f32 = 80.0;
+
+/// A colour in 8-bit RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+}
+
+impl Rgb {
+    /// Returns the relative luminance of the colour, from 0 for black to 1
+    /// for white, as defined by WCAG.
+    pub fn luminance(self) -> f32 {
+        fn linear(c: u8) -> f32 {
+            let c = f32::from(c) / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Returns whether the colour is dark, so text drawn over it should be
+    /// light.
+    pub fn is_dark(self) -> bool {
+        self.luminance() < 0.179
+    }
+
+    /// Returns the saturation of the colour, from 0 for greys to 1.
+    fn saturation(self) -> f32 {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        if max == 0 {
+            0.0
+        } else {
+            f32::from(max - min) / f32::from(max)
+        }
+    }
+
+    /// Returns the distance between two colours in RGB space.
+    fn distance(self, other: Rgb) -> f32 {
+        let d = |a: u8, b: u8| f32::from(a) - f32::from(b);
+        let (r, g, b) = (d(self.r, other.r), d(self.g, other.g), d(self.b, other.b));
+        (r * r + g * g + b * b).sqrt()
+    }
+}
+
+/// Formats the colour as a CSS hex colour, such as `#1a2b3c`.
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// The colours picked from a cover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// The most common colour of the cover.
+    pub dominant: Rgb,
+    /// The most vivid colour of the cover that is clearly different from the
+    /// dominant one. If the cover has no such colour, such as a single
+    /// colour cover, this is a lighter or darker shade of the dominant
+    /// colour.
+    pub accent: Rgb,
+    /// The main colours of the cover, most common first, with the fraction
+    /// of the cover each covers.
+    pub swatches: Vec<(Rgb, f32)>,
+}
+
+/// Picks the dominant and accent colours of the image `bytes`.
+///
+/// Any format sunk's image support decodes is accepted: BMP, GIF, JPEG, PNG
+/// and WebP, which between them cover what Subsonic servers serve. Fully
+/// transparent pixels are ignored.
+///
+/// # Errors
+///
+/// Errors if the image can't be decoded, or has no opaque pixels.
+pub fn extract_palette(bytes: &[u8]) -> Result<Palette> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| Error::Decode(e.to_string()))?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgba8();
+
+    // Group similar colours, keeping the sum of each group to average them.
+    let mut groups = HashMap::<(u8, u8, u8), (u32, [u32; 3])>::new();
+    let mut total = 0;
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        let group = groups
+            .entry((r >> (8 - BITS), g >> (8 - BITS), b >> (8 - BITS)))
+            .or_insert((0, [0; 3]));
+        group.0 += 1;
+        group.1[0] += u32::from(r);
+        group.1[1] += u32::from(g);
+        group.1[2] += u32::from(b);
+        total += 1;
+    }
+    if total == 0 {
+        return Err(Error::Other("image has no opaque pixels"));
+    }
+
+    let mut swatches = groups
+        .into_values()
+        .map(|(n, [r, g, b])| {
+            let colour = Rgb {
+                r: (r / n) as u8,
+                g: (g / n) as u8,
+                b: (b / n) as u8,
+            };
+            (colour, n as f32 / total as f32)
+        })
+        .collect::<Vec<_>>();
+    // Ties are broken on the colour, so the palette doesn't depend on the
+    // order colours were counted in.
+    swatches.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| (a.0.r, a.0.g, a.0.b).cmp(&(b.0.r, b.0.g, b.0.b)))
+    });
+    // Colours spread over many groups, as in photos, may leave every group
+    // under the cutoff; the largest is always kept.
+    let kept = swatches.iter().take_while(|s| s.1 >= 0.01).count();
+    swatches.truncate(kept.max(1));
+
+    let dominant = swatches[0].0;
+    let accent = swatches
+        .iter()
+        .filter(|&&(c, _)| c.distance(dominant) >= MIN_ACCENT_DISTANCE)
+        .max_by(|a, b| {
+            let score = |&(c, share): &(Rgb, f32)| (0.1 + c.saturation()) * share.sqrt();
+            score(a).total_cmp(&score(b))
+        })
+        .map(|&(c, _)| c)
+        .unwrap_or_else(|| shade(dominant));
+
+    Ok(Palette {
+        dominant,
+        accent,
+        swatches,
+    })
+}
+
+/// Returns a shade of `colour` that stands out from it: lighter if it is
+/// dark, and darker if it is light.
+fn shade(colour: Rgb) -> Rgb {
+    let mix = |c: u8| {
+        if colour.is_dark() {
+            c + (255 - c) / 2
+        } else {
+            c / 2
+        }
+    };
+    Rgb {
+        r: mix(colour.r),
+        g: mix(colour.g),
+        b: mix(colour.b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn png(image: RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn dominant_and_accent() {
+        // Mostly navy, with a red stripe and a little near-navy noise.
+        let image = RgbaImage::from_fn(100, 100, |x, y| match (x, y) {
+            (_, 0..=19) => Rgba([220, 30, 40, 255]),
+            (0..=4, _) => Rgba([22, 34, 84, 255]),
+            _ => Rgba([20, 30, 80, 255]),
+        });
+        let palette = extract_palette(&png(image)).unwrap();
+
+        assert!(
+            palette.dominant.distance(Rgb {
+                r: 20,
+                g: 30,
+                b: 80
+            }) < 5.0
+        );
+        assert!(
+            palette.accent.distance(Rgb {
+                r: 220,
+                g: 30,
+                b: 40
+            }) < 5.0
+        );
+        assert!(palette.dominant.is_dark());
+        let shares = palette.swatches.iter().map(|s| s.1).sum::<f32>();
+        assert!((shares - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn spread_colours() {
+        let image = RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 255])
+        });
+        let palette = extract_palette(&png(image)).unwrap();
+        assert_eq!(palette.swatches.len(), 1);
+        assert_eq!(palette.dominant, palette.swatches[0].0);
+    }
+
+    #[test]
+    fn single_colour() {
+        let image = RgbaImage::from_pixel(10, 10, Rgba([240, 240, 240, 255]));
+        let palette = extract_palette(&png(image)).unwrap();
+        assert_eq!(
+            palette.dominant,
+            Rgb {
+                r: 240,
+                g: 240,
+                b: 240
+            }
+        );
+        assert_eq!(
+            palette.accent,
+            Rgb {
+                r: 120,
+                g: 120,
+                b: 120
+            }
+        );
+        assert_eq!(palette.dominant.to_string(), "#f0f0f0");
+    }
+
+    #[test]
+    fn rejects_bad_images() {
+        assert!(extract_palette(b"not an image").is_err());
+        let clear = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        assert!(extract_palette(&png(clear)).is_err());
+    }
+}
@@ -0,0 +1,525 @@
+//! Playing songs with [rodio].
+//!
+//! A [`Player`] streams songs from the server, decodes them as they arrive
+//! and plays them one after another. Songs in a format rodio can't decode
+//! are transcoded to MP3 by the server first.
+//!
+//! sunk depends on rodio without its `playback` feature, so builds don't
+//! need an audio backend. Players are attached to a rodio mixer; to hear
+//! them, open an output stream with rodio's `playback` feature enabled in
+//! your own `Cargo.toml`:
+//!
+//! ```toml
+//! [dependencies]
+//! rodio = "0.21"
+//! sunk = { version = "0.2", features = ["playback"] }
+//! ```
+//!
+//! Players can normalise the volume of songs with the ReplayGain metadata
+//! OpenSubsonic servers send; see [`ReplayGainSettings`].
+//!
+//! Songs queued with [`Player::queue`] are started shortly before the song
+//! before them ends, for gapless playback, and can be warmed ahead of time
+//! so skipping to them is instant; see [`Player::with_warming`].
+//!
+//! To switch between playing here and on the server's jukebox, wrap the
+//! player in a [`LocalPlayer`].
+//!
+//! [rodio]: https://docs.rs/rodio
+//! [`Player`]: struct.Player.html
+//! [`ReplayGainSettings`]: struct.ReplayGainSettings.html
+//! [`Player::queue`]: struct.Player.html#method.queue
+//! [`Player::with_warming`]: struct.Player.html#method.with_warming
+//! [`LocalPlayer`]: ../player/struct.LocalPlayer.html
+//!
+//! # Examples
+//!
+//! ```ignore
+//! extern crate rodio;
+//! extern crate sunk;
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let stream = rodio::OutputStreamBuilder::open_default_stream().unwrap();
+//!
+//! let song = Song::get(&client, 1)?;
+//! let player = song.play(&client, stream.mixer())?;
+//! player.sink().sleep_until_end();
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rodio::decoder::Decoder;
+use rodio::mixer::Mixer;
+use rodio::{Sink, Source};
+
+use crate::song::{ReplayGain, Song};
+use crate::warm::{QueueWarmer, WarmOptions};
+use crate::{Client, Error, Result, StreamReader};
+
+/// Which ReplayGain to apply when playing songs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplayGainMode {
+    /// Play songs as they are.
+    #[default]
+    Off,
+    /// Play every song at the same loudness, for shuffled playback.
+    Track,
+    /// Keep the differences in loudness between the songs of an album, for
+    /// playing whole albums.
+    Album,
+}
+
+/// How a [`Player`] normalises the volume of songs with their ReplayGain
+/// metadata.
+///
+/// Songs without ReplayGain metadata are played at the server's fallback
+/// gain if it sent one, and as they are otherwise.
+///
+/// [`Player`]: struct.Player.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayGainSettings {
+    /// Which gain to apply; off by default.
+    pub mode: ReplayGainMode,
+    /// Decibels added to the gain of every song, as ReplayGain tends to make
+    /// songs quieter.
+    pub preamp: f32,
+    /// Whether to lower the gain of songs whose peak would be pushed past
+    /// full scale; on by default.
+    pub prevent_clipping: bool,
+}
+
+impl Default for ReplayGainSettings {
+    fn default() -> ReplayGainSettings {
+        ReplayGainSettings {
+            mode: ReplayGainMode::Off,
+            preamp: 0.0,
+            prevent_clipping: true,
+        }
+    }
+}
+
+impl ReplayGainSettings {
+    /// Returns the amplitude factor to play `song` at.
+    pub fn factor(&self, song: &Song) -> f32 {
+        let rg = match song.replay_gain {
+            Some(ref rg) => rg,
+            None => return 1.0,
+        };
+        let (gain, peak) = match self.mode {
+            ReplayGainMode::Off => return 1.0,
+            ReplayGainMode::Track => (rg.track_gain, rg.track_peak),
+            ReplayGainMode::Album => match rg.album_gain {
+                Some(gain) => (Some(gain), rg.album_peak),
+                None => (rg.track_gain, rg.track_peak),
+            },
+        };
+        let factor = match gain.or(rg.fallback_gain) {
+            Some(gain) => ReplayGain::to_amplitude(gain + self.preamp),
+            None => return 1.0,
+        };
+        match peak {
+            Some(peak) if self.prevent_clipping && peak > 0.0 => factor.min(1.0 / peak),
+            _ => factor,
+        }
+    }
+}
+
+/// When a [`Player`] starts streaming the songs in its queue, and how far
+/// ahead it reads them.
+///
+/// [`Player`]: struct.Player.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchSettings {
+    /// How long before the end of the playing song the next one is started;
+    /// 10 seconds by default.
+    pub window: Duration,
+    /// How many bytes of a song are read ahead of playback; 1 MiB by
+    /// default.
+    pub buffer_size: usize,
+}
+
+impl Default for PrefetchSettings {
+    fn default() -> PrefetchSettings {
+        PrefetchSettings {
+            window: Duration::from_secs(10),
+            buffer_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Plays a queue of songs streamed from the server.
+///
+/// Songs added with [`enqueue`] are streamed straight away. Songs added with
+/// [`queue`] wait until the song before them is about to end, as set with
+/// [`with_prefetch`], and are then streamed and decoded in time to follow it
+/// without a gap; call [`update`] regularly, such as from a UI loop, to let
+/// the player start them.
+///
+/// [`enqueue`]: #method.enqueue
+/// [`queue`]: #method.queue
+/// [`with_prefetch`]: #method.with_prefetch
+/// [`update`]: #method.update
+///
+/// With [`with_warming`], the songs at the head of the queue are also warmed
+/// as it changes, so that even songs skipped to start without delay.
+///
+/// [`with_warming`]: #method.with_warming
+pub struct Player {
+    sink: Sink,
+    replay_gain: ReplayGainSettings,
+    prefetch: PrefetchSettings,
+    /// Songs queued but not yet streamed.
+    upcoming: Mutex<VecDeque<Song>>,
+    /// The durations of the songs in the sink, in order.
+    playing: Mutex<VecDeque<Option<Duration>>>,
+    warmer: Option<Mutex<QueueWarmer>>,
+}
+
+impl Player {
+    /// Creates a player with nothing queued, playing into `mixer`.
+    pub fn new(mixer: &Mixer) -> Player {
+        Player {
+            sink: Sink::connect_new(mixer),
+            replay_gain: ReplayGainSettings::default(),
+            prefetch: PrefetchSettings::default(),
+            upcoming: Mutex::new(VecDeque::new()),
+            playing: Mutex::new(VecDeque::new()),
+            warmer: None,
+        }
+    }
+
+    /// Warms the songs at the head of the queue on every [`update`], as
+    /// `options` ask. See the [`warm`] module.
+    ///
+    /// [`update`]: #method.update
+    /// [`warm`]: ../warm/index.html
+    pub fn with_warming(self, options: WarmOptions) -> Player {
+        let mut player = self;
+        player.warmer = Some(Mutex::new(QueueWarmer::new(options).for_decoding()));
+        player
+    }
+
+    /// Returns what the player warms ahead of time, if anything.
+    pub fn warming(&self) -> Option<WarmOptions> {
+        self.warmer.as_ref().map(|w| w.lock().unwrap().options())
+    }
+
+    /// Sets when songs in the queue are started, and how far ahead they are
+    /// read.
+    pub fn with_prefetch(self, settings: PrefetchSettings) -> Player {
+        let mut player = self;
+        player.prefetch = settings;
+        player
+    }
+
+    /// Returns when songs in the queue are started, and how far ahead they
+    /// are read.
+    pub fn prefetch(&self) -> PrefetchSettings {
+        self.prefetch
+    }
+
+    /// Normalises the volume of the songs queued from now on with their
+    /// ReplayGain metadata.
+    pub fn with_replay_gain(self, settings: ReplayGainSettings) -> Player {
+        let mut player = self;
+        player.replay_gain = settings;
+        player
+    }
+
+    /// Returns how the player normalises the volume of songs.
+    pub fn replay_gain(&self) -> ReplayGainSettings {
+        self.replay_gain
+    }
+
+    /// Starts streaming `song`, and queues it to play after the songs
+    /// already queued.
+    ///
+    /// # Errors
+    ///
+    /// Aside from errors that the `Client` may cause, the method will error
+    /// if the start of the song can't be decoded.
+    pub fn enqueue(&self, client: &Client, song: &Song) -> Result<()> {
+        let mut playing = self.playing.lock().unwrap();
+        self.append(client, song, &mut playing)
+    }
+
+    /// Queues `song` to be streamed shortly before the songs already queued
+    /// have finished. See [`update`].
+    ///
+    /// [`update`]: #method.update
+    pub fn queue(&self, song: Song) {
+        self.upcoming.lock().unwrap().push_back(song);
+    }
+
+    /// Returns the songs queued that haven't been streamed yet.
+    pub fn upcoming(&self) -> Vec<Song> {
+        self.upcoming.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Starts streaming the next song in the queue if the playing song ends
+    /// within the prefetch window, or nothing is playing. Returns the song
+    /// started, if any.
+    ///
+    /// The next song is only started once a single song is left playing.
+    /// If the playing song's duration isn't known, the next song is started
+    /// when it ends instead.
+    ///
+    /// If the player warms the queue, the songs coming up are warmed
+    /// afterwards. This blocks while their first seconds are streamed, as
+    /// described on [`WarmOptions::audio`], so call this off the thread that
+    /// drives the interface. Songs that fail to warm are logged and streamed
+    /// when they start instead.
+    ///
+    /// [`WarmOptions::audio`]: ../warm/struct.WarmOptions.html#structfield.audio
+    ///
+    /// # Errors
+    ///
+    /// Errors if the next song can't be streamed or decoded; it is then left
+    /// out of the queue.
+    pub fn update(&self, client: &Client) -> Result<Option<Song>> {
+        let started = self.start_due(client)?;
+        if let Some(ref warmer) = self.warmer {
+            let upcoming = self.upcoming();
+            if let Err(e) = warmer.lock().unwrap().warm(client, &upcoming) {
+                warn!("Failed to warm the queue: {}", e);
+            }
+        }
+        Ok(started)
+    }
+
+    /// Starts the next song in the queue if it is due.
+    fn start_due(&self, client: &Client) -> Result<Option<Song>> {
+        let mut playing = self.playing.lock().unwrap();
+        while playing.len() > self.sink.len() {
+            playing.pop_front();
+        }
+        let due = match playing.front() {
+            None => true,
+            Some(_) if playing.len() > 1 => false,
+            Some(Some(duration)) => {
+                duration.saturating_sub(self.sink.get_pos()) <= self.prefetch.window
+            }
+            Some(None) => false,
+        };
+        if !due {
+            return Ok(None);
+        }
+
+        let song = match self.upcoming.lock().unwrap().pop_front() {
+            Some(song) => song,
+            None => return Ok(None),
+        };
+        self.append(client, &song, &mut playing)?;
+        Ok(Some(song))
+    }
+
+    /// Streams `song` and adds it to the sink.
+    fn append(
+        &self,
+        client: &Client,
+        song: &Song,
+        playing: &mut VecDeque<Option<Duration>>,
+    ) -> Result<()> {
+        let reader = match self.warmer {
+            Some(ref warmer) => warmer.lock().unwrap().stream_reader(client, song)?,
+            None => song.decodable_reader(client)?,
+        };
+        let reader = reader.prefetch(self.prefetch.buffer_size);
+        let factor = self.replay_gain.factor(song);
+        self.sink.append(decoder(reader)?.amplify(factor));
+        playing.push_back(song.duration());
+        Ok(())
+    }
+
+    /// Stops the songs playing and empties the queue. A paused player stays
+    /// paused.
+    pub fn clear(&self) {
+        let mut playing = self.playing.lock().unwrap();
+        self.upcoming.lock().unwrap().clear();
+        playing.clear();
+        let paused = self.sink.is_paused();
+        self.sink.clear();
+        if !paused {
+            self.sink.play();
+        }
+    }
+
+    /// Returns the rodio sink the player plays into, to pause, skip or
+    /// change the volume.
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+}
+
+impl fmt::Debug for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Player")
+            .field("queued", &self.sink.len())
+            .field("paused", &self.sink.is_paused())
+            .field("replay_gain", &self.replay_gain)
+            .field("prefetch", &self.prefetch)
+            .field("upcoming", &self.upcoming.lock().unwrap().len())
+            .field("warming", &self.warming())
+            .finish()
+    }
+}
+
+impl Song {
+    /// Streams the song and starts playing it into `mixer`, returning the
+    /// player it plays on.
+    ///
+    /// See the [`playback`] module for how to get a mixer.
+    ///
+    /// [`playback`]: ../playback/index.html
+    pub fn play(&self, client: &Client, mixer: &Mixer) -> Result<Player> {
+        let player = Player::new(mixer);
+        player.enqueue(client, self)?;
+        Ok(player)
+    }
+}
+
+/// Streams `song`, and returns a decoder over it.
+///
+/// The song is streamed as the server sends it if rodio can decode that
+/// format, and transcoded to MP3 otherwise.
+pub fn decode(client: &Client, song: &Song) -> Result<Decoder<StreamReader>> {
+    decoder(song.decodable_reader(client)?)
+}
+
+/// Returns a decoder over the media read by `reader`.
+fn decoder(reader: StreamReader) -> Result<Decoder<StreamReader>> {
+    let mut builder = Decoder::builder().with_seekable(true);
+    if let Some(len) = reader.byte_len() {
+        builder = builder.with_byte_len(len);
+    }
+    if let Some(mime) = reader.content_type().map(String::from) {
+        builder = builder.with_mime_type(&mime);
+    }
+    builder
+        .with_data(reader)
+        .build()
+        .map_err(|e| Error::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn song(replay_gain: serde_json::Value) -> Song {
+        test_util::song_with("1", serde_json::json!({ "replayGain": replay_gain }))
+    }
+
+    fn settings(mode: ReplayGainMode) -> ReplayGainSettings {
+        ReplayGainSettings {
+            mode,
+            ..ReplayGainSettings::default()
+        }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn replay_gain_factor() {
+        let tagged = song(serde_json::json!({
+            "trackGain": -6.0, "albumGain": -3.0, "trackPeak": 0.5, "albumPeak": 0.9,
+        }));
+        assert_eq!(settings(ReplayGainMode::Off).factor(&tagged), 1.0);
+        assert!(close(
+            settings(ReplayGainMode::Track).factor(&tagged),
+            0.501
+        ));
+        assert!(close(
+            settings(ReplayGainMode::Album).factor(&tagged),
+            0.708
+        ));
+
+        let loud = ReplayGainSettings {
+            preamp: 12.0,
+            ..settings(ReplayGainMode::Track)
+        };
+        // +6 dB would double a peak of 0.5, which just fits.
+        assert!(close(loud.factor(&tagged), 1.995));
+        let louder = ReplayGainSettings {
+            preamp: 15.0,
+            ..loud
+        };
+        assert!(close(louder.factor(&tagged), 2.0));
+        let unguarded = ReplayGainSettings {
+            prevent_clipping: false,
+            ..louder
+        };
+        assert!(close(unguarded.factor(&tagged), 2.818));
+    }
+
+    #[test]
+    fn replay_gain_fallbacks() {
+        let album = settings(ReplayGainMode::Album);
+        let track_only = song(serde_json::json!({ "trackGain": -6.0 }));
+        assert!(close(album.factor(&track_only), 0.501));
+
+        let untagged = song(serde_json::json!({ "fallbackGain": -2.0 }));
+        assert!(close(album.factor(&untagged), 0.794));
+
+        let none = song(serde_json::Value::Null);
+        assert_eq!(album.factor(&none), 1.0);
+    }
+
+    #[test]
+    fn update_waits_for_queue() {
+        let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let player = Player::new(&mixer).with_prefetch(PrefetchSettings {
+            window: Duration::from_secs(5),
+            ..PrefetchSettings::default()
+        });
+        assert_eq!(player.prefetch().window, Duration::from_secs(5));
+        assert!(player.update(&client).unwrap().is_none());
+
+        // Nothing is playing, so the queued song is due straight away; it
+        // can't be streamed here, and is dropped from the queue.
+        player.queue(song(serde_json::Value::Null));
+        assert_eq!(player.upcoming().len(), 1);
+        assert!(player.update(&client).is_err());
+        assert!(player.upcoming().is_empty());
+    }
+
+    #[test]
+    fn warms_upcoming() {
+        let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let player = Player::new(&mixer).with_warming(WarmOptions::default());
+        assert_eq!(player.warming(), Some(WarmOptions::default()));
+
+        // Something endless is playing, so the queued songs aren't due.
+        player.sink.append(rodio::source::Zero::new(2, 44100));
+        player.playing.lock().unwrap().push_back(None);
+
+        player.queue(song(serde_json::Value::Null));
+        assert!(player.update(&client).unwrap().is_none());
+        // Only resolving URLs, warming works offline.
+        let id = &player.upcoming()[0].id;
+        assert!(player
+            .warmer
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some());
+    }
+}
@@ -0,0 +1,528 @@
+//! Playing a queue of songs on this device or on the server's speakers.
+//!
+//! A [`PlaybackTarget`] plays the songs of a [`Queue`], wherever they are
+//! played: the [`LocalPlayer`] streams them and plays them here, with the
+//! `playback` feature, and the [`JukeboxPlayer`] has the server play them
+//! through its jukebox, with the `jukebox` feature. Both keep the queue the
+//! same way, so an application can offer both behind a
+//! `Box<dyn PlaybackTarget>`, and move what is playing from one to the other
+//! with [`PlaybackTarget::take_over`].
+//!
+//! [`PlaybackTarget`]: trait.PlaybackTarget.html
+//! [`Queue`]: struct.Queue.html
+//! [`LocalPlayer`]: struct.LocalPlayer.html
+//! [`JukeboxPlayer`]: struct.JukeboxPlayer.html
+//! [`PlaybackTarget::take_over`]: trait.PlaybackTarget.html#method.take_over
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(all(feature = "playback", feature = "jukebox"))]
+//! # fn run() -> sunk::Result<()> {
+//! use sunk::player::{JukeboxPlayer, LocalPlayer, PlaybackTarget};
+//! use sunk::search::{self, SearchPage};
+//! use sunk::Client;
+//!
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! // The mixer of an output stream opened with rodio, from `stream.mixer()`.
+//! # let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+//!
+//! let page = SearchPage::new().with_size(10);
+//! let songs = client.search("dada", search::NONE, search::NONE, page)?.songs;
+//!
+//! let mut player: Box<dyn PlaybackTarget> = Box::new(LocalPlayer::new(&mixer));
+//! player.play(&client, songs)?;
+//!
+//! // Move to the server's speakers, carrying on with the same queue.
+//! let mut jukebox: Box<dyn PlaybackTarget> = Box::new(JukeboxPlayer::new());
+//! jukebox.take_over(&client, &mut *player)?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+#[cfg(feature = "playback")]
+use std::fmt;
+
+#[cfg(feature = "playback")]
+use rodio::mixer::Mixer;
+
+#[cfg(feature = "playback")]
+use crate::playback;
+#[cfg(feature = "jukebox")]
+use crate::Jukebox;
+use crate::{Client, Error, Result, Song};
+
+/// The songs a [`PlaybackTarget`] plays, and which of them is playing.
+///
+/// [`PlaybackTarget`]: trait.PlaybackTarget.html
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    songs: Vec<Song>,
+    position: usize,
+}
+
+impl Queue {
+    /// Creates an empty queue.
+    pub fn new() -> Queue {
+        Queue::default()
+    }
+
+    /// Returns the songs in the queue, including those already played.
+    pub fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    /// Returns the number of songs in the queue.
+    pub fn len(&self) -> usize {
+        self.songs.len()
+    }
+
+    /// Returns whether the queue has no songs.
+    pub fn is_empty(&self) -> bool {
+        self.songs.is_empty()
+    }
+
+    /// Returns the index of the song playing, or the length of the queue
+    /// once every song has been played.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the song playing, if any.
+    pub fn current(&self) -> Option<&Song> {
+        self.songs.get(self.position)
+    }
+
+    /// Returns the songs after the one playing.
+    pub fn upcoming(&self) -> &[Song] {
+        self.songs.get(self.position + 1..).unwrap_or(&[])
+    }
+
+    /// Replaces the songs in the queue, starting from the first.
+    pub fn replace(&mut self, songs: Vec<Song>) {
+        self.songs = songs;
+        self.position = 0;
+    }
+
+    /// Adds songs to the end of the queue.
+    pub fn extend(&mut self, songs: Vec<Song>) {
+        self.songs.extend(songs);
+    }
+
+    /// Moves the queue to the song at index `n`, or past the end if `n` is the
+    /// length of the queue.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `n` is past the end of the queue.
+    pub fn set_position(&mut self, n: usize) -> Result<()> {
+        if n > self.songs.len() {
+            return Err(Error::Other("position past the end of the queue"));
+        }
+        self.position = n;
+        Ok(())
+    }
+}
+
+/// Plays the songs of a [`Queue`].
+///
+/// Implementations only tell their output what to play; the provided
+/// methods keep the queue and call them, so every player is controlled the
+/// same way.
+///
+/// [`Queue`]: struct.Queue.html
+pub trait PlaybackTarget {
+    /// Returns the songs the player plays.
+    fn queue(&self) -> &Queue;
+
+    /// Returns the songs the player plays, to change them. Call [`load`]
+    /// afterwards to have the player play the changed queue.
+    ///
+    /// [`load`]: #tymethod.load
+    fn queue_mut(&mut self) -> &mut Queue;
+
+    /// Plays the queue from the start of its current song, replacing
+    /// whatever the player was playing.
+    fn load(&mut self, client: &Client) -> Result<()>;
+
+    /// Plays `songs` after what the player is playing. The songs are added to
+    /// the queue once this succeeds.
+    fn append(&mut self, client: &Client, songs: &[Song]) -> Result<()>;
+
+    /// Pauses playback.
+    fn pause(&mut self, client: &Client) -> Result<()>;
+
+    /// Resumes playback.
+    fn resume(&mut self, client: &Client) -> Result<()>;
+
+    /// Sets the volume, from `0.0` to `1.0`.
+    fn set_volume(&mut self, client: &Client, volume: f32) -> Result<()>;
+
+    /// Moves the queue on to the song playing. Call this regularly, such as
+    /// from a UI loop.
+    fn update(&mut self, client: &Client) -> Result<()>;
+
+    /// Replaces the queue with `songs`, and plays them from the first.
+    fn play(&mut self, client: &Client, songs: Vec<Song>) -> Result<()> {
+        self.queue_mut().replace(songs);
+        self.load(client)
+    }
+
+    /// Adds `songs` to the end of the queue. If the queue had played through,
+    /// they are played straight away.
+    fn enqueue(&mut self, client: &Client, songs: Vec<Song>) -> Result<()> {
+        if self.queue().current().is_some() {
+            self.append(client, &songs)?;
+            self.queue_mut().extend(songs);
+            return Ok(());
+        }
+        let start = self.queue().len();
+        self.queue_mut().extend(songs);
+        self.queue_mut().set_position(start)?;
+        self.load(client)
+    }
+
+    /// Plays the song at index `n` of the queue.
+    ///
+    /// # Errors
+    ///
+    /// Aside from errors that the `Client` may cause, the method will error
+    /// if there is no song at index `n`.
+    fn skip_to(&mut self, client: &Client, n: usize) -> Result<()> {
+        if n >= self.queue().len() {
+            return Err(Error::Other("no song at that position in the queue"));
+        }
+        self.queue_mut().set_position(n)?;
+        self.load(client)
+    }
+
+    /// Plays the next song in the queue. Returns `false`, doing nothing, if
+    /// there is none.
+    fn next(&mut self, client: &Client) -> Result<bool> {
+        let n = self.queue().position() + 1;
+        if n >= self.queue().len() {
+            return Ok(false);
+        }
+        self.skip_to(client, n).map(|_| true)
+    }
+
+    /// Plays the song before the one playing, or the playing song again from
+    /// its start if it is the first.
+    fn previous(&mut self, client: &Client) -> Result<()> {
+        let n = self.queue().position().saturating_sub(1);
+        self.skip_to(client, n)
+    }
+
+    /// Pauses `other`, and plays its queue from the start of its current song
+    /// instead.
+    ///
+    /// This is how playback is moved between this device and the server's
+    /// speakers.
+    fn take_over(&mut self, client: &Client, other: &mut dyn PlaybackTarget) -> Result<()> {
+        other.update(client)?;
+        other.pause(client)?;
+        *self.queue_mut() = other.queue().clone();
+        self.load(client)
+    }
+}
+
+/// Plays the queue on this device, streaming it from the server with a
+/// [`playback::Player`].
+///
+/// [`playback::Player`]: ../playback/struct.Player.html
+#[cfg(feature = "playback")]
+pub struct LocalPlayer {
+    player: playback::Player,
+    queue: Queue,
+}
+
+#[cfg(feature = "playback")]
+impl LocalPlayer {
+    /// Creates a player with nothing queued, playing into `mixer`.
+    pub fn new(mixer: &Mixer) -> LocalPlayer {
+        LocalPlayer::with_player(playback::Player::new(mixer))
+    }
+
+    /// Plays the queue with `player`, as set up with its ReplayGain,
+    /// prefetch and warming settings. Anything `player` has queued is
+    /// cleared on the next [`load`].
+    ///
+    /// [`load`]: trait.PlaybackTarget.html#tymethod.load
+    pub fn with_player(player: playback::Player) -> LocalPlayer {
+        LocalPlayer {
+            player,
+            queue: Queue::new(),
+        }
+    }
+
+    /// Returns the player the queue is played with.
+    pub fn player(&self) -> &playback::Player {
+        &self.player
+    }
+}
+
+#[cfg(feature = "playback")]
+impl PlaybackTarget for LocalPlayer {
+    fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    fn queue_mut(&mut self) -> &mut Queue {
+        &mut self.queue
+    }
+
+    fn load(&mut self, client: &Client) -> Result<()> {
+        self.player.clear();
+        if let Some(song) = self.queue.current() {
+            self.player.enqueue(client, song)?;
+            for song in self.queue.upcoming() {
+                self.player.queue(song.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, _: &Client, songs: &[Song]) -> Result<()> {
+        for song in songs {
+            self.player.queue(song.clone());
+        }
+        Ok(())
+    }
+
+    fn pause(&mut self, _: &Client) -> Result<()> {
+        self.player.sink().pause();
+        Ok(())
+    }
+
+    fn resume(&mut self, _: &Client) -> Result<()> {
+        self.player.sink().play();
+        Ok(())
+    }
+
+    fn set_volume(&mut self, _: &Client, volume: f32) -> Result<()> {
+        self.player.sink().set_volume(volume);
+        Ok(())
+    }
+
+    fn update(&mut self, client: &Client) -> Result<()> {
+        self.player.update(client)?;
+        // The songs from the current one on are either in the sink or yet to
+        // be streamed.
+        let left = self.player.upcoming().len() + self.player.sink().len();
+        let position = self.queue.len().saturating_sub(left);
+        self.queue.set_position(position)
+    }
+}
+
+#[cfg(feature = "playback")]
+impl fmt::Debug for LocalPlayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalPlayer")
+            .field("player", &self.player)
+            .field("queued", &self.queue.len())
+            .field("position", &self.queue.position())
+            .finish()
+    }
+}
+
+/// Plays the queue on the server's speakers, through its [`Jukebox`].
+///
+/// The user must be allowed to control the jukebox.
+///
+/// [`Jukebox`]: ../struct.Jukebox.html
+#[cfg(feature = "jukebox")]
+#[derive(Debug, Default)]
+pub struct JukeboxPlayer {
+    queue: Queue,
+}
+
+#[cfg(feature = "jukebox")]
+impl JukeboxPlayer {
+    /// Creates a player with nothing queued.
+    ///
+    /// Nothing is sent to the server until the player is first loaded, so
+    /// whatever the jukebox is playing carries on until then.
+    pub fn new() -> JukeboxPlayer {
+        JukeboxPlayer::default()
+    }
+}
+
+#[cfg(feature = "jukebox")]
+impl PlaybackTarget for JukeboxPlayer {
+    fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    fn queue_mut(&mut self) -> &mut Queue {
+        &mut self.queue
+    }
+
+    fn load(&mut self, client: &Client) -> Result<()> {
+        let jukebox = Jukebox::start(client);
+        jukebox.set_all(self.queue.songs())?;
+        if self.queue.current().is_some() {
+            jukebox.skip_to(self.queue.position())?;
+            jukebox.play()?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, client: &Client, songs: &[Song]) -> Result<()> {
+        Jukebox::start(client).add_all(songs).map(|_| ())
+    }
+
+    fn pause(&mut self, client: &Client) -> Result<()> {
+        Jukebox::start(client).stop().map(|_| ())
+    }
+
+    fn resume(&mut self, client: &Client) -> Result<()> {
+        Jukebox::start(client).play().map(|_| ())
+    }
+
+    fn set_volume(&mut self, client: &Client, volume: f32) -> Result<()> {
+        Jukebox::start(client).set_volume(volume).map(|_| ())
+    }
+
+    fn update(&mut self, client: &Client) -> Result<()> {
+        let status = Jukebox::start(client).status()?;
+        if status.index >= 0 {
+            let position = (status.index as usize).min(self.queue.len());
+            self.queue.set_position(position)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::song;
+
+    fn ids(songs: &[Song]) -> Vec<&str> {
+        songs.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    /// Records what it is told to play.
+    #[derive(Default)]
+    struct Recorder {
+        queue: Queue,
+        loads: Vec<usize>,
+        appended: Vec<String>,
+        paused: bool,
+    }
+
+    impl PlaybackTarget for Recorder {
+        fn queue(&self) -> &Queue {
+            &self.queue
+        }
+
+        fn queue_mut(&mut self) -> &mut Queue {
+            &mut self.queue
+        }
+
+        fn load(&mut self, _: &Client) -> Result<()> {
+            self.loads.push(self.queue.position());
+            Ok(())
+        }
+
+        fn append(&mut self, _: &Client, songs: &[Song]) -> Result<()> {
+            self.appended.extend(songs.iter().map(|s| s.id.to_string()));
+            Ok(())
+        }
+
+        fn pause(&mut self, _: &Client) -> Result<()> {
+            self.paused = true;
+            Ok(())
+        }
+
+        fn resume(&mut self, _: &Client) -> Result<()> {
+            self.paused = false;
+            Ok(())
+        }
+
+        fn set_volume(&mut self, _: &Client, _: f32) -> Result<()> {
+            Ok(())
+        }
+
+        fn update(&mut self, _: &Client) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn queue() {
+        let mut queue = Queue::new();
+        assert!(queue.current().is_none() && queue.upcoming().is_empty());
+        queue.replace(vec![song("1"), song("2"), song("3")]);
+        assert_eq!(queue.current().unwrap().id.as_str(), "1");
+        assert_eq!(ids(queue.upcoming()), ["2", "3"]);
+
+        queue.set_position(3).unwrap();
+        assert!(queue.current().is_none() && queue.upcoming().is_empty());
+        assert!(queue.set_position(4).is_err());
+    }
+
+    #[test]
+    fn controls() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let mut player = Recorder::default();
+        player.play(&client, vec![song("1"), song("2")]).unwrap();
+        player.enqueue(&client, vec![song("3")]).unwrap();
+        assert_eq!(player.appended, ["3"]);
+        assert_eq!(ids(player.queue().songs()), ["1", "2", "3"]);
+
+        assert!(player.next(&client).unwrap());
+        assert!(player.next(&client).unwrap());
+        assert!(!player.next(&client).unwrap());
+        player.previous(&client).unwrap();
+        assert!(player.skip_to(&client, 3).is_err());
+        assert_eq!(player.loads, [0, 1, 2, 1]);
+
+        // Songs added once the queue has played through are played at once.
+        player.queue_mut().set_position(3).unwrap();
+        player.enqueue(&client, vec![song("4")]).unwrap();
+        assert_eq!(player.appended, ["3"]);
+        assert_eq!(player.loads.last(), Some(&3));
+
+        let mut other = Recorder::default();
+        other.take_over(&client, &mut player).unwrap();
+        assert!(player.paused);
+        assert_eq!(other.queue().current().unwrap().id.as_str(), "4");
+        assert_eq!(other.loads, [3]);
+    }
+
+    #[cfg(feature = "jukebox")]
+    #[test]
+    fn jukebox() {
+        use crate::query::Query;
+
+        let control = |args: Query, index: isize| {
+            let body = serde_json::json!({
+                "jukeboxStatus": {
+                    "currentIndex": index, "playing": true, "gain": 0.5, "position": 0,
+                },
+            });
+            ("jukeboxControl", args, body)
+        };
+        let client = crate::test_util::replay_client(&[
+            control(
+                Query::with("action", "set")
+                    .arg_list("id", &["1", "2"])
+                    .build(),
+                -1,
+            ),
+            control(Query::with("action", "skip").arg("index", 1).build(), 1),
+            control(Query::with("action", "start"), 1),
+            control(Query::with("action", "status"), 0),
+        ]);
+        let mut player = JukeboxPlayer::new();
+        player.queue_mut().replace(vec![song("1"), song("2")]);
+        player.skip_to(&client, 1).unwrap();
+        player.update(&client).unwrap();
+        assert_eq!(player.queue().position(), 0);
+    }
+}
@@ -0,0 +1,231 @@
+//! Progress reporting for long-running operations.
+//!
+//! Downloads, cover art prefetching, mirror syncs and library scans can take
+//! a while. Each of them reports how far along it is to a
+//! [`ProgressObserver`]: once when it starts, as bytes arrive or items are
+//! done, and once when it finishes or fails.
+//!
+//! Pass `()` to ignore progress, a closure wrapped in [`from_fn`] to only
+//! watch updates, or a type of your own to handle every event.
+//!
+//! [`ProgressObserver`]: trait.ProgressObserver.html
+//! [`from_fn`]: fn.from_fn.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::progress;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let album = client.albums().get("1")?;
+//!
+//! album.download_to(&client, "music", progress::from_fn(|p| {
+//!     if let Some(fraction) = p.fraction() {
+//!         println!("{:.0}%", fraction * 100.0);
+//!     }
+//! }))?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use crate::{Error, Result};
+
+/// How far along an operation is.
+///
+/// Operations that transfer files count `bytes`; operations over a number of
+/// things, such as the songs of an album, count `items`. Some do both. The
+/// totals are `None` until, or unless, they are known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes transferred so far.
+    pub bytes: u64,
+    /// Bytes expected in all.
+    pub total_bytes: Option<u64>,
+    /// Items done so far.
+    pub items: u64,
+    /// Items expected in all.
+    pub total_items: Option<u64>,
+}
+
+impl Progress {
+    /// Returns the fraction of the operation done, between 0 and 1.
+    ///
+    /// Bytes are a finer measure than items, so are preferred when their total
+    /// is known. Returns `None` if neither total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        let (done, total) = match (self.total_bytes, self.total_items) {
+            (Some(total), _) if total > 0 => (self.bytes, total),
+            (_, Some(total)) if total > 0 => (self.items, total),
+            (Some(_), _) | (_, Some(_)) => return Some(1.0),
+            (None, None) => return None,
+        };
+        Some((done as f64 / total as f64).min(1.0))
+    }
+}
+
+/// Something told about the progress of a long-running operation.
+///
+/// Every method does nothing by default, so implementors only handle the
+/// events they care about. An operation calls [`started`] once, then
+/// [`progress`] any number of times, then exactly one of [`finished`] or
+/// [`failed`].
+///
+/// [`started`]: #method.started
+/// [`progress`]: #method.progress
+/// [`finished`]: #method.finished
+/// [`failed`]: #method.failed
+pub trait ProgressObserver {
+    /// Called when the operation starts, with any totals already known.
+    fn started(&mut self, _progress: &Progress) {}
+
+    /// Called as bytes arrive or items are done.
+    fn progress(&mut self, _progress: &Progress) {}
+
+    /// Called when the operation has finished.
+    fn finished(&mut self, _progress: &Progress) {}
+
+    /// Called when the operation fails, with how far it got.
+    fn failed(&mut self, _error: &Error, _progress: &Progress) {}
+}
+
+/// Ignores progress.
+impl ProgressObserver for () {}
+
+impl<O: ProgressObserver + ?Sized> ProgressObserver for &mut O {
+    fn started(&mut self, progress: &Progress) {
+        (**self).started(progress)
+    }
+
+    fn progress(&mut self, progress: &Progress) {
+        (**self).progress(progress)
+    }
+
+    fn finished(&mut self, progress: &Progress) {
+        (**self).finished(progress)
+    }
+
+    fn failed(&mut self, error: &Error, progress: &Progress) {
+        (**self).failed(error, progress)
+    }
+}
+
+/// An observer that calls a closure on every update.
+///
+/// Created by [`from_fn`].
+///
+/// [`from_fn`]: fn.from_fn.html
+#[derive(Debug)]
+pub struct FromFn<F>(F);
+
+/// Returns an observer that calls `f` on every update, including the last
+/// one when the operation finishes. Failures are left to the operation's
+/// result.
+pub fn from_fn<F: FnMut(&Progress)>(f: F) -> FromFn<F> {
+    FromFn(f)
+}
+
+impl<F: FnMut(&Progress)> ProgressObserver for FromFn<F> {
+    fn progress(&mut self, progress: &Progress) {
+        (self.0)(progress)
+    }
+
+    fn finished(&mut self, progress: &Progress) {
+        (self.0)(progress)
+    }
+}
+
+/// Tells `observer` how an operation ended, and passes its result on.
+pub(crate) fn finish<T, O>(observer: &mut O, progress: &Progress, res: Result<T>) -> Result<T>
+where
+    O: ProgressObserver + ?Sized,
+{
+    match res {
+        Ok(_) => observer.finished(progress),
+        Err(ref e) => observer.failed(e, progress),
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl ProgressObserver for Events {
+        fn started(&mut self, p: &Progress) {
+            self.0.push(format!("started {}", p.items))
+        }
+
+        fn progress(&mut self, p: &Progress) {
+            self.0.push(format!("progress {}", p.items))
+        }
+
+        fn finished(&mut self, p: &Progress) {
+            self.0.push(format!("finished {}", p.items))
+        }
+
+        fn failed(&mut self, e: &Error, _: &Progress) {
+            self.0.push(format!("failed {}", e))
+        }
+    }
+
+    #[test]
+    fn fraction() {
+        let mut p = Progress::default();
+        assert_eq!(p.fraction(), None);
+
+        p.total_items = Some(4);
+        p.items = 1;
+        assert_eq!(p.fraction(), Some(0.25));
+
+        // Bytes are preferred once their total is known.
+        p.total_bytes = Some(200);
+        p.bytes = 150;
+        assert_eq!(p.fraction(), Some(0.75));
+
+        p.total_bytes = Some(0);
+        p.total_items = Some(0);
+        assert_eq!(p.fraction(), Some(1.0));
+    }
+
+    #[test]
+    fn finish_reports_outcome() {
+        let mut events = Events::default();
+        let p = Progress {
+            items: 2,
+            ..Progress::default()
+        };
+        events.started(&Progress::default());
+        assert!(finish(&mut events, &p, Ok(())).is_ok());
+        assert!(finish::<(), _>(&mut events, &p, Err(Error::Other("boom"))).is_err());
+        assert_eq!(events.0, ["started 0", "finished 2", "failed boom"]);
+    }
+
+    #[test]
+    fn closure_sees_updates() {
+        let mut seen = Vec::new();
+        {
+            let mut observer = from_fn(|p: &Progress| seen.push(p.bytes));
+            let observer: &mut dyn ProgressObserver = &mut observer;
+            observer.started(&Progress::default());
+            observer.progress(&Progress {
+                bytes: 10,
+                ..Progress::default()
+            });
+            observer.finished(&Progress {
+                bytes: 20,
+                ..Progress::default()
+            });
+        }
+        assert_eq!(seen, [10, 20]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Using the client as a protocol layer over another HTTP stack.
+//!
+//! Applications that already have an HTTP client, such as an async one, can
+//! let sunk build requests and parse responses while sending them
+//! themselves. [`Client::http_request`] builds the request for an endpoint,
+//! and [`Client::parse_response`] reads what the server sent back.
+//!
+//! [`Client::http_request`]: ../struct.Client.html#method.http_request
+//! [`Client::parse_response`]: ../struct.Client.html#method.parse_response
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::collections::HashMap;
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn send(_: sunk::protocol::Request) -> Vec<u8> { Vec::new() }
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! let request = client.http_request("getRandomSongs", &[("size", "20")])?;
+//! // Send the request with your own HTTP client...
+//! let body = send(request);
+//!
+//! let mut random = client.parse_response_as::<HashMap<String, Vec<Song>>>(&body)?;
+//! let songs = random.remove("song").unwrap_or_default();
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use serde::de::DeserializeOwned;
+
+use crate::client::FORM;
+use crate::de;
+use crate::query::Query;
+use crate::{Client, Error, Result};
+
+/// A request built by [`Client::http_request`].
+///
+/// [`Client::http_request`]: ../struct.Client.html#method.http_request
+pub type Request = http::Request<Vec<u8>>;
+
+impl Client {
+    /// Builds the HTTP request for an endpoint, with authentication, without
+    /// sending it.
+    ///
+    /// `endpoint` is the name of a method of the [Subsonic API], such as
+    /// `"getAlbum"`, and `args` its arguments. The request is a `GET`, or a
+    /// `POST` with the arguments in its body if they are longer than the
+    /// client's [POST threshold].
+    ///
+    /// [Subsonic API]: http://www.subsonic.org/pages/api.jsp
+    /// [POST threshold]: #method.with_post_threshold
+    pub fn http_request<K, V>(&self, endpoint: &str, args: &[(K, V)]) -> Result<Request>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut query = Query::new();
+        for (key, value) in args {
+            query.arg(key.as_ref(), value.as_ref());
+        }
+
+        let (url, body) = self.prepare(endpoint, query.build())?;
+        let request = match body {
+            Some(body) => http::Request::post(url.as_str())
+                .header(http::header::CONTENT_TYPE, FORM)
+                .body(body.into_bytes()),
+            None => http::Request::get(url.as_str()).body(Vec::new()),
+        };
+        request.map_err(|_| Error::Other("unable to build request"))
+    }
+
+    /// Reads the body of a response from the server.
+    ///
+    /// Returns the content of the response, such as the `album` object of a
+    /// response to `getAlbum`, or the error the server returned. Empty
+    /// responses, such as those to `star`, are returned as `Value::Null`.
+    pub fn parse_response(&self, body: &[u8]) -> Result<serde_json::Value> {
+        self.parse_body(&String::from_utf8_lossy(body))
+    }
+
+    /// Reads the body of a response from the server into `T`.
+    ///
+    /// Responses are parsed as with [`parse_response`], following the
+    /// client's settings, such as [lenient lists].
+    ///
+    /// [`parse_response`]: #method.parse_response
+    /// [lenient lists]: #method.with_lenient_lists
+    pub fn parse_response_as<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T> {
+        de::from_value(self, self.parse_response(body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiError;
+
+    #[test]
+    fn request_with_long_arguments() {
+        let client = Client::new("http://localhost", "user", "pass")
+            .unwrap()
+            .with_post_threshold(20);
+
+        let short = client.http_request("getAlbum", &[("id", "1")]).unwrap();
+        assert_eq!(short.method(), http::Method::GET);
+        assert!(short.uri().query().unwrap().ends_with("&id=1"));
+
+        let ids = vec![("songId", "1234567890"); 4];
+        let long = client.http_request("createPlaylist", &ids).unwrap();
+        assert_eq!(long.method(), http::Method::POST);
+        assert_eq!(long.headers()[http::header::CONTENT_TYPE], FORM);
+        assert_eq!(long.body().len(), 4 * "songId=1234567890".len() + 3);
+    }
+
+    #[test]
+    fn parse_responses() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+
+        let ok = br#"{"subsonic-response": {
+            "status": "ok", "version": "1.16.1",
+            "genres": {"genre": [{"value": "Jazz", "songCount": 3, "albumCount": 1}]}
+        }}"#;
+        let genres = client.parse_response(ok).unwrap();
+        assert_eq!(genres["genre"][0]["value"], "Jazz");
+
+        let failed = br#"{"subsonic-response": {
+            "status": "failed", "version": "1.16.1",
+            "error": {"code": 70, "message": "Album not found"}
+        }}"#;
+        match client.parse_response(failed) {
+            Err(Error::Api(ApiError::NotFound)) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+}
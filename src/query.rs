@@ -1,7 +1,12 @@
 use std::{fmt, iter};
 
+use url::form_urlencoded;
+
+/// Arguments whose values are hidden from errors and logs.
+const SECRETS: &[&str] = &["password", "p", "t", "s", "token", "apiKey"];
+
 /// An expandable query set for an API call.
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Query {
     inner: Vec<(String, Arg)>,
 }
@@ -104,26 +109,89 @@ impl iter::Extend<(String, Arg)> for Query {
     }
 }
 
+impl Query {
+    /// Returns `true` if the query sets `key`.
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.inner.iter().any(|(k, arg)| k == key && arg.is_some())
+    }
+
+    /// Returns the first value set for `key`, if any.
+    pub(crate) fn value(&self, key: &str) -> Option<&str> {
+        self.inner
+            .iter()
+            .filter(|(k, _)| k == key)
+            .find_map(|(_, arg)| arg.0.as_deref())
+    }
+
+    /// Formats the query like `Display`, with the values of passwords and
+    /// other secrets hidden, for errors and logs.
+    pub(crate) fn sanitized(&self) -> String {
+        let hidden = self.inner.iter().map(|(key, arg)| match arg.0 {
+            Some(_) if SECRETS.contains(&key.as_str()) => (key.clone(), Arg(Some("***".into()))),
+            _ => (key.clone(), arg.clone()),
+        });
+        Query {
+            inner: hidden.collect(),
+        }
+        .to_string()
+    }
+
+    /// Returns the arguments that are set, unencoded, with the values of
+    /// passwords and other secrets hidden as in `sanitized`.
+    pub(crate) fn sanitized_pairs(&self) -> Vec<(String, String)> {
+        self.inner
+            .iter()
+            .filter_map(|(key, arg)| {
+                let value = arg.0.as_ref()?;
+                let value = if SECRETS.contains(&key.as_str()) {
+                    "***".to_owned()
+                } else {
+                    value.clone()
+                };
+                Some((key.clone(), value))
+            })
+            .collect()
+    }
+}
+
+/// Formats the query as a URL query string, percent-encoding keys and values.
+/// Arguments without a value are left out.
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (n, a) in self.inner.iter().enumerate() {
-            if a.1.is_some() {
-                write!(f, "{}={}", a.0, a.1)?;
-                if n + 1 < self.inner.len() {
-                    write!(f, "&")?;
-                }
+        let args = self
+            .inner
+            .iter()
+            .filter_map(|(key, arg)| arg.0.as_ref().map(|value| (key, value)));
+        for (n, (key, value)) in args.enumerate() {
+            if n > 0 {
+                f.write_str("&")?;
             }
+            write!(f, "{}={}", encode(key), encode(value))?;
         }
         Ok(())
     }
 }
 
+/// Percent-encodes a key or value for a URL query string.
+///
+/// Everything but ASCII letters, digits and `*-._` is encoded, spaces
+/// included as `%20`. Forms encode spaces as `+`, but not every server or
+/// proxy decodes it, where `%20` is read the same everywhere.
+pub(crate) fn encode(s: &str) -> String {
+    // `+` itself is encoded as `%2B`, so any left stand for spaces.
+    form_urlencoded::byte_serialize(s.as_bytes())
+        .map(|part| if part == "+" { "%20" } else { part })
+        .collect()
+}
+
 impl Default for Query {
     fn default() -> Query {
         Query::new()
     }
 }
 
+/// The value of an argument to an API call. An unset argument is left out of
+/// the query.
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub struct Arg(Option<String>);
 
@@ -143,7 +211,9 @@ impl fmt::Display for Arg {
     }
 }
 
+/// A type that can be the value of an argument to an API call.
 pub trait IntoArg {
+    /// Converts the value to an argument.
     fn into_arg(self) -> Arg;
 }
 
@@ -231,6 +301,58 @@ mod tests {
         assert_eq!("id=64", &format!("{}", q));
     }
 
+    #[test]
+    fn sanitized_query() {
+        let q = Query::with("username", "bob")
+            .arg("password", "hunter2")
+            .build();
+        assert_eq!(q.sanitized(), "username=bob&password=***");
+    }
+
+    #[test]
+    fn trailing_optional_query() {
+        let q = Query::new().arg("id", 64).arg("album", Arg(None)).build();
+        assert_eq!("id=64", &format!("{}", q));
+    }
+
+    #[test]
+    fn encoded_query() {
+        let q = Query::new()
+            .arg("query", "Simon & Garfunkel")
+            .arg("genre", "Rock+Roll")
+            .arg("artist", "Björk")
+            .build();
+        assert_eq!(
+            "query=Simon%20%26%20Garfunkel&genre=Rock%2BRoll&artist=Bj%C3%B6rk",
+            &format!("{}", q)
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn query_round_trips(args in proptest::collection::vec((".*", ".*"), 0..8)) {
+            let mut q = Query::new();
+            for (key, value) in &args {
+                q.arg(key, value.as_str());
+            }
+
+            let parsed = form_urlencoded::parse(q.build().to_string().as_bytes())
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect::<Vec<_>>();
+            proptest::prop_assert_eq!(parsed, args);
+        }
+
+        #[test]
+        fn encoding_is_strict(s in ".*") {
+            let encoded = encode(&s);
+            let unreserved = |c: char| c.is_ascii_alphanumeric() || "*-._%".contains(c);
+            proptest::prop_assert!(encoded.chars().all(unreserved), "{}", encoded);
+
+            let decoded = percent_encoding::percent_decode_str(&encoded).decode_utf8();
+            proptest::prop_assert_eq!(decoded.unwrap(), s.as_str());
+        }
+    }
+
     #[test]
     fn query_vec() {
         let ids = &[1, 2, 3, 4];
@@ -0,0 +1,246 @@
+//! Known deviations of Subsonic server implementations.
+//!
+//! Many servers implement the Subsonic API besides Subsonic itself, and each
+//! deviates from it in its own ways: some leave out endpoints, and some send
+//! responses that don't quite match the documented schema. A [`Quirks`]
+//! profile describes these deviations for one implementation, so that the
+//! [`Client`] can account for them.
+//!
+//! [`Quirks`]: ../struct.Quirks.html
+//! [`Client`]: ../struct.Client.html
+
+use std::fmt;
+
+/// A Subsonic server implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerKind {
+    /// Subsonic itself, or a server that doesn't identify itself.
+    Subsonic,
+    /// Navidrome.
+    Navidrome,
+    /// Airsonic or Airsonic-Advanced.
+    Airsonic,
+    /// gonic.
+    Gonic,
+    /// Ampache's Subsonic API.
+    Ampache,
+    /// Another server, by the name it reports.
+    Other(String),
+}
+
+impl ServerKind {
+    /// Identifies a server from the `type` it reports in its responses.
+    pub fn from_type(server_type: &str) -> ServerKind {
+        let lower = server_type.to_lowercase();
+        if lower.is_empty() || lower == "subsonic" {
+            ServerKind::Subsonic
+        } else if lower.contains("navidrome") {
+            ServerKind::Navidrome
+        } else if lower.contains("airsonic") {
+            ServerKind::Airsonic
+        } else if lower.contains("gonic") {
+            ServerKind::Gonic
+        } else if lower.contains("ampache") {
+            ServerKind::Ampache
+        } else {
+            ServerKind::Other(server_type.to_string())
+        }
+    }
+}
+
+impl fmt::Display for ServerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ServerKind::Subsonic => "Subsonic",
+            ServerKind::Navidrome => "Navidrome",
+            ServerKind::Airsonic => "Airsonic",
+            ServerKind::Gonic => "gonic",
+            ServerKind::Ampache => "Ampache",
+            ServerKind::Other(ref s) => s,
+        })
+    }
+}
+
+/// Endpoints for video, which only Subsonic and its forks implement.
+const VIDEO: &[&str] = &["getVideos", "getVideoInfo", "getCaptions", "hls"];
+
+/// Endpoints for the chat, which only Subsonic and its forks implement.
+const CHAT: &[&str] = &["getChatMessages", "addChatMessage"];
+
+/// Endpoints for creating and changing users. Servers that leave them out
+/// may still list users with `getUser` and `getUsers`.
+const USER_ADMIN: &[&str] = &["createUser", "updateUser", "deleteUser", "changePassword"];
+
+/// How a server deviates from the Subsonic API.
+///
+/// The client uses its profile to decide how strictly to parse responses, and
+/// to refuse requests to endpoints the server is known not to implement
+/// without a round trip to the server.
+///
+/// A profile can be detected from the server with
+/// [`Client::detect_quirks`], or set by hand with [`Client::with_quirks`].
+///
+/// [`Client::detect_quirks`]: ./struct.Client.html#method.detect_quirks
+/// [`Client::with_quirks`]: ./struct.Client.html#method.with_quirks
+///
+/// # Examples
+///
+/// ```
+/// use sunk::{Quirks, ServerKind};
+///
+/// let quirks = Quirks::for_server(ServerKind::Navidrome);
+/// assert!(quirks.lenient_lists());
+/// assert!(!quirks.supports("getVideos"));
+/// assert!(quirks.supports("getAlbumList2"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Quirks {
+    server: ServerKind,
+    lenient_lists: bool,
+    directory_fallback: bool,
+    empty_search: bool,
+    unsupported: Vec<&'static str>,
+}
+
+impl Quirks {
+    /// Returns the profile of a server implementation.
+    ///
+    /// Servers the crate doesn't know about are assumed to follow the
+    /// Subsonic API.
+    pub fn for_server(server: ServerKind) -> Quirks {
+        let (lenient_lists, unsupported): (bool, &[&[&str]]) = match server {
+            ServerKind::Subsonic | ServerKind::Airsonic | ServerKind::Other(_) => (false, &[]),
+            ServerKind::Navidrome => (true, &[VIDEO, CHAT, USER_ADMIN]),
+            ServerKind::Gonic => (
+                true,
+                &[VIDEO, CHAT, USER_ADMIN, &["getUsers", "jukeboxControl"]],
+            ),
+            ServerKind::Ampache => (true, &[VIDEO, CHAT, &["jukeboxControl"]]),
+        };
+
+        // Subsonic only organised the library by ID3 tags from API 1.8.0, and
+        // unknown servers may be forks of an older version.
+        let directory_fallback = matches!(server, ServerKind::Subsonic | ServerKind::Other(_));
+        let empty_search = matches!(server, ServerKind::Navidrome | ServerKind::Gonic);
+
+        Quirks {
+            server,
+            lenient_lists,
+            directory_fallback,
+            empty_search,
+            unsupported: unsupported.concat(),
+        }
+    }
+
+    /// Returns the server implementation the profile is for.
+    pub fn server(&self) -> &ServerKind {
+        &self.server
+    }
+
+    /// Returns whether malformed elements of lists are skipped by default.
+    ///
+    /// See [`Client::with_lenient_lists`].
+    ///
+    /// [`Client::with_lenient_lists`]: ./struct.Client.html#method.with_lenient_lists
+    pub fn lenient_lists(&self) -> bool {
+        self.lenient_lists
+    }
+
+    /// Returns whether artists and albums are browsed by folder when the
+    /// server doesn't organise them by ID3 tags.
+    ///
+    /// If set, `getIndexes` and `getMusicDirectory` are used in place of
+    /// `getArtists`, `getArtist` and `getAlbum` when the profile marks those
+    /// as unsupported, when the server answers them saying it doesn't know
+    /// the endpoint or the ID, or when `getArtists` lists no artists. Other
+    /// errors, such as wrong credentials, are returned rather than hidden.
+    pub fn directory_fallback(&self) -> bool {
+        self.directory_fallback
+    }
+
+    /// Sets whether to browse by folder when the server doesn't organise the
+    /// library by ID3 tags. See [`directory_fallback`].
+    ///
+    /// [`directory_fallback`]: #method.directory_fallback
+    pub fn with_directory_fallback(mut self, fallback: bool) -> Quirks {
+        self.directory_fallback = fallback;
+        self
+    }
+
+    /// Returns whether `search3` with an empty query matches every song, so
+    /// that the library can be listed by paging through it.
+    ///
+    /// Subsonic itself answers an empty query with nothing.
+    pub fn empty_search(&self) -> bool {
+        self.empty_search
+    }
+
+    /// Sets whether `search3` with an empty query matches every song. See
+    /// [`empty_search`].
+    ///
+    /// [`empty_search`]: #method.empty_search
+    pub fn with_empty_search(mut self, empty_search: bool) -> Quirks {
+        self.empty_search = empty_search;
+        self
+    }
+
+    /// Returns whether the server implements an endpoint, such as
+    /// `"getVideos"`.
+    pub fn supports(&self, endpoint: &str) -> bool {
+        !self.unsupported.contains(&endpoint)
+    }
+
+    /// Marks an endpoint as unsupported by the server.
+    pub fn without(mut self, endpoint: &'static str) -> Quirks {
+        self.unsupported.push(endpoint);
+        self
+    }
+}
+
+impl Default for Quirks {
+    /// Returns the profile of Subsonic itself, which has no quirks.
+    fn default() -> Quirks {
+        Quirks::for_server(ServerKind::Subsonic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_server() {
+        assert_eq!(ServerKind::from_type("navidrome"), ServerKind::Navidrome);
+        assert_eq!(
+            ServerKind::from_type("Airsonic-Advanced"),
+            ServerKind::Airsonic
+        );
+        assert_eq!(ServerKind::from_type(""), ServerKind::Subsonic);
+        assert_eq!(
+            ServerKind::from_type("funkwhale"),
+            ServerKind::Other("funkwhale".into())
+        );
+    }
+
+    #[test]
+    fn profiles() {
+        let gonic = Quirks::for_server(ServerKind::Gonic);
+        assert!(!gonic.supports("jukeboxControl"));
+        assert!(gonic.supports("getArtists"));
+        assert!(!gonic.directory_fallback());
+        assert!(gonic.empty_search());
+
+        let navidrome = Quirks::for_server(ServerKind::Navidrome);
+        assert!(navidrome.supports("getUser"));
+        assert!(navidrome.supports("getUsers"));
+        assert!(!navidrome.supports("createUser"));
+
+        let subsonic = Quirks::default().without("getLyrics");
+        assert!(!subsonic.lenient_lists());
+        assert!(subsonic.supports("getVideos"));
+        assert!(!subsonic.supports("getLyrics"));
+        assert!(subsonic.directory_fallback());
+        assert!(!subsonic.empty_search());
+        assert!(!subsonic.with_directory_fallback(false).directory_fallback());
+    }
+}
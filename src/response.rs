@@ -1,6 +1,11 @@
+#[cfg(feature = "cache")]
+use reqwest::header::HeaderValue;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::StatusCode;
 use serde_json;
+use std::str;
 
-use crate::ApiError;
+use crate::{ApiError, Error, Result};
 
 /// A top-level response from a Subsonic server.
 #[derive(Debug, Deserialize)]
@@ -15,6 +20,8 @@ pub struct Response {
 struct InnerResponse {
     status: String,
     version: String,
+    #[serde(rename = "type")]
+    server_type: Option<String>,
     error: Option<ApiError>,
     license: Option<serde_json::Value>,
     music_folders: Option<serde_json::Value>,
@@ -52,6 +59,8 @@ struct InnerResponse {
     newest_podcasts: Option<serde_json::Value>,
     jukebox_status: Option<serde_json::Value>,
     jukebox_playlist: Option<serde_json::Value>,
+    lyrics_list: Option<serde_json::Value>,
+    open_subsonic_extensions: Option<serde_json::Value>,
     internet_radio_stations: Option<serde_json::Value>,
     chat_messages: Option<serde_json::Value>,
     user: Option<serde_json::Value>,
@@ -59,6 +68,10 @@ struct InnerResponse {
     bookmarks: Option<serde_json::Value>,
     play_queue: Option<serde_json::Value>,
     scan_status: Option<serde_json::Value>,
+    /// Everything else in the response, such as the payloads of server
+    /// extensions.
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Response {
@@ -104,10 +117,12 @@ impl Response {
             jukebox_status,
             license,
             lyrics,
+            lyrics_list,
             music_folders,
             music_folders,
             newest_podcasts,
             now_playing,
+            open_subsonic_extensions,
             play_queue,
             playlist,
             playlists,
@@ -130,7 +145,14 @@ impl Response {
             video_info,
             videos
         );
-        None
+
+        // Fall back to the payload of an endpoint we don't know, skipping
+        // scalar fields like `serverVersion`.
+        self.inner
+            .other
+            .into_iter()
+            .map(|(_, v)| v)
+            .find(|v| v.is_object() || v.is_array())
     }
 
     /// Extracts the error struct of the response. Returns `None` if the
@@ -139,6 +161,12 @@ impl Response {
         self.inner.error
     }
 
+    /// Returns the name of the server implementation, if the server reports
+    /// it.
+    pub fn server_type(&self) -> Option<&str> {
+        self.inner.server_type.as_deref()
+    }
+
     /// Returns `true` if the response is `"ok"`.
     pub fn is_ok(&self) -> bool {
         self.inner.error.is_none()
@@ -154,6 +182,89 @@ impl Response {
     // self.into_value().is_none() }
 }
 
+/// An unprocessed response from a Subsonic server, such as an image, a
+/// caption file or an HLS playlist.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    /// The body of the response.
+    pub bytes: Vec<u8>,
+    /// The media type of the body, such as `"image/jpeg"`, if the server
+    /// sent it.
+    pub content_type: Option<String>,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The HTTP status of the response.
+    pub status: StatusCode,
+}
+
+impl RawResponse {
+    /// Reads a response sent by the server.
+    pub(crate) fn read(res: reqwest::blocking::Response) -> Result<RawResponse> {
+        let status = res.status();
+        let headers = res.headers().clone();
+        let bytes = res.bytes()?.to_vec();
+        Ok(RawResponse::new(status, headers, bytes))
+    }
+
+    /// Creates a response from its parts, taking the content type from the
+    /// headers.
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap, bytes: Vec<u8>) -> RawResponse {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|t| t.to_str().ok())
+            .map(String::from);
+        RawResponse {
+            bytes,
+            content_type,
+            headers,
+            status,
+        }
+    }
+
+    /// Creates a successful response for an image kept in a cache, guessing
+    /// its content type from its first bytes.
+    #[cfg(feature = "cache")]
+    pub(crate) fn cached_image(bytes: Vec<u8>) -> RawResponse {
+        let mut headers = HeaderMap::new();
+        if let Some(t) = image_type(&bytes) {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(t));
+        }
+        RawResponse::new(StatusCode::OK, headers, bytes)
+    }
+
+    /// Returns the body as text.
+    ///
+    /// # Errors
+    ///
+    /// The method will error if the body isn't valid UTF-8.
+    pub fn text(&self) -> Result<&str> {
+        str::from_utf8(&self.bytes).map_err(|_| Error::Other("response is not UTF-8"))
+    }
+
+    /// Returns `true` if the body is an image.
+    pub fn is_image(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|t| t.starts_with("image/"))
+    }
+}
+
+/// Guesses the media type of an image from its signature.
+#[cfg(feature = "cache")]
+fn image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\xFF\xD8\xFF") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1A\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,6 +287,46 @@ mod tests {
             "version": "1.14.0"
         }}"#;
         let success = serde_json::from_str::<Response>(success).unwrap();
+        assert!(success.server_type().is_none());
         assert!(success.into_error().is_none());
     }
+
+    #[test]
+    fn server_type() {
+        let ping = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "type": "navidrome",
+            "serverVersion": "0.53.3",
+            "openSubsonic": true
+        }}"#;
+        let ping = serde_json::from_str::<Response>(ping).unwrap();
+        assert_eq!(ping.server_type(), Some("navidrome"));
+        assert!(ping.into_value().is_none());
+    }
+
+    #[test]
+    fn unknown_payload() {
+        let res = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "serverVersion": "0.53.3",
+            "openSubsonic": true,
+            "nowPlayingCount": { "count": 3 }
+        }}"#;
+        let res = serde_json::from_str::<Response>(res).unwrap();
+        assert_eq!(res.into_value(), Some(serde_json::json!({ "count": 3 })));
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn cached_image_type() {
+        let png = RawResponse::cached_image(b"\x89PNG\r\n\x1A\n\0\0".to_vec());
+        assert_eq!(png.content_type.as_deref(), Some("image/png"));
+        assert!(png.is_image());
+
+        let unknown = RawResponse::cached_image(b"hello".to_vec());
+        assert_eq!(unknown.content_type, None);
+        assert_eq!(unknown.text().unwrap(), "hello");
+    }
 }
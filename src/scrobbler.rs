@@ -0,0 +1,539 @@
+//! Submitting listens to ListenBrainz and Last.fm directly.
+//!
+//! Subsonic servers scrobble to last.fm themselves, when the user has set up
+//! their credentials on the server. Where that can't be done, such as on a
+//! shared account or a server without the option, a [`Scrobbler`] given to
+//! the client with [`Client::with_scrobbler`] submits the listens itself.
+//!
+//! Scrobblers are told of every song reported through the client with
+//! [`Song::now_playing`] and [`Song::scrobble_at`], after the server has been
+//! told. Submissions are sent from a background thread, and one that fails is
+//! logged and otherwise ignored, so an unreachable scrobbling service never
+//! holds up or fails playback.
+//!
+//! [`Scrobbler`]: trait.Scrobbler.html
+//! [`Client::with_scrobbler`]: ../struct.Client.html#method.with_scrobbler
+//! [`Song::now_playing`]: ../song/struct.Song.html#method.now_playing
+//! [`Song::scrobble_at`]: ../song/struct.Song.html#method.scrobble_at
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::scrobbler::{LastFm, ListenBrainz};
+//! use sunk::song::Song;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let last_fm = LastFm::login("api key", "shared secret", "user", "password")?;
+//! let client = Client::new(site, user, password)?
+//!     .with_scrobbler(ListenBrainz::new("user token"))
+//!     .with_scrobbler(last_fm);
+//!
+//! let song = Song::get(&client, 27)?;
+//! song.scrobble_at(&client, None)?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::sync::mpsc::{self, SendError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::Client as HttpClient;
+use serde_json::{json, Value};
+
+use crate::history::PlayKind;
+use crate::{Error, Result, Song};
+
+/// How long the built-in scrobblers wait for a service to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the HTTP client the built-in scrobblers submit with.
+fn http_client() -> HttpClient {
+    HttpClient::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| HttpClient::new())
+}
+
+/// A service listens are submitted to.
+pub trait Scrobbler: Debug + Send + Sync {
+    /// Reports that `song` has started playing.
+    fn now_playing(&self, song: &Song) -> Result<()>;
+
+    /// Submits a listen of `song`, played at `time`.
+    fn scrobble(&self, song: &Song, time: SystemTime) -> Result<()>;
+}
+
+/// A play waiting to be submitted to the scrobblers.
+struct Submission {
+    song: Song,
+    kind: PlayKind,
+    time: SystemTime,
+}
+
+/// The scrobblers of a client, and the background thread that submits plays
+/// to them.
+///
+/// The thread is started on the first play, and submits plays one at a time
+/// in the order they were made. It stops once the client is dropped and every
+/// play made has been submitted.
+#[derive(Debug, Default)]
+pub(crate) struct Scrobblers {
+    scrobblers: Vec<Arc<dyn Scrobbler>>,
+    queue: Mutex<Option<Sender<Submission>>>,
+}
+
+impl Scrobblers {
+    /// Adds a scrobbler to submit plays to.
+    pub(crate) fn push(&mut self, scrobbler: Arc<dyn Scrobbler>) {
+        self.scrobblers.push(scrobbler);
+        // A running thread only knows the scrobblers it was started with, so
+        // leave it to finish its plays and start another on the next play.
+        *self.queue.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Returns the scrobblers plays are submitted to.
+    pub(crate) fn as_slice(&self) -> &[Arc<dyn Scrobbler>] {
+        &self.scrobblers
+    }
+
+    /// Queues a play to be submitted to every scrobbler.
+    pub(crate) fn submit(&self, song: &Song, kind: PlayKind, time: SystemTime) {
+        if self.scrobblers.is_empty() {
+            return;
+        }
+        let submission = Submission {
+            song: song.clone(),
+            kind,
+            time,
+        };
+
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let submission = match *queue {
+            Some(ref tx) => match tx.send(submission) {
+                Ok(()) => return,
+                // The thread is gone, so start another.
+                Err(SendError(submission)) => submission,
+            },
+            None => submission,
+        };
+
+        let (tx, rx) = mpsc::channel::<Submission>();
+        let scrobblers = self.scrobblers.clone();
+        let started = thread::Builder::new()
+            .name("sunk-scrobbler".into())
+            .spawn(move || {
+                for play in rx {
+                    for scrobbler in &scrobblers {
+                        let res = match play.kind {
+                            PlayKind::NowPlaying => scrobbler.now_playing(&play.song),
+                            PlayKind::Scrobble => scrobbler.scrobble(&play.song, play.time),
+                        };
+                        if let Err(e) = res {
+                            warn!(
+                                "Failed to submit {} to {:?}: {}",
+                                play.song.title, scrobbler, e
+                            );
+                        }
+                    }
+                }
+            });
+        if let Err(e) = started {
+            warn!("Failed to start submitting plays: {}", e);
+            return;
+        }
+        // The receiver only goes away with the thread, which just started.
+        let _ = tx.send(submission);
+        *queue = Some(tx);
+    }
+}
+
+/// Submits listens to [ListenBrainz](https://listenbrainz.org), or a server
+/// with the same API.
+#[derive(Clone)]
+pub struct ListenBrainz {
+    token: String,
+    url: String,
+    http: HttpClient,
+}
+
+impl ListenBrainz {
+    /// The ListenBrainz API.
+    pub const URL: &'static str = "https://api.listenbrainz.org";
+
+    /// Submits listens with the user token from the user's ListenBrainz
+    /// settings.
+    pub fn new<S: Into<String>>(token: S) -> ListenBrainz {
+        ListenBrainz {
+            token: token.into(),
+            url: ListenBrainz::URL.into(),
+            http: http_client(),
+        }
+    }
+
+    /// Submits listens to a server at `url` with the ListenBrainz API, such
+    /// as a self-hosted one, rather than to ListenBrainz itself.
+    pub fn with_url<S: Into<String>>(self, url: S) -> ListenBrainz {
+        let mut lb = self;
+        lb.url = url.into();
+        lb
+    }
+
+    /// Returns the server listens are submitted to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn submit(&self, body: &Value) -> Result<()> {
+        let url = format!("{}/1/submit-listens", self.url.trim_end_matches('/'));
+        let res = self
+            .http
+            .post(&url)
+            .header("Authorization", format!("Token {}", self.token))
+            .json(body)
+            .send()?;
+        if res.status().is_success() {
+            return Ok(());
+        }
+        let status = res.status();
+        let message = res
+            .json::<Value>()
+            .ok()
+            .and_then(|v| v["error"].as_str().map(String::from))
+            .unwrap_or_else(|| status.to_string());
+        Err(Error::Scrobble(format!("ListenBrainz: {}", message)))
+    }
+}
+
+// Leaves the token out, as scrobblers are logged when submissions fail.
+impl fmt::Debug for ListenBrainz {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListenBrainz")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl Scrobbler for ListenBrainz {
+    fn now_playing(&self, song: &Song) -> Result<()> {
+        self.submit(&listens("playing_now", song, None)?)
+    }
+
+    fn scrobble(&self, song: &Song, time: SystemTime) -> Result<()> {
+        self.submit(&listens("single", song, Some(time))?)
+    }
+}
+
+/// Returns the body of a ListenBrainz submission of a listen of `song`.
+fn listens(kind: &str, song: &Song, time: Option<SystemTime>) -> Result<Value> {
+    let artist = song
+        .artist
+        .as_deref()
+        .ok_or(Error::Other("song has no artist to submit"))?;
+
+    let mut info = json!({
+        "submission_client": "sunk",
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(ref mbid) = song.musicbrainz_id {
+        info["recording_mbid"] = mbid.as_str().into();
+    }
+    if let Some(duration) = song.duration() {
+        info["duration_ms"] = (duration.as_millis() as u64).into();
+    }
+    if let Some(track) = song.track {
+        info["tracknumber"] = track.into();
+    }
+
+    let mut metadata = json!({
+        "artist_name": artist,
+        "track_name": song.title,
+        "additional_info": info,
+    });
+    if let Some(ref album) = song.album {
+        metadata["release_name"] = album.to_string().into();
+    }
+    let mut listen = json!({ "track_metadata": metadata });
+    if let Some(time) = time {
+        listen["listened_at"] = epoch_secs(time).into();
+    }
+    Ok(json!({ "listen_type": kind, "payload": [listen] }))
+}
+
+/// Submits listens to [Last.fm](https://www.last.fm), or a server with the
+/// same API such as Libre.fm.
+///
+/// Last.fm needs an API account, for the API key and shared secret, and a
+/// session key for the user, which [`login`] gets.
+///
+/// [`login`]: #method.login
+#[derive(Clone)]
+pub struct LastFm {
+    api_key: String,
+    secret: String,
+    session_key: String,
+    url: String,
+    http: HttpClient,
+}
+
+impl LastFm {
+    /// The Last.fm API.
+    pub const URL: &'static str = "https://ws.audioscrobbler.com/2.0/";
+
+    /// Submits listens for the user the session key `session_key` was issued
+    /// to.
+    pub fn new<S: Into<String>>(api_key: S, secret: S, session_key: S) -> LastFm {
+        LastFm {
+            api_key: api_key.into(),
+            secret: secret.into(),
+            session_key: session_key.into(),
+            url: LastFm::URL.into(),
+            http: http_client(),
+        }
+    }
+
+    /// Logs in to Last.fm as `user`, and submits listens for them.
+    ///
+    /// The session key doesn't expire, so can be kept, with
+    /// [`session_key`], to skip logging in next time.
+    ///
+    /// [`session_key`]: #method.session_key
+    ///
+    /// # Errors
+    ///
+    /// Errors if Last.fm rejects the API account or the user's credentials.
+    pub fn login(api_key: &str, secret: &str, user: &str, password: &str) -> Result<LastFm> {
+        let mut last_fm = LastFm::new(api_key, secret, "");
+        let mut params = BTreeMap::new();
+        params.insert("method", "auth.getMobileSession".to_string());
+        params.insert("username", user.into());
+        params.insert("password", password.into());
+        let res = last_fm.call(params)?;
+        last_fm.session_key = res["session"]["key"]
+            .as_str()
+            .ok_or(Error::Other("Last.fm sent no session key"))?
+            .into();
+        Ok(last_fm)
+    }
+
+    /// Submits listens to a server at `url` with the Last.fm API, rather
+    /// than to Last.fm itself.
+    pub fn with_url<S: Into<String>>(self, url: S) -> LastFm {
+        let mut last_fm = self;
+        last_fm.url = url.into();
+        last_fm
+    }
+
+    /// Returns the server listens are submitted to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the user's session key.
+    pub fn session_key(&self) -> &str {
+        &self.session_key
+    }
+
+    /// Returns the parameters Last.fm takes to describe `song`.
+    fn track(&self, method: &str, song: &Song) -> Result<BTreeMap<&'static str, String>> {
+        let artist = song
+            .artist
+            .as_deref()
+            .ok_or(Error::Other("song has no artist to submit"))?;
+        let mut params = BTreeMap::new();
+        params.insert("method", method.to_string());
+        params.insert("artist", artist.to_string());
+        params.insert("track", song.title.clone());
+        params.insert("sk", self.session_key.clone());
+        if let Some(ref album) = song.album {
+            params.insert("album", album.to_string());
+        }
+        if let Some(track) = song.track {
+            params.insert("trackNumber", track.to_string());
+        }
+        if let Some(duration) = song.duration {
+            params.insert("duration", duration.to_string());
+        }
+        if let Some(ref mbid) = song.musicbrainz_id {
+            params.insert("mbid", mbid.clone());
+        }
+        Ok(params)
+    }
+
+    /// Signs and sends a call to the API, and returns its response.
+    fn call(&self, params: BTreeMap<&'static str, String>) -> Result<Value> {
+        let mut params = params;
+        params.insert("api_key", self.api_key.clone());
+        let sig = signature(&params, &self.secret);
+        params.insert("api_sig", sig);
+        params.insert("format", "json".into());
+
+        let res = self.http.post(&self.url).form(&params).send()?;
+        let status = res.status();
+        let body = res.json::<Value>().unwrap_or(Value::Null);
+        if let Some(message) = body["message"]
+            .as_str()
+            .filter(|_| body["error"].is_number())
+        {
+            return Err(Error::Scrobble(format!("Last.fm: {}", message)));
+        }
+        if !status.is_success() {
+            return Err(Error::Scrobble(format!("Last.fm: {}", status)));
+        }
+        Ok(body)
+    }
+}
+
+impl fmt::Debug for LastFm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LastFm").field("url", &self.url).finish()
+    }
+}
+
+impl Scrobbler for LastFm {
+    fn now_playing(&self, song: &Song) -> Result<()> {
+        self.call(self.track("track.updateNowPlaying", song)?)?;
+        Ok(())
+    }
+
+    fn scrobble(&self, song: &Song, time: SystemTime) -> Result<()> {
+        let mut params = self.track("track.scrobble", song)?;
+        params.insert("timestamp", epoch_secs(time).to_string());
+        self.call(params)?;
+        Ok(())
+    }
+}
+
+/// Returns the signature of a Last.fm call: the MD5 of its parameters, in
+/// order of name, followed by the shared secret.
+fn signature(params: &BTreeMap<&'static str, String>, secret: &str) -> String {
+    let mut raw = params
+        .iter()
+        .map(|(k, v)| format!("{}{}", k, v))
+        .collect::<String>();
+    raw.push_str(secret);
+    format!("{:x}", md5::compute(raw))
+}
+
+/// Returns the number of seconds since the Unix epoch, as both services take
+/// times.
+fn epoch_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::Client;
+
+    fn song() -> Song {
+        test_util::song_with(
+            "1",
+            json!({
+                "title": "Bellevue", "artist": "Misteur Valaire", "album": "Bellevue",
+                "track": 1, "duration": 225,
+                "musicBrainzId": "6f6b5c8e-0000-0000-0000-000000000000",
+            }),
+        )
+    }
+
+    #[test]
+    fn listenbrainz_payload() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+        let body = listens("single", &song(), Some(time)).unwrap();
+        let listen = &body["payload"][0];
+        assert_eq!(body["listen_type"], "single");
+        assert_eq!(listen["listened_at"], 1_500_000_000);
+        assert_eq!(listen["track_metadata"]["artist_name"], "Misteur Valaire");
+        assert_eq!(listen["track_metadata"]["release_name"], "Bellevue");
+        let info = &listen["track_metadata"]["additional_info"];
+        assert_eq!(info["duration_ms"], 225_000);
+        assert_eq!(
+            info["recording_mbid"],
+            "6f6b5c8e-0000-0000-0000-000000000000"
+        );
+
+        let playing = listens("playing_now", &song(), None).unwrap();
+        assert!(playing["payload"][0].get("listened_at").is_none());
+    }
+
+    #[test]
+    fn lastfm_signature() {
+        let last_fm = LastFm::new("key", "secret", "session");
+        let params = last_fm.track("track.scrobble", &song()).unwrap();
+        assert_eq!(params["sk"], "session");
+        assert_eq!(params["duration"], "225");
+
+        let mut params = BTreeMap::new();
+        params.insert("method", "auth.getMobileSession".to_string());
+        params.insert("api_key", "key".to_string());
+        let expected = format!(
+            "{:x}",
+            md5::compute("api_keykeymethodauth.getMobileSessionsecret")
+        );
+        assert_eq!(signature(&params, "secret"), expected);
+        assert!(!format!("{:?}", last_fm).contains("secret"));
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Scrobbler for Arc<Recorder> {
+        fn now_playing(&self, song: &Song) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("playing {}", song.title));
+            Ok(())
+        }
+
+        fn scrobble(&self, song: &Song, _: SystemTime) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("scrobbled {}", song.title));
+            Err(Error::Other("unreachable"))
+        }
+    }
+
+    #[test]
+    fn client_reports_plays() {
+        let recorder = Arc::new(Recorder::default());
+        let client = Client::new("http://localhost", "user", "pass")
+            .unwrap()
+            .with_scrobbler(recorder.clone());
+        assert_eq!(client.scrobblers().len(), 1);
+
+        // Plays are submitted in the background.
+        let submitted = |n| {
+            for _ in 0..500 {
+                if recorder.0.lock().unwrap().len() >= n {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            panic!("play not submitted");
+        };
+        // Plays are submitted in the order they were made, and a failed
+        // submission is only logged.
+        client.record_play(&song(), PlayKind::NowPlaying, SystemTime::now());
+        client.record_play(&song(), PlayKind::Scrobble, SystemTime::now());
+        submitted(2);
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            ["playing Bellevue", "scrobbled Bellevue"]
+        );
+    }
+}
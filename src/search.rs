@@ -172,6 +172,8 @@
 
 use crate::song::Song;
 use crate::{Album, Artist};
+#[cfg(feature = "unicode")]
+use std::borrow::Cow;
 use std::fmt;
 
 /// The maximum number of results most searches will accept.
@@ -248,7 +250,7 @@ impl fmt::Display for SearchPage {
 }
 
 /// A holder struct for a search result.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SearchResult {
     /// Artists found in the search.
     #[serde(rename = "artist")]
@@ -263,3 +265,104 @@ pub struct SearchResult {
     #[serde(default)]
     pub songs: Vec<Song>,
 }
+
+/// A Unicode normalization form for search terms.
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalForm {
+    /// Canonical composition, which joins letters and accents typed apart,
+    /// such as `o` followed by a combining diaeresis, into `ö`.
+    #[default]
+    Nfc,
+    /// Compatibility composition, which also replaces variants of characters
+    /// with the characters themselves, such as `ﬁ` with `fi` and full-width
+    /// `Ｂ` with `B`.
+    Nfkc,
+}
+
+/// How search terms are normalized before they are sent.
+///
+/// Servers index names as they are tagged, and compare search terms against
+/// them as they arrive, so a search for "Björk" typed with a combining
+/// accent, or tagged without one, can find nothing. Normalizing terms the way
+/// the library is tagged avoids that. See [`Client::with_query_normalization`].
+///
+/// [`Client::with_query_normalization`]: ../struct.Client.html#method.with_query_normalization
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Normalization {
+    /// The form terms are normalized to.
+    pub form: NormalForm,
+    /// Whether accents are removed from letters, searching for "Bjork" in
+    /// place of "Björk".
+    ///
+    /// Only the combining diacritical marks used by Latin, Greek and Cyrillic
+    /// are removed, so marks that are part of a letter in other scripts, such
+    /// as the dakuten of kana, are kept. Letters that aren't written with a
+    /// separate accent, such as `ø` and `ß`, are kept as they are.
+    pub strip_diacritics: bool,
+}
+
+#[cfg(feature = "unicode")]
+impl Normalization {
+    /// Returns `query` normalized.
+    pub fn apply<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        use icu_normalizer::{ComposingNormalizerBorrowed, DecomposingNormalizerBorrowed};
+
+        let compose = match self.form {
+            NormalForm::Nfc => ComposingNormalizerBorrowed::new_nfc(),
+            NormalForm::Nfkc => ComposingNormalizerBorrowed::new_nfkc(),
+        };
+        if !self.strip_diacritics {
+            return compose.normalize(query);
+        }
+
+        let decompose = match self.form {
+            NormalForm::Nfc => DecomposingNormalizerBorrowed::new_nfd(),
+            NormalForm::Nfkc => DecomposingNormalizerBorrowed::new_nfkd(),
+        };
+        let decomposed = decompose.normalize(query);
+        if !decomposed.chars().any(is_diacritic) {
+            return compose.normalize(query);
+        }
+        let stripped = decomposed
+            .chars()
+            .filter(|&c| !is_diacritic(c))
+            .collect::<String>();
+        Cow::Owned(compose.normalize(&stripped).into_owned())
+    }
+}
+
+/// Returns whether `c` is one of the combining diacritical marks.
+#[cfg(feature = "unicode")]
+fn is_diacritic(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+#[cfg(all(test, feature = "unicode"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization() {
+        let nfc = Normalization::default();
+        assert_eq!(nfc.apply("Bjo\u{308}rk"), "Björk");
+        assert!(matches!(nfc.apply("Björk"), Cow::Borrowed(_)));
+        assert_eq!(nfc.apply("ﬁve"), "ﬁve");
+
+        let nfkc = Normalization {
+            form: NormalForm::Nfkc,
+            ..Normalization::default()
+        };
+        assert_eq!(nfkc.apply("ﬁve Ｂ"), "five B");
+
+        let stripped = Normalization {
+            strip_diacritics: true,
+            ..Normalization::default()
+        };
+        assert_eq!(stripped.apply("Björk, Sigur Rós"), "Bjork, Sigur Ros");
+        assert_eq!(stripped.apply("Bjo\u{308}rk"), "Bjork");
+        assert_eq!(stripped.apply("Røyksopp"), "Røyksopp");
+        assert_eq!(stripped.apply("ガゼット"), "ガゼット");
+    }
+}
@@ -0,0 +1,640 @@
+//! Helpers for sorting artists, albums and songs.
+//!
+//! Music libraries typically file artists such as "The Beatles" under "B"
+//! rather than "T". OpenSubsonic servers provide a `sort_name` on artists and
+//! albums for exactly this purpose; on older servers, the leading article is
+//! stripped using the server's list of ignored articles (see
+//! [`Client::ignored_articles`]).
+//!
+//! [`Client::ignored_articles`]: ../struct.Client.html#method.ignored_articles
+//!
+//! Artists and albums can be grouped into A–Z sections for a list with a
+//! jump bar with [`alphabetical`].
+//!
+//! [`alphabetical`]: fn.alphabetical.html
+//!
+//! Albums and songs can also be put in library order, the way a music player
+//! lists them, with [`album_order`] and [`song_order`].
+//!
+//! [`album_order`]: fn.album_order.html
+//! [`song_order`]: fn.song_order.html
+//!
+//! For sorted views that should read the way a dictionary does, a
+//! [`Collator`] compares names ignoring case and accents except to break
+//! ties, following the alphabet of a [`Language`] where it differs.
+//!
+//! [`Collator`]: struct.Collator.html
+//! [`Language`]: enum.Language.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::{sort, Artist, Client};
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let articles = client.ignored_articles()?;
+//!
+//! let mut artists = Artist::list(&client, None)?;
+//! sort::sort_by_name(&mut artists, &articles);
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::song::Song;
+use crate::{Album, Artist};
+
+/// A named item that can be sorted by name.
+pub trait SortName {
+    /// The name of the item, as displayed.
+    fn name(&self) -> &str;
+
+    /// The name the item should be sorted by, if the server provided one.
+    fn sort_name(&self) -> Option<&str>;
+}
+
+impl SortName for Artist {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sort_name(&self) -> Option<&str> {
+        self.sort_name.as_deref()
+    }
+}
+
+impl SortName for Album {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sort_name(&self) -> Option<&str> {
+        self.sort_name.as_deref()
+    }
+}
+
+impl<T: SortName + ?Sized> SortName for &T {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn sort_name(&self) -> Option<&str> {
+        (**self).sort_name()
+    }
+}
+
+/// Strips a leading article from a name.
+///
+/// Articles are matched case-insensitively, and only when followed by a
+/// space; "Theatre of Tragedy" is not stripped to "atre of Tragedy". Elided
+/// articles ending in an apostrophe, such as "L'", need no space.
+///
+/// # Examples
+///
+/// ```
+/// use sunk::sort::strip_article;
+///
+/// let articles = ["The", "Les", "L'"];
+/// assert_eq!(strip_article("The Beatles", &articles), "Beatles");
+/// assert_eq!(strip_article("Theatre of Tragedy", &articles), "Theatre of Tragedy");
+/// assert_eq!(strip_article("L'Arc~en~Ciel", &articles), "Arc~en~Ciel");
+/// ```
+pub fn strip_article<'a, S: AsRef<str>>(name: &'a str, articles: &[S]) -> &'a str {
+    for article in articles {
+        let article = article.as_ref();
+        let rest = match strip_prefix_ignore_case(name, article) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let elided = article.ends_with(is_apostrophe);
+        if elided && !rest.is_empty() {
+            return rest;
+        }
+        if rest.starts_with(' ') && !rest.trim_start().is_empty() {
+            return rest.trim_start();
+        }
+    }
+    name
+}
+
+/// Returns the rest of `name` if it starts with `prefix`, ignoring case and
+/// the kind of apostrophe used.
+fn strip_prefix_ignore_case<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut chars = name.chars();
+    for p in prefix.chars() {
+        let c = chars.next()?;
+        let same = c == p
+            || (is_apostrophe(c) && is_apostrophe(p))
+            || c.to_lowercase().eq(p.to_lowercase());
+        if !same {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Returns the key an item should be sorted by.
+///
+/// Prefers the item's sort name where the server provided one, otherwise
+/// strips any leading article from the name. The key is lowercased so that
+/// sorting is case-insensitive.
+pub fn sort_key<T: SortName + ?Sized, S: AsRef<str>>(item: &T, articles: &[S]) -> String {
+    match item.sort_name() {
+        Some(sort_name) if !sort_name.is_empty() => sort_name.to_lowercase(),
+        _ => strip_article(item.name(), articles).to_lowercase(),
+    }
+}
+
+/// Sorts a list of artists or albums by name, ignoring leading articles.
+pub fn sort_by_name<T: SortName, S: AsRef<str>>(items: &mut [T], articles: &[S]) {
+    items.sort_by_cached_key(|item| sort_key(item, articles));
+}
+
+/// A group of items whose names start with the same letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<T> {
+    /// The letter the section is filed under: `'A'` to `'Z'`, or `'#'` for
+    /// names that don't start with a letter.
+    pub letter: char,
+    /// The items in the section, sorted by name.
+    pub items: Vec<T>,
+}
+
+/// Groups artists or albums into sections by the first letter of their name.
+///
+/// Takes items by value or by reference, such as the artists of a
+/// [`Mirror`].
+///
+/// [`Mirror`]: ../mirror/struct.Mirror.html
+///
+/// Items are filed and sorted by their [`sort_key`], so "The Beatles" is
+/// found under "B". Accented Latin letters are filed under the plain letter,
+/// and names starting with anything else under `'#'`. Sections are ordered
+/// from A to Z, with `'#'` last; letters without any items are left out.
+///
+/// [`sort_key`]: fn.sort_key.html
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate sunk;
+/// # use sunk::{sort, Artist, Client};
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let articles = client.ignored_articles()?;
+/// let artists = Artist::list(&client, None)?;
+///
+/// for section in sort::alphabetical(artists, &articles) {
+///     println!("{} ({} artists)", section.letter, section.items.len());
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn alphabetical<T, I, S>(items: I, articles: &[S]) -> Vec<Section<T>>
+where
+    T: SortName,
+    I: IntoIterator<Item = T>,
+    S: AsRef<str>,
+{
+    let mut keyed = items
+        .into_iter()
+        .map(|item| {
+            let key = sort_key(&item, articles);
+            (section_letter(&key), key, item)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| {
+        (a.0 == '#', a.0)
+            .cmp(&(b.0 == '#', b.0))
+            .then_with(|| a.1.cmp(&b.1))
+    });
+
+    let mut sections: Vec<Section<T>> = Vec::new();
+    for (letter, _, item) in keyed {
+        match sections.last_mut() {
+            Some(section) if section.letter == letter => section.items.push(item),
+            _ => sections.push(Section {
+                letter,
+                items: vec![item],
+            }),
+        }
+    }
+    sections
+}
+
+/// Returns the section a sort key is filed under.
+fn section_letter(key: &str) -> char {
+    let first = match key.chars().next() {
+        Some(c) => c,
+        None => return '#',
+    };
+    let letter = base_letters(first)
+        .and_then(|base| base.chars().next())
+        .unwrap_or(first);
+    if letter.is_ascii_alphabetic() {
+        letter.to_ascii_uppercase()
+    } else {
+        '#'
+    }
+}
+
+/// Returns the plain letters an accented or combined lowercase Latin letter
+/// is written with, such as `"e"` for `é` and `"ae"` for `æ`.
+fn base_letters(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĳ' => "ij",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' | 'ŉ' | 'ŋ' => "n",
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' | 'ſ' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'þ' => "th",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+/// A language whose alphabet orders letters differently from English.
+///
+/// Languages not listed here order Latin letters as English does, with
+/// accented letters sorted as their plain letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// Orders letters as English does.
+    #[default]
+    Root,
+    /// Danish and Norwegian, which place `æ`, `ø` and `å` after `z`.
+    Danish,
+    /// Spanish, which places `ñ` after `n`.
+    Spanish,
+    /// Swedish and Finnish, which place `å`, `ä` and `ö` after `z`.
+    Swedish,
+}
+
+impl Language {
+    /// Returns the primary weight of `c` if the language gives it its own
+    /// place in the alphabet.
+    fn weight(self, c: char) -> Option<u32> {
+        let after_z = |n: u32| weight('z') + n * 2;
+        match (self, c) {
+            (Language::Danish, 'æ') | (Language::Danish, 'ä') => Some(after_z(1)),
+            (Language::Danish, 'ø') | (Language::Danish, 'ö') => Some(after_z(2)),
+            (Language::Danish, 'å') => Some(after_z(3)),
+            (Language::Spanish, 'ñ') => Some(weight('n') + 1),
+            (Language::Swedish, 'å') => Some(after_z(1)),
+            (Language::Swedish, 'ä') | (Language::Swedish, 'æ') => Some(after_z(2)),
+            (Language::Swedish, 'ö') | (Language::Swedish, 'ø') => Some(after_z(3)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the primary weight of a plain character, leaving odd weights
+/// free for letters a language places in between.
+fn weight(c: char) -> u32 {
+    (c as u32) << 1
+}
+
+/// Compares names the way a dictionary orders them.
+///
+/// Names are compared first by their letters alone, ignoring case and
+/// accents, with leading articles stripped as with [`strip_article`]. Names
+/// with the same letters are then ordered by their accents, unaccented first,
+/// and then by case, lowercase first. Accented Latin letters are compared as
+/// their plain letter, unless the [`Language`] gives them their own place in
+/// the alphabet; other scripts are compared by code point.
+///
+/// [`strip_article`]: fn.strip_article.html
+/// [`Language`]: enum.Language.html
+///
+/// # Examples
+///
+/// ```
+/// use sunk::sort::Collator;
+///
+/// let collator = Collator::new(&["The"]);
+/// let mut names = vec!["Zoé", "Édith Piaf", "the Cure", "Eels"];
+/// names.sort_by_key(|name| collator.key(name));
+/// assert_eq!(names, ["the Cure", "Édith Piaf", "Eels", "Zoé"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Collator {
+    articles: Vec<String>,
+    language: Language,
+}
+
+/// The key a [`Collator`] orders a name by.
+///
+/// [`Collator`]: struct.Collator.html
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CollationKey {
+    /// The weights of the letters.
+    primary: Vec<u32>,
+    /// The letters lowercased, to order accents.
+    secondary: Vec<char>,
+    /// Whether each letter is uppercase.
+    tertiary: Vec<bool>,
+}
+
+impl Collator {
+    /// Creates a collator stripping leading `articles`, such as those of
+    /// [`Client::ignored_articles`].
+    ///
+    /// [`Client::ignored_articles`]: ../struct.Client.html#method.ignored_articles
+    pub fn new<S: AsRef<str>>(articles: &[S]) -> Collator {
+        Collator {
+            articles: articles.iter().map(|a| a.as_ref().to_owned()).collect(),
+            language: Language::default(),
+        }
+    }
+
+    /// Orders letters following the alphabet of `language`.
+    pub fn with_language(self, language: Language) -> Collator {
+        let mut collator = self;
+        collator.language = language;
+        collator
+    }
+
+    /// Returns the language whose alphabet is followed.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the articles stripped from names.
+    pub fn articles(&self) -> &[String] {
+        &self.articles
+    }
+
+    /// Returns the key `name` is ordered by.
+    pub fn key(&self, name: &str) -> CollationKey {
+        self.key_as_is(strip_article(name, &self.articles))
+    }
+
+    /// Returns the key `name` is ordered by, without stripping an article.
+    fn key_as_is(&self, name: &str) -> CollationKey {
+        let mut key = CollationKey {
+            primary: Vec::with_capacity(name.len()),
+            secondary: Vec::with_capacity(name.len()),
+            tertiary: Vec::with_capacity(name.len()),
+        };
+        for c in name.chars() {
+            key.tertiary.push(c.is_uppercase());
+            for lower in c.to_lowercase() {
+                key.secondary.push(lower);
+                match self.language.weight(lower) {
+                    Some(w) => key.primary.push(w),
+                    None => match base_letters(lower) {
+                        Some(base) => key.primary.extend(base.chars().map(weight)),
+                        None => key.primary.push(weight(lower)),
+                    },
+                }
+            }
+        }
+        key
+    }
+
+    /// Returns the key an artist or album is ordered by.
+    ///
+    /// The item's sort name is used as it is where the server provided one,
+    /// and its name otherwise.
+    pub fn item_key<T: SortName + ?Sized>(&self, item: &T) -> CollationKey {
+        match item.sort_name() {
+            Some(sort_name) if !sort_name.is_empty() => self.key_as_is(sort_name),
+            _ => self.key(item.name()),
+        }
+    }
+
+    /// Compares two names.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    /// Sorts a list of artists or albums by name.
+    pub fn sort_by_name<T: SortName>(&self, items: &mut [T]) {
+        items.sort_by_cached_key(|item| self.item_key(item));
+    }
+}
+
+/// Orders albums by artist, then by year, then by name.
+///
+/// Names are compared case-insensitively. Albums without an artist or a
+/// year are placed last.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate sunk;
+/// # use sunk::{sort, Album, Client, ListType};
+/// # use sunk::search;
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let mut albums = Album::list(&client, ListType::default(), search::ALL, 0)?;
+/// albums.sort_by(sort::album_order);
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn album_order(a: &Album, b: &Album) -> Ordering {
+    cmp_names(a.artist.as_deref(), b.artist.as_deref())
+        .then_with(|| last_if_none(a.year, b.year))
+        .then_with(|| cmp_names(Some(&a.name), Some(&b.name)))
+}
+
+/// Orders songs by artist, then by album, then by disc and track number.
+///
+/// Names are compared case-insensitively, and songs with the same track
+/// number are ordered by title. Songs missing any of these are placed last.
+pub fn song_order(a: &Song, b: &Song) -> Ordering {
+    cmp_names(a.artist.as_deref(), b.artist.as_deref())
+        .then_with(|| cmp_names(a.album.as_deref(), b.album.as_deref()))
+        .then_with(|| last_if_none(a.disc_number, b.disc_number))
+        .then_with(|| last_if_none(a.track, b.track))
+        .then_with(|| cmp_names(Some(&a.title), Some(&b.title)))
+}
+
+/// Compares two optional names case-insensitively, placing `None` last.
+fn cmp_names(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a
+            .chars()
+            .flat_map(char::to_lowercase)
+            .cmp(b.chars().flat_map(char::to_lowercase)),
+        _ => last_if_none(a, b),
+    }
+}
+
+/// Compares two optional values, placing `None` last.
+fn last_if_none<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    (a.is_none(), a).cmp(&(b.is_none(), b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str, Option<&'static str>);
+
+    impl SortName for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn sort_name(&self) -> Option<&str> {
+            self.1
+        }
+    }
+
+    #[test]
+    fn strip_articles() {
+        let articles = ["The", "El", "La", "Los", "Las", "Le", "Les"];
+        assert_eq!(
+            strip_article("The Dada Weatherman", &articles),
+            "Dada Weatherman"
+        );
+        assert_eq!(
+            strip_article("the dada weatherman", &articles),
+            "dada weatherman"
+        );
+        assert_eq!(
+            strip_article("Lesbians on Ecstasy", &articles),
+            "Lesbians on Ecstasy"
+        );
+        assert_eq!(strip_article("The", &articles), "The");
+        assert_eq!(strip_article("Élan", &articles), "Élan");
+        assert_eq!(strip_article("THE Band", &articles), "Band");
+        assert_eq!(strip_article("Él Guapo", &["Él"]), "Guapo");
+        assert_eq!(strip_article("L’Arc~en~Ciel", &["L'"]), "Arc~en~Ciel");
+        assert_eq!(strip_article("L'", &["L'"]), "L'");
+    }
+
+    #[test]
+    fn collation() {
+        let collator = Collator::new(&["The"]);
+        let mut names = vec![
+            "zz",
+            "Ångström",
+            "Ab",
+            "ab",
+            "Äb",
+            "Straße",
+            "Strasse",
+            "the Ac",
+        ];
+        names.sort_by_key(|n| collator.key(n));
+        assert_eq!(
+            names,
+            [
+                "ab",
+                "Ab",
+                "Äb",
+                "the Ac",
+                "Ångström",
+                "Strasse",
+                "Straße",
+                "zz"
+            ]
+        );
+
+        let swedish = collator.clone().with_language(Language::Swedish);
+        let mut names = vec!["Öst", "Zeta", "Ärla", "Åsa", "Asa"];
+        names.sort_by_key(|n| swedish.key(n));
+        assert_eq!(names, ["Asa", "Zeta", "Åsa", "Ärla", "Öst"]);
+
+        let spanish = collator.with_language(Language::Spanish);
+        assert_eq!(spanish.compare("Niño", "Ninos"), Ordering::Greater);
+        assert_eq!(spanish.compare("Ñu", "Oso"), Ordering::Less);
+        assert_eq!(spanish.compare("Ñu", "Nz"), Ordering::Greater);
+    }
+
+    #[test]
+    fn collator_prefers_sort_name() {
+        let mut items = vec![
+            Named("Émilie Simon", None),
+            Named("The Beatles", None),
+            Named("Björk", Some("Bjork")),
+            Named("Eels", None),
+            Named("The The", Some("The The")),
+        ];
+        Collator::new(&["The"]).sort_by_name(&mut items);
+        let names = items.iter().map(|i| i.0).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["The Beatles", "Björk", "Eels", "Émilie Simon", "The The"]
+        );
+    }
+
+    #[test]
+    fn sort_prefers_sort_name() {
+        let articles = ["The"];
+        let mut items = vec![
+            Named("The Beatles", None),
+            Named("ABBA", None),
+            Named("Misteur Valaire", None),
+            Named("Björk", Some("Bjork")),
+            Named("The The", Some("The The")),
+        ];
+        sort_by_name(&mut items, &articles);
+
+        let names = items.iter().map(|i| i.0).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["ABBA", "The Beatles", "Björk", "Misteur Valaire", "The The"]
+        );
+    }
+
+    #[test]
+    fn alphabetical_sections() {
+        let articles = ["The"];
+        let items = [
+            Named("Misteur Valaire", None),
+            Named("The Beatles", None),
+            Named("2Pac", None),
+            Named("Édith Piaf", None),
+            Named("ABBA", None),
+            Named("Björk", Some("Bjork")),
+        ];
+
+        let sections = alphabetical(items.iter(), &articles)
+            .into_iter()
+            .map(|s| (s.letter, s.items.iter().map(|i| i.0).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            [
+                ('A', vec!["ABBA"]),
+                ('B', vec!["The Beatles", "Björk"]),
+                ('E', vec!["Édith Piaf"]),
+                ('M', vec!["Misteur Valaire"]),
+                ('#', vec!["2Pac"]),
+            ]
+        );
+    }
+}
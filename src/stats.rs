@@ -0,0 +1,366 @@
+//! Statistics about the library, such as for an overview page.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use crate::library;
+#[cfg(feature = "mirror")]
+use crate::mirror::Mirror;
+use crate::{Client, Result, Song};
+
+/// How much of the library is in a genre.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenreStats {
+    /// The name of the genre.
+    pub name: String,
+    /// The number of songs in the genre.
+    pub song_count: u64,
+    /// The number of albums in the genre.
+    pub album_count: u64,
+    /// The total length of the songs in the genre. Only known when the
+    /// statistics are taken with a mirror of the library.
+    pub duration: Option<Duration>,
+}
+
+/// Returns the number of songs and albums in each genre, most songs first.
+///
+/// The counts are the server's, from `getGenres`. The server doesn't report
+/// how long the songs of a genre are, so durations are added up from
+/// `mirror` if one is given. If the client has a [genre map], variants of the
+/// same genre are counted together.
+///
+/// [genre map]: ../struct.Client.html#method.with_genre_map
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use sunk::{stats, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// for genre in stats::genres(&client, None)?.iter().take(10) {
+///     println!("{}: {} songs", genre.name, genre.song_count);
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[cfg(feature = "mirror")]
+pub fn genres<'a, M>(client: &Client, mirror: M) -> Result<Vec<GenreStats>>
+where
+    M: Into<Option<&'a Mirror>>,
+{
+    let mut stats = client
+        .genres()?
+        .into_iter()
+        .map(|genre| GenreStats {
+            name: genre.name,
+            song_count: genre.song_count,
+            album_count: genre.album_count,
+            duration: None,
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(mirror) = mirror.into() {
+        let same = |a: &str, b: &str| match client.genre_map() {
+            Some(map) => map.same(a, b),
+            None => a.eq_ignore_ascii_case(b),
+        };
+        add_durations(&mut stats, mirror.songs(), same);
+    }
+
+    stats.sort_by(|a, b| b.song_count.cmp(&a.song_count).then(a.name.cmp(&b.name)));
+    Ok(stats)
+}
+
+/// Adds up the durations of the songs in each genre.
+#[cfg(feature = "mirror")]
+fn add_durations<F>(stats: &mut [GenreStats], songs: &[Song], same: F)
+where
+    F: Fn(&str, &str) -> bool,
+{
+    for genre in stats.iter_mut() {
+        let secs = songs
+            .iter()
+            .filter(|s| s.all_genres().iter().any(|g| same(g, &genre.name)))
+            .filter_map(|s| s.duration)
+            .sum();
+        genre.duration = Some(Duration::from_secs(secs));
+    }
+}
+
+/// Restricts which songs [`top_with`] counts.
+///
+/// Every restriction is optional; the default counts every song.
+///
+/// [`top_with`]: fn.top_with.html
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    /// Only count songs of this genre, ignoring case.
+    pub genre: Option<String>,
+    /// Only count songs released in these years.
+    pub years: Option<RangeInclusive<u64>>,
+}
+
+impl StatsFilter {
+    /// Returns whether a song is counted.
+    fn matches(&self, song: &Song) -> bool {
+        let genre = match self.genre {
+            Some(ref genre) => song
+                .all_genres()
+                .iter()
+                .any(|g| g.eq_ignore_ascii_case(genre)),
+            None => true,
+        };
+        let year = match self.years {
+            Some(ref years) => song.year.is_some_and(|y| years.contains(&y)),
+            None => true,
+        };
+        genre && year
+    }
+}
+
+/// How often something was played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayCount<T> {
+    /// What was played.
+    pub item: T,
+    /// The number of times it was played.
+    pub plays: u64,
+    /// An estimate of the time spent listening to it: the number of times
+    /// each song was played by its length.
+    pub listening: Duration,
+}
+
+/// The most played artists, albums and songs, found by [`top`].
+///
+/// [`top`]: fn.top.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopPlayed {
+    /// The most played artists, by name.
+    pub artists: Vec<PlayCount<String>>,
+    /// The most played albums, by name.
+    pub albums: Vec<PlayCount<String>>,
+    /// The most played songs.
+    pub songs: Vec<PlayCount<Song>>,
+    /// An estimate of the total time spent listening to the library.
+    pub listening: Duration,
+}
+
+/// Returns the `n` most played artists, albums and songs, and how long was
+/// spent listening to them, from the play counts the server keeps.
+///
+/// Every song in the library is listed with [`library::all_songs`] to add
+/// up the play counts. Use [`Mirror::top`] instead if the library is
+/// mirrored, and [`top_with`] to only count some songs.
+///
+/// [`library::all_songs`]: ../library/fn.all_songs.html
+/// [`Mirror::top`]: ../mirror/struct.Mirror.html#method.top
+/// [`top_with`]: fn.top_with.html
+pub fn top(client: &Client, n: usize) -> Result<TopPlayed> {
+    top_with(client, n, &StatsFilter::default())
+}
+
+/// Returns the `n` most played artists, albums and songs among those
+/// `filter` allows. See [`top`].
+///
+/// [`top`]: fn.top.html
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use sunk::stats::{self, StatsFilter};
+/// use sunk::Client;
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+///
+/// let nineties = StatsFilter {
+///     years: Some(1990..=1999),
+///     ..Default::default()
+/// };
+/// let top = stats::top_with(&client, 10, &nineties)?;
+/// println!("{} hours of 90s music", top.listening.as_secs() / 3600);
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+pub fn top_with(client: &Client, n: usize, filter: &StatsFilter) -> Result<TopPlayed> {
+    let mut tally = Tally::default();
+    for song in library::all_songs(client) {
+        tally.add(song?, filter);
+    }
+    Ok(tally.top(n))
+}
+
+#[cfg(feature = "mirror")]
+impl Mirror {
+    /// Returns the `n` most played artists, albums and songs in the mirror
+    /// among those `filter` allows. See [`stats::top`].
+    ///
+    /// Play counts are as they were when the mirror was last synced.
+    ///
+    /// [`stats::top`]: ../stats/fn.top.html
+    pub fn top(&self, n: usize, filter: &StatsFilter) -> TopPlayed {
+        let mut tally = Tally::default();
+        for song in self.songs() {
+            tally.add(song.clone(), filter);
+        }
+        tally.top(n)
+    }
+}
+
+/// Running totals of play counts.
+#[derive(Default)]
+struct Tally {
+    artists: HashMap<String, (u64, u64)>,
+    albums: HashMap<String, (String, u64, u64)>,
+    songs: Vec<PlayCount<Song>>,
+    listening: u64,
+}
+
+impl Tally {
+    /// Counts the plays of a song.
+    fn add(&mut self, song: Song, filter: &StatsFilter) {
+        let plays = song.play_count.unwrap_or(0);
+        if plays == 0 || !filter.matches(&song) {
+            return;
+        }
+        let secs = plays * song.duration.unwrap_or(0);
+        self.listening += secs;
+
+        if let Some(artist) = song.artist.as_deref() {
+            let entry = self.artists.entry(artist.to_string()).or_default();
+            entry.0 += plays;
+            entry.1 += secs;
+        }
+        if let Some(album) = song.album.as_deref() {
+            // Albums of the same name by different artists are told apart by
+            // ID where the server sends one.
+            let key = match song.album_id() {
+                Some(id) => id.to_string(),
+                None => album.to_string(),
+            };
+            let entry = self
+                .albums
+                .entry(key)
+                .or_insert_with(|| (album.to_string(), 0, 0));
+            entry.1 += plays;
+            entry.2 += secs;
+        }
+        self.songs.push(PlayCount {
+            item: song,
+            plays,
+            listening: Duration::from_secs(secs),
+        });
+    }
+
+    /// Returns the `n` most played of each.
+    fn top(self, n: usize) -> TopPlayed {
+        let count = |(item, plays, secs)| PlayCount {
+            item,
+            plays,
+            listening: Duration::from_secs(secs),
+        };
+        let artists = self
+            .artists
+            .into_iter()
+            .map(|(name, (plays, secs))| count((name, plays, secs)))
+            .collect();
+        let albums = self.albums.into_values().map(count).collect();
+
+        TopPlayed {
+            artists: most_played(artists, n),
+            albums: most_played(albums, n),
+            songs: most_played(self.songs, n),
+            listening: Duration::from_secs(self.listening),
+        }
+    }
+}
+
+/// Sorts by plays, most first, and keeps the first `n`.
+fn most_played<T>(mut counts: Vec<PlayCount<T>>, n: usize) -> Vec<PlayCount<T>> {
+    counts.sort_by(|a, b| b.plays.cmp(&a.plays).then(b.listening.cmp(&a.listening)));
+    counts.truncate(n);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn song(genre: &str, duration: u64) -> Song {
+        let fields = serde_json::json!({ "genre": genre, "duration": duration });
+        test_util::song_with("1", fields)
+    }
+
+    #[test]
+    fn top_played() {
+        let played = |genre: &str, album: &str, year: u64, plays: u64| {
+            let mut raw = serde_json::to_value(song(genre, 200)).unwrap();
+            raw["artist"] = "Misteur Valaire".into();
+            raw["album"] = album.into();
+            raw["year"] = year.into();
+            raw["playCount"] = plays.into();
+            serde_json::from_value::<Song>(raw).unwrap()
+        };
+
+        let mut tally = Tally::default();
+        let filter = StatsFilter::default();
+        tally.add(played("Electronic", "Bellevue", 2017, 3), &filter);
+        tally.add(played("Electronic", "Golden Bombay", 2010, 5), &filter);
+        tally.add(played("Electronic", "Bellevue", 2017, 4), &filter);
+        tally.add(played("Electronic", "Bellevue", 2017, 0), &filter);
+        let top = tally.top(1);
+
+        assert_eq!(top.artists[0].item, "Misteur Valaire");
+        assert_eq!(top.artists[0].plays, 12);
+        assert_eq!(top.albums.len(), 1);
+        assert_eq!(top.albums[0].item, "Bellevue");
+        assert_eq!(top.albums[0].plays, 7);
+        assert_eq!(top.songs[0].plays, 5);
+        assert_eq!(top.listening, Duration::from_secs(12 * 200));
+
+        let mut tally = Tally::default();
+        let filter = StatsFilter {
+            genre: Some("electronic".into()),
+            years: Some(2015..=2019),
+        };
+        tally.add(played("Electronic", "Bellevue", 2017, 3), &filter);
+        tally.add(played("Electronic", "Golden Bombay", 2010, 5), &filter);
+        tally.add(played("Rock", "Bellevue", 2017, 4), &filter);
+        assert_eq!(tally.top(10).listening, Duration::from_secs(3 * 200));
+    }
+
+    #[cfg(feature = "mirror")]
+    #[test]
+    fn genre_durations() {
+        let genre = |name: &str| GenreStats {
+            name: name.into(),
+            song_count: 2,
+            album_count: 1,
+            duration: None,
+        };
+        let mut stats = [genre("Electronic"), genre("Jazz")];
+        let songs = [
+            song("Electronic", 100),
+            song("electronic", 50),
+            song("Rock", 10),
+        ];
+
+        add_durations(&mut stats, &songs, |a, b| a.eq_ignore_ascii_case(b));
+        assert_eq!(stats[0].duration, Some(Duration::from_secs(150)));
+        assert_eq!(stats[1].duration, Some(Duration::from_secs(0)));
+    }
+}
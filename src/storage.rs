@@ -0,0 +1,240 @@
+//! Where downloads are written.
+//!
+//! Downloading songs and albums writes files. By default they go to a
+//! directory on disk, but the methods ending in `_to_storage`, such as
+//! [`Album::download_to_storage`], write through a [`Storage`] backend
+//! instead, so a backup tool can stream a library straight into object
+//! storage, a WebDAV share or an archive without a copy on disk.
+//!
+//! Files are named by paths relative to the storage, laid out as the songs
+//! are on the server, such as `Artist/Album/01 Song.mp3`. [`LocalStorage`]
+//! writes them under a directory.
+//!
+//! [`Album::download_to_storage`]: ../struct.Album.html#method.download_to_storage
+//! [`Storage`]: trait.Storage.html
+//! [`LocalStorage`]: struct.LocalStorage.html
+//!
+//! # Examples
+//!
+//! A backend that keeps files in memory:
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::collections::HashMap;
+//! use std::io::{self, Write};
+//! use std::path::{Path, PathBuf};
+//! use std::sync::Mutex;
+//! use sunk::storage::{Storage, StorageWriter};
+//! use sunk::Client;
+//!
+//! #[derive(Default)]
+//! struct Memory(Mutex<HashMap<PathBuf, Vec<u8>>>);
+//!
+//! struct MemoryWriter<'a> {
+//!     store: &'a Memory,
+//!     path: PathBuf,
+//!     buf: Vec<u8>,
+//! }
+//!
+//! impl<'a> Write for MemoryWriter<'a> {
+//!     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+//!         self.buf.write(buf)
+//!     }
+//!
+//!     fn flush(&mut self) -> io::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl<'a> StorageWriter for MemoryWriter<'a> {
+//!     fn finish(self: Box<Self>) -> sunk::Result<()> {
+//!         let this = *self;
+//!         this.store.0.lock().unwrap().insert(this.path, this.buf);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl Storage for Memory {
+//!     fn exists(&self, path: &Path) -> sunk::Result<bool> {
+//!         Ok(self.0.lock().unwrap().contains_key(path))
+//!     }
+//!
+//!     fn create(&self, path: &Path) -> sunk::Result<Box<dyn StorageWriter + '_>> {
+//!         Ok(Box::new(MemoryWriter {
+//!             store: self,
+//!             path: path.to_owned(),
+//!             buf: Vec::new(),
+//!         }))
+//!     }
+//! }
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let album = client.albums().get("1")?;
+//!
+//! let memory = Memory::default();
+//! album.download_to_storage(&client, &memory, ())?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+
+/// A place downloads can be written to.
+///
+/// Paths passed to a storage are relative, and never contain `..`.
+pub trait Storage {
+    /// Returns whether a file is stored at `path`.
+    fn exists(&self, path: &Path) -> Result<bool>;
+
+    /// Starts writing the file at `path`, replacing any stored there once
+    /// the writer is finished.
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageWriter + '_>>;
+}
+
+/// A file being written to a [`Storage`].
+///
+/// The file is only stored once [`finish`] is called. A writer dropped
+/// without finishing, such as when a download fails, should discard what was
+/// written where the backend allows, so partial files aren't left behind.
+///
+/// [`Storage`]: trait.Storage.html
+/// [`finish`]: #tymethod.finish
+pub trait StorageWriter: Write {
+    /// Stores the file written.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+impl<S: Storage + ?Sized> Storage for &S {
+    fn exists(&self, path: &Path) -> Result<bool> {
+        (**self).exists(path)
+    }
+
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageWriter + '_>> {
+        (**self).create(path)
+    }
+}
+
+/// Stores files under a directory on disk.
+///
+/// Files are written next to where they belong, with a `.part` extension
+/// added, and moved into place once finished, so a download that fails or is
+/// interrupted never leaves a partial file under its real name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Stores files under `root`, creating folders as needed.
+    pub fn new<P: Into<PathBuf>>(root: P) -> LocalStorage {
+        LocalStorage { root: root.into() }
+    }
+
+    /// Returns the directory files are stored under.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Storage for LocalStorage {
+    fn exists(&self, path: &Path) -> Result<bool> {
+        Ok(self.root.join(path).exists())
+    }
+
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageWriter + '_>> {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut part = path.clone().into_os_string();
+        part.push(".part");
+        let part = PathBuf::from(part);
+        Ok(Box::new(LocalWriter {
+            file: Some(BufWriter::new(File::create(&part)?)),
+            part,
+            path,
+        }))
+    }
+}
+
+/// A file being written by a [`LocalStorage`].
+///
+/// [`LocalStorage`]: struct.LocalStorage.html
+struct LocalWriter {
+    /// The file, until it is finished.
+    file: Option<BufWriter<File>>,
+    part: PathBuf,
+    path: PathBuf,
+}
+
+impl LocalWriter {
+    fn file(&mut self) -> &mut BufWriter<File> {
+        self.file.as_mut().expect("writer is finished")
+    }
+}
+
+impl Write for LocalWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file().flush()
+    }
+}
+
+impl StorageWriter for LocalWriter {
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if let Some(file) = self.file.take() {
+            file.into_inner().map_err(|e| e.into_error())?;
+            fs::rename(&self.part, &self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LocalWriter {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            drop(file);
+            let _ = fs::remove_file(&self.part);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_storage() {
+        let dir = std::env::temp_dir().join(format!("sunk-storage-{}", std::process::id()));
+        let storage = LocalStorage::new(&dir);
+        let path = Path::new("Artist/Album/01.mp3");
+        assert!(!storage.exists(path).unwrap());
+
+        let mut writer = storage.create(path).unwrap();
+        writer.write_all(b"song").unwrap();
+        assert!(!storage.exists(path).unwrap());
+        writer.finish().unwrap();
+        assert!(storage.exists(path).unwrap());
+        assert_eq!(fs::read(dir.join(path)).unwrap(), b"song");
+
+        // Abandoned writes leave nothing behind, and keep the old file.
+        let mut writer = storage.create(path).unwrap();
+        writer.write_all(b"partial").unwrap();
+        drop(writer);
+        assert_eq!(fs::read(dir.join(path)).unwrap(), b"song");
+        assert!(!dir.join("Artist/Album/01.mp3.part").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,9 +1,91 @@
-use client;
-use error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
 
+use crate::client;
+use crate::error;
+use crate::query::Query;
+use crate::vcr::{Cassette, Interaction};
+use crate::Song;
+
+/// The fixture the demo server's responses are replayed from.
+const DEMO_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/demo.json");
+
+/// Returns a client for the Subsonic demo server, whose responses are
+/// replayed from `fixtures/demo.json`.
+///
+/// The fixture is synthetic: it was written by hand in the shape of the demo
+/// server's responses, not recorded from it, so its library, license and
+/// cover art are made up.
+///
+/// Set `SUNK_LIVE` to send the requests to the server instead.
 pub fn demo_site() -> error::Result<client::Client> {
     let site = "http://demo.subsonic.org";
     let user = "guest3";
     let password = "guest";
-    client::Client::new(site, user, password)
+    let client = client::Client::new(site, user, password)?;
+    if std::env::var_os("SUNK_LIVE").is_some() {
+        return Ok(client);
+    }
+    Ok(client.with_cassette(Cassette::replay(DEMO_FIXTURE)?))
+}
+
+/// Returns a client answering each `(endpoint, args, body)` from memory, and
+/// any other request with an error.
+///
+/// Each body is wrapped in a `subsonic-response` with an `ok` status, which
+/// the body may override, such as to answer with an error.
+pub fn replay_client(answers: &[(&str, Query, Value)]) -> client::Client {
+    let interactions = answers
+        .iter()
+        .map(|(endpoint, args, body)| {
+            let mut response = json!({ "status": "ok", "version": "1.16.1" });
+            if let (Some(response), Some(body)) = (response.as_object_mut(), body.as_object()) {
+                response.extend(body.clone());
+            }
+            let body = json!({ "subsonic-response": response });
+            Interaction::json(endpoint, args, body)
+        })
+        .collect();
+    client::Client::new("http://localhost", "user", "pass")
+        .unwrap()
+        .with_cassette(Cassette::from_interactions(interactions))
+}
+
+/// Asserts that a value serializes into a form it can be parsed back from,
+/// without losing any information on the way.
+pub fn assert_round_trip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let serialized = serde_json::to_value(value).unwrap();
+    let parsed = serde_json::from_value::<T>(serialized.clone()).unwrap();
+    assert_eq!(serialized, serde_json::to_value(&parsed).unwrap());
+}
+
+/// Returns a song with the given id and only the fields a song can't be
+/// parsed without.
+pub fn song(id: &str) -> Song {
+    song_with(id, json!({}))
+}
+
+/// Returns a song like [`song`] does, with `fields` set over its own.
+///
+/// [`song`]: fn.song.html
+pub fn song_with(id: &str, fields: Value) -> Song {
+    let mut raw = json!({
+        "id": id,
+        "title": "Bellevue Avenue",
+        "size": 1,
+        "contentType": "audio/mpeg",
+        "suffix": "mp3",
+        "path": format!("{}.mp3", id),
+        "created": "2017-03-12T11:07:25.000Z",
+        "type": "music",
+        "isDir": false,
+    });
+    if let (Some(raw), Value::Object(fields)) = (raw.as_object_mut(), fields) {
+        raw.extend(fields);
+    }
+    serde_json::from_value(raw).unwrap()
 }
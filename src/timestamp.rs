@@ -0,0 +1,192 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+/// Parses an ISO8601 timestamp as sent by a Subsonic server. See
+/// [`parse_system`] for the formats accepted.
+///
+/// [`parse_system`]: fn.parse_system.html
+#[cfg(feature = "chrono")]
+pub(crate) fn parse(s: &str) -> Option<DateTime<Utc>> {
+    parse_system(s).map(DateTime::from)
+}
+
+/// Parses an ISO8601 timestamp as sent by a Subsonic server into a
+/// `SystemTime`.
+///
+/// Most servers send a full RFC3339 timestamp, but some omit the offset
+/// entirely; these are assumed to be in UTC.
+pub(crate) fn parse_system(s: &str) -> Option<SystemTime> {
+    let b = s.as_bytes();
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    if b.len() < 19
+        || !separators.iter().all(|&(i, c)| b[i] == c)
+        || !matches!(b[10], b'T' | b't' | b' ')
+    {
+        return None;
+    }
+    let (year, month, day) = (digits(&s[0..4])?, digits(&s[5..7])?, digits(&s[8..10])?);
+    let (hour, min, sec) = (
+        digits(&s[11..13])?,
+        digits(&s[14..16])?,
+        digits(&s[17..19])?,
+    );
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || min > 59
+        || sec > 60
+    {
+        return None;
+    }
+
+    let mut rest = s.get(19..)?;
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        let kept = &fraction[..len.min(9)];
+        nanos = kept.parse::<u32>().ok()? * 10u32.pow(9 - kept.len() as u32);
+        rest = &fraction[len..];
+    }
+    let offset = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (hours, mins) = match rest.len() {
+                6 if rest.as_bytes()[3] == b':' => (rest.get(1..3)?, rest.get(4..6)?),
+                5 => (rest.get(1..3)?, rest.get(3..5)?),
+                _ => return None,
+            };
+            sign * (digits(hours)? * 3600 + digits(mins)? * 60)
+        }
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + min * 60 + sec - offset;
+    let time = if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64))?
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+    };
+    time.checked_add(Duration::from_nanos(u64::from(nanos)))
+}
+
+/// Formats a time as an ISO8601 timestamp in UTC, to the second, as the
+/// server sends them.
+pub(crate) fn format(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // Converts days since the epoch into a civil date; see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Parses a field made only of ASCII digits.
+fn digits(s: &str) -> Option<i64> {
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days between the Unix epoch and a civil date; the
+/// inverse of the conversion in `format`. See
+/// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn format_timestamp() {
+        assert_eq!(format(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(format(at(1_496_346_505).unwrap()), "2017-06-01T19:48:25Z");
+        assert_eq!(format(at(951_825_600).unwrap()), "2000-02-29T12:00:00Z");
+    }
+
+    #[test]
+    fn parse_offsets() {
+        let fraction = Duration::from_millis(635);
+        assert_eq!(
+            parse_system("2017-06-01T19:48:25.635Z"),
+            at(1_496_346_505).map(|t| t + fraction)
+        );
+        assert_eq!(parse_system("2017-06-01T19:48:25"), at(1_496_346_505));
+        assert_eq!(parse_system("2017-06-01T21:48:25+02:00"), at(1_496_346_505));
+        assert_eq!(parse_system("2017-06-01T14:48:25-0500"), at(1_496_346_505));
+        assert_eq!(parse_system("2000-02-29T12:00:00+00:00"), at(951_825_600));
+        assert_eq!(parse_system("1970-01-01T00:00:00Z"), at(0));
+        assert!(parse_system("1969-12-31T23:59:59Z").unwrap() < UNIX_EPOCH);
+    }
+
+    #[test]
+    fn parse_garbage() {
+        assert!(parse_system("last tuesday").is_none());
+        assert!(parse_system("2017-13-01T19:48:25Z").is_none());
+        assert!(parse_system("2017-02-29T19:48:25Z").is_none());
+        assert!(parse_system("2017-06-01T19:48:25+2").is_none());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn parse_full_timestamp() {
+        use chrono::{Datelike, Timelike};
+
+        let t = parse("2017-03-12T11:07:27.000Z").unwrap();
+        assert_eq!((t.year(), t.month(), t.day()), (2017, 3, 12));
+        assert_eq!((t.hour(), t.minute(), t.second()), (11, 7, 27));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn parse_timestamp_without_offset() {
+        let t = parse("2017-06-01T19:48:25").unwrap();
+        assert_eq!(t, parse("2017-06-01T19:48:25Z").unwrap());
+    }
+}
@@ -1,10 +1,9 @@
-use serde_json;
-
+use crate::endpoint::{ChangePassword, CreateUser, DeleteUser, GetUser, GetUsers, UpdateUser};
 use crate::query::Query;
-use crate::{Client, Result};
+use crate::{Bitrate, Client, Result};
 
 /// A struct representing a Subsonic user.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct User {
     /// A user's name.
     pub username: String,
@@ -15,7 +14,7 @@ pub struct User {
     /// disables this.
     #[serde(rename = "maxBitRate")]
     #[serde(default)]
-    pub max_bit_rate: u64,
+    pub max_bit_rate: Bitrate,
     /// Whether the user is allowed to scrobble their songs to last.fm.
     #[serde(rename = "scrobblingEnabled")]
     pub scrobbling_enabled: bool,
@@ -68,15 +67,16 @@ pub struct User {
     /// The list of media folders the user has access to.
     #[serde(rename = "folder")]
     pub folders: Vec<u64>,
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     _private: bool,
 }
 
 impl User {
     /// Fetches a single user's information from the server.
     pub fn get(client: &Client, username: &str) -> Result<User> {
-        let res = client.get("getUser", Query::with("username", username))?;
-        Ok(serde_json::from_value::<User>(res)?)
+        client.call(&GetUser {
+            username: username.to_string(),
+        })
     }
 
     /// Lists all users on the server.
@@ -88,8 +88,7 @@ impl User {
     ///
     /// [`NotAuthorized`]: ./enum.ApiError.html#variant.NotAuthorized
     pub fn list(client: &Client) -> Result<Vec<User>> {
-        let user = client.get("getUsers", Query::none())?;
-        Ok(get_list_as!(user, User))
+        client.call(&GetUsers)
     }
 
     /// Changes the user's password.
@@ -99,11 +98,10 @@ impl User {
     /// A user may only change their own password, and only if they have the
     /// `settings_role` permission, unless they are an administrator.
     pub fn change_password(&self, client: &Client, password: &str) -> Result<()> {
-        let args = Query::with("username", self.username.as_str())
-            .arg("password", password)
-            .build();
-        client.get("changePassword", args)?;
-        Ok(())
+        client.call(&ChangePassword {
+            username: self.username.clone(),
+            password: password.to_string(),
+        })
     }
 
     /// Returns the user's avatar image as a collection of bytes.
@@ -125,11 +123,9 @@ impl User {
 
     /// Removes the user from the Subsonic server.
     pub fn delete(&self, client: &Client) -> Result<()> {
-        client.get(
-            "deleteUser",
-            Query::with("username", self.username.as_str()),
-        )?;
-        Ok(())
+        client.call(&DeleteUser {
+            username: self.username.clone(),
+        })
     }
 
     /// Pushes any changes made to the user to the server.
@@ -157,8 +153,13 @@ impl User {
     /// # }
     /// ```
     pub fn update(&self, client: &Client) -> Result<()> {
-        let args = Query::with("username", self.username.as_ref())
-            .arg("email", self.email.as_ref())
+        client.call(&UpdateUser { user: self })
+    }
+
+    /// The arguments `updateUser` takes to make the server's copy match.
+    pub(crate) fn args(&self) -> Query {
+        Query::with("username", self.username.as_str())
+            .arg("email", self.email.as_str())
             .arg("ldapAuthenticated", self.ldap_authenticated)
             .arg("adminRole", self.admin_role)
             .arg("settingsRole", self.settings_role)
@@ -173,9 +174,7 @@ impl User {
             .arg("videoConversionRole", self.video_conversion_role)
             .arg_list("musicFolderId", &self.folders.clone())
             .arg("maxBitRate", self.max_bit_rate)
-            .build();
-        client.get("updateUser", args)?;
-        Ok(())
+            .build()
     }
 }
 
@@ -198,7 +197,7 @@ pub struct UserBuilder {
     share_role: bool,
     video_conversion_role: bool,
     folders: Vec<u64>,
-    max_bit_rate: u64,
+    max_bit_rate: Bitrate,
 }
 
 macro_rules! build {
@@ -255,13 +254,18 @@ impl UserBuilder {
     build!(folders: &[u64]);
     /// The maximum bit rate (in Kbps) the user is allowed to stream at. Higher
     /// bit rate streams will be downsampled to their limit.
-    build!(max_bit_rate: u64);
+    build!(max_bit_rate: Bitrate);
 
     /// Pushes a defined new user to the Subsonic server.
     pub fn create(&self, client: &Client) -> Result<()> {
-        let args = Query::with("username", self.username.as_ref())
-            .arg("password", self.password.as_ref())
-            .arg("email", self.email.as_ref())
+        client.call(&CreateUser { user: self })
+    }
+
+    /// The arguments `createUser` takes to create the user.
+    pub(crate) fn args(&self) -> Query {
+        Query::with("username", self.username.as_str())
+            .arg("password", self.password.as_str())
+            .arg("email", self.email.as_str())
             .arg("ldapAuthenticated", self.ldap_authenticated)
             .arg("adminRole", self.admin_role)
             .arg("settingsRole", self.settings_role)
@@ -276,16 +280,14 @@ impl UserBuilder {
             .arg("videoConversionRole", self.video_conversion_role)
             .arg_list("musicFolderId", &self.folders)
             .arg("maxBitRate", self.max_bit_rate)
-            .build();
-        client.get("createUser", args)?;
-        Ok(())
+            .build()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test_util;
+    use crate::test_util;
 
     #[test]
     fn remote_parse_user() {
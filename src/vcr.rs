@@ -0,0 +1,396 @@
+//! Recording requests to a server and replaying them, for tests.
+//!
+//! Tests that talk to a real server are slow, and break whenever the server
+//! is down or its library changes. A [`Cassette`] given to a client with
+//! [`Client::with_cassette`] either records each request the client sends,
+//! along with the server's response, to a fixture file, or answers the
+//! client's requests from that file without touching the network. Record a
+//! test once against a real server, check the fixture in, and replay it from
+//! then on.
+//!
+//! Requests are matched by endpoint and arguments. The credentials and other
+//! authentication parameters are never recorded, so fixtures recorded with
+//! one account replay with any, and passwords given as arguments, such as to
+//! `changePassword`, are recorded as `***`. The responses are recorded as
+//! the server sent them, and may contain the user's name.
+//!
+//! Only requests to the Subsonic API are recorded; images fetched from other
+//! sites, such as last.fm, and HLS segments are sent as usual. Streams and
+//! downloads are read in full while recording.
+//!
+//! [`Cassette`]: struct.Cassette.html
+//! [`Client::with_cassette`]: ../struct.Client.html#method.with_cassette
+//!
+//! # Examples
+//!
+//! A test that records its fixture the first time it runs, and replays it
+//! after that:
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::vcr::Cassette;
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let cassette = Cassette::open("fixtures/ping.json")?;
+//! let client = Client::new(site, user, password)?.with_cassette(cassette);
+//! client.ping()?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::blocking::Response;
+
+use crate::query::Query;
+use crate::{Error, Result};
+
+/// Headers that aren't recorded, as they hold secrets or change from one
+/// request to the next.
+const SKIPPED_HEADERS: &[&str] = &["date", "set-cookie"];
+
+/// A fixture file of requests and the server's responses to them.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    recording: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Whether each interaction has been replayed.
+    replayed: Vec<bool>,
+}
+
+/// The contents of a fixture file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Fixture {
+    interactions: Vec<Interaction>,
+}
+
+/// A request and the response to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+impl Interaction {
+    /// Returns a request to `endpoint` with `args`, answered with `body` as
+    /// JSON.
+    #[cfg(test)]
+    pub(crate) fn json(endpoint: &str, args: &Query, body: serde_json::Value) -> Interaction {
+        let mut headers = BTreeMap::new();
+        headers.insert("content-type".to_owned(), "application/json".to_owned());
+        Interaction {
+            request: RecordedRequest {
+                endpoint: endpoint.to_owned(),
+                args: args.sanitized_pairs(),
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers,
+                body: Body::Json(body),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedRequest {
+    endpoint: String,
+    #[serde(default)]
+    args: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Body,
+}
+
+/// The body of a response, recorded as JSON where it is JSON so that
+/// fixtures can be read and edited.
+///
+/// The body is tagged with how it was recorded, such as `{"json": {...}}`,
+/// so that a JSON string or array is told apart from text or bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Body {
+    Json(serde_json::Value),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Body {
+    fn new(bytes: Vec<u8>, content_type: Option<&str>) -> Body {
+        let is_json = content_type.is_some_and(|t| t.contains("json"));
+        if is_json {
+            if let Ok(value) = serde_json::from_slice(&bytes) {
+                return Body::Json(value);
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => Body::Text(text),
+            Err(e) => Body::Bytes(e.into_bytes()),
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Body::Text(text) => text.into_bytes(),
+            Body::Bytes(bytes) => bytes,
+            Body::Json(value) => value.to_string().into_bytes(),
+        }
+    }
+}
+
+impl Cassette {
+    /// Records requests to the file at `path`, replacing any fixture there.
+    ///
+    /// The file is written after each request, so a test that fails partway
+    /// still leaves the requests it made.
+    pub fn record<P: Into<PathBuf>>(path: P) -> Cassette {
+        Cassette {
+            path: path.into(),
+            recording: true,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Replays the requests recorded in the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be read or isn't a fixture.
+    pub fn replay<P: Into<PathBuf>>(path: P) -> Result<Cassette> {
+        let path = path.into();
+        let fixture = serde_json::from_slice::<Fixture>(&fs::read(&path)?)?;
+        let mut cassette = Cassette::from_interactions(fixture.interactions);
+        cassette.path = path;
+        Ok(cassette)
+    }
+
+    /// Replays the given requests, without a fixture file. The path of the
+    /// cassette is empty.
+    pub(crate) fn from_interactions(interactions: Vec<Interaction>) -> Cassette {
+        let replayed = vec![false; interactions.len()];
+        Cassette {
+            path: PathBuf::new(),
+            recording: false,
+            state: Mutex::new(State {
+                interactions,
+                replayed,
+            }),
+        }
+    }
+
+    /// Replays the requests recorded in the file at `path` if there is one,
+    /// and records them to it otherwise.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Cassette> {
+        let path = path.into();
+        if path.exists() {
+            Cassette::replay(path)
+        } else {
+            Ok(Cassette::record(path))
+        }
+    }
+
+    /// Returns the path of the fixture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether requests are being recorded rather than replayed.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Returns the number of requests recorded.
+    pub fn len(&self) -> usize {
+        self.lock().interactions.len()
+    }
+
+    /// Returns whether no requests are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Answers a request with the response recorded for it.
+    ///
+    /// A request recorded several times is answered with each response in
+    /// turn, and then with the last one.
+    pub(crate) fn replay_request(&self, endpoint: &str, args: &Query) -> Result<Response> {
+        let request = RecordedRequest {
+            endpoint: endpoint.to_owned(),
+            args: args.sanitized_pairs(),
+        };
+        let mut state = self.lock();
+        let state = &mut *state;
+        let matching = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.request == request)
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+        let n = matching
+            .iter()
+            .find(|&&n| !state.replayed[n])
+            .or_else(|| matching.last())
+            .copied()
+            .ok_or(Error::Other("no response recorded for request"))?;
+        state.replayed[n] = true;
+        response(state.interactions[n].response.clone())
+    }
+
+    /// Records the response to a request, and returns it to be read as if it
+    /// came from the server.
+    pub(crate) fn record_request(
+        &self,
+        endpoint: &str,
+        args: &Query,
+        res: Response,
+    ) -> Result<Response> {
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+            .collect::<BTreeMap<_, _>>();
+        let bytes = res.bytes()?.to_vec();
+        let body = Body::new(bytes, headers.get("content-type").map(String::as_str));
+
+        let interaction = Interaction {
+            request: RecordedRequest {
+                endpoint: endpoint.to_owned(),
+                args: args.sanitized_pairs(),
+            },
+            response: RecordedResponse {
+                status,
+                headers,
+                body,
+            },
+        };
+        let recorded = interaction.response.clone();
+
+        let mut state = self.lock();
+        state.interactions.push(interaction);
+        state.replayed.push(true);
+        let fixture = Fixture {
+            interactions: state.interactions.clone(),
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(&fixture)?)?;
+        drop(state);
+
+        response(recorded)
+    }
+}
+
+/// Rebuilds a recorded response.
+fn response(recorded: RecordedResponse) -> Result<Response> {
+    let mut builder = http::Response::builder().status(recorded.status);
+    for (name, value) in &recorded.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder
+        .body(recorded.body.into_bytes())
+        .map(Response::from)
+        .map_err(|_| Error::Other("unable to rebuild recorded response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+
+    #[test]
+    fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("sunk-vcr-{}.json", std::process::id()));
+        let cassette = Cassette::record(&path);
+        let ok = http::Response::builder()
+            .header("content-type", "application/json")
+            .header("date", "Thu, 01 Jan 2026 00:00:00 GMT")
+            .body(r#"{"subsonic-response":{"status":"ok","version":"1.16.1"}}"#)
+            .unwrap();
+        let args = Query::with("id", 1).arg("password", "hunter2").build();
+        let res = cassette
+            .record_request("ping", &args, Response::from(ok))
+            .unwrap();
+        assert_eq!(
+            res.text().unwrap(),
+            r#"{"subsonic-response":{"status":"ok","version":"1.16.1"}}"#
+        );
+
+        let fixture = fs::read_to_string(&path).unwrap();
+        assert!(fixture.contains("\"subsonic-response\": {"));
+        assert!(!fixture.contains("hunter2") && !fixture.contains("date"));
+
+        let client = Client::new("http://localhost", "someone", "else")
+            .unwrap()
+            .with_cassette(Cassette::replay(&path).unwrap());
+        let replayed = client
+            .cassette()
+            .unwrap()
+            .replay_request("ping", &args)
+            .unwrap();
+        assert_eq!(replayed.headers()["content-type"], "application/json");
+        client.get("ping", args).unwrap();
+        assert!(client.get("ping", Query::none()).is_err());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn bodies_keep_their_kind() {
+        let json = |body: Body| serde_json::to_value(body).unwrap();
+        let bodies = [
+            Body::new(br#""quoted""#.to_vec(), Some("application/json")),
+            Body::new(b"[1, 2]".to_vec(), Some("application/json")),
+            Body::new(b"#EXTM3U".to_vec(), Some("application/vnd.apple.mpegurl")),
+            Body::new(vec![0xff, 0x00], Some("image/gif")),
+        ];
+        assert_eq!(
+            json(bodies[0].clone()),
+            serde_json::json!({ "json": "quoted" })
+        );
+        assert_eq!(
+            json(bodies[1].clone()),
+            serde_json::json!({ "json": [1, 2] })
+        );
+        assert_eq!(
+            json(bodies[2].clone()),
+            serde_json::json!({ "text": "#EXTM3U" })
+        );
+        assert_eq!(
+            json(bodies[3].clone()),
+            serde_json::json!({ "bytes": [255, 0] })
+        );
+
+        for body in &bodies {
+            let parsed = serde_json::from_value::<Body>(json(body.clone())).unwrap();
+            assert_eq!(parsed, *body);
+        }
+        let bytes = bodies[0].clone().into_bytes();
+        assert_eq!(bytes, br#""quoted""#);
+    }
+}
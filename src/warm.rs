@@ -0,0 +1,268 @@
+//! Warming up the songs coming up in a play queue.
+//!
+//! Skipping to the next song means asking the server for it, and, on a slow
+//! connection, waiting for the first of it to arrive. A [`QueueWarmer`] does
+//! that work ahead of time for the next few songs in a queue: it resolves
+//! their stream URLs, fetches their cover art into the client's cover art
+//! cache, and can start streaming them and keep their first seconds.
+//!
+//! Call [`QueueWarmer::warm`] whenever the queue changes, then take the
+//! warmed songs from it as they come up.
+//!
+//! [`QueueWarmer`]: struct.QueueWarmer.html
+//! [`QueueWarmer::warm`]: struct.QueueWarmer.html#method.warm
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use std::time::Duration;
+//! use sunk::song::Song;
+//! use sunk::warm::{QueueWarmer, WarmOptions};
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//! let queue = Song::random(&client, 10)?;
+//!
+//! let mut warmer = QueueWarmer::new(WarmOptions {
+//!     audio: Some(Duration::from_secs(5)),
+//!     ..WarmOptions::default()
+//! });
+//! warmer.warm(&client, &queue)?;
+//!
+//! // When the user skips to the next song:
+//! let reader = warmer.stream_reader(&client, &queue[0])?;
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::time::Duration;
+
+use url::Url;
+
+use crate::song::Song;
+use crate::{Client, Result, SongId, StreamReader, Streamable};
+
+/// What a [`QueueWarmer`] does ahead of time.
+///
+/// [`QueueWarmer`]: struct.QueueWarmer.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmOptions {
+    /// How many songs at the head of the queue to warm; 3 by default.
+    pub count: usize,
+    /// Whether to fetch cover art into the client's cover art cache; true by
+    /// default. Does nothing if the client has no cover art cache.
+    pub covers: bool,
+    /// The size to fetch cover art at, or `None` for the full size.
+    pub cover_size: Option<usize>,
+    /// How much of each song to stream ahead of time, or `None` to not
+    /// stream songs until they are taken.
+    ///
+    /// [`QueueWarmer::warm`] blocks until this much of each new song has
+    /// arrived, so with audio set it is best called from a background
+    /// thread.
+    ///
+    /// [`QueueWarmer::warm`]: struct.QueueWarmer.html#method.warm
+    pub audio: Option<Duration>,
+}
+
+impl Default for WarmOptions {
+    fn default() -> WarmOptions {
+        WarmOptions {
+            count: 3,
+            covers: true,
+            cover_size: None,
+            audio: None,
+        }
+    }
+}
+
+/// A song that has been warmed.
+#[derive(Debug)]
+pub struct Warmed {
+    /// The song.
+    pub song: Song,
+    /// The URL the song streams from.
+    pub stream_url: Url,
+    reader: Option<StreamReader>,
+}
+
+impl Warmed {
+    /// Returns the song's stream, with its first seconds already read, if
+    /// audio was streamed ahead of time.
+    pub fn reader(&self) -> Option<&StreamReader> {
+        self.reader.as_ref()
+    }
+
+    /// Returns the song's stream, if audio was streamed ahead of time.
+    pub fn into_reader(self) -> Option<StreamReader> {
+        self.reader
+    }
+}
+
+/// Warms the songs at the head of a play queue.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: index.html
+#[derive(Debug)]
+pub struct QueueWarmer {
+    options: WarmOptions,
+    warmed: Vec<Warmed>,
+    #[cfg(feature = "playback")]
+    decodable: bool,
+}
+
+impl QueueWarmer {
+    /// Creates a warmer that does what `options` ask.
+    pub fn new(options: WarmOptions) -> QueueWarmer {
+        QueueWarmer {
+            options,
+            warmed: Vec::new(),
+            #[cfg(feature = "playback")]
+            decodable: false,
+        }
+    }
+
+    /// Streams songs in a format sunk's decoders understand, for players
+    /// that decode what is warmed.
+    #[cfg(feature = "playback")]
+    pub(crate) fn for_decoding(self) -> QueueWarmer {
+        let mut warmer = self;
+        warmer.decodable = true;
+        warmer
+    }
+
+    /// Returns what the warmer does ahead of time.
+    pub fn options(&self) -> WarmOptions {
+        self.options
+    }
+
+    /// Warms the first songs of `queue` that haven't been warmed yet, and
+    /// forgets warmed songs that are no longer among them. Returns the number
+    /// of songs newly warmed.
+    ///
+    /// Cover art and audio are fetched on a best-effort basis: a song whose
+    /// cover or stream can't be fetched is still warmed, without them.
+    ///
+    /// This blocks while the cover art and the first [`audio`] of each new
+    /// song are fetched.
+    ///
+    /// [`audio`]: struct.WarmOptions.html#structfield.audio
+    ///
+    /// # Errors
+    ///
+    /// Errors if a stream URL can't be built.
+    pub fn warm(&mut self, client: &Client, queue: &[Song]) -> Result<usize> {
+        let head = &queue[..queue.len().min(self.options.count)];
+        self.warmed
+            .retain(|w| head.iter().any(|song| song.id == w.song.id));
+
+        let new = head
+            .iter()
+            .filter(|song| !self.warmed.iter().any(|w| w.song.id == song.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return Ok(0);
+        }
+
+        #[cfg(feature = "cache")]
+        {
+            if self.options.covers && client.cover_cache().is_some() {
+                if let Err(e) = client.prefetch_covers(&new, self.options.cover_size, ()) {
+                    warn!("Failed to warm cover art: {}", e);
+                }
+            }
+        }
+
+        for song in &new {
+            let stream_url = song.stream_url(client)?;
+            let reader = match self.options.audio {
+                Some(audio) => match self.open(client, song, song.stream_bytes(audio)) {
+                    Ok(reader) => Some(reader),
+                    Err(e) => {
+                        warn!("Failed to warm the stream of {}: {}", song.title, e);
+                        None
+                    }
+                },
+                None => None,
+            };
+            self.warmed.push(Warmed {
+                song: song.clone(),
+                stream_url,
+                reader,
+            });
+        }
+        Ok(new.len())
+    }
+
+    /// Returns the warmed song with the ID `id`, if any.
+    pub fn get(&self, id: &SongId) -> Option<&Warmed> {
+        self.warmed.iter().find(|w| w.song.id == *id)
+    }
+
+    /// Removes and returns the warmed song with the ID `id`, if any.
+    pub fn take(&mut self, id: &SongId) -> Option<Warmed> {
+        let i = self.warmed.iter().position(|w| w.song.id == *id)?;
+        Some(self.warmed.remove(i))
+    }
+
+    /// Returns a stream of `song`, using the one started ahead of time if
+    /// there is one, and starting one otherwise.
+    pub fn stream_reader(&mut self, client: &Client, song: &Song) -> Result<StreamReader> {
+        match self.take(&song.id).and_then(Warmed::into_reader) {
+            Some(reader) => Ok(reader),
+            None => self.open(client, song, 0),
+        }
+    }
+
+    /// Starts streaming `song`, and reads its first `bytes`.
+    fn open(&self, client: &Client, song: &Song, bytes: u64) -> Result<StreamReader> {
+        #[cfg(feature = "playback")]
+        let mut reader = if self.decodable {
+            song.decodable_reader(client)?
+        } else {
+            song.stream_reader(client)?
+        };
+        #[cfg(not(feature = "playback"))]
+        let mut reader = song.stream_reader(client)?;
+
+        reader.buffer_to(bytes)?;
+        Ok(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::song;
+
+    #[test]
+    fn warms_head_of_queue() {
+        let client = Client::new("http://localhost", "user", "pass").unwrap();
+        let mut warmer = QueueWarmer::new(WarmOptions {
+            count: 2,
+            ..WarmOptions::default()
+        });
+        let queue = vec![song("1"), song("2"), song("3")];
+
+        assert_eq!(warmer.warm(&client, &queue).unwrap(), 2);
+        assert_eq!(warmer.warm(&client, &queue).unwrap(), 0);
+        let warmed = warmer.get(&queue[0].id).unwrap();
+        assert!(warmed.stream_url.as_str().contains("stream"));
+        assert!(warmed.reader().is_none());
+        assert!(warmer.get(&queue[2].id).is_none());
+
+        // Skipping the first song warms the third, and forgets the first.
+        assert_eq!(warmer.warm(&client, &queue[1..]).unwrap(), 1);
+        assert!(warmer.get(&queue[0].id).is_none());
+        assert!(warmer.take(&queue[2].id).is_some());
+        assert!(warmer.get(&queue[2].id).is_none());
+    }
+}
@@ -0,0 +1,351 @@
+//! Watching what the server's users are playing.
+//!
+//! The Subsonic API has no push notifications; [`Client::now_playing`] only
+//! lists what each player is playing at the moment it is asked. A
+//! [`NowPlayingWatcher`] asks again and again, remembers the answer, and
+//! turns the differences into [`NowPlayingEvent`]s: a player starting,
+//! changing track, or stopping. This is what a rich-presence integration or a
+//! bridge to a scrobbling service needs.
+//!
+//! [`NowPlayingWatcher::events`] polls in a loop and returns the events as a
+//! blocking iterator. Async applications can instead fetch `getNowPlaying`
+//! themselves, such as with the [`protocol`] module, and pass the entries to
+//! [`NowPlayingWatcher::update`].
+//!
+//! [`Client::now_playing`]: ../struct.Client.html#method.now_playing
+//! [`NowPlayingWatcher`]: struct.NowPlayingWatcher.html
+//! [`NowPlayingEvent`]: enum.NowPlayingEvent.html
+//! [`NowPlayingWatcher::events`]: struct.NowPlayingWatcher.html#method.events
+//! [`NowPlayingWatcher::update`]: struct.NowPlayingWatcher.html#method.update
+//! [`protocol`]: ../protocol/index.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate sunk;
+//! use sunk::watch::{NowPlayingEvent, NowPlayingWatcher};
+//! use sunk::Client;
+//!
+//! # fn run() -> sunk::Result<()> {
+//! # let site = "http://demo.subsonic.org";
+//! # let user = "guest3";
+//! # let password = "guest";
+//! let client = Client::new(site, user, password)?;
+//!
+//! for event in NowPlayingWatcher::new().events(&client) {
+//!     match event? {
+//!         NowPlayingEvent::Started(now) | NowPlayingEvent::TrackChanged { now, .. } => {
+//!             let song = now.song_info(&client)?;
+//!             println!("{} is playing {}", now.user, song.title);
+//!         }
+//!         NowPlayingEvent::Stopped(last) => println!("{} stopped", last.user),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! # fn main() { }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Client, NowPlaying, Result};
+
+/// A change in what a player is playing.
+///
+/// Players are told apart by their user and player ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NowPlayingEvent {
+    /// A player started playing.
+    Started(NowPlaying),
+    /// A player moved on to another song or video.
+    TrackChanged {
+        /// What the player was playing before.
+        previous: NowPlaying,
+        /// What the player is playing now.
+        now: NowPlaying,
+    },
+    /// A player stopped playing; this is what it played last.
+    Stopped(NowPlaying),
+}
+
+impl NowPlayingEvent {
+    /// Returns the entry the event is about: what is playing now, or what
+    /// was playing last if the player stopped.
+    pub fn entry(&self) -> &NowPlaying {
+        match *self {
+            NowPlayingEvent::Started(ref now) => now,
+            NowPlayingEvent::TrackChanged { ref now, .. } => now,
+            NowPlayingEvent::Stopped(ref last) => last,
+        }
+    }
+}
+
+/// Tracks what the server's players are playing between polls of
+/// `getNowPlaying`.
+#[derive(Debug, Clone)]
+pub struct NowPlayingWatcher {
+    playing: HashMap<(String, usize), NowPlaying>,
+    interval: Duration,
+    stale_after: Option<Duration>,
+}
+
+impl NowPlayingWatcher {
+    /// Creates a watcher that has seen nothing playing, polling every 15
+    /// seconds.
+    pub fn new() -> NowPlayingWatcher {
+        NowPlayingWatcher {
+            playing: HashMap::new(),
+            interval: Duration::from_secs(15),
+            stale_after: None,
+        }
+    }
+
+    /// Sets how long [`events`] waits between polls.
+    ///
+    /// [`events`]: #method.events
+    pub fn with_interval(self, interval: Duration) -> NowPlayingWatcher {
+        let mut watcher = self;
+        watcher.interval = interval;
+        watcher
+    }
+
+    /// Returns how long [`events`] waits between polls.
+    ///
+    /// [`events`]: #method.events
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Treats a player as stopped once it hasn't updated the server for
+    /// `stale_after`.
+    ///
+    /// Servers keep listing a player for some minutes after it stops, since
+    /// players don't tell them when they do. An entry is only updated when a
+    /// track starts, so this should be longer than the longest track played.
+    /// By default, a player is stopped only once the server stops listing it.
+    pub fn with_stale_after(self, stale_after: Duration) -> NowPlayingWatcher {
+        let mut watcher = self;
+        watcher.stale_after = Some(stale_after);
+        watcher
+    }
+
+    /// Returns how long a player may go without updating the server before
+    /// it is treated as stopped, if set.
+    pub fn stale_after(&self) -> Option<Duration> {
+        self.stale_after
+    }
+
+    /// Returns what each player is playing, as of the last poll.
+    pub fn playing(&self) -> impl Iterator<Item = &NowPlaying> {
+        self.playing.values()
+    }
+
+    /// Asks the server what is playing, and returns what changed since the
+    /// last poll.
+    pub fn poll(&mut self, client: &Client) -> Result<Vec<NowPlayingEvent>> {
+        Ok(self.update(client.now_playing()?))
+    }
+
+    /// Takes `entries` as what is playing now, such as from a
+    /// `getNowPlaying` response fetched elsewhere, and returns what changed.
+    ///
+    /// Players that stopped are returned first, then those that changed
+    /// track, then those that started.
+    pub fn update(&mut self, entries: Vec<NowPlaying>) -> Vec<NowPlayingEvent> {
+        let stale_after = self.stale_after;
+        let current = entries
+            .into_iter()
+            .filter(|e| stale_after.is_none_or(|s| minutes(e.minutes_ago) < s))
+            .map(|e| ((e.user.clone(), e.player_id), e))
+            .collect::<HashMap<_, _>>();
+
+        let mut previous = std::mem::take(&mut self.playing);
+        let mut changed = Vec::new();
+        let mut started = Vec::new();
+        for (key, now) in &current {
+            match previous.remove(key) {
+                None => started.push((key, NowPlayingEvent::Started(now.clone()))),
+                Some(ref before) if before.id() == now.id() => (),
+                Some(before) => changed.push((
+                    key,
+                    NowPlayingEvent::TrackChanged {
+                        previous: before,
+                        now: now.clone(),
+                    },
+                )),
+            }
+        }
+        let mut stopped = previous.into_iter().collect::<Vec<_>>();
+
+        // Players are listed in a stable order, rather than the map's.
+        stopped.sort_by(|a, b| a.0.cmp(&b.0));
+        changed.sort_by(|a, b| a.0.cmp(b.0));
+        started.sort_by(|a, b| a.0.cmp(b.0));
+        let mut events = stopped
+            .into_iter()
+            .map(|(_, last)| NowPlayingEvent::Stopped(last))
+            .collect::<Vec<_>>();
+        events.extend(changed.into_iter().map(|(_, e)| e));
+        events.extend(started.into_iter().map(|(_, e)| e));
+
+        self.playing = current;
+        events
+    }
+
+    /// Polls the server every [`interval`], and returns the changes as they
+    /// are seen.
+    ///
+    /// The iterator never ends; errors polling the server are returned, and
+    /// polling carries on after them.
+    ///
+    /// [`interval`]: #method.interval
+    pub fn events(self, client: &Client) -> Events<'_> {
+        Events {
+            watcher: self,
+            client,
+            pending: VecDeque::new(),
+            last_poll: None,
+        }
+    }
+}
+
+/// Converts the `minutes_ago` of an entry to a duration.
+fn minutes(minutes_ago: usize) -> Duration {
+    Duration::from_secs(minutes_ago as u64 * 60)
+}
+
+impl Default for NowPlayingWatcher {
+    fn default() -> NowPlayingWatcher {
+        NowPlayingWatcher::new()
+    }
+}
+
+/// The changes in what is playing, as a blocking iterator. See
+/// [`NowPlayingWatcher::events`].
+///
+/// [`NowPlayingWatcher::events`]: struct.NowPlayingWatcher.html#method.events
+#[derive(Debug)]
+pub struct Events<'a> {
+    watcher: NowPlayingWatcher,
+    client: &'a Client,
+    pending: VecDeque<NowPlayingEvent>,
+    last_poll: Option<Instant>,
+}
+
+impl<'a> Events<'a> {
+    /// Returns the watcher, with what it saw playing last.
+    pub fn watcher(&self) -> &NowPlayingWatcher {
+        &self.watcher
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<NowPlayingEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if let Some(last) = self.last_poll {
+                let next = last + self.watcher.interval;
+                let now = Instant::now();
+                if next > now {
+                    thread::sleep(next - now);
+                }
+            }
+            self.last_poll = Some(Instant::now());
+            match self.watcher.poll(self.client) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::test_util;
+
+    fn entry(user: &str, player_id: usize, id: &str, minutes_ago: usize) -> NowPlaying {
+        serde_json::from_value(serde_json::json!({
+            "username": user,
+            "playerId": player_id,
+            "id": id,
+            "minutesAgo": minutes_ago,
+            "isVideo": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn events() {
+        let mut watcher = NowPlayingWatcher::new();
+        let alice = entry("alice", 1, "27", 0);
+        let bob = entry("bob", 2, "28", 0);
+        assert_eq!(
+            watcher.update(vec![bob.clone(), alice.clone()]),
+            [
+                NowPlayingEvent::Started(alice.clone()),
+                NowPlayingEvent::Started(bob.clone()),
+            ]
+        );
+        assert_eq!(
+            watcher.update(vec![alice.clone(), entry("bob", 2, "28", 3)]),
+            []
+        );
+
+        // Another player for the same user is watched separately.
+        let next = entry("alice", 1, "29", 0);
+        let phone = entry("alice", 3, "27", 0);
+        assert_eq!(
+            watcher.update(vec![next.clone(), phone.clone()]),
+            [
+                NowPlayingEvent::Stopped(entry("bob", 2, "28", 3)),
+                NowPlayingEvent::TrackChanged {
+                    previous: alice,
+                    now: next.clone(),
+                },
+                NowPlayingEvent::Started(phone.clone()),
+            ]
+        );
+        assert_eq!(watcher.playing().count(), 2);
+
+        let mut watcher = watcher.with_stale_after(Duration::from_secs(10 * 60));
+        assert_eq!(
+            watcher.update(vec![next, entry("alice", 3, "27", 12)]),
+            [NowPlayingEvent::Stopped(phone)]
+        );
+    }
+
+    #[test]
+    fn polls() {
+        let response = |entries: serde_json::Value| {
+            let body = serde_json::json!({ "nowPlaying": { "entry": entries } });
+            ("getNowPlaying", Query::none(), body)
+        };
+        let playing = serde_json::json!([{
+            "username": "alice", "playerId": 1, "id": "27", "minutesAgo": 0, "isVideo": false,
+        }]);
+        let client =
+            test_util::replay_client(&[response(playing), response(serde_json::json!([]))]);
+        let watcher = NowPlayingWatcher::new().with_interval(Duration::from_millis(1));
+        let events = watcher
+            .events(&client)
+            .take(2)
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events[0],
+            NowPlayingEvent::Started(entry("alice", 1, "27", 0))
+        );
+        assert_eq!(
+            events[1],
+            NowPlayingEvent::Stopped(entry("alice", 1, "27", 0))
+        );
+    }
+}